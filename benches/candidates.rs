@@ -0,0 +1,153 @@
+// Benchmarks for candidate generation strategies.
+//
+// Measures the time to generate candidates and the time of a 2-opt descent over them, and prints
+// the tour length reached by the descent for each strategy, so that the default strategy can be
+// chosen by both speed and quality. Instances are generated uniformly at random and as clusters.
+// Nearest neighbours are found by brute force, with a k-d tree and with an HNSW graph, the latter
+// two through the candidate set builder.
+// Delaunay candidates are not measured, since the crate has no triangulation to generate them.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use cykl::{
+    alg::{
+        cand_gen_nn, cand_gen_quadrant, lkh::enumerate_2opt, solvers::descend, CandidateSetBuilder,
+        HnswParams, NeighborIndex, Objective,
+    },
+    data::{DataStore, Metric, NodeKind},
+    tour::{Tour, TwoLevelList},
+    Scalar,
+};
+
+const SIZES: [usize; 2] = [500, 2000];
+const K: usize = 8;
+const GROUPSIZE: usize = 64;
+const SEED: u64 = 42;
+
+#[derive(Clone, Copy, Debug)]
+enum Strategy {
+    BruteForce,
+    KdTree,
+    Hnsw,
+    Quadrant,
+}
+
+const STRATEGIES: [Strategy; 4] = [
+    Strategy::BruteForce,
+    Strategy::KdTree,
+    Strategy::Hnsw,
+    Strategy::Quadrant,
+];
+
+/// Name of a kind of instance and the function that generates an instance of a given size.
+type Instance = (&'static str, fn(usize) -> DataStore<()>);
+
+fn uniform(n_nodes: usize) -> DataStore<()> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut store = DataStore::with_capacity(Metric::Euc2d, n_nodes);
+    for _ in 0..n_nodes {
+        let pos = vec![rng.gen_range(0.0..1000.), rng.gen_range(0.0..1000.)];
+        store.add(NodeKind::Target, pos, ());
+    }
+    store.compute();
+    store
+}
+
+fn clustered(n_nodes: usize) -> DataStore<()> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let centres: Vec<(f64, f64)> = (0..(n_nodes / 50).max(1))
+        .map(|_| (rng.gen_range(0.0..1000.), rng.gen_range(0.0..1000.)))
+        .collect();
+
+    let mut store = DataStore::with_capacity(Metric::Euc2d, n_nodes);
+    for ii in 0..n_nodes {
+        let (x, y) = centres[ii % centres.len()];
+        let pos = vec![x + rng.gen_range(-20.0..20.), y + rng.gen_range(-20.0..20.)];
+        store.add(NodeKind::Target, pos, ());
+    }
+    store.compute();
+    store
+}
+
+fn generate(tour: &mut TwoLevelList, store: &DataStore<()>, strategy: Strategy) {
+    match strategy {
+        Strategy::BruteForce => cand_gen_nn(tour, K, Objective::Minimize),
+        Strategy::KdTree => build(tour, store, NeighborIndex::KdTree),
+        Strategy::Hnsw => build(tour, store, NeighborIndex::Hnsw(HnswParams::default())),
+        Strategy::Quadrant => cand_gen_quadrant(tour, store, K),
+    }
+}
+
+fn build(tour: &mut TwoLevelList, store: &DataStore<()>, index: NeighborIndex) {
+    CandidateSetBuilder::new()
+        .k(K)
+        .neighbor_index(index)
+        .build(store)
+        .apply(tour)
+        .expect("Candidates of another store");
+}
+
+fn two_opt(tour: &mut TwoLevelList) -> Scalar {
+    descend(tour, |tour, node, moves| {
+        moves.extend(enumerate_2opt(tour, node, node.candidates()))
+    })
+}
+
+fn benchmark_candidates(c: &mut Criterion) {
+    let instances: [Instance; 2] = [("uniform", uniform), ("clustered", clustered)];
+
+    for (name, create) in instances {
+        let mut gen_group = c.benchmark_group(format!("cand_gen/{}", name));
+        gen_group.sample_size(10);
+        let mut opt_group_stores = Vec::new();
+
+        for n_nodes in SIZES {
+            let store = create(n_nodes);
+            for strategy in STRATEGIES {
+                gen_group.bench_with_input(
+                    BenchmarkId::new(format!("{:?}", strategy), n_nodes),
+                    &strategy,
+                    |b, strategy| {
+                        let mut tour = TwoLevelList::new(&store, GROUPSIZE);
+                        b.iter(|| generate(&mut tour, &store, *strategy));
+                    },
+                );
+            }
+            opt_group_stores.push((n_nodes, store));
+        }
+        gen_group.finish();
+
+        let mut opt_group = c.benchmark_group(format!("two_opt/{}", name));
+        opt_group.sample_size(10);
+        for (n_nodes, store) in opt_group_stores.iter() {
+            for strategy in STRATEGIES {
+                let mut tour = TwoLevelList::new(store, GROUPSIZE);
+                generate(&mut tour, store, strategy);
+                two_opt(&mut tour);
+                println!(
+                    "two_opt/{}/{:?}/{}: length {:.1}",
+                    name,
+                    strategy,
+                    n_nodes,
+                    tour.tour_order().cost()
+                );
+
+                opt_group.bench_with_input(
+                    BenchmarkId::new(format!("{:?}", strategy), n_nodes),
+                    &strategy,
+                    |b, strategy| {
+                        b.iter(|| {
+                            let mut tour = TwoLevelList::new(store, GROUPSIZE);
+                            generate(&mut tour, store, *strategy);
+                            two_opt(&mut tour)
+                        });
+                    },
+                );
+            }
+        }
+        opt_group.finish();
+    }
+}
+
+criterion_group!(benches, benchmark_candidates);
+criterion_main!(benches);
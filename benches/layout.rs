@@ -0,0 +1,71 @@
+// Benchmarks for the memory layout of tours.
+//
+// Measures a full walk along the successors of a tour whose order is unrelated to the order in
+// which its nodes were added, before and after the vertices are repacked to follow the tour.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use cykl::{
+    data::{DataStore, MemoryBudget, Metric, NodeKind},
+    tour::{Tour, TourOrder, TwoLevelList},
+};
+
+const SIZES: [usize; 2] = [10_000, 100_000];
+const GROUPSIZE: usize = 64;
+const SEED: u64 = 42;
+
+fn uniform(n_nodes: usize) -> DataStore<()> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut store = DataStore::with_capacity(Metric::Euc2d, n_nodes);
+    // Walks do not need the cost matrix.
+    store.set_memory_budget(MemoryBudget::new(0));
+    for _ in 0..n_nodes {
+        let pos = vec![rng.gen_range(0.0..1000.), rng.gen_range(0.0..1000.)];
+        store.add(NodeKind::Target, pos, ());
+    }
+    store.compute();
+    store
+}
+
+fn shuffled(store: &DataStore<()>) -> TwoLevelList {
+    let mut order: Vec<usize> = (0..store.len()).collect();
+    order.shuffle(&mut StdRng::seed_from_u64(SEED));
+    let mut tour = TwoLevelList::new(store, GROUPSIZE);
+    tour.apply(&TourOrder::with_ord(order)).unwrap();
+    tour
+}
+
+fn walk(tour: &TwoLevelList) -> usize {
+    let mut node = tour.get(0).unwrap();
+    for _ in 0..tour.len() {
+        node = tour.successor(&node).unwrap();
+    }
+    tour.index_of(&node)
+}
+
+fn benchmark_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("walk");
+    group.sample_size(20);
+
+    for n_nodes in SIZES {
+        let store = uniform(n_nodes);
+        let scattered = shuffled(&store);
+        let mut repacked = shuffled(&store);
+        repacked.repack();
+
+        group.bench_with_input(
+            BenchmarkId::new("scattered", n_nodes),
+            &scattered,
+            |b, tour| b.iter(|| walk(black_box(tour))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("repacked", n_nodes),
+            &repacked,
+            |b, tour| b.iter(|| walk(black_box(tour))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_layout);
+criterion_main!(benches);
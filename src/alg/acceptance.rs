@@ -0,0 +1,195 @@
+//! Acceptance criteria that decide whether a candidate tour replaces the current tour.
+use rand::{Rng, SeedableRng};
+
+use crate::Scalar;
+
+use super::rng::SolverRng;
+
+/// Strategy that decides whether a move is accepted by a local search.
+pub trait Acceptance {
+    /// Returns `true` if a candidate tour of length `candidate` replaces the current tour of
+    /// length `current`.
+    fn accept(&mut self, current: Scalar, candidate: Scalar) -> bool;
+
+    /// Informs the strategy about the lengths of the current and the best tour at the end of an
+    /// iteration.
+    fn update(&mut self, _current: Scalar, _best: Scalar) {}
+}
+
+/// Only accepts candidates that are strictly shorter than the current tour.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Greedy;
+
+impl Acceptance for Greedy {
+    fn accept(&mut self, current: Scalar, candidate: Scalar) -> bool {
+        candidate < current
+    }
+}
+
+/// Accepts longer candidates with a probability of `exp(-delta / temperature)`, where the
+/// temperature decreases geometrically after each iteration.
+///
+/// With the `serde` feature, the state of the random number generator is serialised along with
+/// the temperature, so that a deserialised strategy continues with the same sequence.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulatedAnnealing {
+    temperature: Scalar,
+    cooling: Scalar,
+    seed: u64,
+    #[cfg_attr(feature = "serde", serde(with = "super::rng::state"))]
+    rng: SolverRng,
+}
+
+impl SimulatedAnnealing {
+    /// Creates the strategy with an initial temperature, a cooling factor in `(0, 1)` and a seed
+    /// for the random number generator.
+    pub fn new(temperature: Scalar, cooling: Scalar, seed: u64) -> Self {
+        Self {
+            temperature,
+            cooling,
+            seed,
+            rng: SolverRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Returns the current temperature.
+    pub fn temperature(&self) -> Scalar {
+        self.temperature
+    }
+
+    /// Returns the seed of the random number generator.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Acceptance for SimulatedAnnealing {
+    fn accept(&mut self, current: Scalar, candidate: Scalar) -> bool {
+        if candidate <= current {
+            return true;
+        }
+
+        if self.temperature <= 0. {
+            return false;
+        }
+
+        self.rng.gen::<Scalar>() < (-(candidate - current) / self.temperature).exp()
+    }
+
+    fn update(&mut self, _current: Scalar, _best: Scalar) {
+        self.temperature *= self.cooling;
+    }
+}
+
+/// Record-to-record travel: accepts candidates that exceed the length of the best tour found so
+/// far by at most a fixed fraction.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordToRecord {
+    deviation: Scalar,
+    record: Scalar,
+}
+
+impl RecordToRecord {
+    /// Creates the strategy with the allowed relative deviation from the record, e.g. `0.01`.
+    pub fn new(deviation: Scalar) -> Self {
+        Self {
+            deviation,
+            record: Scalar::MAX,
+        }
+    }
+}
+
+impl Acceptance for RecordToRecord {
+    fn accept(&mut self, current: Scalar, candidate: Scalar) -> bool {
+        self.record = self.record.min(current);
+        candidate <= self.record * (1. + self.deviation)
+    }
+
+    fn update(&mut self, current: Scalar, best: Scalar) {
+        self.record = self.record.min(current).min(best);
+    }
+}
+
+/// Great Deluge: accepts candidates below a water level that starts at the length of the first
+/// tour and decreases by a fixed amount after each iteration.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GreatDeluge {
+    level: Option<Scalar>,
+    decay: Scalar,
+}
+
+impl GreatDeluge {
+    /// Creates the strategy with the amount by which the level decreases per iteration.
+    pub fn new(decay: Scalar) -> Self {
+        Self { level: None, decay }
+    }
+
+    /// Returns the current water level, or `None` before the first decision.
+    pub fn level(&self) -> Option<Scalar> {
+        self.level
+    }
+}
+
+impl Acceptance for GreatDeluge {
+    fn accept(&mut self, current: Scalar, candidate: Scalar) -> bool {
+        let level = *self.level.get_or_insert(current);
+        candidate <= current || candidate <= level
+    }
+
+    fn update(&mut self, _current: Scalar, _best: Scalar) {
+        if let Some(level) = &mut self.level {
+            *level -= self.decay;
+        }
+    }
+}
+
+/// Late acceptance hill climbing: accepts candidates that are not longer than the current tour
+/// or the tour that was current a fixed number of iterations ago.
+///
+/// The lengths of past tours are kept in a ring buffer that is filled with the length of the
+/// first tour.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LateAcceptance {
+    history: Vec<Scalar>,
+    len: usize,
+    iteration: usize,
+}
+
+impl LateAcceptance {
+    /// Creates the strategy with the length of its history. The length must be positive.
+    pub fn new(len: usize) -> Self {
+        Self {
+            history: Vec::with_capacity(len.max(1)),
+            len: len.max(1),
+            iteration: 0,
+        }
+    }
+
+    #[inline]
+    fn slot(&self) -> usize {
+        self.iteration % self.len
+    }
+}
+
+impl Acceptance for LateAcceptance {
+    fn accept(&mut self, current: Scalar, candidate: Scalar) -> bool {
+        if self.history.is_empty() {
+            self.history.resize(self.len, current);
+        }
+
+        candidate <= current || candidate <= self.history[self.slot()]
+    }
+
+    fn update(&mut self, current: Scalar, _best: Scalar) {
+        if !self.history.is_empty() {
+            let slot = self.slot();
+            self.history[slot] = current;
+            self.iteration += 1;
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Hyper-heuristic that distributes a time budget among several improvement operators.
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rand::{Rng, SeedableRng};
+
+use crate::{
+    tour::{Tour, TourNode, TourOrder, UpdateTourError},
+    CancellationToken, Scalar,
+};
+
+use super::{
+    bounds::BoundsTracker,
+    incumbent::Incumbent,
+    lkh::{enumerate_2opt, improve_lkh, random_double_bridge, KOpt, Move, OrOpt, TwoOpt},
+    pipeline::descend_rated,
+    rng::SolverRng,
+    Objective, Scan,
+};
+
+/// Minimum probability with which each operator is selected, so that operators whose statistics
+/// are outdated get a chance to recover.
+const MIN_SHARE: Scalar = 0.05;
+
+/// Longest path moved by [`Operator::OrOpt`].
+const OR_OPT_LEN: usize = 3;
+
+/// Improvement operator that can be selected by [`Adaptive`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operator {
+    /// Descends with 2-opt moves between nodes and their candidates.
+    TwoOpt,
+    /// Descends with Or-opt moves, which relocate paths of up to three nodes next to a candidate,
+    /// either forwards or reversed.
+    OrOpt,
+    /// Improves the tour with the sequential search of the LKH solver, restricted to 2-opt moves
+    /// between nodes and their candidates.
+    LkhTwoOpt,
+    /// Perturbs the tour with a random double-bridge move and descends with 2-opt moves. The
+    /// perturbation is undone if it does not lead to a better tour.
+    Kick,
+}
+
+impl Operator {
+    /// All operators.
+    pub const ALL: [Operator; 4] = [
+        Operator::TwoOpt,
+        Operator::OrOpt,
+        Operator::LkhTwoOpt,
+        Operator::Kick,
+    ];
+}
+
+/// Statistics of an operator collected by [`Adaptive`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperatorStats {
+    /// Number of times the operator was executed.
+    pub calls: usize,
+    /// Total decrease of the tour length, or total increase when maximising.
+    pub improvement: Scalar,
+    /// Total time spent in the operator.
+    pub time: Duration,
+}
+
+impl OperatorStats {
+    /// Returns the average improvement of the tour length per second.
+    pub fn rate(&self) -> Scalar {
+        let secs = self.time.as_secs_f64();
+        if secs > 0. {
+            self.improvement / secs
+        } else {
+            0.
+        }
+    }
+}
+
+/// Time-budgeted driver that repeatedly selects an improvement operator at random, with
+/// probabilities proportional to the improvement per second that each operator has achieved so
+/// far.
+///
+/// The operators rely on the candidates of the nodes, which must be generated before running the
+/// driver, e.g. with [`cand_gen_nn`](crate::alg::cand_gen_nn).
+///
+/// With the `serde` feature, the statistics of the operators are serialised with the state of the
+/// random number generator, so that a restored driver keeps its learned selection probabilities.
+/// The cancellation token, bounds tracker and incumbent are not serialised.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Adaptive {
+    operators: Vec<Operator>,
+    stats: Vec<OperatorStats>,
+    scan: Scan,
+    #[cfg_attr(feature = "serde", serde(default))]
+    objective: Objective,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cancellation: CancellationToken,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    bounds: Option<Arc<BoundsTracker>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    incumbent: Option<Arc<Incumbent>>,
+    #[cfg_attr(feature = "serde", serde(with = "super::rng::state"))]
+    rng: SolverRng,
+}
+
+impl Adaptive {
+    /// Creates a driver that uses all operators.
+    pub fn new(seed: u64) -> Self {
+        Self::with_operators(&Operator::ALL, seed)
+    }
+
+    /// Creates a driver that uses the given operators.
+    pub fn with_operators(operators: &[Operator], seed: u64) -> Self {
+        Self {
+            operators: operators.to_vec(),
+            stats: vec![OperatorStats::default(); operators.len()],
+            scan: Scan::default(),
+            objective: Objective::Minimize,
+            cancellation: CancellationToken::new(),
+            bounds: None,
+            incumbent: None,
+            rng: SolverRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Sets the strategy with which the 2-opt and Or-opt descents choose their moves. Defaults to
+    /// [`Scan::FirstImprovement`].
+    pub fn with_scan(mut self, scan: Scan) -> Self {
+        self.scan = scan;
+        self
+    }
+
+    /// Sets the direction in which the operators change the tour and in which the best tour is
+    /// chosen. Defaults to [`Objective::Minimize`].
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Sets a token that stops the driver before its time budget is used up. The token is checked
+    /// before each operator is executed.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Attaches a tracker to which the best tours are offered. The driver stops once the target
+    /// gap of the tracker is reached, which is checked in each iteration.
+    pub fn with_bounds(mut self, bounds: Arc<BoundsTracker>) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Attaches an incumbent to which the best tours are offered as soon as they are found, e.g.
+    /// to report them with [`Incumbent::on_improvement`].
+    pub fn with_incumbent(mut self, incumbent: Arc<Incumbent>) -> Self {
+        self.incumbent = Some(incumbent);
+        self
+    }
+
+    /// Returns the operators together with their statistics.
+    pub fn stats(&self) -> impl Iterator<Item = (Operator, &OperatorStats)> {
+        self.operators.iter().copied().zip(self.stats.iter())
+    }
+
+    /// Improves a tour until the time budget is used up, and returns the best tour found.
+    ///
+    /// Each operator is executed once before the selection takes the statistics into account.
+    /// Statistics are kept between runs.
+    pub fn run<T>(&mut self, tour: &mut T, budget: Duration) -> Result<TourOrder, UpdateTourError>
+    where
+        T: Tour,
+    {
+        let start = Instant::now();
+        let mut best = tour.tour_order();
+        self.offer(&best);
+
+        while !self.operators.is_empty()
+            && start.elapsed() < budget
+            && !self.cancellation.is_cancelled()
+            && !self.is_target_reached()
+        {
+            let idx = self.select();
+            let before = tour.total_distance();
+            let op_start = Instant::now();
+
+            self.execute(self.operators[idx], tour, &best)?;
+
+            // The order is only taken for a new best tour, since it takes O(N) time.
+            let after = tour.total_distance();
+            let stats = &mut self.stats[idx];
+            stats.calls += 1;
+            stats.improvement += self.objective.cost(before - after).max(0.);
+            stats.time += op_start.elapsed();
+
+            if self.objective.is_better(after, best.cost()) {
+                best = tour.tour_order();
+                self.offer(&best);
+            }
+        }
+
+        tour.apply(&best)?;
+        Ok(best)
+    }
+
+    /// Offers a tour to the attached tracker and incumbent.
+    fn offer(&self, order: &TourOrder) {
+        if let Some(bounds) = &self.bounds {
+            bounds.offer(order);
+        }
+        if let Some(incumbent) = &self.incumbent {
+            incumbent.offer(order);
+        }
+    }
+
+    /// Returns `true` if the attached tracker has reached its target gap.
+    fn is_target_reached(&self) -> bool {
+        matches!(&self.bounds, Some(bounds) if bounds.is_target_reached())
+    }
+
+    fn select(&mut self) -> usize {
+        if let Some(idx) = self.stats.iter().position(|stats| stats.calls == 0) {
+            return idx;
+        }
+
+        let n = self.operators.len() as Scalar;
+        let rates: Vec<Scalar> = self.stats.iter().map(|stats| stats.rate()).collect();
+        let total: Scalar = rates.iter().sum();
+
+        let mut target = self.rng.gen::<Scalar>();
+        for (idx, rate) in rates.iter().enumerate() {
+            let share = if total > 0. {
+                MIN_SHARE + (1. - MIN_SHARE * n) * rate / total
+            } else {
+                1. / n
+            };
+
+            if target < share {
+                return idx;
+            }
+            target -= share;
+        }
+
+        rates.len() - 1
+    }
+
+    fn execute<T>(
+        &mut self,
+        op: Operator,
+        tour: &mut T,
+        best: &TourOrder,
+    ) -> Result<(), UpdateTourError>
+    where
+        T: Tour,
+    {
+        match op {
+            Operator::TwoOpt => {
+                descend_rated(tour, self.scan, self.objective, two_opt_moves);
+            }
+            Operator::OrOpt => {
+                descend_rated(tour, self.scan, self.objective, or_opt_moves);
+            }
+            Operator::LkhTwoOpt => {
+                improve_lkh(tour, KOpt::Opt2, self.objective)?;
+            }
+            Operator::Kick => {
+                if let Some(kick) = random_double_bridge(tour, &mut self.rng) {
+                    kick.apply(tour);
+                    descend_rated(tour, self.scan, self.objective, two_opt_moves);
+
+                    if !self.objective.is_better(tour.total_distance(), best.cost()) {
+                        tour.apply(best)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(super) fn two_opt_moves<T>(tour: &T, node: &TourNode, moves: &mut Vec<TwoOpt>)
+where
+    T: Tour,
+{
+    moves.extend(enumerate_2opt(tour, node, node.candidates()));
+}
+
+pub(super) fn or_opt_moves<T>(tour: &T, node: &TourNode, moves: &mut Vec<OrOpt>)
+where
+    T: Tour,
+{
+    let mut last = *node;
+
+    for _ in 0..OR_OPT_LEN {
+        for after in node.candidates() {
+            for reversed in [false, true] {
+                if let Some(mv) = OrOpt::new(tour, node, &last, after, reversed) {
+                    moves.push(mv);
+                }
+            }
+        }
+
+        last = match tour.successor(&last) {
+            Some(next) if next != *node => next,
+            _ => break,
+        };
+    }
+}
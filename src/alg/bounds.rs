@@ -0,0 +1,125 @@
+//! Lower and upper bounds of the optimal tour length that are tracked while solving.
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    stats,
+    tour::{Tour, TourOrder},
+    Scalar,
+};
+
+use super::incumbent::Incumbent;
+
+/// Lower bound and incumbent tour, which bound the optimal tour length from both sides.
+///
+/// Drivers such as [`Lns`] and [`Adaptive`] offer their best tours to an attached tracker and stop
+/// once the gap between the bounds reaches the target gap of the tracker. The tracker can be
+/// shared between threads, and reading the gap is lock-free at any moment.
+///
+/// [`Lns`]: super::lns::Lns
+/// [`Adaptive`]: super::adaptive::Adaptive
+#[derive(Debug)]
+pub struct BoundsTracker {
+    lower: AtomicU64,
+    incumbent: Incumbent,
+    target_gap: Option<Scalar>,
+}
+
+impl BoundsTracker {
+    /// Creates a tracker with a lower bound of zero and no incumbent tour.
+    pub fn new() -> Self {
+        Self {
+            lower: AtomicU64::new(0_f64.to_bits()),
+            incumbent: Incumbent::new(),
+            target_gap: None,
+        }
+    }
+
+    /// Creates a tracker whose lower bound is the Held-Karp bound of the nodes of a tour, see
+    /// [`stats::held_karp_bound`], and whose incumbent is the tour.
+    pub fn for_tour<T>(tour: &T) -> Self
+    where
+        T: Tour,
+    {
+        let tracker = Self::new();
+        tracker.raise_lower(stats::held_karp_bound(tour));
+        tracker.offer(&tour.tour_order());
+        tracker
+    }
+
+    /// Sets the relative gap at which drivers stop, e.g. `0.01` to stop at 1% above the lower
+    /// bound.
+    pub fn with_target_gap(mut self, gap: Scalar) -> Self {
+        self.target_gap = Some(gap);
+        self
+    }
+
+    /// Returns the target gap.
+    pub fn target_gap(&self) -> Option<Scalar> {
+        self.target_gap
+    }
+
+    /// Returns the lower bound.
+    #[inline]
+    pub fn lower(&self) -> Scalar {
+        Scalar::from_bits(self.lower.load(Ordering::Acquire))
+    }
+
+    /// Returns the length of the incumbent tour, or infinity if no tour was offered yet.
+    #[inline]
+    pub fn upper(&self) -> Scalar {
+        self.incumbent.cost()
+    }
+
+    /// Returns the incumbent, which holds the best tour offered so far.
+    pub fn incumbent(&self) -> &Incumbent {
+        &self.incumbent
+    }
+
+    /// Raises the lower bound and returns `true` if the given bound is higher than the current one.
+    pub fn raise_lower(&self, bound: Scalar) -> bool {
+        let mut current = self.lower.load(Ordering::Acquire);
+        while bound > Scalar::from_bits(current) {
+            match self.lower.compare_exchange_weak(
+                current,
+                bound.to_bits(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => current = actual,
+            }
+        }
+        false
+    }
+
+    /// Offers a tour and returns `true` if it replaced the incumbent, see [`Incumbent::offer`].
+    pub fn offer(&self, order: &TourOrder) -> bool {
+        self.incumbent.offer(order)
+    }
+
+    /// Returns the gap between the bounds relative to the lower bound.
+    ///
+    /// The gap is zero if the incumbent is provably optimal, and infinite if there is no incumbent
+    /// or the lower bound is not positive.
+    pub fn gap(&self) -> Scalar {
+        let (lower, upper) = (self.lower(), self.upper());
+        if upper <= lower {
+            0.
+        } else if lower > 0. {
+            (upper - lower) / lower
+        } else {
+            Scalar::INFINITY
+        }
+    }
+
+    /// Returns `true` if the gap does not exceed the target gap.
+    pub fn is_target_reached(&self) -> bool {
+        matches!(self.target_gap, Some(target) if self.gap() <= target)
+    }
+}
+
+impl Default for BoundsTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
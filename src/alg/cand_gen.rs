@@ -1,16 +1,55 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    convert::TryFrom,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
 use crate::{
-    tour::{Tour, TourNode},
-    Scalar,
+    data::{DataStore, GetIndex},
+    spatial::KdTree,
+    tour::{Edge, Tour, TourNode},
+    CancellationToken, CyklError, Scalar,
+};
+
+use super::{
+    hnsw::{Hnsw, HnswParams},
+    Objective,
 };
 
 /// Generates candidates for each node by using the k-nearest-neighbour method.
 ///
-/// The operation will take O(k*N^2) time to complete.
-pub fn cand_gen_nn<T>(tour: &mut T, k: usize)
+/// Neighbours are ranked according to the objective, i.e. the farthest nodes are chosen when
+/// maximising. The operation will take O(k*N^2) time to complete.
+pub fn cand_gen_nn<T>(tour: &mut T, k: usize, objective: Objective)
+where
+    T: Tour,
+{
+    // A fresh token is never cancelled.
+    let _ = cand_gen_nn_with(tour, k, objective, &CancellationToken::new());
+}
+
+/// Generates candidates like [`cand_gen_nn`], and stops with [`CyklError::Cancelled`] once the
+/// token is cancelled.
+///
+/// The token is checked before the candidates of each node are generated. Nodes that were not
+/// reached keep their previous candidates.
+pub fn cand_gen_nn_with<T>(
+    tour: &mut T,
+    k: usize,
+    objective: Objective,
+    token: &CancellationToken,
+) -> Result<(), CyklError>
 where
     T: Tour,
 {
     for mut base in tour.itr() {
+        if token.is_cancelled() {
+            return Err(CyklError::Cancelled);
+        }
+
         // Vec of candidates.
         let mut vec_c: Vec<TourNode> = vec![TourNode::default(); k];
         // Vec of distance to nearest candidates.
@@ -27,7 +66,7 @@ where
             }
             let mut c_idx = count - 1;
 
-            let d = tour.distance(&base, &targ);
+            let d = objective.cost(tour.distance(&base, &targ));
 
             while c_idx > 0 && d < vec_d[c_idx - 1] {
                 vec_c[c_idx] = vec_c[c_idx - 1];
@@ -44,4 +83,556 @@ where
         debug_assert_eq!(k, vec_c.len(), "{:?}", &base);
         base.set_candidates(vec_c);
     }
+
+    Ok(())
+}
+
+/// Generates candidates for each node from its nearest neighbours in each of the four quadrants
+/// around it.
+///
+/// Up to `k / 4` neighbours are taken from each quadrant, and the remaining places are filled with
+/// the nearest of the other nodes. Unlike [`cand_gen_nn`], the candidates of a node at the border
+/// of a cluster thus also lead into the neighbouring clusters. Quadrants are determined by the
+/// first two coordinates of the nodes, so nodes without coordinates keep their candidates.
+///
+/// The neighbours in each quadrant are found with a [`KdTree`] by their Euclidean distance, and
+/// the candidates are then ordered by their distance in the tour. The operation will take
+/// O(k*N log N) time to complete for evenly spread nodes.
+pub fn cand_gen_quadrant<T, M>(tour: &mut T, store: &DataStore<M>, k: usize)
+where
+    T: Tour,
+{
+    let per_quadrant = k / 4;
+    let tree = KdTree::new(store);
+    let quadrant = |pos: (Scalar, Scalar, Scalar), other: usize| {
+        tree.position(other)
+            .map(|o| (o.0 >= pos.0) as usize + 2 * (o.1 >= pos.1) as usize)
+    };
+
+    for mut base in tour.itr() {
+        let index = base.index().get();
+        let pos = match (store.coord(index), tree.position(index)) {
+            (Some(coord), Some(pos)) if coord.len() >= 2 => pos,
+            _ => continue,
+        };
+        let in_tour = |other: usize| other != index && tour.get(other).is_some();
+
+        let mut chosen: Vec<usize> = Vec::with_capacity(k);
+        for q in 0..4 {
+            chosen.extend(tree.k_nearest_where(pos, per_quadrant, |other| {
+                in_tour(other) && quadrant(pos, other) == Some(q)
+            }));
+        }
+        let n_rest = k.saturating_sub(chosen.len());
+        let rest = tree.k_nearest_where(pos, n_rest, |other| {
+            in_tour(other) && !chosen.contains(&other)
+        });
+        chosen.extend(rest);
+
+        let mut chosen: Vec<(Scalar, TourNode)> = chosen
+            .into_iter()
+            .filter_map(|other| tour.get(other))
+            .map(|targ| (tour.distance(&base, &targ), targ))
+            .collect();
+        chosen.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        base.set_candidates(chosen.into_iter().map(|(_, targ)| targ).collect());
+    }
+}
+
+/// Magic bytes at the start of a file with candidates.
+const MAGIC: &[u8; 8] = b"CYKLCAND";
+/// Version of the file format for candidates.
+const VERSION: u32 = 1;
+
+/// Candidates of all nodes of an instance, which can be stored on disk to avoid generating them
+/// again for later runs on the same instance.
+///
+/// A set remembers a hash of the instance it was generated for. Loading a set for a different
+/// instance fails. Two sets are equal if they were generated for the same instance and have the
+/// same candidates.
+#[derive(Clone, Debug)]
+pub struct CandidateSet {
+    fingerprint: u64,
+    candidates: Vec<Vec<usize>>,
+    // Upper bound of the distance between a node of the tree and its k-th candidate, together
+    // with the k it was computed for, which bounds the range queries of the updates.
+    reach: Option<(usize, Scalar)>,
+}
+
+impl PartialEq for CandidateSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.fingerprint == other.fingerprint && self.candidates == other.candidates
+    }
+}
+
+impl Eq for CandidateSet {}
+
+impl CandidateSet {
+    fn new(fingerprint: u64, candidates: Vec<Vec<usize>>) -> Self {
+        Self {
+            fingerprint,
+            candidates,
+            reach: None,
+        }
+    }
+
+    /// Collects the current candidates of all nodes in a tour over the nodes of the given store.
+    pub fn from_tour<T, M>(tour: &T, store: &DataStore<M>) -> Self
+    where
+        T: Tour,
+    {
+        Self::new(
+            store.fingerprint(),
+            tour.itr()
+                .map(|node| node.candidates().iter().map(|c| c.index().get()).collect())
+                .collect(),
+        )
+    }
+
+    /// Sets the candidates of all nodes in a tour.
+    ///
+    /// Returns an error if the number of nodes differs from the size of the set or if a candidate
+    /// does not refer to any node.
+    pub fn apply<T>(&self, tour: &mut T) -> Result<(), CyklError>
+    where
+        T: Tour,
+    {
+        if tour.len() != self.candidates.len() {
+            return Err(CyklError::LenMismatched {
+                expected: self.candidates.len(),
+                received: tour.len(),
+            });
+        }
+
+        for (index, cands) in self.candidates.iter().enumerate() {
+            let nodes = cands
+                .iter()
+                .map(|c| {
+                    tour.get(*c).ok_or(CyklError::IndexOutOfBounds {
+                        index: *c,
+                        len: tour.len(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if let Some(mut node) = tour.get(index) {
+                node.set_candidates(nodes);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generates the `k` nearest candidates of each node of a tree, ranked by the Euclidean
+    /// distance between their coordinates, which agrees with [`cand_gen_nn`] for Euclidean
+    /// metrics. Nodes outside of the tree have no candidates.
+    pub fn from_kd_tree<M>(store: &DataStore<M>, tree: &KdTree, k: usize) -> Self {
+        Self::new(
+            store.fingerprint(),
+            (0..store.len())
+                .map(|index| nearest_of(tree, index, k))
+                .collect(),
+        )
+    }
+
+    /// Inserts a node that was added to the store into the tree, and updates the set of `k`
+    /// nearest candidates accordingly instead of generating it again. A node that is already in
+    /// the tree is moved, as if it were removed with [`CandidateSet::remove_node`] first.
+    ///
+    /// Besides the list of the new node, only the lists of nodes to which the new node is nearer
+    /// than their own `k`-th candidate change. These nodes lie within a range query around the new
+    /// node, whose radius is a bound of the distance between any node and its `k`-th candidate.
+    /// The bound is kept up to date by the updates, and is only computed over all lists when `k`
+    /// changes. Lists are ranked like [`CandidateSet::from_kd_tree`].
+    pub fn insert_node<M>(
+        &mut self,
+        store: &DataStore<M>,
+        tree: &mut KdTree,
+        index: usize,
+        k: usize,
+    ) -> Result<(), CyklError> {
+        let len = store.len();
+        if index >= len {
+            return Err(CyklError::IndexOutOfBounds { index, len });
+        }
+        let point = store.coords(index).ok_or(CyklError::MissingCoordinates)?;
+
+        // Lists that rank the node by its former position would otherwise keep it.
+        self.remove_node(tree, index, k);
+
+        let radius = self.reach(tree, k);
+        tree.insert(index, point);
+        if self.candidates.len() < len {
+            self.candidates.resize(len, Vec::new());
+        }
+
+        for other in tree.within(point, radius) {
+            if other == index {
+                continue;
+            }
+
+            let rank = |c: usize| (dist2(tree, other, c), c);
+            let cands = &mut self.candidates[other];
+            let item = rank(index);
+            if let Some(&last) = cands.get(k.max(1) - 1) {
+                if item >= rank(last) {
+                    continue;
+                }
+            }
+
+            let pos = cands
+                .iter()
+                .position(|c| item < rank(*c))
+                .unwrap_or(cands.len());
+            if pos < k {
+                cands.insert(pos, index);
+                cands.truncate(k);
+            }
+        }
+
+        self.candidates[index] = nearest_of(tree, index, k);
+        self.widen_reach(tree, index, k);
+        self.fingerprint = store.fingerprint();
+        Ok(())
+    }
+
+    /// Removes a node from the tree and from the set of `k` nearest candidates, and returns
+    /// `false` if the node was not in the tree.
+    ///
+    /// Only the lists that contain the removed node are generated again, which are found with a
+    /// range query like in [`CandidateSet::insert_node`]. The node keeps its index in the store,
+    /// but has no candidates.
+    pub fn remove_node(&mut self, tree: &mut KdTree, index: usize, k: usize) -> bool {
+        let point = match tree.position(index) {
+            Some(point) => point,
+            None => return false,
+        };
+
+        let radius = self.reach(tree, k);
+        tree.remove(index);
+        for other in tree.within(point, radius) {
+            if self.candidates[other].contains(&index) {
+                self.candidates[other] = nearest_of(tree, other, k);
+                self.widen_reach(tree, other, k);
+            }
+        }
+
+        if let Some(cands) = self.candidates.get_mut(index) {
+            cands.clear();
+        }
+        true
+    }
+
+    /// Returns a bound of the distance between a node of the tree and its `k`-th candidate, which
+    /// is infinite if a node has fewer candidates.
+    ///
+    /// The bound is computed over all lists if it is unknown for `k` or infinite, and is only
+    /// widened by later updates. Removals may thus leave it larger than necessary, which widens
+    /// the range queries but does not change their outcome.
+    fn reach(&mut self, tree: &KdTree, k: usize) -> Scalar {
+        match self.reach {
+            Some((known, reach)) if known == k && reach.is_finite() => reach,
+            _ => {
+                let reach = (0..self.candidates.len())
+                    .filter(|index| tree.contains(*index))
+                    .map(|index| self.node_reach(tree, index, k))
+                    .fold(0., Scalar::max);
+                self.reach = Some((k, reach));
+                reach
+            }
+        }
+    }
+
+    /// Widens the bound of [`CandidateSet::reach`] after the list of a node has changed.
+    fn widen_reach(&mut self, tree: &KdTree, index: usize, k: usize) {
+        let node_reach = self.node_reach(tree, index, k);
+        if let Some((known, reach)) = &mut self.reach {
+            if *known == k {
+                *reach = reach.max(node_reach);
+            }
+        }
+    }
+
+    /// Returns the distance between a node and its `k`-th candidate, or infinity if the node has
+    /// fewer candidates.
+    fn node_reach(&self, tree: &KdTree, index: usize, k: usize) -> Scalar {
+        let cands = &self.candidates[index];
+        match cands.get(k.max(1) - 1) {
+            Some(c) if cands.len() >= k => dist2(tree, index, *c).sqrt(),
+            _ => Scalar::INFINITY,
+        }
+    }
+
+    /// Makes the candidate lists symmetric, so that `b` is a candidate of `a` if and only if `a`
+    /// is a candidate of `b`, while no node keeps more than `max_degree` candidates.
+    ///
+    /// Moves along an edge are only found from the nodes that list it, thus asymmetric lists hide
+    /// improving moves from the search. Edges are admitted in the order of their best rank in the
+    /// lists of their two endpoints, where ties are broken by shorter costs. Since the lists are
+    /// ranked by their generator, e.g. by alpha-nearness, better edges take precedence. An edge is
+    /// dropped if either endpoint already has `max_degree` candidates.
+    pub fn symmetrize<M>(&mut self, store: &DataStore<M>, max_degree: usize) {
+        let mut edges: HashMap<Edge, usize> = HashMap::new();
+        for (a, cands) in self.candidates.iter().enumerate() {
+            for (rank, &b) in cands.iter().enumerate() {
+                if a != b {
+                    let rank_ab = edges.entry(Edge::new(a, b)).or_insert(rank);
+                    *rank_ab = (*rank_ab).min(rank);
+                }
+            }
+        }
+
+        let mut edges: Vec<_> = edges
+            .into_iter()
+            .map(|(edge, rank)| (rank, edge.length(store), edge.low(), edge.high()))
+            .collect();
+        edges.sort_by(|x, y| {
+            x.0.cmp(&y.0)
+                .then(x.1.partial_cmp(&y.1).unwrap_or(Ordering::Equal))
+                .then((x.2, x.3).cmp(&(y.2, y.3)))
+        });
+
+        let mut candidates = vec![Vec::new(); self.candidates.len()];
+        for (_, _, a, b) in edges {
+            if candidates[a].len() < max_degree && candidates[b].len() < max_degree {
+                candidates[a].push(b);
+                candidates[b].push(a);
+            }
+        }
+
+        self.candidates = candidates;
+        self.reach = None;
+    }
+
+    /// Returns the fingerprint of the instance the set was generated for.
+    #[inline]
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Returns the candidates of the node at the given index.
+    pub fn candidates(&self, index: usize) -> Option<&[usize]> {
+        self.candidates.get(index).map(|v| v.as_slice())
+    }
+
+    /// Returns the number of bytes allocated by the set.
+    pub fn memory_footprint(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.candidates.capacity() * std::mem::size_of::<Vec<usize>>()
+            + self
+                .candidates
+                .iter()
+                .map(|cands| cands.capacity() * std::mem::size_of::<usize>())
+                .sum::<usize>()
+    }
+
+    /// Returns the number of nodes in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Returns `true` if the set contains no nodes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Writes the set into a file.
+    ///
+    /// The binary format consists of the magic bytes `CYKLCAND`, the format version, the hash of
+    /// the instance and the number of nodes, followed by the number of candidates and the
+    /// candidates of each node. All integers are little-endian, node indices take four bytes.
+    ///
+    /// Returns [`CyklError::IndexOutOfBounds`] if a node index or a number of candidates does not
+    /// fit into four bytes, in which case the file is left incomplete.
+    pub fn save<P>(&self, path: P) -> Result<(), CyklError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&self.fingerprint.to_le_bytes())?;
+        w.write_all(&(self.candidates.len() as u64).to_le_bytes())?;
+
+        for cands in &self.candidates {
+            w.write_all(&to_u32(cands.len())?.to_le_bytes())?;
+            for c in cands {
+                w.write_all(&to_u32(*c)?.to_le_bytes())?;
+            }
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Reads a set from a file written by [`CandidateSet::save`].
+    ///
+    /// Returns an error if the file is malformed or was written for an instance other than the
+    /// given store. A node with more candidates than the store has nodes is reported as
+    /// [`CyklError::Parse`] before any memory is reserved for its candidates.
+    pub fn load<P, M>(path: P, store: &DataStore<M>) -> Result<Self, CyklError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut r = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(CyklError::Parse("not a candidate file".to_string()));
+        }
+
+        let version = read_u32(&mut r)?;
+        if version != VERSION {
+            return Err(CyklError::Parse(format!(
+                "unsupported candidate file version {}",
+                version
+            )));
+        }
+
+        let fingerprint = read_u64(&mut r)?;
+        if fingerprint != store.fingerprint() {
+            return Err(CyklError::InstanceMismatch {
+                expected: store.fingerprint(),
+                found: fingerprint,
+            });
+        }
+
+        let len = read_u64(&mut r)? as usize;
+        if len != store.len() {
+            return Err(CyklError::LenMismatched {
+                expected: store.len(),
+                received: len,
+            });
+        }
+
+        let mut candidates = Vec::with_capacity(len);
+        for _ in 0..len {
+            let n_cands = read_u32(&mut r)? as usize;
+            if n_cands > len {
+                return Err(CyklError::Parse(format!(
+                    "{} candidates of node {} exceed the {} nodes",
+                    n_cands,
+                    candidates.len(),
+                    len
+                )));
+            }
+            let mut cands = Vec::with_capacity(n_cands.min(len));
+            for _ in 0..n_cands {
+                let c = read_u32(&mut r)? as usize;
+                if c >= len {
+                    return Err(CyklError::IndexOutOfBounds { index: c, len });
+                }
+                cands.push(c);
+            }
+            candidates.push(cands);
+        }
+
+        Ok(Self::new(fingerprint, candidates))
+    }
+}
+
+/// Index with which a [`CandidateSetBuilder`] finds the nearest neighbours of the nodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NeighborIndex {
+    /// Finds the exact neighbours by the Euclidean distance between coordinates with a
+    /// [`KdTree`], see [`CandidateSet::from_kd_tree`].
+    #[default]
+    KdTree,
+    /// Finds approximate neighbours by cost with a hierarchical navigable small world graph.
+    ///
+    /// The graph only evaluates costs, thus it also serves explicit metrics and metrics on many
+    /// dimensions, and takes about O(N log N) cost evaluations on instances too large for
+    /// [`cand_gen_nn`]. A few of the nearest neighbours may be missed.
+    Hnsw(HnswParams),
+}
+
+/// Collects the options for generating a [`CandidateSet`] and generates it in one step.
+#[derive(Clone, Debug, Default)]
+pub struct CandidateSetBuilder {
+    k: Option<usize>,
+    index: Option<NeighborIndex>,
+}
+
+impl CandidateSetBuilder {
+    pub fn new() -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    /// Sets the number of candidates per node. Defaults to 10.
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = Some(k);
+        self
+    }
+
+    /// Sets the index with which the neighbours are found. Defaults to [`NeighborIndex::KdTree`].
+    pub fn neighbor_index(mut self, index: NeighborIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Generates the candidates of the nodes of a store.
+    ///
+    /// With a [`NeighborIndex::KdTree`], nodes without coordinates have no candidates.
+    pub fn build<M>(self, store: &DataStore<M>) -> CandidateSet {
+        let k = self.k.unwrap_or(10);
+        match self.index.unwrap_or_default() {
+            NeighborIndex::KdTree => CandidateSet::from_kd_tree(store, &KdTree::new(store), k),
+            NeighborIndex::Hnsw(params) => {
+                let graph = Hnsw::new(store, params);
+                CandidateSet::new(
+                    store.fingerprint(),
+                    (0..store.len())
+                        .map(|index| graph.nearest(store, index, k))
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+/// Returns the `k` nodes of a tree nearest to the node at the given index, excluding itself.
+fn nearest_of(tree: &KdTree, index: usize, k: usize) -> Vec<usize> {
+    match tree.position(index) {
+        Some(point) => tree
+            .k_nearest(point, k + 1)
+            .into_iter()
+            .filter(|c| *c != index)
+            .take(k)
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Returns the squared Euclidean distance between two nodes of a tree.
+fn dist2(tree: &KdTree, a: usize, b: usize) -> Scalar {
+    match (tree.position(a), tree.position(b)) {
+        (Some(a), Some(b)) => (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2),
+        _ => Scalar::INFINITY,
+    }
+}
+
+/// Converts a node index or a number of candidates into the four bytes of the file format.
+fn to_u32(value: usize) -> Result<u32, CyklError> {
+    u32::try_from(value).map_err(|_| CyklError::IndexOutOfBounds {
+        index: value,
+        len: u32::MAX as usize,
+    })
+}
+
+fn read_u32<R: Read>(r: &mut R) -> Result<u32, CyklError> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> Result<u64, CyklError> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
 }
@@ -0,0 +1,281 @@
+//! Approximate nearest-neighbour index for candidate generation on large or high-dimensional
+//! instances, selected with [`NeighborIndex::Hnsw`](super::NeighborIndex::Hnsw).
+//!
+//! A hierarchical navigable small world graph links every node to its nearest nodes on a random
+//! number of layers, which thin out towards the top. Searches descend from the sparse upper
+//! layers to the bottom layer, and only evaluate costs between nodes, so that the index works for
+//! any metric. The [`HnswParams`] trade quality against time: `m` sets the number of links per
+//! node, `ef_construction` and `ef_search` the number of nodes considered while linking and
+//! searching, and `seed` the draw of the layers, which makes the graph reproducible.
+//!
+//! See Y. Malkov and D. Yashunin (2018), "Efficient and robust approximate nearest neighbor search
+//! using hierarchical navigable small world graphs".
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashSet},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{oracle::DistanceOracle, Scalar};
+
+/// Parameters of the hierarchical navigable small world graph behind
+/// [`NeighborIndex::Hnsw`](super::NeighborIndex::Hnsw).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HnswParams {
+    m: usize,
+    ef_construction: usize,
+    ef_search: usize,
+    seed: u64,
+}
+
+impl Default for HnswParams {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+            seed: 0,
+        }
+    }
+}
+
+impl HnswParams {
+    /// Creates the default parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of links per node in the upper layers of the graph, twice as many are kept
+    /// in the bottom layer. Defaults to 16.
+    pub fn m(mut self, m: usize) -> Self {
+        self.m = m.max(2);
+        self
+    }
+
+    /// Sets the number of nodes considered while linking a new node. Larger values give a graph
+    /// of better quality at the expense of construction time. Defaults to 100.
+    pub fn ef_construction(mut self, ef: usize) -> Self {
+        self.ef_construction = ef.max(1);
+        self
+    }
+
+    /// Sets the number of nodes considered while searching for the neighbours of a node, which is
+    /// raised to the number of neighbours if lower. Defaults to 64.
+    pub fn ef_search(mut self, ef: usize) -> Self {
+        self.ef_search = ef.max(1);
+        self
+    }
+
+    /// Sets the seed with which the layers of the nodes are drawn. Defaults to 0.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// Node at some distance from a query, ordered by distance and then by index.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Near {
+    dist: Scalar,
+    index: usize,
+}
+
+impl Eq for Near {}
+
+impl PartialOrd for Near {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Near {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist
+            .total_cmp(&other.dist)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+/// Hierarchical navigable small world graph over the nodes of an oracle, in which the distance
+/// between two nodes is their cost.
+///
+/// Only costs are evaluated, thus the graph works for any metric, including explicit ones and
+/// metrics on many dimensions.
+pub(crate) struct Hnsw {
+    /// Links of each node in each of its layers, starting with the bottom layer.
+    links: Vec<Vec<Vec<usize>>>,
+    entry: Option<usize>,
+    params: HnswParams,
+}
+
+impl Hnsw {
+    /// Builds the graph by inserting the nodes of the oracle in the order of their indices.
+    pub(crate) fn new<O: DistanceOracle>(oracle: &O, params: HnswParams) -> Self {
+        let mut graph = Self {
+            links: Vec::with_capacity(oracle.len()),
+            entry: None,
+            params,
+        };
+        let mut rng = StdRng::seed_from_u64(params.seed);
+        let level_mult = 1. / (params.m as Scalar).ln();
+
+        for index in 0..oracle.len() {
+            let u: Scalar = rng.gen_range(Scalar::EPSILON..1.);
+            let level = (-u.ln() * level_mult).floor() as usize;
+            graph.insert(oracle, index, level);
+        }
+
+        graph
+    }
+
+    /// Returns up to `k` nodes nearest to the node at the given index, excluding itself, ordered
+    /// by their cost from that node.
+    pub(crate) fn nearest<O: DistanceOracle>(
+        &self,
+        oracle: &O,
+        index: usize,
+        k: usize,
+    ) -> Vec<usize> {
+        let dist = |other: usize| oracle.cost(index, other);
+        let ef = self.params.ef_search.max(k + 1);
+
+        self.search(dist, ef)
+            .into_iter()
+            .map(|near| near.index)
+            .filter(|other| *other != index)
+            .take(k)
+            .collect()
+    }
+
+    fn insert<O: DistanceOracle>(&mut self, oracle: &O, index: usize, level: usize) {
+        self.links.push(vec![Vec::new(); level + 1]);
+        let entry = match self.entry {
+            Some(entry) => entry,
+            None => {
+                self.entry = Some(index);
+                return;
+            }
+        };
+
+        let dist = |other: usize| oracle.cost(index, other);
+        let top = self.links[entry].len() - 1;
+        let mut eps = vec![Near {
+            dist: dist(entry),
+            index: entry,
+        }];
+
+        for layer in (level + 1..=top).rev() {
+            eps = self.search_layer(&dist, &eps, 1, layer);
+        }
+
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&dist, &eps, self.params.ef_construction, layer);
+            let max_links = self.max_links(layer);
+            for near in found.iter().take(max_links) {
+                self.links[index][layer].push(near.index);
+                self.links[near.index][layer].push(index);
+                self.shrink(oracle, near.index, layer);
+            }
+            eps = found;
+        }
+
+        if level > top {
+            self.entry = Some(index);
+        }
+    }
+
+    /// Keeps the nearest links of a node in a layer if it has more than allowed.
+    fn shrink<O: DistanceOracle>(&mut self, oracle: &O, index: usize, layer: usize) {
+        let max_links = self.max_links(layer);
+        let links = &mut self.links[index][layer];
+        if links.len() <= max_links {
+            return;
+        }
+
+        let mut nears: Vec<Near> = links
+            .iter()
+            .map(|other| Near {
+                dist: oracle.cost(index, *other),
+                index: *other,
+            })
+            .collect();
+        nears.sort();
+        *links = nears
+            .into_iter()
+            .take(max_links)
+            .map(|near| near.index)
+            .collect();
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            2 * self.params.m
+        } else {
+            self.params.m
+        }
+    }
+
+    /// Descends from the entry point to the bottom layer, and returns the `ef` nodes nearest to
+    /// the query found there, ordered by distance.
+    fn search<F>(&self, dist: F, ef: usize) -> Vec<Near>
+    where
+        F: Fn(usize) -> Scalar,
+    {
+        let entry = match self.entry {
+            Some(entry) => entry,
+            None => return Vec::new(),
+        };
+
+        let mut eps = vec![Near {
+            dist: dist(entry),
+            index: entry,
+        }];
+        for layer in (1..self.links[entry].len()).rev() {
+            eps = self.search_layer(&dist, &eps, 1, layer);
+        }
+        self.search_layer(&dist, &eps, ef, 0)
+    }
+
+    /// Searches a layer greedily from the entry points, and returns the `ef` nodes nearest to the
+    /// query found, ordered by distance.
+    fn search_layer<F>(&self, dist: &F, eps: &[Near], ef: usize, layer: usize) -> Vec<Near>
+    where
+        F: Fn(usize) -> Scalar,
+    {
+        let mut visited: HashSet<usize> = eps.iter().map(|near| near.index).collect();
+        let mut candidates: BinaryHeap<Reverse<Near>> = eps.iter().copied().map(Reverse).collect();
+        let mut found: BinaryHeap<Near> = eps.iter().copied().collect();
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            match found.peek() {
+                Some(farthest) if current.dist > farthest.dist && found.len() >= ef => break,
+                _ => {}
+            }
+
+            for &other in &self.links[current.index][layer] {
+                if !visited.insert(other) {
+                    continue;
+                }
+
+                let near = Near {
+                    dist: dist(other),
+                    index: other,
+                };
+                let admit = match found.peek() {
+                    Some(farthest) => found.len() < ef || near < *farthest,
+                    None => true,
+                };
+                if admit {
+                    candidates.push(Reverse(near));
+                    found.push(near);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec()
+    }
+}
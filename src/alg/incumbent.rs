@@ -0,0 +1,343 @@
+//! Best tour shared between worker threads.
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use arc_swap::ArcSwapOption;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{tour::TourOrder, Scalar};
+
+use super::Objective;
+
+/// Function that is called with each tour that replaces an incumbent.
+type Observer = Box<dyn Fn(&TourOrder) + Send + Sync>;
+
+/// Best tour found so far by a group of workers.
+///
+/// Reading the cost of the incumbent is a single atomic load, so that workers can cheaply prune
+/// runs that cannot beat it. The tour itself is swapped atomically, thus readers never block
+/// writers and always observe a consistent tour.
+///
+/// Tours are compared by their length in the direction of an [`Objective`], which defaults to
+/// [`Objective::Minimize`].
+pub struct Incumbent {
+    cost: AtomicU64,
+    best: ArcSwapOption<TourOrder>,
+    objective: Objective,
+    observer: Option<Observer>,
+}
+
+impl Incumbent {
+    /// Creates an incumbent without any tour, whose cost is infinite.
+    pub fn new() -> Self {
+        Self::with_objective(Objective::Minimize)
+    }
+
+    /// Creates an incumbent without any tour that keeps the best tour with respect to the
+    /// objective. Its cost is infinite when minimising and negative infinite when maximising.
+    pub fn with_objective(objective: Objective) -> Self {
+        let worst = match objective {
+            Objective::Minimize => Scalar::INFINITY,
+            Objective::Maximize => Scalar::NEG_INFINITY,
+        };
+        Self {
+            cost: AtomicU64::new(worst.to_bits()),
+            best: ArcSwapOption::empty(),
+            objective,
+            observer: None,
+        }
+    }
+
+    /// Sets a function that is called with each tour that replaces the incumbent, e.g. to report
+    /// the progress of a solver while it runs.
+    ///
+    /// The function is called on the thread that offered the tour, after the tour has become the
+    /// incumbent. Tours offered concurrently by several threads may be reported out of order.
+    pub fn on_improvement<F>(mut self, observer: F) -> Self
+    where
+        F: Fn(&TourOrder) + Send + Sync + 'static,
+    {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Returns the objective by which tours are compared.
+    #[inline]
+    pub fn objective(&self) -> Objective {
+        self.objective
+    }
+
+    /// Returns the cost of the incumbent tour, or the cost of an empty incumbent if no tour was
+    /// offered yet, see [`Incumbent::with_objective`].
+    #[inline]
+    pub fn cost(&self) -> Scalar {
+        Scalar::from_bits(self.cost.load(Ordering::Acquire))
+    }
+
+    /// Returns the incumbent tour.
+    pub fn best(&self) -> Option<Arc<TourOrder>> {
+        self.best.load_full()
+    }
+
+    /// Offers a tour and returns `true` if it replaced the incumbent, i.e. if it is better with
+    /// respect to the objective.
+    pub fn offer(&self, order: &TourOrder) -> bool {
+        let objective = self.objective;
+        if !objective.is_better(order.cost(), self.cost()) {
+            return false;
+        }
+
+        let candidate = Arc::new(order.clone());
+        let mut replaced = false;
+        self.best.rcu(|current| match current {
+            Some(best) if !objective.is_better(order.cost(), best.cost()) => {
+                replaced = false;
+                Some(Arc::clone(best))
+            }
+            _ => {
+                replaced = true;
+                Some(Arc::clone(&candidate))
+            }
+        });
+
+        if replaced {
+            // The cost may lag behind the tour, but it is never better than the cost of the stored
+            // tour.
+            let mut current = self.cost.load(Ordering::Acquire);
+            while objective.is_better(order.cost(), Scalar::from_bits(current)) {
+                match self.cost.compare_exchange_weak(
+                    current,
+                    order.cost().to_bits(),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => break,
+                    Err(actual) => current = actual,
+                }
+            }
+
+            if let Some(observer) = &self.observer {
+                observer(order);
+            }
+        }
+
+        replaced
+    }
+}
+
+impl fmt::Debug for Incumbent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Incumbent")
+            .field("cost", &self.cost())
+            .field("best", &self.best())
+            .field("objective", &self.objective)
+            .field("observer", &self.observer.is_some())
+            .finish()
+    }
+}
+
+impl Default for Incumbent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Policy that derives the starting tours of restarts from the incumbent by perturbing it.
+///
+/// The strength of the perturbation cycles from `min_strength` to `max_strength` over consecutive
+/// restarts, so that the search alternates between intensifying around the incumbent and
+/// diversifying away from it. The perturbation of each restart only depends on the seed and the
+/// number of the restart, thus runs are reproducible regardless of the number of threads as long
+/// as the incumbent is the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RestartPolicy {
+    min_strength: usize,
+    max_strength: usize,
+    seed: u64,
+}
+
+impl RestartPolicy {
+    /// Creates a policy whose strength cycles from `min_strength` to `max_strength`, which is
+    /// raised to `min_strength` if it is smaller, with a seed for the random perturbations.
+    pub fn new(min_strength: usize, max_strength: usize, seed: u64) -> Self {
+        Self {
+            min_strength,
+            max_strength: max_strength.max(min_strength),
+            seed,
+        }
+    }
+
+    /// Returns the number of perturbing operations applied at the given restart, see
+    /// [`TourOrder::perturbed`].
+    pub fn strength(&self, restart: usize) -> usize {
+        self.min_strength + restart % (self.max_strength - self.min_strength + 1)
+    }
+
+    /// Returns the starting order of the given restart, or `None` if the incumbent has no tour
+    /// yet.
+    pub fn restart(&self, restart: usize, incumbent: &Incumbent) -> Option<TourOrder> {
+        Some(self.perturb(restart, incumbent.best()?.as_ref()))
+    }
+
+    /// Perturbs an order with the strength and the random operations of the given restart.
+    pub fn perturb(&self, restart: usize, order: &TourOrder) -> TourOrder {
+        let mut rng =
+            StdRng::seed_from_u64(self.seed ^ (restart as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        order.perturbed(&mut rng, self.strength(restart))
+    }
+}
+
+/// Distribution of independent starts over threads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Parallelism {
+    n_threads: usize,
+    deterministic: bool,
+}
+
+impl Parallelism {
+    /// Creates a non-deterministic distribution over `n_threads` threads.
+    pub fn new(n_threads: usize) -> Self {
+        Self {
+            n_threads,
+            deterministic: false,
+        }
+    }
+
+    /// Sets whether the result must not depend on the scheduling of the threads.
+    ///
+    /// In deterministic mode, starts run in rounds of one start per thread. All starts of a round
+    /// observe the incumbent as it was at the beginning of the round, and their tours are offered
+    /// in the order of the starts once the round is complete, so that ties are resolved in favour
+    /// of the lower start. Given the same seeds and number of threads, runs thus produce
+    /// bit-identical tours, at the expense of threads waiting for the slowest start of each round.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Returns the number of threads.
+    #[inline]
+    pub fn n_threads(&self) -> usize {
+        self.n_threads
+    }
+
+    /// Returns `true` if the result does not depend on the scheduling of the threads.
+    #[inline]
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+/// Runs `n_starts` independent starts on `n_threads` threads and returns the best tour found.
+///
+/// Each start receives its number and the shared incumbent, which it can use to abandon runs that
+/// cannot beat the best tour. Tours returned by the starts are offered to the incumbent. Since
+/// tours cannot be shared between threads, each start has to construct its own tour. Starts may
+/// derive their tours from the incumbent with a [`RestartPolicy`], see [`multi_start_restarts`].
+pub fn multi_start<F>(
+    n_starts: usize,
+    n_threads: usize,
+    incumbent: &Incumbent,
+    start: F,
+) -> Option<Arc<TourOrder>>
+where
+    F: Fn(usize, &Incumbent) -> Option<TourOrder> + Sync,
+{
+    multi_start_with(n_starts, Parallelism::new(n_threads), incumbent, start)
+}
+
+/// Runs `n_starts` starts like [`multi_start_with`], and passes each start the order it begins
+/// with, which `policy` derives from the incumbent, or `None` as long as the incumbent has no
+/// tour, in which case the start has to construct its tour from scratch.
+pub fn multi_start_restarts<F>(
+    n_starts: usize,
+    parallelism: Parallelism,
+    incumbent: &Incumbent,
+    policy: RestartPolicy,
+    start: F,
+) -> Option<Arc<TourOrder>>
+where
+    F: Fn(usize, Option<TourOrder>, &Incumbent) -> Option<TourOrder> + Sync,
+{
+    multi_start_with(n_starts, parallelism, incumbent, |idx, incumbent| {
+        let initial = policy.restart(idx, incumbent);
+        start(idx, initial, incumbent)
+    })
+}
+
+/// Runs `n_starts` independent starts like [`multi_start`], distributed over threads as given by
+/// `parallelism`.
+///
+/// In deterministic mode, starts receive a snapshot of the incumbent, thus tours must be returned
+/// rather than offered by the starts.
+pub fn multi_start_with<F>(
+    n_starts: usize,
+    parallelism: Parallelism,
+    incumbent: &Incumbent,
+    start: F,
+) -> Option<Arc<TourOrder>>
+where
+    F: Fn(usize, &Incumbent) -> Option<TourOrder> + Sync,
+{
+    let n_threads = parallelism.n_threads.clamp(1, n_starts.max(1));
+
+    if parallelism.deterministic {
+        for first in (0..n_starts).step_by(n_threads) {
+            let snapshot = Incumbent::new();
+            if let Some(best) = incumbent.best() {
+                snapshot.offer(&best);
+            }
+
+            let last = (first + n_threads).min(n_starts);
+            let results: Vec<_> = std::thread::scope(|scope| {
+                let handles: Vec<_> = (first..last)
+                    .map(|idx| {
+                        let (start, snapshot) = (&start, &snapshot);
+                        scope.spawn(move || start(idx, snapshot))
+                    })
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|err| std::panic::resume_unwind(err))
+                    })
+                    .collect()
+            });
+
+            for order in results.into_iter().flatten() {
+                incumbent.offer(&order);
+            }
+        }
+
+        return incumbent.best();
+    }
+
+    std::thread::scope(|scope| {
+        for thread in 0..n_threads {
+            let start = &start;
+            scope.spawn(move || {
+                for idx in (thread..n_starts).step_by(n_threads) {
+                    if let Some(order) = start(idx, incumbent) {
+                        incumbent.offer(&order);
+                    }
+                }
+            });
+        }
+    });
+
+    incumbent.best()
+}
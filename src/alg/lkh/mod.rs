@@ -1,8 +1,8 @@
 mod types;
-pub use types::KOpt;
+pub use types::{KOpt, SearchResult};
 
 mod solver;
-pub use solver::solve_lkh;
+pub use solver::{improve_lkh, solve_lkh};
 
 mod moves;
 pub use moves::*;
@@ -1,4 +1,10 @@
-use crate::tour::{NodeRel, Tour, TourNode};
+use rand::Rng;
+
+use crate::{
+    data::GetIndex,
+    tour::{NodeRel, Tour, TourNode},
+    Scalar,
+};
 
 /// Executes the 2-opt move.
 #[inline]
@@ -95,7 +101,7 @@ pub fn move_3_opt<T>(
 
             match tour.relation(f1, f2) {
                 NodeRel::Predecessor => tour.flip(f1, f2, f3, t3),
-                NodeRel::Successor => tour.flip(f2, f1, f3, t3),
+                NodeRel::Successor => tour.flip(f2, f1, t3, f3),
                 NodeRel::None => panic!("Broken tour"),
             }
 
@@ -248,7 +254,7 @@ pub fn move_4_opt<T>(
 
             match tour.relation(t1, t3) {
                 NodeRel::Predecessor => tour.flip(t1, t3, f4, t4),
-                NodeRel::Successor => tour.flip(f3, t1, t4, f4),
+                NodeRel::Successor => tour.flip(t3, t1, t4, f4),
                 NodeRel::None => panic!("Broken tour"),
             }
         }
@@ -425,3 +431,488 @@ pub fn move_4_opt<T>(
         }
     }
 }
+
+/// A 2-opt move that replaces the edges (f1, t1) and (f2, t2) by (f1, f2) and (t1, t2).
+///
+/// The move is only described, it is executed by passing its pairs to [`move_2_opt`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TwoOpt {
+    pub pair_1: (TourNode, TourNode),
+    pub pair_2: (TourNode, TourNode),
+    /// Decrease of the tour length if the move is executed.
+    pub gain: Scalar,
+}
+
+/// A 3-opt move that replaces the edges (f1, t1), (f2, t2) and (f3, t3) according to its case.
+///
+/// The move is only described, it is executed by passing its pairs and case to [`move_3_opt`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThreeOpt {
+    pub pair_1: (TourNode, TourNode),
+    pub pair_2: (TourNode, TourNode),
+    pub pair_3: (TourNode, TourNode),
+    pub case: Opt3Move,
+    /// Decrease of the tour length if the move is executed.
+    pub gain: Scalar,
+}
+
+/// Enumerates all 2-opt moves that connect a node with one of the given candidates.
+///
+/// For each candidate, two moves are yielded: one that removes the edges leaving the node and the
+/// candidate, and one that removes the edges entering them. Moves are not executed and may have a
+/// negative gain. Candidates that are adjacent to the node or not part of the tour are skipped.
+pub fn enumerate_2opt<'a, T>(
+    tour: &'a T,
+    node: &'a TourNode,
+    candidates: &'a [TourNode],
+) -> impl Iterator<Item = TwoOpt> + 'a
+where
+    T: Tour,
+{
+    candidates
+        .iter()
+        .flat_map(move |cand| {
+            let forward = (
+                Some(*node),
+                tour.successor(node),
+                Some(*cand),
+                tour.successor(cand),
+            );
+            let backward = (
+                tour.predecessor(node),
+                Some(*node),
+                tour.predecessor(cand),
+                Some(*cand),
+            );
+            std::iter::once(forward).chain(std::iter::once(backward))
+        })
+        .filter_map(move |nodes| match nodes {
+            (Some(f1), Some(t1), Some(f2), Some(t2)) if distinct(&[f1, t1, f2, t2]) => {
+                let [f1i, t1i, f2i, t2i] = [f1, t1, f2, t2].map(|n| n.index().get());
+                let mut d = [0.; 4];
+                tour.distances(&[(f1i, t1i), (f2i, t2i), (f1i, f2i), (t1i, t2i)], &mut d);
+                let gain = d[0] + d[1] - d[2] - d[3];
+                Some(TwoOpt {
+                    pair_1: (f1, t1),
+                    pair_2: (f2, t2),
+                    gain,
+                })
+            }
+            _ => None,
+        })
+}
+
+/// Draws a random 2-opt move that removes the edges leaving two distinct, non-adjacent nodes of
+/// the tour.
+///
+/// The move is not executed and may have a negative gain. Returns `None` if the tour has fewer
+/// than four included nodes.
+pub fn random_2opt<T, R>(tour: &T, rng: &mut R) -> Option<TwoOpt>
+where
+    T: Tour,
+    R: Rng,
+{
+    let len = tour.n_included();
+    if len < 4 {
+        return None;
+    }
+
+    let pos_1 = rng.gen_range(0..len);
+    let pos_2 = (pos_1 + rng.gen_range(2..(len - 1))) % len;
+
+    let f1 = tour.at_position(pos_1)?;
+    let f2 = tour.at_position(pos_2)?;
+    let (t1, t2) = (tour.successor(&f1)?, tour.successor(&f2)?);
+    let gain = tour.distance(&f1, &t1) + tour.distance(&f2, &t2)
+        - tour.distance(&f1, &f2)
+        - tour.distance(&t1, &t2);
+
+    Some(TwoOpt {
+        pair_1: (f1, t1),
+        pair_2: (f2, t2),
+        gain,
+    })
+}
+
+/// Draws a random double-bridge move whose removed edges leave four nodes that are pairwise at
+/// least two positions apart.
+///
+/// The move is not executed and may have a negative gain. Returns `None` if the tour has fewer
+/// than eight included nodes.
+pub fn random_double_bridge<T, R>(tour: &T, rng: &mut R) -> Option<DoubleBridge>
+where
+    T: Tour,
+    R: Rng,
+{
+    let len = tour.n_included();
+    if len < 8 {
+        return None;
+    }
+
+    // Four positions that are pairwise at least two apart, in the order of the traversal.
+    let mut positions = [0; 4];
+    loop {
+        for pos in positions.iter_mut() {
+            *pos = rng.gen_range(0..len);
+        }
+        positions.sort_unstable();
+
+        let gaps_ok = positions.windows(2).all(|w| w[1] - w[0] >= 2)
+            && positions[0] + len - positions[3] >= 2;
+        if gaps_ok {
+            break;
+        }
+    }
+
+    let mut nodes = [TourNode::default(); 4];
+    for (node, pos) in nodes.iter_mut().zip(positions.iter()) {
+        *node = tour.at_position(*pos)?;
+    }
+
+    DoubleBridge::new(tour, nodes)
+}
+
+/// Enumerates all pure 3-opt moves, i.e. [`Opt3Move::Move4`] to [`Opt3Move::Move7`], that start
+/// with the edge leaving a node and whose other removed edges leave two of the given candidates.
+///
+/// Moves are not executed and may have a negative gain. Combinations in which the removed edges
+/// share nodes are skipped.
+pub fn enumerate_3opt<'a, T>(
+    tour: &'a T,
+    node: &'a TourNode,
+    candidates: &'a [TourNode],
+) -> impl Iterator<Item = ThreeOpt> + 'a
+where
+    T: Tour,
+{
+    const CASES: [Opt3Move; 4] = [
+        Opt3Move::Move4,
+        Opt3Move::Move5,
+        Opt3Move::Move6,
+        Opt3Move::Move7,
+    ];
+
+    let t1 = tour.successor(node);
+
+    candidates
+        .iter()
+        .flat_map(move |f2| candidates.iter().map(move |f3| (*f2, *f3)))
+        .filter_map(move |(f2, f3)| {
+            let (f1, t1) = (*node, t1?);
+            let (t2, t3) = (tour.successor(&f2)?, tour.successor(&f3)?);
+
+            if distinct(&[f1, t1, f2, t2, f3, t3]) && tour.between(&t1, &f2, &f3) {
+                Some(((f1, t1), (f2, t2), (f3, t3)))
+            } else {
+                None
+            }
+        })
+        .flat_map(move |(pair_1, pair_2, pair_3)| {
+            let (f1, t1) = pair_1;
+            let (f2, t2) = pair_2;
+            let (f3, t3) = pair_3;
+            let removed =
+                tour.distance(&f1, &t1) + tour.distance(&f2, &t2) + tour.distance(&f3, &t3);
+
+            CASES.iter().map(move |case| {
+                let added = match case {
+                    Opt3Move::Move4 => [(f1, f3), (t2, t1), (f2, t3)],
+                    Opt3Move::Move5 => [(f1, f2), (t1, f3), (t2, t3)],
+                    Opt3Move::Move6 => [(f1, t2), (f3, f2), (t1, t3)],
+                    _ => [(f1, t2), (f3, t1), (f2, t3)],
+                };
+
+                ThreeOpt {
+                    pair_1,
+                    pair_2,
+                    pair_3,
+                    case: *case,
+                    gain: removed
+                        - added
+                            .iter()
+                            .map(|(a, b)| tour.distance(a, b))
+                            .sum::<Scalar>(),
+                }
+            })
+        })
+}
+
+/// Returns true if no node appears more than once.
+fn distinct(nodes: &[TourNode]) -> bool {
+    nodes
+        .iter()
+        .enumerate()
+        .all(|(ii, a)| nodes[(ii + 1)..].iter().all(|b| a != b))
+}
+
+/// Common interface of moves, so that solver drivers can be generic over the kinds of moves.
+pub trait Move {
+    /// Returns the decrease of the tour length if the move is applied.
+    fn gain(&self) -> Scalar;
+
+    /// Executes the move.
+    fn apply<T>(&self, tour: &mut T)
+    where
+        T: Tour;
+
+    /// Undoes the move, assuming that it was the last one applied to the tour.
+    ///
+    /// The resulting tour contains the same edges as before the move, but might be traversed in
+    /// the opposite direction.
+    fn revert<T>(&self, tour: &mut T)
+    where
+        T: Tour;
+}
+
+impl Move for TwoOpt {
+    fn gain(&self) -> Scalar {
+        self.gain
+    }
+
+    fn apply<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        let ((f1, t1), (f2, t2)) = (self.pair_1, self.pair_2);
+        move_2_opt(tour, &f1, &t1, &f2, &t2);
+    }
+
+    fn revert<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        let ((f1, t1), (f2, t2)) = (self.pair_1, self.pair_2);
+        exchange(tour, (f1, f2), (t1, t2));
+    }
+}
+
+impl Move for ThreeOpt {
+    fn gain(&self) -> Scalar {
+        self.gain
+    }
+
+    fn apply<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        let (p1, p2, p3) = (self.pair_1, self.pair_2, self.pair_3);
+        move_3_opt(
+            tour,
+            (&p1.0, &p1.1),
+            (&p2.0, &p2.1),
+            (&p3.0, &p3.1),
+            self.case,
+        );
+    }
+
+    fn revert<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        let (f1, t1) = self.pair_1;
+        let (f2, t2) = self.pair_2;
+        let (f3, t3) = self.pair_3;
+
+        // The sequences of 2-opt moves executed by `move_3_opt`, in terms of exchanged edges.
+        match self.case {
+            Opt3Move::Move1 => undo(tour, &[((f1, t1), (f2, t2))]),
+            Opt3Move::Move2 => undo(tour, &[((f2, t2), (f3, t3))]),
+            Opt3Move::Move3 => undo(tour, &[((f1, t1), (f3, t3))]),
+            Opt3Move::Move4 => undo(tour, &[((f1, t1), (f2, t2)), ((f1, f2), (f3, t3))]),
+            Opt3Move::Move5 => undo(tour, &[((f2, t2), (f3, t3)), ((f1, t1), (f2, f3))]),
+            Opt3Move::Move6 => undo(tour, &[((f1, t1), (f3, t3)), ((f1, f3), (t2, f2))]),
+            Opt3Move::Move7 => undo(
+                tour,
+                &[
+                    ((f1, t1), (f2, t2)),
+                    ((f1, f2), (f3, t3)),
+                    ((f1, f3), (t2, t1)),
+                ],
+            ),
+        }
+    }
+}
+
+/// An Or-opt move that moves a path of nodes between two adjacent nodes, optionally reversing it.
+///
+/// The move is a special case of a pure 3-opt move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrOpt {
+    inner: ThreeOpt,
+}
+
+impl OrOpt {
+    /// Describes the move of the path from `first` to `last` in the forward traversal of the tour
+    /// between `after` and its successor.
+    ///
+    /// Returns `None` if `after` lies on the path or is adjacent to it.
+    pub fn new<T>(
+        tour: &T,
+        first: &TourNode,
+        last: &TourNode,
+        after: &TourNode,
+        reversed: bool,
+    ) -> Option<Self>
+    where
+        T: Tour,
+    {
+        let (f1, f2, f3) = (tour.predecessor(first)?, *last, *after);
+        let (t1, t2, t3) = (*first, tour.successor(last)?, tour.successor(after)?);
+
+        if !distinct(&[f1, t1, f2, t2, f3, t3]) || tour.between(first, after, last) {
+            return None;
+        }
+
+        // Results in [f1-t2]-[f3-f2]-[t1-t3] and [f1-t2]-[f3-t1]-[f2-t3] respectively.
+        let (case, added) = if reversed {
+            (Opt3Move::Move6, [(f1, t2), (f3, f2), (t1, t3)])
+        } else {
+            (Opt3Move::Move7, [(f1, t2), (f3, t1), (f2, t3)])
+        };
+
+        let removed = tour.distance(&f1, &t1) + tour.distance(&f2, &t2) + tour.distance(&f3, &t3);
+        let gain = removed
+            - added
+                .iter()
+                .map(|(a, b)| tour.distance(a, b))
+                .sum::<Scalar>();
+
+        Some(Self {
+            inner: ThreeOpt {
+                pair_1: (f1, t1),
+                pair_2: (f2, t2),
+                pair_3: (f3, t3),
+                case,
+                gain,
+            },
+        })
+    }
+}
+
+impl Move for OrOpt {
+    fn gain(&self) -> Scalar {
+        self.inner.gain
+    }
+
+    fn apply<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        self.inner.apply(tour)
+    }
+
+    fn revert<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        self.inner.revert(tour)
+    }
+}
+
+/// A double-bridge move that reorders the tour A-B-C-D into A-D-C-B, where the paths B, C and D
+/// start at t1, t2 and t3 respectively.
+///
+/// The move can not be undone by a sequential move and is therefore commonly used as a kick.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DoubleBridge {
+    pairs: [(TourNode, TourNode); 4],
+    gain: Scalar,
+}
+
+impl DoubleBridge {
+    /// Describes the double-bridge move that removes the edges leaving the given nodes.
+    ///
+    /// Returns `None` if the nodes are not given in the order of the forward traversal of the
+    /// tour or if the removed edges share nodes.
+    pub fn new<T>(tour: &T, nodes: [TourNode; 4]) -> Option<Self>
+    where
+        T: Tour,
+    {
+        let mut pairs = [(TourNode::default(), TourNode::default()); 4];
+        for (pair, node) in pairs.iter_mut().zip(nodes.iter()) {
+            *pair = (*node, tour.successor(node)?);
+        }
+
+        let [(f1, t1), (f2, t2), (f3, t3), (f4, t4)] = pairs;
+        if !distinct(&[f1, t1, f2, t2, f3, t3, f4, t4])
+            || !tour.between(&t1, &f2, &f3)
+            || !tour.between(&t2, &f3, &f4)
+            || !tour.between(&t3, &f4, &f1)
+        {
+            return None;
+        }
+
+        let removed: Scalar = pairs.iter().map(|(a, b)| tour.distance(a, b)).sum();
+        let added = tour.distance(&f1, &t3)
+            + tour.distance(&f4, &t2)
+            + tour.distance(&f3, &t1)
+            + tour.distance(&f2, &t4);
+
+        Some(Self {
+            pairs,
+            gain: removed - added,
+        })
+    }
+
+    /// Returns the sequence of 2-opt moves that executes the move, in terms of exchanged edges.
+    ///
+    /// The paths B, C and D are reversed as a whole, after which each of them is reversed
+    /// individually.
+    fn steps(&self) -> [Exchange; 4] {
+        let [(f1, t1), (f2, t2), (f3, t3), (f4, t4)] = self.pairs;
+        [
+            ((f1, t1), (f4, t4)),
+            ((f1, f4), (t3, f3)),
+            ((f4, f3), (t2, f2)),
+            ((f3, f2), (t1, t4)),
+        ]
+    }
+}
+
+impl Move for DoubleBridge {
+    fn gain(&self) -> Scalar {
+        self.gain
+    }
+
+    fn apply<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        for (pair_1, pair_2) in self.steps() {
+            exchange(tour, pair_1, pair_2);
+        }
+    }
+
+    fn revert<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        undo(tour, &self.steps());
+    }
+}
+
+/// Pairs of edges that are replaced by a 2-opt move.
+type Exchange = ((TourNode, TourNode), (TourNode, TourNode));
+
+/// Replaces the edges (a, b) and (c, d) by (a, c) and (b, d), regardless of the direction in
+/// which the edges are traversed.
+fn exchange<T>(tour: &mut T, (a, b): (TourNode, TourNode), (c, d): (TourNode, TourNode))
+where
+    T: Tour,
+{
+    match tour.relation(&a, &b) {
+        NodeRel::Predecessor => tour.flip(&a, &b, &c, &d),
+        NodeRel::Successor => tour.flip(&b, &a, &d, &c),
+        NodeRel::None => panic!("Broken tour"),
+    }
+}
+
+/// Undoes a sequence of 2-opt moves.
+fn undo<T>(tour: &mut T, steps: &[Exchange])
+where
+    T: Tour,
+{
+    for ((a, b), (c, d)) in steps.iter().rev() {
+        exchange(tour, (*a, *c), (*b, *d));
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
-    alg::lkh::move_2_opt,
-    tour::{NodeRel, Tour, TourNode, UpdateTourError},
-    Scalar,
+    alg::Objective,
+    data::GetIndex,
+    tour::{FlipSpec, NodeRel, Tour, TourNode, UpdateTourError},
+    CyklError, Scalar,
 };
 
 use super::types::SearchResult;
@@ -10,16 +11,23 @@ pub fn search_2_opt<T>(
     tour: &mut T,
     base: &TourNode,
     base_s: &TourNode,
+    objective: Objective,
 ) -> Result<SearchResult, UpdateTourError>
 where
     T: Tour,
 {
-    let g0 = tour.distance(base, base_s);
+    let d = |tour: &T, a: &TourNode, b: &TourNode| objective.cost(tour.distance(a, b));
+
+    let g0 = d(tour, base, base_s);
     let mut _g2_best = Scalar::MIN;
     let pair = None;
 
     for cand in base_s.candidates() {
-        let g1 = g0 - tour.distance(base_s, cand);
+        if !tour.is_included(tour.index_of(cand)) {
+            continue;
+        }
+
+        let g1 = g0 - d(tour, base_s, cand);
         if tour.relation(base_s, cand) != NodeRel::None || g1 <= 0. {
             continue;
         }
@@ -31,11 +39,11 @@ where
 
         // g2
         // let delta = tour.distance(&cand_p, cand) - tour.distance(base, &cand_p);
-        let g2 = g1 + tour.distance(&cand_p, cand) - tour.distance(base, &cand_p);
+        let g2 = g1 + d(tour, &cand_p, cand) - d(tour, base, &cand_p);
 
         if g2 > 0. {
             // gain criterion satisfied.
-            move_2_opt(tour, base, base_s, &cand_p, cand);
+            apply_step(tour, [(base, base_s, &cand_p, cand)])?;
             return Ok(SearchResult::Gainful(g2));
         } else {
             // Non-gainful move.
@@ -49,13 +57,177 @@ where
     }
 
     if let Some((cand_1, cand_2)) = pair {
-        move_2_opt(tour, base, base_s, &cand_1, &cand_2);
+        apply_step(tour, [(base, base_s, &cand_1, &cand_2)])?;
         return Ok(SearchResult::NonGainful(cand_1));
     }
 
     Err(UpdateTourError::SearchFailed)
 }
 
+/// Applies the flips that make up one step of the search as a single operation with
+/// [`Tour::apply_moves`], which leaves the tour unchanged if any flip of the step is invalid.
+///
+/// Each flip is given as `(from_a, to_a, from_b, to_b)`, like the arguments of [`Tour::flip`].
+fn apply_step<T, const N: usize>(
+    tour: &mut T,
+    flips: [(&TourNode, &TourNode, &TourNode, &TourNode); N],
+) -> Result<(), UpdateTourError>
+where
+    T: Tour,
+{
+    let moves = flips.map(|(fa, ta, fb, tb)| {
+        FlipSpec::new(
+            tour.index_of(fa),
+            tour.index_of(ta),
+            tour.index_of(fb),
+            tour.index_of(tb),
+        )
+    });
+
+    tour.apply_moves(&moves).map_err(|err| match err {
+        CyklError::Tour(err) => err,
+        _ => UpdateTourError::BrokenTour,
+    })
+}
+
+/// Returns the best position at which an excluded node can be inserted in terms of
+/// [`Objective::cost`], as the increase of the tour length together with the node after which the
+/// node would be inserted.
+///
+/// Returns `None` if the node is already part of the tour or the index is out of bounds.
+fn best_insertion<T>(tour: &T, index: usize, objective: Objective) -> Option<(Scalar, TourNode)>
+where
+    T: Tour,
+{
+    if objective == Objective::Minimize {
+        return tour.cheapest_insertion(index);
+    }
+
+    let node = tour.get(index)?;
+    if tour.is_included(index) {
+        return None;
+    }
+
+    let mut result: Option<(Scalar, TourNode)> = None;
+    for from in tour.itr() {
+        if !tour.is_included(tour.index_of(&from)) {
+            continue;
+        }
+
+        let to = tour.successor(&from)?;
+        let cost =
+            tour.distance(&from, &node) + tour.distance(&node, &to) - tour.distance(&from, &to);
+        match result {
+            Some((best, _)) if objective.cost(best) <= objective.cost(cost) => {}
+            _ => result = Some((cost, from)),
+        }
+    }
+
+    result
+}
+
+/// Searches for an insertion or removal move that involves a base node.
+///
+/// The objective applies to the sum of the tour length and the prizes of the skipped nodes. When
+/// minimising, an included node is removed if the saved distance exceeds its prize, while an
+/// excluded node is inserted at its cheapest position if its prize exceeds the additional
+/// distance. Returns the gain of the executed move in terms of [`Objective::cost`].
+pub fn search_prize<T>(
+    tour: &mut T,
+    base: &TourNode,
+    objective: Objective,
+) -> Result<SearchResult, UpdateTourError>
+where
+    T: Tour,
+{
+    let index = base.index().get();
+    let prize = tour.prize(index);
+
+    if tour.is_included(index) {
+        if tour.n_included() <= 1 {
+            return Err(UpdateTourError::SearchFailed);
+        }
+
+        let (pred, succ) = match (tour.predecessor(base), tour.successor(base)) {
+            (Some(pred), Some(succ)) => (pred, succ),
+            _ => return Err(UpdateTourError::NodeNotFound),
+        };
+
+        let saving =
+            tour.distance(&pred, base) + tour.distance(base, &succ) - tour.distance(&pred, &succ);
+        let gain = objective.cost(saving - prize);
+        if gain > 0. {
+            tour.exclude(index)?;
+            return Ok(SearchResult::Gainful(gain));
+        }
+    } else if let Some((cost, after)) = best_insertion(tour, index, objective) {
+        let gain = objective.cost(prize - cost);
+        if gain > 0. {
+            tour.insert_after(index, tour.index_of(&after))?;
+            return Ok(SearchResult::Gainful(gain));
+        }
+    }
+
+    Err(UpdateTourError::SearchFailed)
+}
+
+/// Searches for a node that replaces a base node as the representative of its group.
+///
+/// Members of the group are evaluated at the position of the base node. The chosen member is then
+/// inserted at its best position, which is never worse than the evaluated one. Returns the gain of
+/// the executed move in terms of [`Objective::cost`].
+pub fn search_swap_representative<T>(
+    tour: &mut T,
+    base: &TourNode,
+    objective: Objective,
+) -> Result<SearchResult, UpdateTourError>
+where
+    T: Tour,
+{
+    let index = base.index().get();
+    let group = match tour.group(index) {
+        Some(group) if tour.is_included(index) => group,
+        _ => return Err(UpdateTourError::SearchFailed),
+    };
+
+    let (pred, succ) = match (tour.predecessor(base), tour.successor(base)) {
+        (Some(pred), Some(succ)) => (pred, succ),
+        _ => return Err(UpdateTourError::NodeNotFound),
+    };
+
+    let current = tour.distance(&pred, base) + tour.distance(base, &succ);
+    let mut best: Option<(Scalar, TourNode)> = None;
+
+    for member in tour.itr() {
+        if member == *base || tour.group(tour.index_of(&member)) != Some(group) {
+            continue;
+        }
+
+        let delta = tour.distance(&pred, &member) + tour.distance(&member, &succ) - current;
+        match best {
+            Some((d, _)) if objective.cost(d) <= objective.cost(delta) => {}
+            _ => best = Some((delta, member)),
+        }
+    }
+
+    match best {
+        Some((delta, member)) if objective.cost(delta) < 0. => {
+            let saving = current - tour.distance(&pred, &succ);
+            tour.exclude(index)?;
+
+            let member = tour.index_of(&member);
+            let (cost, after) = match best_insertion(tour, member, objective) {
+                Some(insertion) => insertion,
+                None => return Err(UpdateTourError::BrokenTour),
+            };
+            tour.insert_after(member, tour.index_of(&after))?;
+
+            Ok(SearchResult::Gainful(objective.cost(saving - cost)))
+        }
+        _ => Err(UpdateTourError::SearchFailed),
+    }
+}
+
 pub fn search_3_opt<T>(
     _tour: &mut T,
     _head_1: &TourNode,
@@ -1,86 +1,110 @@
 use std::collections::VecDeque;
 
 use crate::{
-    alg::tour_gen::init_tour,
-    tour::{NodeStatus, Tour, TourNode, TourOrder, UpdateTourError},
+    alg::{tour_gen::init_tour, Objective},
+    tour::{NodeRel, NodeStatus, Tour, TourNode, TourOrder, UpdateTourError},
     Scalar,
 };
 
 use super::{searches::search_2_opt, types::SearchResult, KOpt};
 
-pub fn solve_lkh<T>(tour: &mut T, kopt: KOpt, trials: usize) -> Result<(), UpdateTourError>
+/// Constructs and improves a tour in each of the given number of trials, and leaves the tour at
+/// the best order found in terms of the objective.
+pub fn solve_lkh<T>(
+    tour: &mut T,
+    kopt: KOpt,
+    trials: usize,
+    objective: Objective,
+) -> Result<(), UpdateTourError>
 where
     T: Tour,
 {
-    let len = tour.len();
-    let mut best_order = TourOrder::default();
+    let mut best_order: Option<TourOrder> = None;
 
     for _ in 0..trials {
         init_tour(tour)?;
+        improve_lkh(tour, kopt, objective)?;
+
+        let order = tour.tour_order();
+        match &best_order {
+            Some(best) if !objective.is_better(order.cost(), best.cost()) => {}
+            _ => best_order = Some(order),
+        }
+    }
 
-        let original_order = tour.tour_order();
-        let mut current_cost = original_order.cost();
+    if let Some(order) = best_order {
+        tour.apply(&order)?;
+    }
 
-        let mut active = VecDeque::with_capacity(len);
+    Ok(())
+}
 
-        for mut node in tour.itr() {
-            if node.is_best_neighbours(&tour.successor(&node).unwrap(), 0)
-                || node.is_best_neighbours(&tour.predecessor(&node).unwrap(), 0)
-            {
-                node.set_status(NodeStatus::Fixed);
-            } else {
-                node.set_status(NodeStatus::Active);
-                active.push_back(node)
-            }
+/// Improves the current tour with the given search until no active node is left, and returns the
+/// total gain in terms of [`Objective::cost`].
+///
+/// Unlike [`solve_lkh`], the tour is not constructed anew. The nodes' candidates must already be
+/// generated.
+pub fn improve_lkh<T>(
+    tour: &mut T,
+    kopt: KOpt,
+    objective: Objective,
+) -> Result<Scalar, UpdateTourError>
+where
+    T: Tour,
+{
+    let len = tour.len();
+    let mut total = 0.;
+
+    let mut active = VecDeque::with_capacity(len);
+
+    for mut node in tour.itr() {
+        if node.is_best_neighbours(&tour.successor(&node).unwrap(), 0)
+            || node.is_best_neighbours(&tour.predecessor(&node).unwrap(), 0)
+        {
+            node.set_status(NodeStatus::Fixed);
+        } else {
+            node.set_status(NodeStatus::Active);
+            active.push_back(node)
         }
+    }
 
-        while let Some(mut base) = active.pop_front() {
-            base.set_status(NodeStatus::Fixed);
+    while let Some(mut base) = active.pop_front() {
+        base.set_status(NodeStatus::Fixed);
 
-            let successor = match tour.successor(&base) {
-                Some(s) => s,
-                None => return Err(UpdateTourError::NodeNotFound),
-            };
+        let successor = match tour.successor(&base) {
+            Some(s) => s,
+            None => return Err(UpdateTourError::NodeNotFound),
+        };
 
-            let predecessor = match tour.predecessor(&base) {
-                Some(p) => p,
-                None => return Err(UpdateTourError::NodeNotFound),
+        let predecessor = match tour.predecessor(&base) {
+            Some(p) => p,
+            None => return Err(UpdateTourError::NodeNotFound),
+        };
+
+        let mut improved = false;
+        for targ in [predecessor, successor] {
+            // The search expects the target to be the successor of the base node.
+            match tour.relation(&base, &targ) {
+                NodeRel::Predecessor => {}
+                NodeRel::Successor => tour.rev(),
+                NodeRel::None => return Err(UpdateTourError::BrokenTour),
             };
 
-            let mut flag = true;
-            for ii in 0..2 {
-                let gain = if ii == 1 {
-                    search(tour, kopt, &base, &successor)?
-                } else {
-                    match tour.relation(&base, &predecessor) {
-                        crate::tour::NodeRel::Predecessor => tour.rev(),
-                        crate::tour::NodeRel::Successor => {}
-                        crate::tour::NodeRel::None => return Err(UpdateTourError::BrokenTour),
-                    };
-
-                    search(tour, kopt, &base, &predecessor)?
-                };
-
-                if gain > 0. {
-                    current_cost -= gain;
-                    break;
-                } else {
-                    base.set_status(NodeStatus::Active);
-
-                    if flag {
-                        active.push_back(base);
-                        flag = false;
-                    }
-                }
+            let gain = search(tour, kopt, &base, &targ, objective)?;
+            if gain > 0. {
+                total += gain;
+                improved = true;
+                break;
             }
         }
 
-        if current_cost < best_order.cost() {
-            best_order = tour.tour_order();
+        if improved {
+            base.set_status(NodeStatus::Active);
+            active.push_back(base);
         }
     }
 
-    Ok(())
+    Ok(total)
 }
 
 fn search<T>(
@@ -88,6 +112,7 @@ fn search<T>(
     kopt: KOpt,
     base: &TourNode,
     base_s: &TourNode,
+    objective: Objective,
 ) -> Result<Scalar, UpdateTourError>
 where
     T: Tour,
@@ -96,7 +121,10 @@ where
 
     while let Some(targ) = next {
         let result = match kopt {
-            KOpt::Opt2 => search_2_opt(tour, base, &targ)?,
+            KOpt::Opt2 => match search_2_opt(tour, base, &targ, objective) {
+                Err(UpdateTourError::SearchFailed) => return Ok(0.),
+                result => result?,
+            },
             KOpt::Opt3 => todo!(),
         };
 
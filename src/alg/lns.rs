@@ -0,0 +1,592 @@
+//! Large neighbourhood search, which repeatedly removes a part of a tour and inserts the removed
+//! nodes again.
+use std::{cmp::Ordering, sync::Arc, time::Instant};
+
+use rand::{Rng, SeedableRng};
+
+use crate::{
+    stats,
+    tour::{Tour, TourOrder, UpdateTourError},
+    CancellationToken, Scalar,
+};
+
+use super::{
+    acceptance::Acceptance, bounds::BoundsTracker, incumbent::Incumbent, objective::TourObjective,
+    rng::SolverRng, Objective, Termination,
+};
+
+/// Exponent that biases the randomised selection of [`Destroy::Worst`], [`Destroy::Shaw`] and
+/// [`Destroy::Related`] towards the top of their rankings. Higher values make the selection more
+/// deterministic.
+const RANDOMNESS: i32 = 3;
+
+/// Minimum number of nodes that remain in a tour after its destruction.
+const MIN_REMAINING: usize = 3;
+
+/// Buffers of the operators and orders of the search, which are reused across iterations so that
+/// an iteration does not allocate.
+#[derive(Debug, Default)]
+struct Scratch {
+    /// Indices of the nodes removed by the last destroy operator.
+    removed: Vec<usize>,
+    /// Indices of the nodes that remain in the tour, among which the destroy operators choose.
+    included: Vec<usize>,
+    /// Remaining nodes ranked by their removal gain, or by their distance or relatedness to a
+    /// removed node, together with the value they are ranked by.
+    ranking: Vec<(Scalar, usize)>,
+    /// Nodes after which the repair operators examine the insertion of a removed node.
+    froms: Vec<usize>,
+    /// Position of each included node along the tour, at the index of the node.
+    positions: Vec<usize>,
+    /// Changes made to the tour in the current iteration.
+    journal: Journal,
+    /// Order of the tour of the current iteration, for objectives that rate complete tours.
+    current: TourOrder,
+    /// Order of the best tour found.
+    best: TourOrder,
+}
+
+/// Changes made to a tour by the operators of one iteration, from which the change of the tour
+/// length is known, and with which a rejected tour is restored in time linear in the number of
+/// removed nodes.
+#[derive(Debug, Default)]
+struct Journal {
+    /// Removed nodes together with their predecessors at the time of removal, in the order of
+    /// removal.
+    removals: Vec<(usize, usize)>,
+    /// Nodes inserted by the repair operator, in the order of insertion.
+    insertions: Vec<usize>,
+    /// Change of the tour length.
+    delta: Scalar,
+}
+
+impl Journal {
+    /// Forgets the recorded changes, e.g. once the changed tour is accepted.
+    fn clear(&mut self) {
+        self.removals.clear();
+        self.insertions.clear();
+        self.delta = 0.;
+    }
+
+    /// Removes a node from a tour and records the removal.
+    fn remove<T>(&mut self, tour: &mut T, index: usize) -> Result<(), UpdateTourError>
+    where
+        T: Tour,
+    {
+        let pred = tour
+            .predecessor_at(index)
+            .ok_or(UpdateTourError::NodeNotFound)?;
+        self.delta -= tour.remove_with_gain(index)?;
+        self.removals.push((index, tour.index_of(&pred)));
+        Ok(())
+    }
+
+    /// Inserts a node into a tour after another one, and records the insertion together with its
+    /// cost.
+    fn insert<T>(
+        &mut self,
+        tour: &mut T,
+        index: usize,
+        after: usize,
+        cost: Scalar,
+    ) -> Result<(), UpdateTourError>
+    where
+        T: Tour,
+    {
+        tour.insert_after(index, after)?;
+        self.insertions.push(index);
+        self.delta += cost;
+        Ok(())
+    }
+
+    /// Reverts the recorded changes in reverse order, which restores the tour as it was before
+    /// the first of them.
+    fn undo<T>(&mut self, tour: &mut T) -> Result<(), UpdateTourError>
+    where
+        T: Tour,
+    {
+        for index in self.insertions.iter().rev() {
+            tour.exclude(*index)?;
+        }
+        for (index, pred) in self.removals.iter().rev() {
+            tour.insert_after(*index, *pred)?;
+        }
+        self.clear();
+        Ok(())
+    }
+}
+
+/// Operator that removes nodes from a tour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Destroy {
+    /// Removes nodes chosen uniformly at random.
+    Random,
+    /// Removes the nodes whose removal shortens the tour the most.
+    Worst,
+    /// Removes nodes that are close to each other, as proposed by Shaw, so that the repair can
+    /// rearrange a whole region of the tour.
+    Shaw,
+    /// Removes clusters of nodes that are related by their distance as well as their proximity
+    /// along the tour, as measured by [`stats::relatedness`].
+    Related,
+}
+
+impl Destroy {
+    /// All destroy operators.
+    pub const ALL: [Destroy; 4] = [
+        Destroy::Random,
+        Destroy::Worst,
+        Destroy::Shaw,
+        Destroy::Related,
+    ];
+}
+
+/// Operator that inserts removed nodes into a tour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Repair {
+    /// Repeatedly inserts the removed node whose insertion is the cheapest.
+    Greedy,
+    /// Repeatedly inserts the removed node with the largest difference between the costs of its
+    /// best and second-best insertion, i.e. the node that loses the most if it is postponed.
+    Regret2,
+}
+
+impl Repair {
+    /// All repair operators.
+    pub const ALL: [Repair; 2] = [Repair::Greedy, Repair::Regret2];
+}
+
+/// Large neighbourhood search that destroys and repairs a tour with operators selected at random.
+///
+/// The operators work through [`Tour::remove_with_gain`] and [`Tour::insert_after`], and examine
+/// insertions next to the candidates of the nodes, which must be generated before running the
+/// driver. All nodes must be part of the tour.
+///
+/// With the `serde` feature, the driver is serialised with the state of its random number
+/// generator, but without its cancellation token, bounds tracker and incumbent.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lns {
+    destroy: Vec<Destroy>,
+    repair: Vec<Repair>,
+    n_remove: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cancellation: CancellationToken,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    bounds: Option<Arc<BoundsTracker>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    incumbent: Option<Arc<Incumbent>>,
+    #[cfg_attr(feature = "serde", serde(with = "super::rng::state"))]
+    rng: SolverRng,
+}
+
+impl Lns {
+    /// Creates a driver that uses all operators and removes `n_remove` nodes per iteration.
+    pub fn new(n_remove: usize, seed: u64) -> Self {
+        Self::with_operators(&Destroy::ALL, &Repair::ALL, n_remove, seed)
+    }
+
+    /// Creates a driver that uses the given operators and removes `n_remove` nodes per iteration.
+    pub fn with_operators(
+        destroy: &[Destroy],
+        repair: &[Repair],
+        n_remove: usize,
+        seed: u64,
+    ) -> Self {
+        Self {
+            destroy: destroy.to_vec(),
+            repair: repair.to_vec(),
+            n_remove,
+            cancellation: CancellationToken::new(),
+            bounds: None,
+            incumbent: None,
+            rng: SolverRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Sets a token that stops the driver before the termination condition is reached. The token
+    /// is checked in each iteration.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Attaches a tracker to which the best tours are offered. The driver stops once the target
+    /// gap of the tracker is reached, which is checked in each iteration.
+    pub fn with_bounds(mut self, bounds: Arc<BoundsTracker>) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Attaches an incumbent to which the best tours are offered as soon as they are found, e.g.
+    /// to report them with [`Incumbent::on_improvement`].
+    pub fn with_incumbent(mut self, incumbent: Arc<Incumbent>) -> Self {
+        self.incumbent = Some(incumbent);
+        self
+    }
+
+    /// Improves a tour until the termination condition is reached, and returns the best tour
+    /// found.
+    ///
+    /// A repaired tour replaces the current tour if the acceptance strategy accepts its length,
+    /// otherwise the removals and insertions are undone.
+    pub fn run<T, A>(
+        &mut self,
+        tour: &mut T,
+        acceptance: &mut A,
+        termination: Termination,
+    ) -> Result<TourOrder, UpdateTourError>
+    where
+        T: Tour,
+        A: Acceptance,
+    {
+        self.run_with_objective(tour, acceptance, termination, &Objective::Minimize)
+    }
+
+    /// Improves a tour like [`Lns::run`], but rates tours with the given objective instead of
+    /// their length.
+    ///
+    /// The acceptance strategy decides on the values of the objective, and the tour with the
+    /// lowest value is returned. The operators still destroy and repair by distance. Objectives
+    /// that only depend on the length, such as [`Objective`], rate the repaired tours from the
+    /// changes of the length, whereas other objectives rate the complete tour in each iteration.
+    pub fn run_with_objective<T, A, O>(
+        &mut self,
+        tour: &mut T,
+        acceptance: &mut A,
+        termination: Termination,
+        objective: &O,
+    ) -> Result<TourOrder, UpdateTourError>
+    where
+        T: Tour,
+        A: Acceptance,
+        O: TourObjective + ?Sized,
+    {
+        let start = Instant::now();
+        let mut scratch = Scratch::default();
+        tour.tour_order_into(&mut scratch.best);
+        let mut best_value = objective.evaluate(&scratch.best);
+        self.offer(&scratch.best);
+        let (mut current_len, mut current_value) = (scratch.best.cost(), best_value);
+        // Whether the tour is the best tour, so that it does not need to be restored at the end.
+        let mut at_best = true;
+        let (mut iterations, mut stagnation) = (0, 0);
+
+        while !self.destroy.is_empty()
+            && !self.repair.is_empty()
+            && !termination.is_reached(iterations, stagnation, start.elapsed())
+            && !self.cancellation.is_cancelled()
+            && !self.is_target_reached()
+        {
+            iterations += 1;
+            stagnation += 1;
+
+            let destroy = self.destroy[self.rng.gen_range(0..self.destroy.len())];
+            let repair = self.repair[self.rng.gen_range(0..self.repair.len())];
+            self.destroy(destroy, tour, &mut scratch)?;
+            repair_tour(repair, tour, &mut scratch)?;
+
+            let candidate_len = current_len + scratch.journal.delta;
+            let candidate_value = match objective.evaluate_length(candidate_len) {
+                Some(value) => value,
+                None => {
+                    tour.tour_order_into(&mut scratch.current);
+                    objective.evaluate(&scratch.current)
+                }
+            };
+            if acceptance.accept(current_value, candidate_value) {
+                scratch.journal.clear();
+                current_len = candidate_len;
+                current_value = candidate_value;
+                at_best = false;
+                if current_value < best_value {
+                    tour.tour_order_into(&mut scratch.best);
+                    self.offer(&scratch.best);
+                    best_value = current_value;
+                    at_best = true;
+                    stagnation = 0;
+                }
+            } else {
+                scratch.journal.undo(tour)?;
+            }
+
+            acceptance.update(current_value, best_value);
+        }
+
+        if !at_best {
+            tour.apply(&scratch.best)?;
+        }
+        Ok(scratch.best)
+    }
+
+    /// Offers a tour to the attached tracker and incumbent.
+    fn offer(&self, order: &TourOrder) {
+        if let Some(bounds) = &self.bounds {
+            bounds.offer(order);
+        }
+        if let Some(incumbent) = &self.incumbent {
+            incumbent.offer(order);
+        }
+    }
+
+    /// Returns `true` if the attached tracker has reached its target gap.
+    fn is_target_reached(&self) -> bool {
+        matches!(&self.bounds, Some(bounds) if bounds.is_target_reached())
+    }
+
+    /// Removes nodes from a tour with the given operator and stores their indices in the scratch
+    /// buffers.
+    fn destroy<T>(
+        &mut self,
+        op: Destroy,
+        tour: &mut T,
+        scratch: &mut Scratch,
+    ) -> Result<(), UpdateTourError>
+    where
+        T: Tour,
+    {
+        let Scratch {
+            removed,
+            included,
+            ranking,
+            positions,
+            journal,
+            ..
+        } = scratch;
+        removed.clear();
+
+        let n_remove = self
+            .n_remove
+            .min(tour.n_included().saturating_sub(MIN_REMAINING));
+        if n_remove == 0 {
+            return Ok(());
+        }
+
+        included.clear();
+        included.extend((0..tour.len()).filter(|x| tour.is_included(*x)));
+
+        match op {
+            Destroy::Random => {
+                for _ in 0..n_remove {
+                    let index = included.swap_remove(self.rng.gen_range(0..included.len()));
+                    journal.remove(tour, index)?;
+                    removed.push(index);
+                }
+            }
+            Destroy::Worst => {
+                // Gains are ranked once, since removals only change the gains of few nodes.
+                ranking.clear();
+                for index in included.iter() {
+                    ranking.push((removal_gain(tour, *index)?, *index));
+                }
+                ranking.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+                for _ in 0..n_remove {
+                    let pos = self.biased(ranking.len());
+                    let (_, index) = ranking.remove(pos);
+                    journal.remove(tour, index)?;
+                    removed.push(index);
+                }
+            }
+            Destroy::Shaw => {
+                let index = included.swap_remove(self.rng.gen_range(0..included.len()));
+                journal.remove(tour, index)?;
+                removed.push(index);
+
+                while removed.len() < n_remove {
+                    let origin = removed[self.rng.gen_range(0..removed.len())];
+                    ranking.clear();
+                    for &index in included.iter() {
+                        let cost = tour
+                            .distance_at(&origin, &index)
+                            .map_err(|_| UpdateTourError::NodeNotFound)?;
+                        ranking.push((cost, index));
+                    }
+                    let pos = self.biased(ranking.len());
+                    let (_, index) = *ranking
+                        .select_nth_unstable_by(pos, |a, b| {
+                            a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal)
+                        })
+                        .1;
+                    included.retain(|x| *x != index);
+                    journal.remove(tour, index)?;
+                    removed.push(index);
+                }
+            }
+            Destroy::Related => {
+                // Clusters are selected on the intact tour, since the relatedness depends on the
+                // positions of the nodes. The positions are read once per destruction.
+                let index = included.swap_remove(self.rng.gen_range(0..included.len()));
+                removed.push(index);
+                fill_positions(tour, index, positions)?;
+                let n = tour.n_included();
+                let mean_edge = tour.total_distance() / n as Scalar;
+
+                while removed.len() < n_remove {
+                    let origin = removed[self.rng.gen_range(0..removed.len())];
+                    ranking.clear();
+                    for &index in included.iter() {
+                        let (a, b) = (positions[origin], positions[index]);
+                        let distance = tour
+                            .distance_at(&origin, &index)
+                            .map_err(|_| UpdateTourError::NodeNotFound)?;
+                        let hops = a.max(b) - a.min(b);
+                        ranking.push((stats::relatedness_of(n, hops, distance, mean_edge), index));
+                    }
+
+                    let pos = self.biased(ranking.len());
+                    let (_, index) = *ranking
+                        .select_nth_unstable_by(pos, |a, b| {
+                            b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal)
+                        })
+                        .1;
+                    included.retain(|x| *x != index);
+                    removed.push(index);
+                }
+
+                for index in removed.iter() {
+                    journal.remove(tour, *index)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a random position in a ranking of the given length, preferring the first entries.
+    fn biased(&mut self, len: usize) -> usize {
+        let y = self.rng.gen::<Scalar>();
+        ((y.powi(RANDOMNESS) * len as Scalar) as usize).min(len - 1)
+    }
+}
+
+/// Stores the position of each included node along a tour, counted from the node at `start`, at
+/// the index of the node in `positions`.
+fn fill_positions<T>(
+    tour: &T,
+    start: usize,
+    positions: &mut Vec<usize>,
+) -> Result<(), UpdateTourError>
+where
+    T: Tour,
+{
+    positions.clear();
+    positions.resize(tour.len(), usize::MAX);
+
+    let mut node = tour.get(start).ok_or(UpdateTourError::NodeNotFound)?;
+    for pos in 0..tour.n_included() {
+        positions[tour.index_of(&node)] = pos;
+        node = tour.successor(&node).ok_or(UpdateTourError::BrokenTour)?;
+    }
+
+    Ok(())
+}
+
+/// Inserts the nodes removed by the last destroy operator into a tour with the given operator.
+fn repair_tour<T>(op: Repair, tour: &mut T, scratch: &mut Scratch) -> Result<(), UpdateTourError>
+where
+    T: Tour,
+{
+    let Scratch {
+        removed,
+        froms,
+        journal,
+        ..
+    } = scratch;
+    while !removed.is_empty() {
+        // Score to be maximised, removed position, node after which to insert and cost of the
+        // insertion.
+        let mut chosen: Option<(Scalar, usize, usize, Scalar)> = None;
+
+        for (pos, index) in removed.iter().enumerate() {
+            let (best, second) = insertion_costs(tour, *index, froms)?;
+            let score = match op {
+                Repair::Greedy => -best.0,
+                Repair::Regret2 => second - best.0,
+            };
+
+            match chosen {
+                Some((top, _, _, _)) if top >= score => {}
+                _ => chosen = Some((score, pos, best.1, best.0)),
+            }
+        }
+
+        if let Some((_, pos, after, cost)) = chosen {
+            let index = removed.swap_remove(pos);
+            journal.insert(tour, index, after, cost)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the decrease of the tour length caused by removing a node.
+fn removal_gain<T>(tour: &T, index: usize) -> Result<Scalar, UpdateTourError>
+where
+    T: Tour,
+{
+    let node = tour.get(index).ok_or(UpdateTourError::NodeNotFound)?;
+    match (tour.predecessor(&node), tour.successor(&node)) {
+        (Some(pred), Some(succ)) => {
+            Ok(tour.distance(&pred, &node) + tour.distance(&node, &succ)
+                - tour.distance(&pred, &succ))
+        }
+        _ => Err(UpdateTourError::BrokenTour),
+    }
+}
+
+/// Returns the cheapest insertion of an excluded node as its cost and the node after which it is
+/// inserted, together with the cost of the second-best insertion.
+///
+/// Only the edges incident to the included candidates of the node are examined, unless none of
+/// the candidates is included. The nodes examined are collected in `froms`.
+fn insertion_costs<T>(
+    tour: &T,
+    index: usize,
+    froms: &mut Vec<usize>,
+) -> Result<((Scalar, usize), Scalar), UpdateTourError>
+where
+    T: Tour,
+{
+    let node = tour.get(index).ok_or(UpdateTourError::NodeNotFound)?;
+
+    froms.clear();
+    froms.extend(
+        node.candidates()
+            .iter()
+            .filter(|cand| tour.is_included(tour.index_of(cand)))
+            .flat_map(|cand| {
+                let pred = tour.predecessor(cand).map(|x| tour.index_of(&x));
+                std::iter::once(tour.index_of(cand)).chain(pred)
+            }),
+    );
+    if froms.is_empty() {
+        froms.extend((0..tour.len()).filter(|x| tour.is_included(*x)));
+    }
+    froms.sort_unstable();
+    froms.dedup();
+
+    let mut best = (Scalar::MAX, usize::MAX);
+    let mut second = Scalar::MAX;
+    for &from in froms.iter() {
+        let prev = tour.get(from).ok_or(UpdateTourError::NodeNotFound)?;
+        let next = tour.successor(&prev).ok_or(UpdateTourError::BrokenTour)?;
+        let cost =
+            tour.distance(&prev, &node) + tour.distance(&node, &next) - tour.distance(&prev, &next);
+
+        if cost < best.0 {
+            second = best.0;
+            best = (cost, from);
+        } else if cost < second {
+            second = cost;
+        }
+    }
+
+    if best.1 == usize::MAX {
+        return Err(UpdateTourError::BrokenTour);
+    }
+
+    Ok((best, second))
+}
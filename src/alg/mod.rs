@@ -1,15 +1,163 @@
 mod tour_gen;
+pub use tour_gen::nearest_fragment;
 
 pub mod lkh;
 
 mod cand_gen;
-pub use cand_gen::cand_gen_nn;
+pub use cand_gen::{
+    cand_gen_nn, cand_gen_nn_with, cand_gen_quadrant, CandidateSet, CandidateSetBuilder,
+    NeighborIndex,
+};
+
+mod hnsw;
+pub use hnsw::HnswParams;
 
 pub mod solvers;
 
+mod rng;
+
+pub mod acceptance;
+
+pub mod adaptive;
+
+pub mod lns;
+
+pub mod pipeline;
+
+pub mod registry;
+
+pub mod objective;
+
+pub mod parallel;
+
+pub mod incumbent;
+
+pub mod bounds;
+
 mod tests;
 
+use std::time::Duration;
+
+use crate::Scalar;
+
+use self::registry::PipelineSpec;
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SolverKind {
     Greedy(Vec<usize>),
+    /// Constructs a tour with the greedy algorithm from the given starting nodes, then removes and
+    /// inserts nodes according to their prizes.
+    PrizeCollecting(Vec<usize>),
+    /// Constructs a tour with the greedy algorithm from the given starting nodes, then keeps one
+    /// node per group and improves the tour with 2-opt and representative swap moves.
+    Generalized(Vec<usize>),
+    /// Constructs a tour with the greedy algorithm from the given starting nodes, then improves it
+    /// with late acceptance hill climbing on random 2-opt moves.
+    LateAcceptance {
+        starters: Vec<usize>,
+        /// Length of the history of tour lengths.
+        history: usize,
+        termination: Termination,
+        /// Seed for the random number generator.
+        seed: u64,
+    },
+    /// Constructs a tour with the greedy algorithm from the given starting nodes, then improves it
+    /// with the [`adaptive::Adaptive`] driver until the time budget is used up.
+    Adaptive {
+        starters: Vec<usize>,
+        budget: Duration,
+        /// Seed for the random number generator.
+        seed: u64,
+    },
+    /// Constructs a tour with the greedy algorithm from the given starting nodes, then improves it
+    /// with the [`lns::Lns`] driver, accepting only shorter tours.
+    Lns {
+        starters: Vec<usize>,
+        /// Number of nodes removed per iteration.
+        n_remove: usize,
+        termination: Termination,
+        /// Seed for the random number generator.
+        seed: u64,
+    },
+    /// Constructs a tour with [`nearest_fragment`], which scales to instances where the greedy
+    /// algorithm is too slow.
+    NearestFragment {
+        /// Maximum number of nodes in a leaf of the k-d tree.
+        leaf_size: usize,
+    },
+    /// Runs the [`Pipeline`](pipeline::Pipeline) described by a specification, whose phases are
+    /// looked up in [`Registry::new`](registry::Registry::new). The objective of the run
+    /// configuration replaces that of the specification.
+    Pipeline(PipelineSpec),
+}
+
+/// Condition under which an iterative solver stops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Termination {
+    /// Stops after the given number of iterations.
+    Iterations(usize),
+    /// Stops after the given number of consecutive iterations without improving the best tour.
+    Stagnation(usize),
+    /// Stops once the given time has elapsed.
+    TimeLimit(Duration),
+}
+
+impl Termination {
+    /// Returns `true` if the solver should stop after having run `iterations` iterations, the
+    /// last `stagnation` of which did not improve the best tour, in the `elapsed` time.
+    #[inline]
+    pub fn is_reached(&self, iterations: usize, stagnation: usize, elapsed: Duration) -> bool {
+        match self {
+            Self::Iterations(limit) => iterations >= *limit,
+            Self::Stagnation(limit) => stagnation >= *limit,
+            Self::TimeLimit(limit) => elapsed >= *limit,
+        }
+    }
+}
+
+/// Strategy for choosing among the improving moves in the neighbourhood of a node.
+///
+/// Which strategy converges faster depends on the instance: first improvement applies many cheap
+/// moves, while best improvement applies fewer moves of higher quality.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Scan {
+    /// Applies the first improving move found.
+    #[default]
+    FirstImprovement,
+    /// Applies the move with the largest gain among all moves of the node.
+    BestImprovement,
+}
+
+/// Direction of the optimisation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Objective {
+    /// Searches for the shortest tour.
+    #[default]
+    Minimize,
+    /// Searches for the longest tour, e.g. for maximum-scatter problems.
+    Maximize,
+}
+
+impl Objective {
+    /// Converts a distance into the cost that is minimised by the algorithms.
+    ///
+    /// Distances are negated when maximising, so that gains computed from them keep their sign
+    /// conventions.
+    #[inline]
+    pub fn cost(&self, d: Scalar) -> Scalar {
+        match self {
+            Self::Minimize => d,
+            Self::Maximize => -d,
+        }
+    }
+
+    /// Returns `true` if the tour length `a` is strictly better than `b`.
+    #[inline]
+    pub fn is_better(&self, a: Scalar, b: Scalar) -> bool {
+        self.cost(a) < self.cost(b)
+    }
 }
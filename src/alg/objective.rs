@@ -0,0 +1,241 @@
+//! Objectives that rate a tour by its length combined with penalty terms.
+//!
+//! The algorithms of this crate minimise the tour length, or rather [`Objective::cost`] of it.
+//! Side constraints such as time windows or a balanced workload can be modelled as penalties that
+//! are added to the length, so that drivers like [`Lns::run_with_objective`] and
+//! [`local_search_with_objective`] trade off distance against the violation of the constraints.
+//! Likewise, [`Stability`] penalises changes to a previous tour, so that re-optimised tours do not
+//! churn unnecessarily, and [`TurnPenalty`] penalises sharp turns between consecutive legs.
+//!
+//! [`Lns::run_with_objective`]: super::lns::Lns::run_with_objective
+//! [`local_search_with_objective`]: super::solvers::local_search_with_objective
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    data::{CostLayer, DataStore},
+    tour::{Edge, TourOrder},
+    viz, Scalar,
+};
+
+use super::Objective;
+
+/// Rates a complete tour. Lower values are better.
+pub trait TourObjective {
+    /// Returns the value of a tour.
+    fn evaluate(&self, order: &TourOrder) -> Scalar;
+
+    /// Returns the value of a tour of the given length, or `None` if the value depends on more
+    /// than the length.
+    ///
+    /// Drivers that know how their moves change the length of a tour use it to rate tours without
+    /// listing their nodes.
+    fn evaluate_length(&self, _length: Scalar) -> Option<Scalar> {
+        None
+    }
+}
+
+/// Rates a tour by its length, negated when maximising.
+impl TourObjective for Objective {
+    fn evaluate(&self, order: &TourOrder) -> Scalar {
+        self.cost(order.cost())
+    }
+
+    fn evaluate_length(&self, length: Scalar) -> Option<Scalar> {
+        Some(self.cost(length))
+    }
+}
+
+/// Penalty term that measures how much a tour violates a side constraint.
+///
+/// Closures that take a [`TourOrder`] and return a [`Scalar`] are penalties, e.g. the lateness
+/// summed over the nodes of a tour with time windows.
+pub trait Penalty {
+    /// Returns the penalty of a tour, which should be zero if the tour satisfies the constraint.
+    fn penalty(&self, order: &TourOrder) -> Scalar;
+}
+
+impl<F> Penalty for F
+where
+    F: Fn(&TourOrder) -> Scalar,
+{
+    fn penalty(&self, order: &TourOrder) -> Scalar {
+        self(order)
+    }
+}
+
+/// Penalty that counts the edges of a tour that are not part of a reference tour.
+///
+/// Edges are undirected, see [`stats::stability`]. Weighted with the cost that a changed edge is
+/// worth, the penalty keeps a re-optimised tour close to the reference unless a change saves more.
+///
+/// [`stats::stability`]: crate::stats::stability
+#[derive(Clone, Debug)]
+pub struct Stability {
+    edges: HashSet<Edge>,
+}
+
+impl Stability {
+    /// Creates the penalty for changes to the given tour.
+    pub fn new(reference: &TourOrder) -> Self {
+        Self {
+            edges: viz::edges(reference),
+        }
+    }
+}
+
+impl Penalty for Stability {
+    fn penalty(&self, order: &TourOrder) -> Scalar {
+        viz::edges(order)
+            .iter()
+            .filter(|edge| !self.edges.contains(edge))
+            .count() as Scalar
+    }
+}
+
+/// Penalty that sums a layer of costs along a tour, so that further costs such as tolls can be
+/// weighed against the length of a tour.
+impl Penalty for CostLayer {
+    fn penalty(&self, order: &TourOrder) -> Scalar {
+        self.total(order)
+    }
+}
+
+/// Penalty that sums the turning angles of a tour, in radians, so that sharp turns cost extra,
+/// e.g. for drones or machining tools that slow down in corners.
+///
+/// The turn at a node is the angle between the leg that arrives at the node and the leg that
+/// leaves it, from zero when going straight on to pi when turning back. Only the part of an angle
+/// above the threshold is counted, and legs of zero length do not turn.
+#[derive(Clone, Debug)]
+pub struct TurnPenalty {
+    coords: Vec<(Scalar, Scalar, Scalar)>,
+    threshold: Scalar,
+}
+
+impl TurnPenalty {
+    /// Creates the penalty from the coordinates of the nodes of a store, which are copied, so that
+    /// later changes to the store are not reflected.
+    pub fn new<M>(store: &DataStore<M>) -> Self {
+        Self {
+            coords: store.iter_coords().collect(),
+            threshold: 0.,
+        }
+    }
+
+    /// Ignores turns up to the given angle in radians, and counts only the excess of sharper
+    /// turns.
+    pub fn with_threshold(mut self, threshold: Scalar) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Returns the angle of the turn at `b` on the way from `a` to `c`.
+    pub fn angle(&self, a: usize, b: usize, c: usize) -> Scalar {
+        let (pa, pb, pc) = match (self.coords.get(a), self.coords.get(b), self.coords.get(c)) {
+            (Some(pa), Some(pb), Some(pc)) => (pa, pb, pc),
+            _ => return 0.,
+        };
+        let u = (pb.0 - pa.0, pb.1 - pa.1, pb.2 - pa.2);
+        let v = (pc.0 - pb.0, pc.1 - pb.1, pc.2 - pb.2);
+        let norms =
+            (u.0 * u.0 + u.1 * u.1 + u.2 * u.2).sqrt() * (v.0 * v.0 + v.1 * v.1 + v.2 * v.2).sqrt();
+        if norms == 0. {
+            return 0.;
+        }
+
+        ((u.0 * v.0 + u.1 * v.1 + u.2 * v.2) / norms)
+            .clamp(-1., 1.)
+            .acos()
+    }
+}
+
+impl Penalty for TurnPenalty {
+    fn penalty(&self, order: &TourOrder) -> Scalar {
+        let nodes = order.order();
+        let len = nodes.len();
+        if len < 3 {
+            return 0.;
+        }
+
+        (0..len)
+            .map(|ii| {
+                let angle = self.angle(
+                    nodes[(ii + len - 1) % len],
+                    nodes[ii],
+                    nodes[(ii + 1) % len],
+                );
+                (angle - self.threshold).max(0.)
+            })
+            .sum()
+    }
+}
+
+/// Objective that adds weighted penalty terms to a base objective.
+pub struct Penalized<O> {
+    base: O,
+    terms: Vec<(Scalar, Box<dyn Penalty + Send + Sync>)>,
+}
+
+impl<O> Penalized<O>
+where
+    O: TourObjective,
+{
+    /// Creates an objective without penalty terms, which rates tours like `base`.
+    pub fn new(base: O) -> Self {
+        Self {
+            base,
+            terms: Vec::new(),
+        }
+    }
+
+    /// Adds a penalty term that is multiplied by `weight`.
+    pub fn with_penalty<P>(mut self, weight: Scalar, penalty: P) -> Self
+    where
+        P: Penalty + Send + Sync + 'static,
+    {
+        self.terms.push((weight, Box::new(penalty)));
+        self
+    }
+
+    /// Returns the base objective.
+    pub fn base(&self) -> &O {
+        &self.base
+    }
+
+    /// Returns the number of penalty terms.
+    pub fn n_penalties(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// Returns the weighted sum of the penalty terms of a tour.
+    pub fn penalties(&self, order: &TourOrder) -> Scalar {
+        self.terms
+            .iter()
+            .map(|(weight, term)| weight * term.penalty(order))
+            .sum()
+    }
+}
+
+impl<O> TourObjective for Penalized<O>
+where
+    O: TourObjective,
+{
+    fn evaluate(&self, order: &TourOrder) -> Scalar {
+        self.base.evaluate(order) + self.penalties(order)
+    }
+}
+
+impl<O> fmt::Debug for Penalized<O>
+where
+    O: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Penalized")
+            .field("base", &self.base)
+            .field(
+                "weights",
+                &self.terms.iter().map(|(w, _)| *w).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
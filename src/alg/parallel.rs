@@ -0,0 +1,96 @@
+//! Local search on disjoint windows of a tour, which can be processed in parallel.
+//!
+//! Tours backed by [`TwoLevelList`](crate::tour::TwoLevelList) cannot be shared between threads,
+//! thus the search operates on the order of a tour and takes a thread-safe cost function. With the
+//! `parallel` feature, windows are processed by `rayon` workers. Otherwise, they are processed
+//! one after another with the same result.
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{tour::TourOrder, Scalar};
+
+/// Improves a tour with 2-opt moves that are restricted to disjoint windows of consecutive nodes.
+///
+/// The order is split into windows of `window` nodes. Each window is a path whose end nodes stay in
+/// place, so that moves in different windows do not interfere with each other. After each round,
+/// the window boundaries are shifted by half a window, so that edges near the boundaries can be
+/// improved in the next round. The search stops after `rounds` rounds or when a round yields no
+/// gain.
+///
+/// The cost function must be symmetric. The returned order is a rotation of the improved tour,
+/// and its cost is measured with the cost function.
+pub fn partitioned_2opt<F>(order: &TourOrder, window: usize, rounds: usize, cost: F) -> TourOrder
+where
+    F: Fn(usize, usize) -> Scalar + Sync,
+{
+    let mut nodes = order.order().clone();
+    let window = window.max(4);
+    let shift = window / 2;
+
+    for round in 0..rounds {
+        if nodes.len() < 4 {
+            break;
+        }
+
+        if round > 0 {
+            let len = nodes.len();
+            nodes.rotate_left(shift % len);
+        }
+
+        #[cfg(feature = "parallel")]
+        let gain: Scalar = nodes
+            .par_chunks_mut(window)
+            .map(|path| optimise_path(path, &cost))
+            .sum();
+
+        #[cfg(not(feature = "parallel"))]
+        let gain: Scalar = nodes
+            .chunks_mut(window)
+            .map(|path| optimise_path(path, &cost))
+            .sum();
+
+        if gain <= 0. {
+            break;
+        }
+    }
+
+    let total = match (nodes.first(), nodes.last()) {
+        (Some(first), Some(last)) => {
+            nodes.windows(2).map(|w| cost(w[0], w[1])).sum::<Scalar>() + cost(*last, *first)
+        }
+        _ => 0.,
+    };
+
+    TourOrder::with_cost(nodes, total)
+}
+
+/// Applies improving 2-opt moves to a path with fixed end nodes until none is left, and returns
+/// the total gain.
+fn optimise_path<F>(path: &mut [usize], cost: &F) -> Scalar
+where
+    F: Fn(usize, usize) -> Scalar,
+{
+    let len = path.len();
+    let mut total = 0.;
+    let mut improved = true;
+
+    while improved {
+        improved = false;
+
+        for ii in 0..len.saturating_sub(3) {
+            for jj in (ii + 2)..(len - 1) {
+                let gain = cost(path[ii], path[ii + 1]) + cost(path[jj], path[jj + 1])
+                    - cost(path[ii], path[jj])
+                    - cost(path[ii + 1], path[jj + 1]);
+
+                if gain > 1e-10 {
+                    path[(ii + 1)..=jj].reverse();
+                    total += gain;
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    total
+}
@@ -0,0 +1,351 @@
+//! Solvers composed of phases, e.g. a greedy construction followed by 2-opt and Lin-Kernighan
+//! descents, iterated with double-bridge kicks.
+//!
+//! A [`Pipeline`] runs its construction phases once, then its improvement phases. If it has
+//! perturbation phases, it iterates: the tour is perturbed and improved again, and the resulting
+//! tour replaces the current one if the [`Acceptance`] strategy accepts it. Phases are built from
+//! the blocks of this crate or implemented by users, including closures that take the tour.
+//!
+//! The [`Objective`] of a pipeline is passed to all phases, and decides which tour is the best
+//! one, so that a pipeline can also search for the longest tour.
+use std::{sync::Arc, time::Instant};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    tour::{Tour, TourNode, TourOrder},
+    CancellationToken, CyklError, Scalar,
+};
+
+use super::{
+    acceptance::{Acceptance, Greedy},
+    adaptive::{or_opt_moves, two_opt_moves},
+    incumbent::{Incumbent, RestartPolicy},
+    lkh::{improve_lkh, random_double_bridge, KOpt, Move},
+    solvers::{descend_with, solve_greedy},
+    Objective, Scan, Termination,
+};
+
+/// Step of a [`Pipeline`] that changes a tour.
+pub trait Phase<T> {
+    /// Runs the phase on a tour, optimising in the direction of the objective of the pipeline.
+    fn run(&mut self, tour: &mut T, objective: Objective) -> Result<(), CyklError>;
+}
+
+/// Closures take the tour only, thus they do not learn the objective of the pipeline.
+impl<T, F> Phase<T> for F
+where
+    F: FnMut(&mut T) -> Result<(), CyklError>,
+{
+    fn run(&mut self, tour: &mut T, _: Objective) -> Result<(), CyklError> {
+        self(tour)
+    }
+}
+
+/// Constructs a tour with the greedy algorithm from the given starting nodes, see
+/// [`solve_greedy`].
+#[derive(Clone, Debug)]
+pub struct GreedyConstruction(pub Vec<usize>);
+
+impl<T> Phase<T> for GreedyConstruction
+where
+    T: Tour,
+{
+    fn run(&mut self, tour: &mut T, objective: Objective) -> Result<(), CyklError> {
+        solve_greedy(tour, &self.0, objective)?;
+        Ok(())
+    }
+}
+
+/// Descends with 2-opt moves between nodes and their candidates.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TwoOptDescent(pub Scan);
+
+impl<T> Phase<T> for TwoOptDescent
+where
+    T: Tour,
+{
+    fn run(&mut self, tour: &mut T, objective: Objective) -> Result<(), CyklError> {
+        descend_rated(tour, self.0, objective, two_opt_moves);
+        Ok(())
+    }
+}
+
+/// Descends with Or-opt moves, which relocate paths of up to three nodes next to a candidate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrOptDescent(pub Scan);
+
+impl<T> Phase<T> for OrOptDescent
+where
+    T: Tour,
+{
+    fn run(&mut self, tour: &mut T, objective: Objective) -> Result<(), CyklError> {
+        descend_rated(tour, self.0, objective, or_opt_moves);
+        Ok(())
+    }
+}
+
+/// Move whose gain is the decrease of the cost of [`Objective::cost`], so that descents lengthen
+/// the tour when maximising.
+struct Rated<M>(M, Objective);
+
+impl<M> Move for Rated<M>
+where
+    M: Move,
+{
+    fn gain(&self) -> Scalar {
+        self.1.cost(self.0.gain())
+    }
+
+    fn apply<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        self.0.apply(tour)
+    }
+
+    fn revert<T>(&self, tour: &mut T)
+    where
+        T: Tour,
+    {
+        self.0.revert(tour)
+    }
+}
+
+/// Descends with the moves of a generator like [`descend_with`], rating them by the objective.
+pub(super) fn descend_rated<T, M, G>(
+    tour: &mut T,
+    scan: Scan,
+    objective: Objective,
+    mut generate: G,
+) where
+    T: Tour,
+    M: Move,
+    G: FnMut(&T, &TourNode, &mut Vec<M>),
+{
+    let mut moves = Vec::new();
+    descend_with(tour, scan, |tour, node, rated: &mut Vec<Rated<M>>| {
+        generate(tour, node, &mut moves);
+        rated.extend(moves.drain(..).map(|mv| Rated(mv, objective)));
+    });
+}
+
+/// Improves the tour with the sequential search of the Lin-Kernighan heuristic, see
+/// [`improve_lkh`].
+#[derive(Clone, Copy, Debug)]
+pub struct LinKernighan(pub KOpt);
+
+impl<T> Phase<T> for LinKernighan
+where
+    T: Tour,
+{
+    fn run(&mut self, tour: &mut T, objective: Objective) -> Result<(), CyklError> {
+        improve_lkh(tour, self.0, objective)?;
+        Ok(())
+    }
+}
+
+/// Perturbs the tour with a random double-bridge move. Tours with fewer than eight nodes are left
+/// unchanged.
+#[derive(Clone, Debug)]
+pub struct DoubleBridgeKick {
+    rng: StdRng,
+}
+
+impl DoubleBridgeKick {
+    /// Creates the phase with a seed for the random number generator.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl<T> Phase<T> for DoubleBridgeKick
+where
+    T: Tour,
+{
+    fn run(&mut self, tour: &mut T, _: Objective) -> Result<(), CyklError> {
+        if let Some(kick) = random_double_bridge(tour, &mut self.rng) {
+            kick.apply(tour);
+        }
+        Ok(())
+    }
+}
+
+/// Perturbs the tour like the restarts of a [`RestartPolicy`], so that an iterated local search
+/// alternates between small and large perturbations over consecutive iterations.
+#[derive(Clone, Debug)]
+pub struct RestartKick {
+    policy: RestartPolicy,
+    restart: usize,
+}
+
+impl RestartKick {
+    /// Creates the phase with the policy whose strengths and perturbations it applies.
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self { policy, restart: 0 }
+    }
+}
+
+impl<T> Phase<T> for RestartKick
+where
+    T: Tour,
+{
+    fn run(&mut self, tour: &mut T, _: Objective) -> Result<(), CyklError> {
+        let order = self.policy.perturb(self.restart, &tour.tour_order());
+        tour.apply(&order)?;
+        self.restart += 1;
+        Ok(())
+    }
+}
+
+/// Solver composed of construction, improvement and perturbation phases and an acceptance
+/// strategy, see the [module documentation](self).
+pub struct Pipeline<T> {
+    pub(super) construct: Vec<Box<dyn Phase<T>>>,
+    pub(super) improve: Vec<Box<dyn Phase<T>>>,
+    pub(super) perturb: Vec<Box<dyn Phase<T>>>,
+    pub(super) acceptance: Box<dyn Acceptance>,
+    termination: Termination,
+    objective: Objective,
+    token: CancellationToken,
+    incumbent: Option<Arc<Incumbent>>,
+}
+
+impl<T> Pipeline<T>
+where
+    T: Tour,
+{
+    /// Creates an empty pipeline that iterates until the termination condition is reached, and
+    /// only accepts better tours.
+    pub fn new(termination: Termination) -> Self {
+        Self {
+            construct: Vec::new(),
+            improve: Vec::new(),
+            perturb: Vec::new(),
+            acceptance: Box::new(Greedy),
+            termination,
+            objective: Objective::Minimize,
+            token: CancellationToken::new(),
+            incumbent: None,
+        }
+    }
+
+    /// Appends a construction phase, which runs once at the start.
+    pub fn construct<P>(mut self, phase: P) -> Self
+    where
+        P: Phase<T> + 'static,
+    {
+        self.construct.push(Box::new(phase));
+        self
+    }
+
+    /// Appends an improvement phase, which runs after the construction and after each
+    /// perturbation.
+    pub fn improve<P>(mut self, phase: P) -> Self
+    where
+        P: Phase<T> + 'static,
+    {
+        self.improve.push(Box::new(phase));
+        self
+    }
+
+    /// Appends a perturbation phase, which runs at the start of each iteration.
+    pub fn perturb<P>(mut self, phase: P) -> Self
+    where
+        P: Phase<T> + 'static,
+    {
+        self.perturb.push(Box::new(phase));
+        self
+    }
+
+    /// Sets the strategy that decides whether a perturbed and improved tour replaces the current
+    /// one. Defaults to [`Greedy`].
+    pub fn accept<A>(mut self, acceptance: A) -> Self
+    where
+        A: Acceptance + 'static,
+    {
+        self.acceptance = Box::new(acceptance);
+        self
+    }
+
+    /// Sets the direction of the optimisation, which is passed to all phases and decides which
+    /// tour is the best one. Defaults to [`Objective::Minimize`].
+    pub fn objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Sets a token with which the caller can stop the iterations, after which the best tour found
+    /// so far is returned.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Attaches an incumbent to which the tour after the construction and each new best tour are
+    /// offered as soon as they are found.
+    pub fn with_incumbent(mut self, incumbent: Arc<Incumbent>) -> Self {
+        self.incumbent = Some(incumbent);
+        self
+    }
+
+    /// Runs all phases on a tour, leaves the best tour found in `tour` and returns it.
+    ///
+    /// Without perturbation phases, the pipeline stops after the first round of improvements. The
+    /// acceptance strategy decides on the costs of [`Objective::cost`].
+    pub fn run(&mut self, tour: &mut T) -> Result<TourOrder, CyklError> {
+        let start = Instant::now();
+        let objective = self.objective;
+        for phase in self.construct.iter_mut().chain(self.improve.iter_mut()) {
+            phase.run(tour, objective)?;
+        }
+
+        let mut current = tour.tour_order();
+        let mut best = current.clone();
+        self.offer(&best);
+        if self.perturb.is_empty() {
+            return Ok(best);
+        }
+
+        let (mut iterations, mut stagnation) = (0, 0);
+        while !self.token.is_cancelled()
+            && !self
+                .termination
+                .is_reached(iterations, stagnation, start.elapsed())
+        {
+            for phase in self.perturb.iter_mut().chain(self.improve.iter_mut()) {
+                phase.run(tour, objective)?;
+            }
+
+            let candidate = tour.tour_order();
+            let cost = |order: &TourOrder| objective.cost(order.cost());
+            if self.acceptance.accept(cost(&current), cost(&candidate)) {
+                current = candidate;
+            } else {
+                tour.apply(&current)?;
+            }
+
+            if objective.is_better(current.cost(), best.cost()) {
+                best = current.clone();
+                self.offer(&best);
+                stagnation = 0;
+            } else {
+                stagnation += 1;
+            }
+            self.acceptance.update(cost(&current), cost(&best));
+            iterations += 1;
+        }
+
+        tour.apply(&best)?;
+        Ok(best)
+    }
+
+    /// Offers a tour to the attached incumbent.
+    fn offer(&self, order: &TourOrder) {
+        if let Some(incumbent) = &self.incumbent {
+            incumbent.offer(order);
+        }
+    }
+}
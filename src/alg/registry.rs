@@ -0,0 +1,205 @@
+//! Registry of named phases, so that a [`Pipeline`] can be described by a configuration file
+//! instead of code.
+//!
+//! A [`PipelineSpec`] lists the phases of a pipeline by name, and can be read from JSON or TOML
+//! with the `serde` feature. [`Registry::build`] looks the names up and assembles the pipeline.
+//! The registry returned by [`Registry::new`] contains the building blocks of this crate:
+//!
+//! | Stage        | Names                                        |
+//! |--------------|----------------------------------------------|
+//! | construction | `greedy`                                     |
+//! | improvement  | `2opt`, `2opt-best`, `oropt`, `lk2`          |
+//! | perturbation | `double-bridge`, `restart`                   |
+//! | acceptance   | `greedy`, `sa`                               |
+//!
+//! `restart` is a [`RestartKick`] whose [`RestartPolicy`] applies one to four random reversals or
+//! swaps, cycling over the iterations. `sa` is [`SimulatedAnnealing`] with an initial temperature
+//! of 1 and a cooling factor of 0.99.
+//! Further phases and acceptance strategies are added with the `register_*` methods.
+//!
+//! Run configurations describe a pipeline with [`SolverKind::Pipeline`](super::SolverKind), which
+//! is built from [`Registry::new`], so that configuration files read by `RunConfig::from_toml` or
+//! `RunConfig::from_json` can choose the phases by name.
+use std::collections::HashMap;
+
+use crate::{tour::Tour, CyklError};
+
+use super::{
+    acceptance::{Acceptance, Greedy, SimulatedAnnealing},
+    incumbent::RestartPolicy,
+    lkh::KOpt,
+    pipeline::{
+        DoubleBridgeKick, GreedyConstruction, LinKernighan, OrOptDescent, Phase, Pipeline,
+        RestartKick, TwoOptDescent,
+    },
+    Objective, Scan, Termination,
+};
+
+/// Creates a phase from a seed for its random number generator.
+pub type PhaseFactory<T> = Box<dyn Fn(u64) -> Box<dyn Phase<T>>>;
+
+/// Creates an acceptance strategy from a seed for its random number generator.
+pub type AcceptanceFactory = Box<dyn Fn(u64) -> Box<dyn Acceptance>>;
+
+/// Description of a [`Pipeline`] by the names of its phases, see [`Registry::build`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PipelineSpec {
+    /// Names of the construction phases.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub construct: Vec<String>,
+    /// Names of the improvement phases.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub improve: Vec<String>,
+    /// Names of the perturbation phases.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub perturb: Vec<String>,
+    /// Name of the acceptance strategy, which defaults to `greedy`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub accept: Option<String>,
+    /// Condition under which the iterations of the perturbation phases stop.
+    pub termination: Termination,
+    /// Direction of the optimisation, which defaults to [`Objective::Minimize`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub objective: Objective,
+    /// Seed from which the seeds of all phases are derived.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: u64,
+}
+
+/// String-keyed collections of the factories of phases and acceptance strategies.
+pub struct Registry<T> {
+    constructions: HashMap<String, PhaseFactory<T>>,
+    improvements: HashMap<String, PhaseFactory<T>>,
+    perturbations: HashMap<String, PhaseFactory<T>>,
+    acceptances: HashMap<String, AcceptanceFactory>,
+}
+
+impl<T> Registry<T>
+where
+    T: Tour + 'static,
+{
+    /// Creates a registry with the built-in phases listed in the [module documentation](self).
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register_construction("greedy", |_| GreedyConstruction(vec![0]));
+        registry.register_improvement("2opt", |_| TwoOptDescent(Scan::FirstImprovement));
+        registry.register_improvement("2opt-best", |_| TwoOptDescent(Scan::BestImprovement));
+        registry.register_improvement("oropt", |_| OrOptDescent(Scan::FirstImprovement));
+        registry.register_improvement("lk2", |_| LinKernighan(KOpt::Opt2));
+        registry.register_perturbation("double-bridge", DoubleBridgeKick::new);
+        registry.register_perturbation("restart", |seed| {
+            RestartKick::new(RestartPolicy::new(1, 4, seed))
+        });
+        registry.register_acceptance("greedy", |_| Greedy);
+        registry.register_acceptance("sa", |seed| SimulatedAnnealing::new(1., 0.99, seed));
+        registry
+    }
+
+    /// Creates a registry without any phases.
+    pub fn empty() -> Self {
+        Self {
+            constructions: HashMap::new(),
+            improvements: HashMap::new(),
+            perturbations: HashMap::new(),
+            acceptances: HashMap::new(),
+        }
+    }
+
+    /// Registers a construction phase under a name, replacing any phase of the same name.
+    pub fn register_construction<F, P>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(u64) -> P + 'static,
+        P: Phase<T> + 'static,
+    {
+        self.constructions.insert(name.to_string(), boxed(factory));
+    }
+
+    /// Registers an improvement phase under a name, replacing any phase of the same name.
+    pub fn register_improvement<F, P>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(u64) -> P + 'static,
+        P: Phase<T> + 'static,
+    {
+        self.improvements.insert(name.to_string(), boxed(factory));
+    }
+
+    /// Registers a perturbation phase under a name, replacing any phase of the same name.
+    pub fn register_perturbation<F, P>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(u64) -> P + 'static,
+        P: Phase<T> + 'static,
+    {
+        self.perturbations.insert(name.to_string(), boxed(factory));
+    }
+
+    /// Registers an acceptance strategy under a name, replacing any strategy of the same name.
+    pub fn register_acceptance<F, A>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(u64) -> A + 'static,
+        A: Acceptance + 'static,
+    {
+        self.acceptances.insert(
+            name.to_string(),
+            Box::new(move |seed| Box::new(factory(seed)) as Box<dyn Acceptance>),
+        );
+    }
+
+    /// Assembles the pipeline described by a specification.
+    ///
+    /// Each phase receives its own seed, derived from the seed of the specification and the
+    /// position of the phase. Returns [`CyklError::UnknownPhase`] if a name is not registered for
+    /// its stage.
+    pub fn build(&self, spec: &PipelineSpec) -> Result<Pipeline<T>, CyklError> {
+        let mut seed = spec.seed;
+        let mut next_seed = || {
+            seed = seed.wrapping_add(1);
+            seed
+        };
+
+        let mut pipeline = Pipeline::new(spec.termination).objective(spec.objective);
+        for (names, registered, phases) in [
+            (
+                &spec.construct,
+                &self.constructions,
+                &mut pipeline.construct,
+            ),
+            (&spec.improve, &self.improvements, &mut pipeline.improve),
+            (&spec.perturb, &self.perturbations, &mut pipeline.perturb),
+        ] {
+            for name in names {
+                let factory = registered
+                    .get(name)
+                    .ok_or_else(|| CyklError::UnknownPhase(name.clone()))?;
+                phases.push(factory(next_seed()));
+            }
+        }
+
+        if let Some(name) = &spec.accept {
+            let factory = self
+                .acceptances
+                .get(name)
+                .ok_or_else(|| CyklError::UnknownPhase(name.clone()))?;
+            pipeline.acceptance = factory(next_seed());
+        }
+
+        Ok(pipeline)
+    }
+}
+
+impl<T> Default for Registry<T>
+where
+    T: Tour + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn boxed<T, F, P>(factory: F) -> PhaseFactory<T>
+where
+    F: Fn(u64) -> P + 'static,
+    P: Phase<T> + 'static,
+{
+    Box::new(move |seed| Box::new(factory(seed)) as Box<dyn Phase<T>>)
+}
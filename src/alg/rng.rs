@@ -0,0 +1,46 @@
+//! Random number generator of the stochastic solvers.
+//!
+//! The generator is the ChaCha12 generator behind [`StdRng`](rand::rngs::StdRng), so seeded runs
+//! produce the same sequences as with `StdRng`. Unlike `StdRng`, its state can be inspected, which
+//! allows the solvers to serialise their generators with the `serde` feature.
+pub(crate) type SolverRng = rand_chacha::ChaCha12Rng;
+
+/// Serialises a generator as its seed, stream and position in the stream, so that a deserialised
+/// generator continues with the same sequence without replaying the values drawn before.
+#[cfg(feature = "serde")]
+pub(crate) mod state {
+    use rand::SeedableRng;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::SolverRng;
+
+    #[derive(Serialize, Deserialize)]
+    struct RngState {
+        seed: [u8; 32],
+        stream: u64,
+        word_pos: u128,
+    }
+
+    pub(crate) fn serialize<S>(rng: &SolverRng, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+        .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<SolverRng, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let state = RngState::deserialize(deserializer)?;
+        let mut rng = SolverRng::from_seed(state.seed);
+        rng.set_stream(state.stream);
+        rng.set_word_pos(state.word_pos);
+        Ok(rng)
+    }
+}
@@ -1,13 +1,34 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
 use crate::{
-    data::GetIndex,
-    tour::{NodeStatus, Tour, TourOrder, UpdateTourError},
-    Scalar,
+    data::DataStore,
+    tour::{NodeStatus, Tour, TourNode, TourOrder, UpdateTourError},
+    CancellationToken, CyklError, Scalar,
+};
+
+use super::{
+    acceptance::{Acceptance, LateAcceptance},
+    incumbent::Incumbent,
+    lkh::{
+        random_2opt,
+        searches::{search_2_opt, search_prize, search_swap_representative},
+        Move, SearchResult,
+    },
+    objective::TourObjective,
+    Objective, Scan, Termination,
 };
 
 /// Uses greedy algorithm to construct a tour.
 pub fn solve_greedy<T>(
     tour: &mut T,
     starters: &[usize],
+    objective: Objective,
 ) -> Result<Option<TourOrder>, UpdateTourError>
 where
     T: Tour,
@@ -29,7 +50,7 @@ where
             None => return Err(UpdateTourError::NodeNotFound),
         };
 
-        v.push(node.index().get());
+        v.push(tour.index_of(&node));
         node.set_status(NodeStatus::Fixed);
 
         while v.len() != len {
@@ -52,8 +73,9 @@ where
                         continue;
                     }
 
-                    let next_d = tour.distance(&node, &next_node);
-                    if next_d < d && next_d > 0. {
+                    // Zero-cost edges are allowed, since nodes might share the same location.
+                    let next_d = objective.cost(tour.distance(&node, &next_node));
+                    if next_d < d {
                         d = next_d;
                         cand = Some(next_node);
                     }
@@ -66,17 +88,17 @@ where
             });
 
             next.set_status(NodeStatus::Fixed);
-            v.push(next.index().get());
+            v.push(tour.index_of(&next));
             node = next;
         }
 
         let mut to = TourOrder::with_ord(v);
         let cost = tour.measure(&to);
 
-        if cost < best_cost {
+        if objective.cost(cost) < best_cost {
             to.set_cost(cost);
             best_tour = Some(to);
-            best_cost = cost;
+            best_cost = objective.cost(cost);
         }
     }
 
@@ -86,3 +108,580 @@ where
 
     Ok(best_tour)
 }
+
+/// Improves a tour for the prize-collecting variant of the problem by repeatedly removing and
+/// inserting nodes until no such move is gainful.
+///
+/// The cost of the returned tour order is the sum of its length and the prizes of all skipped
+/// nodes, which is minimised or maximised according to the objective.
+pub fn solve_prize_collecting<T>(
+    tour: &mut T,
+    objective: Objective,
+) -> Result<TourOrder, UpdateTourError>
+where
+    T: Tour,
+{
+    let mut improved = true;
+    while improved {
+        improved = false;
+
+        for index in 0..tour.len() {
+            let base = match tour.get(index) {
+                Some(node) => node,
+                None => return Err(UpdateTourError::NodeNotFound),
+            };
+
+            match search_prize(tour, &base, objective) {
+                Ok(SearchResult::Gainful(_)) => improved = true,
+                Ok(SearchResult::NonGainful(_)) | Err(UpdateTourError::SearchFailed) => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    let mut to = tour.tour_order();
+    to.set_cost(to.cost() + tour.penalty());
+    Ok(to)
+}
+
+/// Turns a tour into a feasible tour for the generalised variant of the problem, in which exactly
+/// one node of each group is visited, and improves it by 2-opt and representative swap moves.
+///
+/// Initially, the members of each group whose removal improves the tour the most in terms of the
+/// objective are excluded until a single representative remains.
+pub fn solve_generalized<T>(
+    tour: &mut T,
+    objective: Objective,
+) -> Result<TourOrder, UpdateTourError>
+where
+    T: Tour,
+{
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for node in tour.itr() {
+        let index = tour.index_of(&node);
+        if let (Some(group), true) = (tour.group(index), tour.is_included(index)) {
+            groups.entry(group).or_default().push(index);
+        }
+    }
+
+    for mut members in groups.into_values() {
+        while members.len() > 1 {
+            let mut best = (Scalar::MIN, 0);
+            for (pos, index) in members.iter().enumerate() {
+                let saving = objective.cost(removal_saving(tour, *index)?);
+                if saving > best.0 {
+                    best = (saving, pos);
+                }
+            }
+
+            tour.exclude(members.swap_remove(best.1))?;
+        }
+    }
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+
+        for index in 0..tour.len() {
+            if !tour.is_included(index) {
+                continue;
+            }
+
+            let base = match tour.get(index) {
+                Some(node) => node,
+                None => return Err(UpdateTourError::NodeNotFound),
+            };
+
+            let result = match tour.successor(&base) {
+                Some(succ) => search_2_opt(tour, &base, &succ, objective),
+                None => return Err(UpdateTourError::NodeNotFound),
+            };
+
+            let result = match result {
+                Err(UpdateTourError::SearchFailed) => {
+                    search_swap_representative(tour, &base, objective)
+                }
+                result => result,
+            };
+
+            match result {
+                Ok(SearchResult::Gainful(_)) => improved = true,
+                Ok(SearchResult::NonGainful(_)) | Err(UpdateTourError::SearchFailed) => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    Ok(tour.tour_order())
+}
+
+/// Returns the distance saved by removing an included node from a tour.
+fn removal_saving<T>(tour: &T, index: usize) -> Result<Scalar, UpdateTourError>
+where
+    T: Tour,
+{
+    let node = tour.get(index).ok_or(UpdateTourError::NodeNotFound)?;
+    match (tour.predecessor(&node), tour.successor(&node)) {
+        (Some(pred), Some(succ)) => {
+            Ok(tour.distance(&pred, &node) + tour.distance(&node, &succ)
+                - tour.distance(&pred, &succ))
+        }
+        _ => Err(UpdateTourError::NodeNotFound),
+    }
+}
+
+/// Applies improving moves until no such move is left, and returns the total gain.
+///
+/// For each node, `generate` appends the moves to be considered to the given buffer. The first
+/// move whose gain exceeds a small tolerance is applied, after which the search continues with the
+/// next node.
+pub fn descend<T, M, G>(tour: &mut T, generate: G) -> Scalar
+where
+    T: Tour,
+    M: Move,
+    G: FnMut(&T, &TourNode, &mut Vec<M>),
+{
+    descend_with(tour, Scan::FirstImprovement, generate)
+}
+
+/// Applies improving moves until no such move is left, and returns the total gain.
+///
+/// For each node, `generate` appends the moves to be considered to the given buffer, among which
+/// `scan` picks the move to be applied. Only moves whose gain exceeds a small tolerance are
+/// applied, after which the search continues with the next node. The buffer is emptied before each
+/// node and reused across nodes, so that the search does not allocate once it has grown.
+pub fn descend_with<T, M, G>(tour: &mut T, scan: Scan, mut generate: G) -> Scalar
+where
+    T: Tour,
+    M: Move,
+    G: FnMut(&T, &TourNode, &mut Vec<M>),
+{
+    const EPSILON: Scalar = 1e-9;
+
+    let mut total = 0.;
+    let mut moves = Vec::new();
+    let mut improved = true;
+    while improved {
+        improved = false;
+
+        for index in 0..tour.len() {
+            let node = match tour.get(index) {
+                Some(node) => node,
+                None => continue,
+            };
+
+            moves.clear();
+            generate(tour, &node, &mut moves);
+            let chosen = match scan {
+                Scan::FirstImprovement => moves.iter().find(|mv| mv.gain() > EPSILON),
+                Scan::BestImprovement => moves
+                    .iter()
+                    .filter(|mv| mv.gain() > EPSILON)
+                    .max_by(|a, b| a.gain().partial_cmp(&b.gain()).unwrap_or(Ordering::Equal)),
+            };
+
+            if let Some(mv) = chosen {
+                mv.apply(tour);
+                total += mv.gain();
+                improved = true;
+            }
+        }
+    }
+
+    total
+}
+
+/// Runs a stochastic local search until the termination condition is reached and returns the best
+/// tour found.
+///
+/// In each iteration, `generate` proposes a move for the current tour. The move is applied if the
+/// acceptance strategy accepts the resulting tour length. Iterations in which no move is proposed
+/// still count towards the termination condition.
+pub fn local_search<T, M, A, G>(
+    tour: &mut T,
+    acceptance: &mut A,
+    generate: G,
+    termination: Termination,
+) -> TourOrder
+where
+    T: Tour,
+    M: Move,
+    A: Acceptance,
+    G: FnMut(&T) -> Option<M>,
+{
+    let mut progress = Progress::new(tour.tour_order(), Objective::Minimize);
+    let token = CancellationToken::new();
+    search(
+        tour,
+        acceptance,
+        generate,
+        termination,
+        &token,
+        &mut progress,
+    );
+    progress.best
+}
+
+/// Runs a stochastic local search like [`local_search`], but rates tours with the given objective
+/// instead of their length, and returns the tour with the lowest value.
+///
+/// Since the gain of a move only reflects the change in length, each proposed move is applied and
+/// the whole tour is evaluated, which takes O(N) time per iteration. The move is reverted if the
+/// acceptance strategy rejects the value of the resulting tour.
+pub fn local_search_with_objective<T, M, A, G, O>(
+    tour: &mut T,
+    acceptance: &mut A,
+    mut generate: G,
+    termination: Termination,
+    objective: &O,
+) -> TourOrder
+where
+    T: Tour,
+    M: Move,
+    A: Acceptance,
+    G: FnMut(&T) -> Option<M>,
+    O: TourObjective + ?Sized,
+{
+    let start = Instant::now();
+    let mut best = tour.tour_order();
+    let mut best_value = objective.evaluate(&best);
+    let mut current_value = best_value;
+    let (mut iterations, mut stagnation) = (0, 0);
+
+    while !termination.is_reached(iterations, stagnation, start.elapsed()) {
+        iterations += 1;
+        stagnation += 1;
+
+        if let Some(mv) = generate(tour) {
+            mv.apply(tour);
+            let candidate = tour.tour_order();
+            let candidate_value = objective.evaluate(&candidate);
+
+            if acceptance.accept(current_value, candidate_value) {
+                current_value = candidate_value;
+                if current_value < best_value {
+                    best = candidate;
+                    best_value = current_value;
+                    stagnation = 0;
+                }
+            } else {
+                mv.revert(tour);
+            }
+        }
+
+        acceptance.update(current_value, best_value);
+    }
+
+    best
+}
+
+/// Resumable state of a stochastic local search, see [`resume_search`].
+///
+/// With the `serde` feature, the state can be serialised to continue a run after the process has
+/// stopped.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchState<A> {
+    current: TourOrder,
+    best: TourOrder,
+    iterations: usize,
+    stagnation: usize,
+    elapsed: Duration,
+    seed: u64,
+    acceptance: A,
+    #[cfg_attr(feature = "serde", serde(default))]
+    fingerprint: Option<u64>,
+}
+
+impl<A> SearchState<A>
+where
+    A: Acceptance,
+{
+    /// Creates the state of a search that starts from the current order of a tour.
+    pub fn new<T>(tour: &T, acceptance: A, seed: u64) -> Self
+    where
+        T: Tour,
+    {
+        let current = tour.tour_order();
+        Self {
+            best: current.clone(),
+            current,
+            iterations: 0,
+            stagnation: 0,
+            elapsed: Duration::ZERO,
+            seed,
+            acceptance,
+            fingerprint: None,
+        }
+    }
+
+    /// Stamps the state with the fingerprint of the instance it belongs to, see
+    /// [`SearchState::verify`].
+    pub fn with_fingerprint<M>(mut self, store: &DataStore<M>) -> Self {
+        self.fingerprint = Some(store.fingerprint());
+        self
+    }
+
+    /// Returns the fingerprint of the instance the state belongs to, if it was stamped.
+    pub fn fingerprint(&self) -> Option<u64> {
+        self.fingerprint
+    }
+
+    /// Checks that a restored state belongs to the given instance before resuming it.
+    ///
+    /// Returns [`CyklError::InstanceMismatch`] if the state was stamped for another instance.
+    /// States without fingerprint are accepted.
+    pub fn verify<M>(&self, store: &DataStore<M>) -> Result<(), CyklError> {
+        match self.fingerprint {
+            Some(found) if found != store.fingerprint() => Err(CyklError::InstanceMismatch {
+                expected: store.fingerprint(),
+                found,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the order of the tour at the end of the last run.
+    pub fn current(&self) -> &TourOrder {
+        &self.current
+    }
+
+    /// Returns the best tour found so far.
+    pub fn best(&self) -> &TourOrder {
+        &self.best
+    }
+
+    /// Returns the number of iterations run so far.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Returns the time spent in the search so far.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the acceptance strategy.
+    pub fn acceptance(&self) -> &A {
+        &self.acceptance
+    }
+
+    /// Consumes the state and returns the best tour found.
+    pub fn into_best(self) -> TourOrder {
+        self.best
+    }
+}
+
+/// Continues a stochastic local search from a saved state until the termination condition is
+/// reached, and updates the state.
+///
+/// The tour is rearranged to the current order of the state first. Iteration counts and elapsed
+/// time include previous runs from the same state, so a run can be split into chunks, e.g. to
+/// save a checkpoint after each chunk. The random number generator passed to `generate` is
+/// reseeded at the end of each run, thus the result depends on how the run is split, but not on
+/// whether the state was serialised in between.
+///
+/// Returns [`CyklError::InstanceMismatch`] without touching the tour if the state was stamped
+/// for another instance than `store`, see [`SearchState::verify`].
+pub fn resume_search<T, S, M, A, G>(
+    tour: &mut T,
+    store: &DataStore<S>,
+    state: &mut SearchState<A>,
+    mut generate: G,
+    termination: Termination,
+) -> Result<(), CyklError>
+where
+    T: Tour,
+    M: Move,
+    A: Acceptance,
+    G: FnMut(&T, &mut StdRng) -> Option<M>,
+{
+    state.verify(store)?;
+    tour.apply(&state.current)?;
+
+    let mut rng = StdRng::seed_from_u64(state.seed);
+    let mut progress = Progress {
+        best: state.best.clone(),
+        current: state.current.cost(),
+        iterations: state.iterations,
+        stagnation: state.stagnation,
+        elapsed: state.elapsed,
+        objective: Objective::Minimize,
+        incumbent: None,
+    };
+
+    search(
+        tour,
+        &mut state.acceptance,
+        |tour: &T| generate(tour, &mut rng),
+        termination,
+        &CancellationToken::new(),
+        &mut progress,
+    );
+
+    state.current = tour.tour_order();
+    state.best = progress.best;
+    state.iterations = progress.iterations;
+    state.stagnation = progress.stagnation;
+    state.elapsed = progress.elapsed;
+    state.seed = rng.gen();
+
+    Ok(())
+}
+
+/// Improves a tour with late acceptance hill climbing on random 2-opt moves, and returns the best
+/// tour found.
+///
+/// `history` is the number of past tour lengths against which candidates are compared. Use
+/// [`solve_lahc_with`] to search for the longest tour.
+///
+/// See E. Burke and Y. Bykov (2017), "The late acceptance hill-climbing heuristic".
+pub fn solve_lahc<T>(tour: &mut T, history: usize, termination: Termination, seed: u64) -> TourOrder
+where
+    T: Tour,
+{
+    solve_lahc_with(
+        tour,
+        history,
+        termination,
+        seed,
+        Objective::Minimize,
+        &CancellationToken::new(),
+    )
+}
+
+/// Improves a tour like [`solve_lahc`] in the direction of the objective, and also stops once the
+/// token is cancelled.
+///
+/// Candidates are compared by their [`Objective::cost`], thus when maximising, a candidate is
+/// accepted if it is not shorter than the current tour or the tour of `history` iterations ago.
+/// The token is checked in each iteration. The best tour found until then is returned.
+pub fn solve_lahc_with<T>(
+    tour: &mut T,
+    history: usize,
+    termination: Termination,
+    seed: u64,
+    objective: Objective,
+    token: &CancellationToken,
+) -> TourOrder
+where
+    T: Tour,
+{
+    solve_lahc_reporting(tour, history, termination, seed, objective, token, None)
+}
+
+/// Improves a tour like [`solve_lahc_with`], and offers the initial tour and each new best tour
+/// to the incumbent as soon as it is found.
+pub(crate) fn solve_lahc_reporting<T>(
+    tour: &mut T,
+    history: usize,
+    termination: Termination,
+    seed: u64,
+    objective: Objective,
+    token: &CancellationToken,
+    incumbent: Option<&Incumbent>,
+) -> TourOrder
+where
+    T: Tour,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = Progress::new(tour.tour_order(), objective);
+    progress.incumbent = incumbent;
+    progress.offer();
+    search(
+        tour,
+        &mut LateAcceptance::new(history),
+        |tour: &T| random_2opt(tour, &mut rng),
+        termination,
+        token,
+        &mut progress,
+    );
+    progress.best
+}
+
+/// Counters of a local search that are carried over between runs.
+///
+/// `current` is the cost of the current tour in the sense of [`Objective::cost`], on which the
+/// acceptance strategy decides. New best tours are offered to `incumbent` if it is set.
+struct Progress<'a> {
+    best: TourOrder,
+    current: Scalar,
+    iterations: usize,
+    stagnation: usize,
+    elapsed: Duration,
+    objective: Objective,
+    incumbent: Option<&'a Incumbent>,
+}
+
+impl Progress<'_> {
+    fn new(best: TourOrder, objective: Objective) -> Self {
+        Self {
+            current: objective.cost(best.cost()),
+            best,
+            iterations: 0,
+            stagnation: 0,
+            elapsed: Duration::ZERO,
+            objective,
+            incumbent: None,
+        }
+    }
+
+    /// Returns the cost of the best tour.
+    fn best_cost(&self) -> Scalar {
+        self.objective.cost(self.best.cost())
+    }
+
+    /// Offers the best tour to the incumbent.
+    fn offer(&self) {
+        if let Some(incumbent) = self.incumbent {
+            incumbent.offer(&self.best);
+        }
+    }
+}
+
+fn search<T, M, A, G>(
+    tour: &mut T,
+    acceptance: &mut A,
+    mut generate: G,
+    termination: Termination,
+    token: &CancellationToken,
+    progress: &mut Progress<'_>,
+) where
+    T: Tour,
+    M: Move,
+    A: Acceptance,
+    G: FnMut(&T) -> Option<M>,
+{
+    let start = Instant::now();
+    let offset = progress.elapsed;
+
+    while !token.is_cancelled()
+        && !termination.is_reached(
+            progress.iterations,
+            progress.stagnation,
+            offset + start.elapsed(),
+        )
+    {
+        progress.iterations += 1;
+        progress.stagnation += 1;
+
+        if let Some(mv) = generate(tour) {
+            let candidate = progress.current - progress.objective.cost(mv.gain());
+            if acceptance.accept(progress.current, candidate) {
+                mv.apply(tour);
+                progress.current = candidate;
+
+                if progress.current < progress.best_cost() {
+                    progress.best = tour.tour_order();
+                    progress.current = progress.best_cost();
+                    progress.stagnation = 0;
+                    progress.offer();
+                }
+            }
+        }
+
+        acceptance.update(progress.current, progress.best_cost());
+    }
+
+    progress.elapsed = offset + start.elapsed();
+}
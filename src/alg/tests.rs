@@ -1,14 +1,47 @@
 #![cfg(test)]
+use std::{sync::Arc, time::Duration};
+
+use rand::{rngs::StdRng, SeedableRng};
+
 use crate::{
     combine_range,
+    data::{DataStore, GetIndex, Metric, NodeKind},
     tour::{
         tests::{create_store, test_tour_order},
-        Tour, TourOrder, TwoLevelList,
+        Tour, TourNode, TourOrder, TwoLevelList,
     },
-    tour_order,
+    tour_order, viz, CancellationToken, CyklError, RunConfigBuilder, Scalar,
 };
 
-use super::lkh::{move_2_opt, move_3_opt, move_4_opt, Opt3Move, Opt4SeqMove};
+use super::{
+    acceptance::{
+        Acceptance, GreatDeluge, Greedy, LateAcceptance, RecordToRecord, SimulatedAnnealing,
+    },
+    adaptive::{Adaptive, Operator},
+    bounds::BoundsTracker,
+    cand_gen_nn, cand_gen_quadrant,
+    incumbent::{
+        multi_start, multi_start_restarts, multi_start_with, Incumbent, Parallelism, RestartPolicy,
+    },
+    lkh::{
+        enumerate_2opt, enumerate_3opt, move_2_opt, move_3_opt, move_4_opt, random_2opt,
+        DoubleBridge, KOpt, Move, Opt3Move, Opt4SeqMove, OrOpt,
+    },
+    lns::{Destroy, Lns, Repair},
+    nearest_fragment,
+    objective::{Penalized, TourObjective},
+    parallel::partitioned_2opt,
+    pipeline::{
+        DoubleBridgeKick, GreedyConstruction, LinKernighan, OrOptDescent, Pipeline, RestartKick,
+        TwoOptDescent,
+    },
+    registry::{PipelineSpec, Registry},
+    solvers::{
+        descend, descend_with, local_search, local_search_with_objective, resume_search,
+        solve_greedy, solve_lahc, solve_lahc_with, SearchState,
+    },
+    Objective, Scan, Termination,
+};
 
 #[test]
 fn test_move_2_opt() {
@@ -63,6 +96,84 @@ fn test_move_3_opt() {
     test_tour_order(&tour, &tour_order![0..6, 11..16, 6..11, 16..20]);
 }
 
+#[test]
+fn test_moves_across_segments() {
+    // The first flip of these moves reverses the traversal direction of the tour, after which the
+    // second flip must be oriented accordingly.
+    let mut tour = TwoLevelList::new(&create_store(12), 4);
+    let (f1, f2, f3) = (
+        tour.get(0).unwrap(),
+        tour.get(6).unwrap(),
+        tour.get(8).unwrap(),
+    );
+    let (t1, t2, t3) = (
+        tour.successor(&f1).unwrap(),
+        tour.successor(&f2).unwrap(),
+        tour.successor(&f3).unwrap(),
+    );
+    move_3_opt(
+        &mut tour,
+        (&f1, &t1),
+        (&f2, &t2),
+        (&f3, &t3),
+        Opt3Move::Move7,
+    );
+    test_tour_order(&tour, &tour_order![0..1, 7..9, 1..7, 9..12]);
+
+    let mut tour = TwoLevelList::new(&create_store(16), 4);
+    let (f1, f2, f3, f4) = (
+        tour.get(0).unwrap(),
+        tour.get(6).unwrap(),
+        tour.get(10).unwrap(),
+        tour.get(12).unwrap(),
+    );
+    let (t1, t2, t3, t4) = (
+        tour.successor(&f1).unwrap(),
+        tour.successor(&f2).unwrap(),
+        tour.successor(&f3).unwrap(),
+        tour.successor(&f4).unwrap(),
+    );
+    move_4_opt(
+        &mut tour,
+        (&f1, &t1),
+        (&f2, &t2),
+        (&f3, &t3),
+        (&f4, &t4),
+        Opt4SeqMove::Move3,
+    );
+    test_tour_order(
+        &tour,
+        &tour_order![0..1, 7..11, (1..7).rev(), (11..13).rev(), 13..16],
+    );
+}
+
+#[test]
+fn test_solve_lkh() {
+    use super::{
+        cand_gen_nn,
+        lkh::{solve_lkh, KOpt},
+        Objective,
+    };
+
+    // Bases whose search fails are not requeued, so the loop terminates.
+    let len = 16;
+    let mut tour = TwoLevelList::new(&create_scattered_store(len), 4);
+    let initial = tour.tour_order().cost();
+    cand_gen_nn(&mut tour, 5, Objective::Minimize);
+    assert!(solve_lkh(&mut tour, KOpt::Opt2, 2, Objective::Minimize).is_ok());
+
+    let after = tour.tour_order();
+    assert!(after.cost() < initial);
+    let mut v = after.order().clone();
+    v.sort_unstable();
+    assert_eq!((0..len).collect::<Vec<_>>(), v);
+
+    // The tour is left at the best trial in terms of the objective.
+    cand_gen_nn(&mut tour, 5, Objective::Maximize);
+    assert!(solve_lkh(&mut tour, KOpt::Opt2, 2, Objective::Maximize).is_ok());
+    assert!(Objective::Maximize.is_better(tour.tour_order().cost(), after.cost()));
+}
+
 #[allow(unused_macros)]
 macro_rules! tour_4 {
     ($($x:expr),+) => {{
@@ -202,6 +313,604 @@ fn test_move_4_opt() {
     );
 }
 
+fn create_scattered_store(n_nodes: usize) -> DataStore<()> {
+    let mut store = DataStore::with_capacity(Metric::Euc2d, n_nodes);
+    for ii in 0..n_nodes {
+        let pos = vec![((ii * 7) % n_nodes) as f64, ((ii * 5) % 11) as f64];
+        store.add(NodeKind::Target, pos, ());
+    }
+    store.compute();
+    store
+}
+
+#[test]
+fn test_enumerate_moves() {
+    let len = 12;
+    let mut tour = TwoLevelList::new(&create_scattered_store(len), 4);
+    let nat_ord = TourOrder::with_nat_ord(len);
+    let before = tour.tour_order().cost();
+
+    for index in 0..len {
+        let node = tour.get(index).unwrap();
+        let cands: Vec<_> = tour.itr().filter(|x| *x != node).collect();
+
+        let moves: Vec<_> = enumerate_2opt(&tour, &node, &cands).collect();
+        assert!(!moves.is_empty());
+        for mv in moves {
+            let ((f1, t1), (f2, t2)) = (mv.pair_1, mv.pair_2);
+            move_2_opt(&mut tour, &f1, &t1, &f2, &t2);
+            let after = tour.tour_order();
+            assert_eq!(len, after.len());
+            assert!((before - after.cost() - mv.gain).abs() < 1e-9);
+            assert!(tour.apply(&nat_ord).is_ok());
+        }
+
+        let moves: Vec<_> = enumerate_3opt(&tour, &node, &cands).collect();
+        assert!(!moves.is_empty());
+        for mv in moves {
+            let (p1, p2, p3) = (mv.pair_1, mv.pair_2, mv.pair_3);
+            move_3_opt(
+                &mut tour,
+                (&p1.0, &p1.1),
+                (&p2.0, &p2.1),
+                (&p3.0, &p3.1),
+                mv.case,
+            );
+            let after = tour.tour_order();
+            assert_eq!(len, after.len());
+            assert!((before - after.cost() - mv.gain).abs() < 1e-9);
+            assert!(tour.apply(&nat_ord).is_ok());
+        }
+    }
+}
+
+#[test]
+fn test_move_revert() {
+    let len = 12;
+    let mut tour = TwoLevelList::new(&create_scattered_store(len), 4);
+    let original = viz::edges(&tour.tour_order());
+
+    let check = |tour: &mut TwoLevelList, mv: &dyn Fn(&mut TwoLevelList, bool)| {
+        let before = tour.tour_order().cost();
+        mv(tour, true);
+        assert_ne!(original, viz::edges(&tour.tour_order()));
+        mv(tour, false);
+        assert_eq!(original, viz::edges(&tour.tour_order()));
+        assert!((before - tour.tour_order().cost()).abs() < 1e-9);
+
+        // Reverting might change the direction of the traversal.
+        assert!(tour.apply(&TourOrder::with_nat_ord(len)).is_ok());
+    };
+
+    let node = tour.get(0).unwrap();
+    let cands: Vec<_> = tour.itr().filter(|x| *x != node).collect();
+    for mv in enumerate_2opt(&tour, &node, &cands).collect::<Vec<_>>() {
+        check(&mut tour, &|t, fwd| {
+            if fwd {
+                mv.apply(t)
+            } else {
+                mv.revert(t)
+            }
+        });
+    }
+    for mv in enumerate_3opt(&tour, &node, &cands).collect::<Vec<_>>() {
+        check(&mut tour, &|t, fwd| {
+            if fwd {
+                mv.apply(t)
+            } else {
+                mv.revert(t)
+            }
+        });
+    }
+
+    let at = |tour: &TwoLevelList, ii| tour.get(ii).unwrap();
+    for &reversed in &[false, true] {
+        let mv = OrOpt::new(&tour, &at(&tour, 2), &at(&tour, 4), &at(&tour, 8), reversed).unwrap();
+        let before = tour.tour_order().cost();
+        mv.apply(&mut tour);
+        assert!((before - tour.tour_order().cost() - mv.gain()).abs() < 1e-9);
+        mv.revert(&mut tour);
+        assert_eq!(original, viz::edges(&tour.tour_order()));
+        assert!(tour.apply(&TourOrder::with_nat_ord(len)).is_ok());
+    }
+    assert!(OrOpt::new(&tour, &at(&tour, 2), &at(&tour, 4), &at(&tour, 3), false).is_none());
+    assert!(OrOpt::new(&tour, &at(&tour, 2), &at(&tour, 4), &at(&tour, 1), false).is_none());
+
+    let nodes = [at(&tour, 1), at(&tour, 4), at(&tour, 7), at(&tour, 10)];
+    let mv = DoubleBridge::new(&tour, nodes).unwrap();
+    let before = tour.tour_order().cost();
+    mv.apply(&mut tour);
+    let mut expected = vec![0, 1];
+    expected.extend(&[8, 9, 10, 5, 6, 7, 2, 3, 4, 11]);
+    assert_eq!(
+        viz::edges(&TourOrder::with_ord(expected)),
+        viz::edges(&tour.tour_order())
+    );
+    assert!((before - tour.tour_order().cost() - mv.gain()).abs() < 1e-9);
+    mv.revert(&mut tour);
+    assert_eq!(original, viz::edges(&tour.tour_order()));
+
+    let nodes = [at(&tour, 1), at(&tour, 7), at(&tour, 4), at(&tour, 10)];
+    assert!(DoubleBridge::new(&tour, nodes).is_none());
+}
+
+#[test]
+fn test_descend() {
+    let len = 12;
+    let mut tour = TwoLevelList::new(&create_scattered_store(len), 4);
+    let before = tour.tour_order().cost();
+
+    let generate = |tour: &TwoLevelList, node: &TourNode, moves: &mut Vec<_>| {
+        let cands: Vec<_> = tour.itr().filter(|x| x != node).collect();
+        moves.extend(enumerate_2opt(tour, node, &cands));
+    };
+    let is_optimal = |tour: &TwoLevelList| {
+        tour.itr().all(|node| {
+            let mut moves = Vec::new();
+            generate(tour, &node, &mut moves);
+            moves.iter().all(|mv| mv.gain() <= 1e-9)
+        })
+    };
+
+    let gain = descend(&mut tour, generate);
+    let after = tour.tour_order();
+    assert_eq!(len, after.len());
+    assert!(gain > 0.);
+    assert!((before - after.cost() - gain).abs() < 1e-9);
+    assert!(is_optimal(&tour));
+
+    let mut tour = TwoLevelList::new(&create_scattered_store(len), 4);
+    let gain = descend_with(&mut tour, Scan::BestImprovement, generate);
+    assert!((before - tour.tour_order().cost() - gain).abs() < 1e-9);
+    assert!(is_optimal(&tour));
+}
+
+#[test]
+fn test_acceptance() {
+    let mut greedy = Greedy;
+    assert!(greedy.accept(10., 9.));
+    assert!(!greedy.accept(10., 10.));
+
+    let mut sa = SimulatedAnnealing::new(1e3, 0.5, 7);
+    assert!(sa.accept(10., 9.));
+    assert!(sa.accept(10., 10. + 1e-6));
+    sa.update(10., 10.);
+    assert_eq!(500., sa.temperature());
+
+    let mut sa = SimulatedAnnealing::new(0., 0.5, 7);
+    assert!(!sa.accept(10., 11.));
+
+    let mut rrt = RecordToRecord::new(0.1);
+    assert!(rrt.accept(10., 10.5));
+    assert!(!rrt.accept(10., 11.5));
+    rrt.update(12., 8.);
+    assert!(rrt.accept(12., 8.7));
+    assert!(!rrt.accept(12., 8.9));
+
+    let mut deluge = GreatDeluge::new(1.);
+    assert!(deluge.accept(10., 10.));
+    assert_eq!(Some(10.), deluge.level());
+    deluge.update(10., 10.);
+    assert!(deluge.accept(12., 9.));
+    assert!(!deluge.accept(8., 9.5));
+
+    let mut late = LateAcceptance::new(2);
+    assert!(late.accept(10., 10.));
+    late.update(12., 10.);
+    late.update(11., 10.);
+    // The history holds [12, 11], the next comparison is with 12.
+    assert!(late.accept(5., 11.5));
+    late.update(5., 5.);
+    assert!(late.accept(5., 11.));
+    assert!(!late.accept(5., 11.5));
+}
+
+#[test]
+fn test_local_search() {
+    let len = 12;
+    let store = create_scattered_store(len);
+    let initial = TwoLevelList::new(&store, 4).tour_order().cost();
+
+    let check = |acceptance: &mut dyn FnMut(&mut TwoLevelList) -> TourOrder| {
+        let mut tour = TwoLevelList::new(&store, 4);
+        let best = acceptance(&mut tour);
+        assert_eq!(len, best.len());
+        assert!(best.cost() < initial);
+
+        let mut v = best.order().clone();
+        v.sort_unstable();
+        assert_eq!((0..len).collect::<Vec<_>>(), v);
+
+        tour.apply(&best).unwrap();
+        assert!((tour.tour_order().cost() - best.cost()).abs() < 1e-9);
+    };
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut generate = move |tour: &TwoLevelList| random_2opt(tour, &mut rng);
+    let limit = Termination::Iterations(500);
+
+    check(&mut |tour| local_search(tour, &mut Greedy, &mut generate, limit));
+    check(&mut |tour| {
+        local_search(
+            tour,
+            &mut SimulatedAnnealing::new(5., 0.99, 1),
+            &mut generate,
+            limit,
+        )
+    });
+    check(&mut |tour| local_search(tour, &mut RecordToRecord::new(0.05), &mut generate, limit));
+    check(&mut |tour| local_search(tour, &mut GreatDeluge::new(0.1), &mut generate, limit));
+    check(&mut |tour| local_search(tour, &mut LateAcceptance::new(10), &mut generate, limit));
+}
+
+#[test]
+fn test_termination() {
+    let second = Duration::from_secs(1);
+    assert!(!Termination::Iterations(3).is_reached(2, 2, second));
+    assert!(Termination::Iterations(3).is_reached(3, 0, Duration::ZERO));
+    assert!(!Termination::Stagnation(3).is_reached(10, 2, second));
+    assert!(Termination::Stagnation(3).is_reached(3, 3, Duration::ZERO));
+    assert!(!Termination::TimeLimit(second).is_reached(10, 10, Duration::ZERO));
+    assert!(Termination::TimeLimit(second).is_reached(0, 0, second));
+}
+
+#[test]
+fn test_lahc() {
+    let len = 12;
+    let store = create_scattered_store(len);
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    let initial = tour.tour_order().cost();
+    let best = solve_lahc(&mut tour, 5, Termination::Stagnation(300), 11);
+    assert_eq!(len, best.len());
+    assert!(best.cost() < initial);
+
+    // The same seed leads to the same result.
+    let mut tour = TwoLevelList::new(&store, 4);
+    let again = solve_lahc(&mut tour, 5, Termination::Stagnation(300), 11);
+    assert_eq!(best.order(), again.order());
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    let timed = solve_lahc(
+        &mut tour,
+        5,
+        Termination::TimeLimit(Duration::from_millis(20)),
+        11,
+    );
+    assert_eq!(len, timed.len());
+    assert!(timed.cost() <= initial);
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    let longest = solve_lahc_with(
+        &mut tour,
+        5,
+        Termination::Stagnation(300),
+        11,
+        Objective::Maximize,
+        &CancellationToken::new(),
+    );
+    assert_eq!(len, longest.len());
+    assert!(longest.cost() > initial);
+    assert!(longest.cost() > best.cost());
+}
+
+#[test]
+fn test_resume_search() {
+    let len = 12;
+    let store = create_scattered_store(len);
+    let generate = |tour: &TwoLevelList, rng: &mut StdRng| random_2opt(tour, rng);
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    let initial = tour.tour_order().cost();
+    let mut state = SearchState::new(&tour, SimulatedAnnealing::new(5., 0.99, 1), 7);
+    resume_search(
+        &mut tour,
+        &store,
+        &mut state,
+        generate,
+        Termination::Iterations(200),
+    )
+    .unwrap();
+    assert_eq!(200, state.iterations());
+
+    // A copy of the state continues on another tour exactly like the original.
+    let mut copy = state.clone();
+    let mut other = TwoLevelList::new(&store, 4);
+    let limit = Termination::Iterations(400);
+    resume_search(&mut tour, &store, &mut state, generate, limit).unwrap();
+    resume_search(&mut other, &store, &mut copy, generate, limit).unwrap();
+
+    assert_eq!(400, state.iterations());
+    assert_eq!(state.best().order(), copy.best().order());
+    assert_eq!(state.current().order(), copy.current().order());
+    assert!(state.best().cost() < initial);
+    assert!(state.best().cost() <= state.current().cost());
+    assert!(state.acceptance().temperature() < 5.);
+
+    // Runs that have already reached their termination condition do nothing.
+    let best = state.best().cost();
+    resume_search(&mut tour, &store, &mut state, generate, limit).unwrap();
+    assert_eq!(400, state.iterations());
+    assert_eq!(best, state.best().cost());
+
+    // Stamped states are only resumed for the instance they belong to.
+    let mut state = state.with_fingerprint(&store);
+    let other = create_scattered_store(len + 1);
+    let limit = Termination::Iterations(500);
+    assert!(matches!(
+        resume_search(&mut tour, &other, &mut state, generate, limit),
+        Err(CyklError::InstanceMismatch { .. })
+    ));
+    assert_eq!(400, state.iterations());
+    resume_search(&mut tour, &store, &mut state, generate, limit).unwrap();
+    assert_eq!(500, state.iterations());
+}
+
+#[test]
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+fn test_resume_search_serde() {
+    let len = 12;
+    let store = create_scattered_store(len);
+    let generate = |tour: &TwoLevelList, rng: &mut StdRng| random_2opt(tour, rng);
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    let mut state =
+        SearchState::new(&tour, SimulatedAnnealing::new(5., 0.99, 1), 7).with_fingerprint(&store);
+    resume_search(
+        &mut tour,
+        &store,
+        &mut state,
+        generate,
+        Termination::Iterations(100),
+    )
+    .unwrap();
+
+    // A deserialised state continues exactly like the original, including the random number
+    // generator of the acceptance strategy.
+    let json = serde_json::to_string(&state).unwrap();
+    let mut restored: SearchState<SimulatedAnnealing> = serde_json::from_str(&json).unwrap();
+    assert_eq!(json, serde_json::to_string(&restored).unwrap());
+    assert_eq!(state.fingerprint(), restored.fingerprint());
+
+    let mut other = TwoLevelList::new(&store, 4);
+    let limit = Termination::Iterations(300);
+    resume_search(&mut tour, &store, &mut state, generate, limit).unwrap();
+    resume_search(&mut other, &store, &mut restored, generate, limit).unwrap();
+    assert_eq!(state.best().order(), restored.best().order());
+    assert_eq!(state.current().order(), restored.current().order());
+    assert_eq!(
+        state.acceptance().temperature(),
+        restored.acceptance().temperature()
+    );
+
+    // The drivers keep the state of their generators across serialisation as well.
+    let mut lns = Lns::new(4, 3);
+    let mut tour = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut tour, 5, Objective::Minimize);
+    lns.run(&mut tour, &mut Greedy, Termination::Iterations(10))
+        .unwrap();
+
+    let mut restored: Lns = serde_json::from_str(&serde_json::to_string(&lns).unwrap()).unwrap();
+    let mut other = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut other, 5, Objective::Minimize);
+    other.apply(&tour.tour_order()).unwrap();
+
+    let limit = Termination::Iterations(20);
+    let best = lns.run(&mut tour, &mut Greedy, limit).unwrap();
+    let copy = restored.run(&mut other, &mut Greedy, limit).unwrap();
+    assert_eq!(best.order(), copy.order());
+}
+
+#[test]
+fn test_adaptive() {
+    let len = 16;
+    let store = create_scattered_store(len);
+    let budget = Duration::from_millis(20);
+
+    let mut ops: Vec<_> = Operator::ALL.iter().map(|op| vec![*op]).collect();
+    ops.push(Operator::ALL.to_vec());
+
+    for operators in ops {
+        let mut tour = TwoLevelList::new(&store, 4);
+        cand_gen_nn(&mut tour, 5, Objective::Minimize);
+        let initial = tour.tour_order().cost();
+
+        let mut adaptive = Adaptive::with_operators(&operators, 3);
+        let best = adaptive.run(&mut tour, budget).unwrap();
+
+        assert_eq!(len, best.len());
+        assert!(best.cost() <= initial, "{:?}", operators);
+        assert!((tour.tour_order().cost() - best.cost()).abs() < 1e-9);
+
+        let mut v = best.order().clone();
+        v.sort_unstable();
+        assert_eq!((0..len).collect::<Vec<_>>(), v);
+
+        assert!(adaptive.stats().all(|(_, stats)| stats.calls > 0));
+    }
+
+    // Maximising keeps the longest tour, which no operator shortens.
+    for operators in Operator::ALL.iter().map(|op| vec![*op]) {
+        let mut tour = TwoLevelList::new(&store, 4);
+        cand_gen_nn(&mut tour, 5, Objective::Maximize);
+        let initial = tour.tour_order().cost();
+
+        let mut adaptive =
+            Adaptive::with_operators(&operators, 3).with_objective(Objective::Maximize);
+        let longest = adaptive.run(&mut tour, budget).unwrap();
+
+        assert!(longest.cost() >= initial, "{:?}", operators);
+        assert!((tour.tour_order().cost() - longest.cost()).abs() < 1e-9);
+        assert!(adaptive
+            .stats()
+            .all(|(_, stats)| stats.improvement >= 0. && stats.calls > 0));
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    let len = 16;
+    let store = create_scattered_store(len);
+
+    // Construction and a single round of improvements.
+    let mut tour = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut tour, 5, Objective::Minimize);
+    let constructed = solve_greedy(&mut tour, &[0], Objective::Minimize)
+        .unwrap()
+        .unwrap();
+    let mut pipeline = Pipeline::new(Termination::Iterations(10))
+        .construct(GreedyConstruction(vec![0]))
+        .improve(TwoOptDescent(Scan::FirstImprovement))
+        .improve(OrOptDescent(Scan::BestImprovement));
+    let improved = pipeline.run(&mut tour).unwrap();
+    assert!(improved.cost() <= constructed.cost() + 1e-9);
+    test_tour_order(&tour, &improved);
+
+    // Iterated local search with kicks, where every iteration is counted by a closure phase.
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let mut tour = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut tour, 5, Objective::Minimize);
+    let mut pipeline = Pipeline::new(Termination::Iterations(20))
+        .construct(GreedyConstruction(vec![0]))
+        .improve(TwoOptDescent::default())
+        .improve(LinKernighan(KOpt::Opt2))
+        .perturb(DoubleBridgeKick::new(5))
+        .perturb(move |_: &mut TwoLevelList| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(())
+        })
+        .accept(SimulatedAnnealing::new(1., 0.9, 2));
+    let best = pipeline.run(&mut tour).unwrap();
+    assert_eq!(20, calls.load(std::sync::atomic::Ordering::Relaxed));
+    assert!(best.cost() <= constructed.cost() + 1e-9);
+    assert!((tour.tour_order().cost() - best.cost()).abs() < 1e-9);
+    let mut v = best.order().clone();
+    v.sort_unstable();
+    assert_eq!((0..len).collect::<Vec<_>>(), v);
+
+    // Maximising keeps the longest tour, which no phase shortens.
+    let mut tour = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut tour, 5, Objective::Maximize);
+    let constructed = solve_greedy(&mut tour, &[0], Objective::Maximize)
+        .unwrap()
+        .unwrap();
+    let longest = Pipeline::new(Termination::Iterations(20))
+        .objective(Objective::Maximize)
+        .construct(GreedyConstruction(vec![0]))
+        .improve(TwoOptDescent::default())
+        .improve(OrOptDescent::default())
+        .improve(LinKernighan(KOpt::Opt2))
+        .perturb(DoubleBridgeKick::new(5))
+        .run(&mut tour)
+        .unwrap();
+    assert!(longest.cost() >= constructed.cost() - 1e-9);
+    assert!(longest.cost() > best.cost());
+    assert!((tour.tour_order().cost() - longest.cost()).abs() < 1e-9);
+}
+
+#[test]
+fn test_registry() {
+    let len = 16;
+    let store = create_scattered_store(len);
+    let names = |names: &[&str]| names.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+    let mut registry = Registry::new();
+    let spec = PipelineSpec {
+        construct: names(&["greedy"]),
+        improve: names(&["2opt", "oropt", "lk2"]),
+        perturb: names(&["double-bridge"]),
+        accept: Some("greedy".to_string()),
+        termination: Termination::Iterations(10),
+        objective: Objective::Minimize,
+        seed: 3,
+    };
+
+    let solve = |registry: &Registry<TwoLevelList>, spec: &PipelineSpec| {
+        let mut tour = TwoLevelList::new(&store, 4);
+        cand_gen_nn(&mut tour, 5, Objective::Minimize);
+        registry.build(spec).unwrap().run(&mut tour).unwrap()
+    };
+    let best = solve(&registry, &spec);
+    assert_eq!(len, best.len());
+    assert_eq!(best, solve(&registry, &spec));
+
+    // Phases registered by users are found by their names.
+    registry.register_improvement("noop", |_| |_: &mut TwoLevelList| Ok(()));
+    let custom = PipelineSpec {
+        improve: names(&["noop"]),
+        perturb: Vec::new(),
+        ..spec.clone()
+    };
+    solve(&registry, &custom);
+
+    for unknown in [
+        PipelineSpec {
+            construct: names(&["hilbert"]),
+            ..spec.clone()
+        },
+        PipelineSpec {
+            perturb: names(&["2opt"]),
+            ..spec.clone()
+        },
+        PipelineSpec {
+            accept: Some("annealing".to_string()),
+            ..spec.clone()
+        },
+    ] {
+        assert!(matches!(
+            registry.build(&unknown),
+            Err(CyklError::UnknownPhase(_))
+        ));
+    }
+
+    let annealing = PipelineSpec {
+        accept: Some("sa".to_string()),
+        ..spec.clone()
+    };
+    assert_eq!(len, solve(&registry, &annealing).len());
+
+    let restarts = PipelineSpec {
+        perturb: names(&["restart"]),
+        ..spec.clone()
+    };
+    let restarted = solve(&registry, &restarts);
+    assert_eq!(len, restarted.len());
+    assert_eq!(restarted, solve(&registry, &restarts));
+}
+
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+#[test]
+fn test_pipeline_spec_serde() {
+    let spec = PipelineSpec {
+        construct: vec!["greedy".to_string()],
+        improve: vec!["2opt".to_string(), "lk2".to_string()],
+        perturb: vec!["double-bridge".to_string()],
+        accept: Some("sa".to_string()),
+        termination: Termination::Stagnation(50),
+        objective: Objective::Maximize,
+        seed: 9,
+    };
+    let json = serde_json::to_string(&spec).unwrap();
+    assert_eq!(spec, serde_json::from_str(&json).unwrap());
+
+    // Omitted stages, acceptance, objective and seed take their defaults.
+    let minimal: PipelineSpec =
+        serde_json::from_str(r#"{"improve": ["2opt"], "termination": {"Iterations": 3}}"#).unwrap();
+    assert_eq!(
+        PipelineSpec {
+            construct: Vec::new(),
+            improve: vec!["2opt".to_string()],
+            perturb: Vec::new(),
+            accept: None,
+            termination: Termination::Iterations(3),
+            objective: Objective::Minimize,
+            seed: 0,
+        },
+        minimal
+    );
+}
+
 // fn create_repo_2() -> Repo {
 //     let mut repo = RepoBuilder::new(tspf::WeightKind::Euc2d)
 //         .capacity(16)
@@ -234,3 +943,383 @@ fn test_move_4_opt() {
 //         assert_eq!(5, node.candidates().len());
 //     }
 // }
+
+#[test]
+fn test_nearest_fragment() {
+    let len = 40;
+    let store = create_scattered_store(len);
+
+    for leaf_size in [1, 4, 7, len] {
+        let order = nearest_fragment(&store, leaf_size);
+
+        let mut visited = order.order().clone();
+        visited.sort_unstable();
+        assert_eq!((0..len).collect::<Vec<_>>(), visited);
+
+        let mut tour = TwoLevelList::new(&store, 4);
+        tour.apply(&order).unwrap();
+        assert!((tour.total_distance() - order.cost()).abs() < 1e-9);
+    }
+
+    assert!(nearest_fragment(&DataStore::<()>::new(Metric::Euc2d), 4).is_empty());
+}
+
+#[test]
+fn test_partitioned_2opt() {
+    let len = 40;
+    let store = create_scattered_store(len);
+    // The store cannot be shared between threads, hence the costs are copied.
+    let costs: Vec<Vec<Scalar>> = (0..len)
+        .map(|a| (0..len).map(|b| store.cost(&a, &b)).collect())
+        .collect();
+    let cost = |a: usize, b: usize| costs[a][b];
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    tour.apply(&TourOrder::with_nat_ord(len)).unwrap();
+    let before = tour.total_distance();
+
+    let result = partitioned_2opt(&tour.tour_order(), 8, 10, cost);
+    assert!(result.cost() < before);
+
+    let mut visited = result.order().clone();
+    visited.sort_unstable();
+    assert_eq!((0..len).collect::<Vec<_>>(), visited);
+
+    tour.apply(&result).unwrap();
+    assert!((tour.total_distance() - result.cost()).abs() < 1e-9);
+}
+
+#[test]
+fn test_incumbent() {
+    let incumbent = Incumbent::new();
+    assert!(incumbent.best().is_none());
+    assert_eq!(Scalar::INFINITY, incumbent.cost());
+
+    assert!(incumbent.offer(&TourOrder::with_cost(vec![0, 1, 2], 10.)));
+    assert!(!incumbent.offer(&TourOrder::with_cost(vec![0, 2, 1], 12.)));
+    assert!(incumbent.offer(&TourOrder::with_cost(vec![1, 0, 2], 8.)));
+    assert_eq!(8., incumbent.cost());
+    assert_eq!(vec![1, 0, 2], *incumbent.best().unwrap().order());
+
+    let len = 12;
+    let incumbent = Incumbent::new();
+    let best = multi_start(8, 3, &incumbent, |idx, _| {
+        let store = create_scattered_store(len);
+        let mut tour = TwoLevelList::new(&store, 4);
+        cand_gen_nn(&mut tour, 4, Objective::Minimize);
+        solve_greedy(&mut tour, &[idx], Objective::Minimize).unwrap()
+    })
+    .unwrap();
+
+    let store = create_scattered_store(len);
+    let mut tour = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut tour, 4, Objective::Minimize);
+    let expected = solve_greedy(&mut tour, &(0..8).collect::<Vec<_>>(), Objective::Minimize)
+        .unwrap()
+        .unwrap();
+    assert!((expected.cost() - best.cost()).abs() < 1e-9);
+    assert_eq!(best.cost(), incumbent.cost());
+
+    // When maximising, longer tours replace the incumbent.
+    let incumbent = Incumbent::with_objective(Objective::Maximize);
+    assert_eq!(Scalar::NEG_INFINITY, incumbent.cost());
+    assert!(incumbent.offer(&TourOrder::with_cost(vec![0, 1, 2], 10.)));
+    assert!(!incumbent.offer(&TourOrder::with_cost(vec![1, 0, 2], 8.)));
+    assert!(incumbent.offer(&TourOrder::with_cost(vec![0, 2, 1], 12.)));
+    assert_eq!(12., incumbent.cost());
+    assert_eq!(vec![0, 2, 1], *incumbent.best().unwrap().order());
+}
+
+#[test]
+fn test_deterministic_multi_start() {
+    // Starts tie in cost and finish in reverse order, so that a scheduling-dependent reduction
+    // would keep the tour of a late start.
+    let parallelism = Parallelism::new(4).deterministic(true);
+    assert!(parallelism.is_deterministic());
+
+    let config = RunConfigBuilder::new().deterministic(true).build();
+    assert!(config.is_deterministic());
+    assert_eq!(parallelism, config.parallelism(4));
+    assert!(!RunConfigBuilder::new()
+        .build()
+        .parallelism(4)
+        .is_deterministic());
+
+    let run = || {
+        let incumbent = Incumbent::new();
+        multi_start_with(8, parallelism, &incumbent, |idx, _| {
+            std::thread::sleep(Duration::from_millis(2 * (8 - idx as u64)));
+            Some(TourOrder::with_cost(vec![idx, 0], 1.))
+        })
+        .unwrap()
+    };
+    assert_eq!(vec![0, 0], *run().order());
+
+    // Restarts derived from the incumbent only see the tours of earlier rounds.
+    let policy = RestartPolicy::new(1, 3, 5);
+    let len = 12;
+    let run = || {
+        let incumbent = Incumbent::new();
+        multi_start_restarts(9, parallelism, &incumbent, policy, |idx, initial, _| {
+            let store = create_scattered_store(len);
+            let mut tour = TwoLevelList::new(&store, 4);
+            cand_gen_nn(&mut tour, 4, Objective::Minimize);
+            match initial {
+                Some(order) => tour.apply(&order).ok()?,
+                None => {
+                    solve_greedy(&mut tour, &[idx], Objective::Minimize).ok()?;
+                }
+            }
+            solve_lahc(&mut tour, 4, Termination::Iterations(50), idx as u64);
+            Some(tour.tour_order())
+        })
+        .unwrap()
+    };
+    let (a, b) = (run(), run());
+    assert_eq!(a.order(), b.order());
+    assert_eq!(a.cost().to_bits(), b.cost().to_bits());
+}
+
+#[test]
+fn test_restart_policy() {
+    let order = TourOrder::with_nat_ord(20);
+    let mut rng = StdRng::seed_from_u64(3);
+    let perturbed = order.perturbed(&mut rng, 5);
+    assert_eq!(0., perturbed.cost());
+    let mut sorted = perturbed.order().clone();
+    sorted.sort_unstable();
+    assert_eq!(order.order(), &sorted);
+    assert_eq!(order.order(), order.perturbed(&mut rng, 0).order());
+
+    let policy = RestartPolicy::new(2, 4, 7);
+    assert_eq!(
+        vec![2, 3, 4, 2],
+        (0..4).map(|r| policy.strength(r)).collect::<Vec<_>>()
+    );
+
+    let incumbent = Incumbent::new();
+    assert!(policy.restart(0, &incumbent).is_none());
+    incumbent.offer(&TourOrder::with_cost((0..20).collect(), 20.));
+    let a = policy.restart(1, &incumbent).unwrap();
+    let b = policy.restart(1, &incumbent).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(20, a.len());
+
+    // Iterated local search with restart kicks of increasing strength.
+    let len = 16;
+    let store = create_scattered_store(len);
+    let mut tour = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut tour, 5, Objective::Minimize);
+    let best = Pipeline::new(Termination::Iterations(20))
+        .construct(GreedyConstruction(vec![0]))
+        .improve(TwoOptDescent(Scan::FirstImprovement))
+        .perturb(RestartKick::new(policy))
+        .run(&mut tour)
+        .unwrap();
+    assert_eq!(len, best.len());
+    assert!((tour.tour_order().cost() - best.cost()).abs() < 1e-9);
+}
+
+#[test]
+fn test_lns() {
+    let len = 16;
+    let store = create_scattered_store(len);
+
+    for destroy in Destroy::ALL {
+        for repair in Repair::ALL {
+            let mut tour = TwoLevelList::new(&store, 4);
+            cand_gen_nn(&mut tour, 5, Objective::Minimize);
+            let initial = tour.tour_order().cost();
+
+            let mut lns = Lns::with_operators(&[destroy], &[repair], 4, 11);
+            let best = lns
+                .run(&mut tour, &mut Greedy, Termination::Iterations(50))
+                .unwrap();
+
+            assert_eq!(len, best.len());
+            assert!(best.cost() <= initial, "{:?} {:?}", destroy, repair);
+            assert_eq!(len, tour.n_included());
+            assert!((tour.tour_order().cost() - best.cost()).abs() < 1e-9);
+
+            let mut v = best.order().clone();
+            v.sort_unstable();
+            assert_eq!((0..len).collect::<Vec<_>>(), v);
+        }
+    }
+
+    // Maximising accepts only repairs that lengthen the tour.
+    let mut tour = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut tour, 5, Objective::Maximize);
+    let initial = tour.tour_order().cost();
+    let longest = Lns::new(4, 11)
+        .run_with_objective(
+            &mut tour,
+            &mut Greedy,
+            Termination::Iterations(50),
+            &Objective::Maximize,
+        )
+        .unwrap();
+    assert_eq!(len, longest.len());
+    assert!(longest.cost() >= initial);
+    assert!((tour.tour_order().cost() - longest.cost()).abs() < 1e-9);
+
+    // Rejected repairs are undone, which leaves every node between its original neighbours.
+    struct Reject;
+    impl Acceptance for Reject {
+        fn accept(&mut self, _current: Scalar, _candidate: Scalar) -> bool {
+            false
+        }
+    }
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut tour, 5, Objective::Minimize);
+    let initial = tour.tour_order();
+    let best = Lns::new(4, 2)
+        .run(&mut tour, &mut Reject, Termination::Iterations(30))
+        .unwrap();
+    assert_eq!(initial.cost(), best.cost());
+    let order = initial.order();
+    for (ii, index) in order.iter().enumerate() {
+        let next = tour.successor_at(*index).unwrap();
+        assert_eq!(order[(ii + 1) % len], next.index().get());
+    }
+
+    let mut tour = TwoLevelList::new(&create_scattered_store(4), 4);
+    cand_gen_nn(&mut tour, 3, Objective::Minimize);
+    let best = Lns::new(10, 0)
+        .run(&mut tour, &mut Greedy, Termination::Iterations(5))
+        .unwrap();
+    assert_eq!(4, best.len());
+}
+
+#[test]
+fn test_bounds_tracker() {
+    let tracker = BoundsTracker::new();
+    assert_eq!(Scalar::INFINITY, tracker.gap());
+    assert!(!tracker.is_target_reached());
+    assert!(tracker.raise_lower(8.));
+    assert!(!tracker.raise_lower(4.));
+    assert!(tracker.offer(&TourOrder::with_cost(vec![0, 1, 2], 10.)));
+    assert!((tracker.gap() - 0.25).abs() < 1e-12);
+
+    let tracker = tracker.with_target_gap(0.25);
+    assert!(tracker.is_target_reached());
+    assert!(tracker.offer(&TourOrder::with_cost(vec![0, 2, 1], 7.)));
+    assert_eq!(0., tracker.gap());
+
+    let len = 16;
+    let store = create_scattered_store(len);
+    let solve = |target| {
+        let mut tour = TwoLevelList::new(&store, 4);
+        cand_gen_nn(&mut tour, 5, Objective::Minimize);
+        let tracker = Arc::new(BoundsTracker::for_tour(&tour).with_target_gap(target));
+        assert!(tracker.lower() > 0.);
+        assert!(tracker.lower() <= tracker.upper());
+
+        let initial = tour.tour_order().cost();
+        let best = Lns::new(4, 3)
+            .with_bounds(Arc::clone(&tracker))
+            .run(&mut tour, &mut Greedy, Termination::Iterations(100))
+            .unwrap();
+        assert_eq!(best.cost(), tracker.upper());
+        (initial, best.cost())
+    };
+
+    // The initial tour is within a gap of 500%, hence the driver stops immediately.
+    let (initial, best) = solve(5.);
+    assert_eq!(initial, best);
+    let (initial, best) = solve(0.);
+    assert!(best < initial);
+}
+
+#[test]
+fn test_penalized_objective() {
+    let len = 16;
+    let store = create_scattered_store(len);
+
+    // Penalises the number of nodes between node 0 and node 8 along the tour.
+    let gap = |order: &TourOrder| {
+        let pos = |x| order.order().iter().position(|node| *node == x).unwrap();
+        let d = (pos(0) as isize - pos(8) as isize).unsigned_abs();
+        (d.min(order.len() - d) - 1) as Scalar
+    };
+    let objective = Penalized::new(Objective::Minimize).with_penalty(1000., gap);
+    assert_eq!(1, objective.n_penalties());
+
+    let order = TourOrder::with_cost((0..len).collect(), 10.);
+    assert_eq!(10., Objective::Minimize.evaluate(&order));
+    assert_eq!(-10., Objective::Maximize.evaluate(&order));
+    assert_eq!(7000., objective.penalties(&order));
+    assert_eq!(7010., objective.evaluate(&order));
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    tour.apply(&order).unwrap();
+    let mut rng = StdRng::seed_from_u64(3);
+    let best = local_search_with_objective(
+        &mut tour,
+        &mut Greedy,
+        |tour: &TwoLevelList| random_2opt(tour, &mut rng),
+        Termination::Stagnation(500),
+        &objective,
+    );
+    assert_eq!(0., objective.penalties(&best));
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    tour.apply(&order).unwrap();
+    cand_gen_nn(&mut tour, 5, Objective::Minimize);
+    let initial = objective.evaluate(&tour.tour_order());
+    let best = Lns::new(4, 5)
+        .run_with_objective(
+            &mut tour,
+            &mut Greedy,
+            Termination::Iterations(200),
+            &objective,
+        )
+        .unwrap();
+    assert!(objective.evaluate(&best) < initial);
+    assert_eq!(len, best.len());
+}
+
+#[test]
+fn test_cand_gen_quadrant() {
+    // Two clusters on a line, with one node above and below the left cluster.
+    let mut store = DataStore::with_capacity(Metric::Euc2d, 8);
+    for pos in [
+        [0., 0.],
+        [1., 0.],
+        [2., 0.],
+        [10., 0.],
+        [11., 0.],
+        [12., 0.],
+        [1., 5.],
+        [1., -5.],
+    ] {
+        store.add(NodeKind::Target, pos.to_vec(), ());
+    }
+    store.compute();
+
+    let mut tour = TwoLevelList::new(&store, 4);
+    cand_gen_nn(&mut tour, 4, Objective::Minimize);
+    let nn: Vec<usize> = tour
+        .get(2)
+        .unwrap()
+        .candidates()
+        .iter()
+        .map(|c| c.index().get())
+        .collect();
+    assert_eq!(vec![1, 0, 6, 7], nn);
+
+    cand_gen_quadrant(&mut tour, &store, 4);
+    let node = tour.get(2).unwrap();
+    let quadrant: Vec<usize> = node.candidates().iter().map(|c| c.index().get()).collect();
+    assert_eq!(4, quadrant.len());
+    assert!(quadrant.contains(&3), "{:?}", quadrant);
+    assert!(quadrant.contains(&1));
+
+    let dists: Vec<Scalar> = node
+        .candidates()
+        .iter()
+        .map(|c| tour.distance(&node, c))
+        .collect();
+    assert!(dists.windows(2).all(|w| w[0] <= w[1]));
+}
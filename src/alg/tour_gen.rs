@@ -1,12 +1,171 @@
+use std::cmp::Ordering;
+
 use crate::{
     combine_range,
+    data::DataStore,
+    oracle::cycle_cost,
     tour::{Tour, TourOrder, UpdateTourError},
-    tour_order,
+    tour_order, Scalar,
 };
 
+/// Number of leaves before and after the current one that are searched for the nearest fragment
+/// while stitching.
+const STITCH_WINDOW: usize = 8;
+
 pub fn init_tour<T>(tour: &mut T) -> Result<(), UpdateTourError>
 where
     T: Tour,
 {
     tour.apply(&tour_order!(0..tour.len()))
 }
+
+/// Constructs a tour by partitioning the nodes with a k-d tree, building a path with the
+/// nearest-neighbour method inside each leaf of at most `leaf_size` nodes, and stitching the
+/// paths together.
+///
+/// Starting with the path of the first leaf, the end of the partial tour is repeatedly connected to
+/// the nearest endpoint of the unused paths in the neighbouring leaves of the tree. Since only a
+/// bounded window of leaves is searched, the construction takes O(N log N + N * leaf_size) time and
+/// does not rely on the candidates of the nodes. This makes it suitable for instances where even
+/// the greedy algorithm is too slow.
+///
+/// Nodes are split only along their coordinates, thus nodes whose costs are given explicitly end
+/// up in a single leaf.
+pub fn nearest_fragment<M>(store: &DataStore<M>, leaf_size: usize) -> TourOrder {
+    let mut nodes: Vec<usize> = (0..store.len()).collect();
+    let mut leaves = Vec::new();
+    partition(store, &mut nodes, 0, leaf_size.max(1), &mut leaves);
+
+    let fragments: Vec<Vec<usize>> = leaves
+        .iter()
+        .map(|&(start, end)| nn_path(store, &nodes[start..end]))
+        .collect();
+
+    let mut used = vec![false; fragments.len()];
+    let mut order = Vec::with_capacity(store.len());
+    // Position of the first fragment that might still be unused.
+    let mut cursor = 0;
+    let mut current = 0;
+
+    while current < fragments.len() {
+        used[current] = true;
+
+        let fragment = &fragments[current];
+        match order.last() {
+            Some(last)
+                if store.cost(last, &fragment[fragment.len() - 1])
+                    < store.cost(last, &fragment[0]) =>
+            {
+                order.extend(fragment.iter().rev())
+            }
+            _ => order.extend(fragment.iter()),
+        }
+
+        let tail = order[order.len() - 1];
+        let lo = current.saturating_sub(STITCH_WINDOW);
+        let hi = (current + STITCH_WINDOW + 1).min(fragments.len());
+
+        let nearest = (lo..hi)
+            .filter(|idx| !used[*idx])
+            .map(|idx| {
+                let fragment = &fragments[idx];
+                let d = store
+                    .cost(&tail, &fragment[0])
+                    .min(store.cost(&tail, &fragment[fragment.len() - 1]));
+                (idx, d)
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        current = match nearest {
+            Some((idx, _)) => idx,
+            None => {
+                while cursor < used.len() && used[cursor] {
+                    cursor += 1;
+                }
+                cursor
+            }
+        };
+    }
+
+    let cost = cycle_cost(store, &order);
+    TourOrder::with_cost(order, cost)
+}
+
+/// Recursively splits the nodes at the median of the coordinate with the largest extent, and
+/// collects the ranges of the leaves in the order of the tree.
+fn partition<M>(
+    store: &DataStore<M>,
+    nodes: &mut [usize],
+    offset: usize,
+    leaf_size: usize,
+    leaves: &mut Vec<(usize, usize)>,
+) {
+    if nodes.is_empty() {
+        return;
+    }
+
+    let mut split = None;
+    if nodes.len() > leaf_size {
+        let mut widest = 0.;
+        for dim in 0..store.dim() {
+            let (min, max) = nodes
+                .iter()
+                .fold((Scalar::MAX, Scalar::MIN), |(min, max), n| {
+                    let x = coord(store, *n, dim);
+                    (min.min(x), max.max(x))
+                });
+
+            if max - min > widest {
+                widest = max - min;
+                split = Some(dim);
+            }
+        }
+    }
+
+    match split {
+        Some(dim) => {
+            let mid = nodes.len() / 2;
+            nodes.select_nth_unstable_by(mid, |a, b| {
+                coord(store, *a, dim)
+                    .partial_cmp(&coord(store, *b, dim))
+                    .unwrap_or(Ordering::Equal)
+            });
+
+            let (left, right) = nodes.split_at_mut(mid);
+            partition(store, left, offset, leaf_size, leaves);
+            partition(store, right, offset + mid, leaf_size, leaves);
+        }
+        // All nodes share their coordinates or the leaf is small enough.
+        None => leaves.push((offset, offset + nodes.len())),
+    }
+}
+
+/// Returns a path through the given nodes built with the nearest-neighbour method.
+fn nn_path<M>(store: &DataStore<M>, nodes: &[usize]) -> Vec<usize> {
+    let mut rest = nodes.to_vec();
+    let mut path = Vec::with_capacity(nodes.len());
+    path.push(rest.swap_remove(0));
+
+    while !rest.is_empty() {
+        let last = path[path.len() - 1];
+        let mut chosen = 0;
+        let mut d = Scalar::MAX;
+
+        for (idx, node) in rest.iter().enumerate() {
+            let next_d = store.cost(&last, node);
+            if next_d < d {
+                d = next_d;
+                chosen = idx;
+            }
+        }
+
+        path.push(rest.swap_remove(chosen));
+    }
+
+    path
+}
+
+#[inline]
+fn coord<M>(store: &DataStore<M>, index: usize, dim: usize) -> Scalar {
+    store.coord(index).map_or(0., |pos| pos[dim])
+}
@@ -0,0 +1,142 @@
+//! Tuning of the groupsize and the number of candidates of a model by quick probes.
+//!
+//! Good parameters depend on the instance: clustered instances profit from more candidates per
+//! node than uniform ones, and the best size of tour segments grows with the number of nodes.
+//! [`probe`] solves the model once for each point of a small grid around the defaults and keeps
+//! the parameters of the best tour.
+use std::time::{Duration, Instant};
+
+use crate::{CyklError, Model, RunConfig, Scalar};
+
+/// Numbers of candidates per node tried by [`probe`].
+pub const CANDS: [usize; 3] = [5, 8, 12];
+
+/// Outcome of a single probe.
+#[derive(Clone, Debug)]
+pub struct Probe {
+    /// Size of the tour segments.
+    pub groupsize: usize,
+    /// Number of candidates per node.
+    pub cands: usize,
+    /// Value of the tour found, see [`Objective::cost`](crate::alg::Objective::cost).
+    pub cost: Scalar,
+    /// Time spent on the probe.
+    pub elapsed: Duration,
+}
+
+/// Parameters chosen by [`probe`].
+#[derive(Clone, Debug)]
+pub struct Tuning {
+    /// Size of the tour segments, which has been set on the model.
+    pub groupsize: usize,
+    /// Configuration with the tuned number of candidates.
+    pub config: RunConfig,
+    /// All probes in the order in which they were run.
+    pub probes: Vec<Probe>,
+}
+
+/// Solves a model with the configuration `base` for a grid of groupsizes around the square root
+/// of the number of nodes and the numbers of candidates in [`CANDS`], and returns the parameters
+/// of the best tour, breaking ties by time.
+///
+/// The budget is shared among the probes: each probe gets the remaining budget divided by the
+/// number of remaining probes as a deadline, after which its solver is cancelled like with
+/// [`RunConfigBuilder::cancellation`](crate::RunConfigBuilder::cancellation) and its best tour so
+/// far counts. Probes that are cancelled before they find a tour are left out. If the budget is
+/// spent before any probe has found a tour, the next probe runs without a deadline, so that at
+/// least one probe finishes.
+///
+/// The groupsize of the model is set to the tuned one. If no probe finds a tour, e.g. because the
+/// model is empty, or if a probe fails, the groupsize of the model is restored, and `base` is
+/// kept.
+pub fn probe<M>(
+    model: &mut Model<M>,
+    base: &RunConfig,
+    budget: Duration,
+) -> Result<Tuning, CyklError> {
+    let original = model.groupsize();
+    let root = (model.n_nodes() as f64).sqrt().ceil().max(1.) as usize;
+    let mut groupsizes = vec![(root / 2).max(1), root, root * 2];
+    groupsizes.dedup();
+
+    let result = probe_grid(model, base, budget, &groupsizes);
+    let (probes, best) = match result {
+        Ok(result) => result,
+        Err(err) => {
+            model.set_groupsize(original);
+            return Err(err);
+        }
+    };
+
+    let groupsize = best.as_ref().map_or(original, |x| x.groupsize);
+    let mut config = base.clone();
+    if let Some(best) = &best {
+        config.cands = best.cands;
+    }
+    model.set_groupsize(groupsize);
+
+    Ok(Tuning {
+        groupsize,
+        config,
+        probes,
+    })
+}
+
+/// Runs the probes of [`probe`] for all combinations of the groupsizes and [`CANDS`], and returns
+/// them with the best one.
+fn probe_grid<M>(
+    model: &mut Model<M>,
+    base: &RunConfig,
+    budget: Duration,
+    groupsizes: &[usize],
+) -> Result<(Vec<Probe>, Option<Probe>), CyklError> {
+    let start = Instant::now();
+    let n_probes = groupsizes.len() * CANDS.len();
+    let mut probes = Vec::with_capacity(n_probes);
+    let mut best: Option<Probe> = None;
+    let grid = groupsizes
+        .iter()
+        .flat_map(|groupsize| CANDS.iter().map(move |cands| (*groupsize, *cands)));
+
+    for (ii, (groupsize, cands)) in grid.enumerate() {
+        let remaining = budget.saturating_sub(start.elapsed());
+        if !probes.is_empty() && remaining.is_zero() {
+            break;
+        }
+
+        let mut config = base.clone();
+        config.cands = cands;
+        // Once the budget is spent without a tour, the next probe runs to its end.
+        if !remaining.is_zero() {
+            let share = remaining / (n_probes - ii) as u32;
+            config.cancellation = base.cancellation.with_deadline(Instant::now() + share);
+        }
+        model.set_groupsize(groupsize);
+
+        let began = Instant::now();
+        let order = match model.solve(&config) {
+            Ok(Some(order)) => order,
+            Ok(None) => continue,
+            // Only the deadline of the probe has passed, rather than the caller cancelling.
+            Err(CyklError::Cancelled) if !base.cancellation.is_cancelled() => continue,
+            Err(err) => return Err(err),
+        };
+        let probe = Probe {
+            groupsize,
+            cands,
+            cost: config.objective().cost(order.cost()),
+            elapsed: began.elapsed(),
+        };
+
+        let better = match &best {
+            Some(x) => (probe.cost, probe.elapsed) < (x.cost, x.elapsed),
+            None => true,
+        };
+        if better {
+            best = Some(probe.clone());
+        }
+        probes.push(probe);
+    }
+
+    Ok((probes, best))
+}
@@ -0,0 +1,157 @@
+//! Solving many independent instances, e.g. one tour per vehicle or territory, and one instance
+//! from many independent starts.
+//!
+//! Models cannot be sent to other threads, thus [`solve_all`] takes a function that builds the
+//! model of an instance from its index, and calls it on the worker thread that solves the
+//! instance. Instances are handed out to workers one at a time, so that a few large instances do
+//! not hold up the others. [`solve_multi_start`] builds a model for each start in the same way.
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    alg::incumbent::{multi_start_with, Incumbent},
+    tour::TourOrder,
+    CyklError, Model, RunConfig,
+};
+
+/// Outcome of solving one instance of a batch.
+#[derive(Debug)]
+pub struct BatchReport {
+    /// Index of the instance.
+    pub index: usize,
+    /// Tour found for the instance, `None` if the instance has no nodes, or the error raised while
+    /// building or solving it.
+    pub result: Result<Option<TourOrder>, CyklError>,
+    /// Time spent on building and solving the instance.
+    pub elapsed: Duration,
+}
+
+/// Builds and solves `n_instances` models with the same configuration on `parallelism` threads,
+/// and returns one report per instance, ordered by index.
+///
+/// The model of instance `ii` is created by `build(ii)`. Errors of single instances are reported
+/// without stopping the others, whereas cancelling the token of the configuration stops all of
+/// them. With a parallelism of zero or one, all instances are solved on the calling thread.
+pub fn solve_all<M, F>(
+    n_instances: usize,
+    build: F,
+    config: &RunConfig,
+    parallelism: usize,
+) -> Vec<BatchReport>
+where
+    F: Fn(usize) -> Result<Model<M>, CyklError> + Sync,
+{
+    let solve = |index: usize| {
+        let start = Instant::now();
+        let result = build(index).and_then(|mut model| model.solve(config));
+        BatchReport {
+            index,
+            result,
+            elapsed: start.elapsed(),
+        }
+    };
+
+    map_indexed(n_instances, parallelism, solve)
+}
+
+/// Solves one instance from `n_starts` independent starts on `n_threads` threads, and returns the
+/// best tour found with respect to the objective of the configuration.
+///
+/// The model of start `ii` is created by `build(ii)`, and solved with the configuration whose seed
+/// is advanced by `ii`, see [`RunConfig::with_seed`], so that start 0 runs the configuration as
+/// given. Solvers without a random number generator produce the same tour in every start. The
+/// tours of the starts are reduced in an [`Incumbent`], distributed over the threads as given by
+/// [`RunConfig::parallelism`], thus deterministic configurations return the same tour for the
+/// same seeds and number of threads.
+///
+/// Returns the first error raised while building or solving a model if no start found a tour.
+pub fn solve_multi_start<M, F>(
+    n_starts: usize,
+    build: F,
+    config: &RunConfig,
+    n_threads: usize,
+) -> Result<Option<TourOrder>, CyklError>
+where
+    F: Fn(usize) -> Result<Model<M>, CyklError> + Sync,
+{
+    let incumbent = Incumbent::with_objective(config.objective());
+    let error = Mutex::new(None);
+    let seed = config.seed();
+
+    let best = multi_start_with(
+        n_starts,
+        config.parallelism(n_threads),
+        &incumbent,
+        |idx, _| {
+            let config = match seed {
+                Some(seed) => config.clone().with_seed(seed.wrapping_add(idx as u64)),
+                None => config.clone(),
+            };
+            match build(idx).and_then(|mut model| model.solve(&config)) {
+                Ok(order) => order,
+                Err(err) => {
+                    if let Ok(mut first) = error.lock() {
+                        first.get_or_insert(err);
+                    }
+                    None
+                }
+            }
+        },
+    );
+
+    match (best, error.into_inner().ok().flatten()) {
+        (Some(best), _) => Ok(Some(best.as_ref().clone())),
+        (None, Some(err)) => Err(err),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Calls `f` on the indices `0..n` on `parallelism` threads, and returns the results ordered by
+/// index.
+///
+/// Indices are handed out to workers one at a time. With a parallelism of zero or one, `f` is
+/// called on the calling thread.
+pub(crate) fn map_indexed<R, F>(n: usize, parallelism: usize, f: F) -> Vec<R>
+where
+    R: Send,
+    F: Fn(usize) -> R + Sync,
+{
+    if parallelism <= 1 {
+        return (0..n).map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..parallelism.min(n))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= n {
+                            break results;
+                        }
+                        results.push((index, f(index)));
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| match worker.join() {
+                Ok(results) => results,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    });
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
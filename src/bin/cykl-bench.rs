@@ -0,0 +1,310 @@
+//! Benchmark of a solver against the published optima of classic TSPLIB instances.
+//!
+//! Instances are read from a cache directory and downloaded into it if missing, using `curl` and
+//! `gunzip`. Each instance is solved with the configured solver under a time limit, and one line
+//! per instance is written to the standard output as CSV:
+//!
+//! ```text
+//! instance,nodes,optimum,length,gap_percent,time_ms
+//! berlin52,52,7542,7542,0.000,1000
+//! ```
+//!
+//! Usage:
+//!
+//! ```text
+//! cykl-bench [--cache DIR] [--url URL] [--solver greedy|adaptive|lns] [--time-limit MS]
+//!            [--cands N] [--seed N] [--config FILE] [--starts N] [--threads N] [INSTANCE...]
+//! ```
+//!
+//! `--config` reads the run configuration from a TOML file with the `config-toml` feature, or
+//! from a JSON file with the `config-json` feature, e.g. to benchmark a pipeline of named
+//! phases. The configuration replaces `--solver`, `--time-limit` and `--cands`, while `--seed`
+//! replaces the seed of its solver.
+//!
+//! `--starts` solves each instance from several independent starts with advancing seeds, which
+//! run on `--threads` threads and are reduced deterministically if the configuration says so. The
+//! time limit applies to each start.
+//!
+//! Without instances, all instances with a known optimum are benchmarked.
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use cykl::{
+    alg::{SolverKind, Termination},
+    batch,
+    data::Metric,
+    load_tsp, RunConfig, RunConfigBuilder, Scalar,
+};
+
+const DEFAULT_CACHE: &str = "target/tsplib";
+const DEFAULT_URL: &str = "http://comopt.ifi.uni-heidelberg.de/software/TSPLIB95/tsp";
+const DEFAULT_TIME_LIMIT_MS: u64 = 1000;
+const DEFAULT_CANDS: usize = 8;
+const GROUPSIZE: usize = 64;
+
+/// Published optimal tour lengths of symmetric TSPLIB instances.
+const OPTIMA: &[(&str, u64)] = &[
+    ("eil51", 426),
+    ("berlin52", 7542),
+    ("st70", 675),
+    ("eil76", 538),
+    ("pr76", 108_159),
+    ("rat99", 1211),
+    ("kroA100", 21_282),
+    ("kroB100", 22_141),
+    ("kroC100", 20_749),
+    ("kroD100", 21_294),
+    ("kroE100", 22_068),
+    ("rd100", 7910),
+    ("eil101", 629),
+    ("lin105", 14_379),
+    ("ch130", 6110),
+    ("ch150", 6528),
+    ("kroA150", 26_524),
+    ("kroA200", 29_368),
+    ("a280", 2579),
+    ("lin318", 42_029),
+    ("pcb442", 50_778),
+    ("rat783", 8806),
+    ("pr1002", 259_045),
+];
+
+#[derive(Debug)]
+struct Options {
+    cache: PathBuf,
+    url: String,
+    solver: String,
+    time_limit: Duration,
+    cands: usize,
+    seed: Option<u64>,
+    config: Option<RunConfig>,
+    starts: usize,
+    threads: usize,
+    instances: Vec<String>,
+}
+
+/// Result of a single benchmark run.
+struct Row {
+    instance: String,
+    nodes: usize,
+    optimum: u64,
+    length: Scalar,
+    time: Duration,
+}
+
+impl Row {
+    fn gap(&self) -> Scalar {
+        100. * (self.length / self.optimum as Scalar - 1.)
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        cache: PathBuf::from(DEFAULT_CACHE),
+        url: DEFAULT_URL.to_string(),
+        solver: "adaptive".to_string(),
+        time_limit: Duration::from_millis(DEFAULT_TIME_LIMIT_MS),
+        cands: DEFAULT_CANDS,
+        seed: None,
+        config: None,
+        starts: 1,
+        threads: 1,
+        instances: Vec::new(),
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--cache" => options.cache = PathBuf::from(value()?),
+            "--url" => options.url = value()?,
+            "--solver" => options.solver = value()?,
+            "--time-limit" => {
+                let ms = value()?.parse().map_err(|_| "invalid time limit")?;
+                options.time_limit = Duration::from_millis(ms);
+            }
+            "--cands" => options.cands = value()?.parse().map_err(|_| "invalid cands")?,
+            "--seed" => options.seed = Some(value()?.parse().map_err(|_| "invalid seed")?),
+            "--config" => options.config = Some(load_config(Path::new(&value()?))?),
+            "--starts" => options.starts = value()?.parse().map_err(|_| "invalid starts")?,
+            "--threads" => options.threads = value()?.parse().map_err(|_| "invalid threads")?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => options.instances.push(arg),
+        }
+    }
+
+    if options.instances.is_empty() {
+        options.instances = OPTIMA.iter().map(|(name, _)| name.to_string()).collect();
+    }
+
+    Ok(options)
+}
+
+fn solver(options: &Options) -> Result<SolverKind, String> {
+    let starters = vec![0];
+    match options.solver.as_str() {
+        "greedy" => Ok(SolverKind::Greedy(starters)),
+        "adaptive" => Ok(SolverKind::Adaptive {
+            starters,
+            budget: options.time_limit,
+            seed: options.seed.unwrap_or(0),
+        }),
+        "lns" => Ok(SolverKind::Lns {
+            starters,
+            n_remove: 10,
+            termination: Termination::TimeLimit(options.time_limit),
+            seed: options.seed.unwrap_or(0),
+        }),
+        other => Err(format!("unknown solver {}", other)),
+    }
+}
+
+/// Reads a run configuration, as JSON if the file has the extension `json` and as TOML otherwise.
+fn load_config(path: &Path) -> Result<RunConfig, String> {
+    if path.extension().is_some_and(|ext| ext == "json") {
+        read_json_config(path)
+    } else {
+        read_toml_config(path)
+    }
+}
+
+#[cfg(feature = "config-toml")]
+fn read_toml_config(path: &Path) -> Result<RunConfig, String> {
+    RunConfig::from_toml(path).map_err(|err| err.to_string())
+}
+
+#[cfg(not(feature = "config-toml"))]
+fn read_toml_config(_: &Path) -> Result<RunConfig, String> {
+    Err("TOML configurations need the config-toml feature".to_string())
+}
+
+#[cfg(feature = "config-json")]
+fn read_json_config(path: &Path) -> Result<RunConfig, String> {
+    let json = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    RunConfig::from_json(&json).map_err(|err| err.to_string())
+}
+
+#[cfg(not(feature = "config-json"))]
+fn read_json_config(_: &Path) -> Result<RunConfig, String> {
+    Err("JSON configurations need the config-json feature".to_string())
+}
+
+/// Returns the path of an instance in the cache, downloading the instance if it is missing.
+fn fetch(options: &Options, instance: &str) -> Result<PathBuf, String> {
+    let path = options.cache.join(format!("{}.tsp", instance));
+    if path.exists() {
+        return Ok(path);
+    }
+
+    std::fs::create_dir_all(&options.cache).map_err(|err| err.to_string())?;
+    let archive = options.cache.join(format!("{}.tsp.gz", instance));
+    let url = format!("{}/{}.tsp.gz", options.url.trim_end_matches('/'), instance);
+
+    run(Command::new("curl")
+        .args(["-fsSL", "-o"])
+        .arg(&archive)
+        .arg(&url))?;
+    run(Command::new("gunzip").arg("-f").arg(&archive))?;
+
+    Ok(path)
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+    let status = command.status().map_err(|err| err.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{:?} failed with {}", command, status))
+    }
+}
+
+fn bench(options: &Options, instance: &str, path: &Path) -> Result<Row, String> {
+    let optimum = OPTIMA
+        .iter()
+        .find(|(name, _)| *name == instance)
+        .map(|(_, optimum)| *optimum)
+        .ok_or(format!("no known optimum for {}", instance))?;
+
+    let mut model = load_tsp(path, GROUPSIZE).map_err(|err| err.to_string())?;
+    let config = match &options.config {
+        Some(config) => match options.seed {
+            Some(seed) => config.clone().with_seed(seed),
+            None => config.clone(),
+        },
+        None => RunConfigBuilder::new()
+            .cands(options.cands)
+            .solver(solver(options)?)
+            .build(),
+    };
+
+    let start = Instant::now();
+    let order = if options.starts > 1 {
+        let build = |_| load_tsp(path, GROUPSIZE);
+        batch::solve_multi_start(options.starts, build, &config, options.threads)
+    } else {
+        model.solve(&config)
+    }
+    .map_err(|err| err.to_string())?
+    .ok_or("no tour found")?;
+    let time = start.elapsed();
+
+    // TSPLIB rounds Euclidean distances to the nearest integer before summing them up.
+    let store = model.store();
+    let nodes = order.order();
+    let length = (0..nodes.len())
+        .map(|ii| {
+            let cost = store.cost(&nodes[ii], &nodes[(ii + 1) % nodes.len()]);
+            match store.metric() {
+                Metric::Euc2d | Metric::Euc3d => cost.round(),
+                _ => cost,
+            }
+        })
+        .sum();
+
+    Ok(Row {
+        instance: instance.to_string(),
+        nodes: nodes.len(),
+        optimum,
+        length,
+        time,
+    })
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+
+    println!("instance,nodes,optimum,length,gap_percent,time_ms");
+
+    let mut failed = false;
+    for instance in options.instances.iter() {
+        let result = fetch(&options, instance).and_then(|path| bench(&options, instance, &path));
+        match result {
+            Ok(row) => println!(
+                "{},{},{},{},{:.3},{}",
+                row.instance,
+                row.nodes,
+                row.optimum,
+                row.length,
+                row.gap(),
+                row.time.as_millis()
+            ),
+            Err(err) => {
+                eprintln!("{}: {}", instance, err);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
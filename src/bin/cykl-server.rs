@@ -0,0 +1,444 @@
+//! HTTP/JSON service that solves instances in the background.
+//!
+//! Endpoints:
+//! - `POST /jobs` submits an instance and returns the id of its job.
+//! - `GET /jobs/{id}` returns the status of a job together with the best tour found so far.
+//! - `DELETE /jobs/{id}` cancels a job.
+//!
+//! An instance is given either by the coordinates of its nodes and a metric, or by a full cost
+//! matrix. Jobs improve their tours until the time limit is used up, and each improvement is
+//! visible in the status of the job as soon as it is found:
+//!
+//! ```text
+//! { "metric": "euc2d", "coords": [[0, 0], [1, 0], [1, 1]], "time_limit_ms": 500 }
+//! { "matrix": [[0, 2, 3], [2, 0, 1], [3, 1, 0]] }
+//! ```
+//!
+//! Instead of the time limit and the number of candidates, a job may give a full run
+//! configuration in the schema of [`RunConfig::from_json`](cykl::RunConfig::from_json), e.g. a
+//! pipeline of named phases:
+//!
+//! ```text
+//! { "coords": [[0, 0], [1, 0], [1, 1]],
+//!   "config": { "solver": { "kind": "pipeline", "construct": ["greedy"], "improve": ["2opt"] } } }
+//! ```
+//!
+//! Time limits are limited to five minutes, and jobs are cancelled five minutes after their
+//! submission at the latest, also if their configuration has no time limit or waited for a solver.
+//! Request bodies are limited to 16 MiB. At most 256 jobs are kept: finished jobs are evicted
+//! after ten minutes, or earlier, oldest first, to make room for new jobs. Jobs are solved on the
+//! pool of [`solve_async`], thus jobs beyond the number of cores wait until a solver is free.
+//!
+//! The server listens on `127.0.0.1:8080` unless another address is given as first argument.
+use std::{
+    collections::HashMap,
+    io::Read,
+    sync::{Arc, Mutex},
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use cykl::{
+    alg::SolverKind,
+    data::{Metric, NodeKind},
+    service::{solve_async, CancellationToken, Improvement},
+    CyklError, Model, RunConfigBuilder,
+};
+use futures::{
+    channel::mpsc,
+    executor::block_on,
+    future::poll_fn,
+    stream::{self, BoxStream, SelectAll},
+    StreamExt,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+const DEFAULT_TIME_LIMIT_MS: u64 = 1000;
+const MAX_TIME_LIMIT: Duration = Duration::from_secs(300);
+const DEFAULT_CANDS: usize = 8;
+const GROUPSIZE: usize = 64;
+const MAX_BODY_BYTES: u64 = 16 << 20;
+const MAX_JOBS: usize = 256;
+const MIN_NODES: usize = 3;
+const FINISHED_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Deserialize)]
+struct JobRequest {
+    metric: Option<String>,
+    coords: Option<Vec<Vec<f64>>>,
+    matrix: Option<Vec<Vec<f64>>>,
+    time_limit_ms: Option<u64>,
+    cands: Option<usize>,
+    seed: Option<u64>,
+    config: Option<Value>,
+}
+
+/// Validated description of an instance, from which the model is built on the solver thread.
+enum Instance {
+    Coords {
+        metric: Metric,
+        coords: Vec<Vec<f64>>,
+    },
+    Matrix {
+        len: usize,
+        costs: Vec<f64>,
+    },
+}
+
+impl Instance {
+    fn len(&self) -> usize {
+        match self {
+            Self::Coords { coords, .. } => coords.len(),
+            Self::Matrix { len, .. } => *len,
+        }
+    }
+
+    fn build(self) -> Result<Model<()>, CyklError> {
+        match self {
+            Self::Coords { metric, coords } => {
+                let mut model = Model::with_capacity(metric, GROUPSIZE, 0, coords.len());
+                for pos in coords {
+                    model.add(NodeKind::Target, pos, ());
+                }
+                Ok(model)
+            }
+            Self::Matrix { len, costs } => {
+                let mut model = Model::with_capacity(Metric::Explicit, GROUPSIZE, 0, len);
+                for _ in 0..len {
+                    model.add(NodeKind::Target, Vec::new(), ());
+                }
+                model.set_costs(costs)?;
+                Ok(model)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+struct Job {
+    status: Status,
+    best: Option<Improvement>,
+    error: Option<String>,
+    token: CancellationToken,
+    finished: Option<Instant>,
+}
+
+impl Job {
+    fn finish(&mut self, status: Status) {
+        if self.status == Status::Running {
+            self.status = status;
+        }
+        self.finished.get_or_insert_with(Instant::now);
+    }
+}
+
+type Jobs = Arc<Mutex<HashMap<u64, Job>>>;
+
+/// Item of the stream of a job, `None` once the stream has ended.
+type JobEvent = (u64, Option<Result<Improvement, CyklError>>);
+
+fn main() {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let server = match Server::http(&addr) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("Failed to listen on {}: {}", addr, err);
+            std::process::exit(1);
+        }
+    };
+    println!("Listening on {}", addr);
+
+    let jobs: Jobs = Arc::default();
+    let streams = spawn_collector(Arc::clone(&jobs));
+    let mut next_id = 0;
+
+    for mut request in server.incoming_requests() {
+        let method = request.method().clone();
+        let url = request.url().trim_matches('/').to_string();
+        let parts: Vec<&str> = url.split('/').collect();
+
+        let (status, body): (u16, Value) = match (method, parts.as_slice()) {
+            (Method::Post, ["jobs"]) => match read_body(&mut request) {
+                Ok(body) => match parse_job(body) {
+                    Ok((instance, config)) => {
+                        if reserve(&jobs) {
+                            next_id += 1;
+                            submit(&jobs, &streams, next_id, instance, config);
+                            (201, json!({ "id": next_id }))
+                        } else {
+                            (503, json!({ "error": "too many running jobs" }))
+                        }
+                    }
+                    Err(msg) => (400, json!({ "error": msg })),
+                },
+                Err((status, msg)) => (status, json!({ "error": msg })),
+            },
+            (Method::Get, ["jobs", id]) => {
+                match id.parse().ok().and_then(|id| job_status(&jobs, id)) {
+                    Some(body) => (200, body),
+                    None => (404, json!({ "error": "unknown job" })),
+                }
+            }
+            (Method::Delete, ["jobs", id]) => {
+                let cancelled = id.parse().ok().is_some_and(|id: u64| {
+                    match jobs.lock().unwrap().get_mut(&id) {
+                        Some(job) => {
+                            job.token.cancel();
+                            job.finish(Status::Cancelled);
+                            true
+                        }
+                        None => false,
+                    }
+                });
+
+                if cancelled {
+                    (200, json!({}))
+                } else {
+                    (404, json!({ "error": "unknown job" }))
+                }
+            }
+            _ => (404, json!({ "error": "unknown endpoint" })),
+        };
+
+        let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(header);
+        if let Err(err) = request.respond(response) {
+            eprintln!("Failed to respond: {}", err);
+        }
+    }
+}
+
+/// Reads the body of a request, or returns the status code and message of the error.
+fn read_body(request: &mut Request) -> Result<String, (u16, String)> {
+    let too_large = || (413, format!("body exceeds {} bytes", MAX_BODY_BYTES));
+    if request
+        .body_length()
+        .is_some_and(|len| len as u64 > MAX_BODY_BYTES)
+    {
+        return Err(too_large());
+    }
+
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_string(&mut body)
+        .map_err(|err| (400, err.to_string()))?;
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Err(too_large());
+    }
+    Ok(body)
+}
+
+fn parse_job(body: String) -> Result<(Instance, cykl::RunConfig), String> {
+    let req: JobRequest = serde_json::from_str(&body).map_err(|err| err.to_string())?;
+
+    let instance = match (req.coords, req.matrix) {
+        (Some(coords), None) => {
+            let metric = parse_metric(req.metric.as_deref().unwrap_or("euc2d"))?;
+            if coords.len() < MIN_NODES {
+                return Err(format!("coords must have at least {} nodes", MIN_NODES));
+            }
+            if coords.iter().any(|pos| pos.len() != metric.dim()) {
+                return Err(format!("coordinates must have {} values", metric.dim()));
+            }
+            Instance::Coords { metric, coords }
+        }
+        (None, Some(matrix)) => {
+            let len = matrix.len();
+            if len < MIN_NODES {
+                return Err(format!("matrix must have at least {} rows", MIN_NODES));
+            }
+            if matrix.iter().any(|row| row.len() != len) {
+                return Err("matrix must be square".to_string());
+            }
+            Instance::Matrix {
+                len,
+                costs: matrix.into_iter().flatten().collect(),
+            }
+        }
+        _ => return Err("either coords or matrix must be given".to_string()),
+    };
+
+    if let Some(config) = req.config {
+        if req.time_limit_ms.is_some() || req.cands.is_some() {
+            return Err("config cannot be combined with time_limit_ms or cands".to_string());
+        }
+        let config =
+            cykl::RunConfig::from_json(&config.to_string()).map_err(|err| err.to_string())?;
+        if config.cands() >= instance.len() {
+            return Err("cands must be smaller than the number of nodes".to_string());
+        }
+        let config = match req.seed {
+            Some(seed) => config.with_seed(seed),
+            None => config,
+        };
+        return Ok((instance, config));
+    }
+
+    let budget = Duration::from_millis(req.time_limit_ms.unwrap_or(DEFAULT_TIME_LIMIT_MS));
+    if budget > MAX_TIME_LIMIT {
+        return Err(format!(
+            "time_limit_ms must not exceed {}",
+            MAX_TIME_LIMIT.as_millis()
+        ));
+    }
+
+    // Nodes cannot have more candidates than other nodes.
+    let cands = req.cands.unwrap_or(DEFAULT_CANDS).min(instance.len() - 1);
+    let config = RunConfigBuilder::new()
+        .cands(cands)
+        .solver(SolverKind::Adaptive {
+            starters: vec![0],
+            budget,
+            seed: req.seed.unwrap_or(0),
+        })
+        .build();
+
+    Ok((instance, config))
+}
+
+fn parse_metric(name: &str) -> Result<Metric, String> {
+    match name.to_lowercase().as_str() {
+        "euc2d" => Ok(Metric::Euc2d),
+        "euc3d" => Ok(Metric::Euc3d),
+        "ceil2d" => Ok(Metric::Ceil2d),
+        "man2d" => Ok(Metric::Man2d),
+        "man3d" => Ok(Metric::Man3d),
+        "max2d" => Ok(Metric::Max2d),
+        "max3d" => Ok(Metric::Max3d),
+        "geo" => Ok(Metric::Geo),
+        "att" => Ok(Metric::Att),
+        "haversine" => Ok(Metric::Haversine),
+        "vincenty" => Ok(Metric::Vincenty),
+        _ => Err(format!("unknown metric {}", name)),
+    }
+}
+
+/// Evicts finished jobs that have expired, and further finished jobs, oldest first, until there
+/// is room for a new job. Returns `false` if all kept jobs are still running.
+fn reserve(jobs: &Jobs) -> bool {
+    let mut jobs = jobs.lock().unwrap();
+    jobs.retain(|_, job| job.finished.is_none_or(|t| t.elapsed() < FINISHED_TTL));
+
+    while jobs.len() >= MAX_JOBS {
+        let oldest = jobs
+            .iter()
+            .filter_map(|(id, job)| job.finished.map(|t| (t, *id)))
+            .min();
+        match oldest {
+            Some((_, id)) => jobs.remove(&id),
+            None => return false,
+        };
+    }
+
+    true
+}
+
+fn submit(
+    jobs: &Jobs,
+    streams: &mpsc::UnboundedSender<BoxStream<'static, JobEvent>>,
+    id: u64,
+    instance: Instance,
+    config: cykl::RunConfig,
+) {
+    let token = CancellationToken::new();
+    jobs.lock().unwrap().insert(
+        id,
+        Job {
+            status: Status::Running,
+            best: None,
+            error: None,
+            token: token.clone(),
+            finished: None,
+        },
+    );
+
+    // The deadline bounds configurations without time limit, and shares the state of the token
+    // of the job, so that cancelling the job still stops the solver.
+    let deadline = token.with_deadline(Instant::now() + MAX_TIME_LIMIT);
+    let events = solve_async(move || instance.build(), config, deadline)
+        .map(move |item| (id, Some(item)))
+        .chain(stream::once(async move { (id, None) }));
+    // The collector runs as long as the server.
+    let _ = streams.unbounded_send(events.boxed());
+}
+
+/// Spawns the thread that records the improvements of all jobs, and returns the sender through
+/// which the streams of new jobs are passed to it.
+fn spawn_collector(jobs: Jobs) -> mpsc::UnboundedSender<BoxStream<'static, JobEvent>> {
+    let (tx, mut rx) = mpsc::unbounded::<BoxStream<'static, JobEvent>>();
+
+    std::thread::spawn(move || {
+        let mut events = SelectAll::new();
+        block_on(poll_fn(|cx| {
+            while let Poll::Ready(Some(stream)) = rx.poll_next_unpin(cx) {
+                events.push(stream);
+            }
+
+            while let Poll::Ready(Some((id, event))) = events.poll_next_unpin(cx) {
+                if let Some(job) = jobs.lock().unwrap().get_mut(&id) {
+                    match event {
+                        Some(Ok(improvement)) => job.best = Some(improvement),
+                        Some(Err(err)) => {
+                            job.error = Some(err.to_string());
+                            job.finish(Status::Failed);
+                        }
+                        None if job.best.is_some() => job.finish(Status::Done),
+                        None => job.finish(Status::Failed),
+                    }
+                }
+            }
+
+            // New streams wake the task through the receiver.
+            Poll::<()>::Pending
+        }));
+    });
+
+    tx
+}
+
+fn job_status(jobs: &Jobs, id: u64) -> Option<Value> {
+    let jobs = jobs.lock().unwrap();
+    let job = jobs.get(&id)?;
+
+    let best = job.best.as_ref().map(|best| {
+        json!({
+            "cost": best.order.cost(),
+            "order": best.order.order(),
+            "elapsed_ms": best.elapsed.as_millis() as u64,
+        })
+    });
+
+    Some(json!({
+        "id": id,
+        "status": job.status.as_str(),
+        "best": best,
+        "error": job.error,
+    }))
+}
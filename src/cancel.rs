@@ -0,0 +1,67 @@
+//! Cooperative cancellation of long-running operations.
+use std::{
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// Token with which a caller asks a running operation to stop.
+///
+/// Clones of a token share their state, thus a token can be kept by the caller, e.g. a UI thread,
+/// while a clone is passed to the operation. Operations check the token between units of work
+/// that take at most linear time, such as the candidates of a node or an iteration of a solver.
+/// Two tokens are equal if they share their state, regardless of their deadlines.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a token that shares the state of this one, but also counts as cancelled once the
+    /// deadline has passed, e.g. to bound the time of one of several runs under a common token.
+    ///
+    /// Cancelling either token cancels both, while the deadline only applies to the returned one.
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        Self {
+            cancelled: Arc::clone(&self.cancelled),
+            deadline: Some(self.deadline.map_or(deadline, |x| x.min(deadline))),
+        }
+    }
+
+    /// Asks the operation to stop.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` if the operation was asked to stop.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+impl Hash for CancellationToken {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.cancelled) as usize).hash(state);
+    }
+}
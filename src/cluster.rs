@@ -0,0 +1,164 @@
+//! Partitioning of nodes into spatial clusters.
+//!
+//! Clusters are returned as partitions, i.e. lists of node indices, which can be used to split an
+//! instance into smaller instances or assigned as groups with [`Model::set_groups`]. Nodes are
+//! compared by the Euclidean distance between their stored coordinates, and nodes without
+//! coordinates are not part of any cluster.
+//!
+//! [`Model::set_groups`]: crate::Model::set_groups
+use std::collections::BTreeMap;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{data::DataStore, Scalar};
+
+type Point = (Scalar, Scalar, Scalar);
+
+/// Partitions the nodes into at most `k` clusters with Lloyd's k-means algorithm.
+///
+/// Centres are initialised with the k-means++ method from the given seed, and refined until no
+/// node changes its cluster or `max_iterations` iterations have been run. Clusters are listed in
+/// the order of their centres and never empty. Each cluster lists its nodes in ascending order.
+pub fn kmeans<M>(
+    store: &DataStore<M>,
+    k: usize,
+    max_iterations: usize,
+    seed: u64,
+) -> Vec<Vec<usize>> {
+    let points: Vec<(usize, Point)> = (0..store.len())
+        .filter_map(|index| store.coords(index).map(|pos| (index, pos)))
+        .collect();
+    if points.is_empty() || k == 0 {
+        return Vec::new();
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut centres = init_centres(&points, k, &mut rng);
+    let mut labels = vec![usize::MAX; points.len()];
+
+    for _ in 0..max_iterations.max(1) {
+        let mut changed = false;
+        for (label, (_, pos)) in labels.iter_mut().zip(points.iter()) {
+            let nearest = nearest_centre(&centres, pos);
+            if *label != nearest {
+                *label = nearest;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+
+        let mut sums = vec![((0., 0., 0.), 0usize); centres.len()];
+        for (label, (_, pos)) in labels.iter().zip(points.iter()) {
+            let (sum, count) = &mut sums[*label];
+            *sum = (sum.0 + pos.0, sum.1 + pos.1, sum.2 + pos.2);
+            *count += 1;
+        }
+
+        // Centres without nodes stay in place.
+        for (centre, (sum, count)) in centres.iter_mut().zip(sums) {
+            if count > 0 {
+                let n = count as Scalar;
+                *centre = (sum.0 / n, sum.1 / n, sum.2 / n);
+            }
+        }
+    }
+
+    let mut clusters = vec![Vec::new(); centres.len()];
+    for (label, (index, _)) in labels.iter().zip(points.iter()) {
+        clusters[*label].push(*index);
+    }
+    clusters.retain(|cluster| !cluster.is_empty());
+    clusters
+}
+
+/// Partitions the nodes by the cells of a uniform grid with the given cell size.
+///
+/// The grid starts at the smallest coordinates of the nodes. Only non-empty cells form clusters,
+/// which are listed in row-major order, i.e. sorted by their z, y and x cells. Each cluster lists
+/// its nodes in ascending order. A cell size that is not positive puts all nodes into one cluster.
+pub fn grid<M>(store: &DataStore<M>, cell_size: Scalar) -> Vec<Vec<usize>> {
+    let bbox = match store.bounding_box() {
+        Some(bbox) => bbox,
+        None => return Vec::new(),
+    };
+
+    let cell = |x: Scalar, min: Scalar| {
+        if cell_size > 0. {
+            ((x - min) / cell_size).floor() as u64
+        } else {
+            0
+        }
+    };
+
+    let mut cells: BTreeMap<(u64, u64, u64), Vec<usize>> = BTreeMap::new();
+    for index in 0..store.len() {
+        if let Some((x, y, z)) = store.coords(index) {
+            let key = (
+                cell(z, bbox.min.2),
+                cell(y, bbox.min.1),
+                cell(x, bbox.min.0),
+            );
+            cells.entry(key).or_default().push(index);
+        }
+    }
+
+    cells.into_values().collect()
+}
+
+/// Chooses `k` centres with the k-means++ method, or fewer if there are fewer distinct points.
+fn init_centres<R>(points: &[(usize, Point)], k: usize, rng: &mut R) -> Vec<Point>
+where
+    R: Rng,
+{
+    let mut centres = vec![points[rng.gen_range(0..points.len())].1];
+    let mut dists: Vec<Scalar> = points
+        .iter()
+        .map(|(_, pos)| distance(pos, &centres[0]))
+        .collect();
+
+    while centres.len() < k {
+        let total: Scalar = dists.iter().sum();
+        if total <= 0. {
+            break;
+        }
+
+        let mut target = rng.gen::<Scalar>() * total;
+        let mut chosen = points.len() - 1;
+        for (idx, d) in dists.iter().enumerate() {
+            if target < *d {
+                chosen = idx;
+                break;
+            }
+            target -= d;
+        }
+
+        let centre = points[chosen].1;
+        for (d, (_, pos)) in dists.iter_mut().zip(points.iter()) {
+            *d = d.min(distance(pos, &centre));
+        }
+        centres.push(centre);
+    }
+
+    centres
+}
+
+/// Returns the position of the centre nearest to a point, preferring the first among equals.
+fn nearest_centre(centres: &[Point], pos: &Point) -> usize {
+    let mut best = (Scalar::MAX, 0);
+    for (idx, centre) in centres.iter().enumerate() {
+        let d = distance(pos, centre);
+        if d < best.0 {
+            best = (d, idx);
+        }
+    }
+    best.1
+}
+
+/// Returns the squared Euclidean distance between two points.
+#[inline]
+fn distance(a: &Point, b: &Point) -> Scalar {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)
+}
@@ -0,0 +1,112 @@
+use std::{fs::File, path::Path};
+
+use arrow::{array::Float64Array, compute::cast, datatypes::DataType, record_batch::RecordBatch};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+use crate::{
+    data::{Metric, NodeKind},
+    CyklError, Model, Scalar,
+};
+
+/// Names of the columns that hold the coordinates, in the order of their dimensions.
+const COORD_COLUMNS: [&str; 3] = ["x", "y", "z"];
+
+/// Name of the optional column that holds the demands of nodes.
+const DEMAND_COLUMN: &str = "demand";
+
+/// Creates a model from the columns of an Arrow record batch.
+///
+/// The coordinates of the nodes are read from the columns `x`, `y` and, for three-dimensional
+/// metrics, `z`. The optional column `demand` is stored as metadata of the nodes, which defaults to
+/// zero. Columns of any numeric type are accepted, but they must not contain null values.
+pub fn from_arrow(
+    batch: &RecordBatch,
+    metric: Metric,
+    groupsize: usize,
+) -> Result<Model<Scalar>, CyklError> {
+    let mut model = Model::with_capacity(metric, groupsize, 0, batch.num_rows());
+    add_batch(&mut model, batch, metric)?;
+    model.complete();
+
+    Ok(model)
+}
+
+/// Creates a model from a Parquet file, whose columns are mapped into nodes as in
+/// [`from_arrow`].
+pub fn from_parquet<P>(
+    path: P,
+    metric: Metric,
+    groupsize: usize,
+) -> Result<Model<Scalar>, CyklError>
+where
+    P: AsRef<Path>,
+{
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)
+        .and_then(|builder| builder.build())
+        .map_err(|err| CyklError::Parse(format!("{:?}", err)))?;
+
+    let mut model = Model::new(metric, groupsize);
+    for batch in reader {
+        let batch = batch.map_err(|err| CyklError::Parse(format!("{:?}", err)))?;
+        add_batch(&mut model, &batch, metric)?;
+    }
+    model.complete();
+
+    Ok(model)
+}
+
+fn add_batch(
+    model: &mut Model<Scalar>,
+    batch: &RecordBatch,
+    metric: Metric,
+) -> Result<(), CyklError> {
+    if metric.dim() == 0 || metric.dim() > COORD_COLUMNS.len() {
+        return Err(CyklError::MissingCoordinates);
+    }
+
+    let coords = COORD_COLUMNS[..metric.dim()]
+        .iter()
+        .map(|name| column(batch, name)?.ok_or(CyklError::MissingCoordinates))
+        .collect::<Result<Vec<_>, _>>()?;
+    let demands = column(batch, DEMAND_COLUMN)?;
+
+    for row in 0..batch.num_rows() {
+        let pos = coords
+            .iter()
+            .map(|col| value(col, row))
+            .collect::<Result<Vec<_>, _>>()?;
+        let demand = match &demands {
+            Some(col) => value(col, row)?,
+            None => 0.,
+        };
+
+        model.add(NodeKind::Target, pos, demand);
+    }
+
+    Ok(())
+}
+
+/// Returns the column with the given name converted into floating-point numbers, or `None` if the
+/// batch has no such column.
+fn column(batch: &RecordBatch, name: &str) -> Result<Option<Float64Array>, CyklError> {
+    match batch.column_by_name(name) {
+        Some(col) => {
+            let col = cast(col, &DataType::Float64)
+                .map_err(|err| CyklError::Parse(format!("{:?}", err)))?;
+            col.as_any()
+                .downcast_ref::<Float64Array>()
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| CyklError::Parse(format!("Column {} is not numeric", name)))
+        }
+        None => Ok(None),
+    }
+}
+
+fn value(col: &Float64Array, row: usize) -> Result<Scalar, CyklError> {
+    if col.is_null(row) {
+        Err(CyklError::Parse(format!("Null value in row {}", row)))
+    } else {
+        Ok(col.value(row))
+    }
+}
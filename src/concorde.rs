@@ -0,0 +1,186 @@
+//! Bridge to the exact Concorde TSP solver, which runs as a subprocess.
+//!
+//! The instance is written in the TSPLIB format as an explicit matrix of integer costs, which
+//! Concorde reads regardless of the metric of the store. Costs are multiplied by a scale factor
+//! before they are rounded, so that instances with fractional costs can be solved to a chosen
+//! precision. The tour written by Concorde is parsed into a [`TourOrder`], whose cost is measured
+//! with the unrounded costs of the store.
+//!
+//! The Concorde binary is not part of this crate and must be provided by the user.
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{data::DataStore, interop, oracle::cycle_cost, tour::TourOrder, CyklError, Scalar};
+
+/// Counter that keeps the working directories of concurrent runs apart.
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+/// Settings of a Concorde run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Concorde {
+    binary: PathBuf,
+    scale: Scalar,
+    seed: Option<u64>,
+    work_dir: Option<PathBuf>,
+}
+
+impl Concorde {
+    /// Creates settings that run the Concorde binary at the given path.
+    pub fn new<P>(binary: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            binary: binary.as_ref().to_path_buf(),
+            scale: 1.,
+            seed: None,
+            work_dir: None,
+        }
+    }
+
+    /// Sets the factor by which costs are multiplied before they are rounded. Defaults to `1`.
+    pub fn with_scale(mut self, scale: Scalar) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the seed of the random number generator of Concorde.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the directory in which the instance, the solution and the temporary files of Concorde
+    /// are written. Defaults to a new directory in the temporary directory of the system, which
+    /// is removed after the run.
+    pub fn with_work_dir<P>(mut self, dir: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        self.work_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Solves the instance of a store to optimality and returns the optimal tour.
+    ///
+    /// Returns [`CyklError::Io`] if Concorde cannot be started or fails, and [`CyklError::Parse`]
+    /// if its solution cannot be read.
+    pub fn solve<M>(&self, store: &DataStore<M>) -> Result<TourOrder, CyklError> {
+        if store.len() < 3 {
+            return Ok(with_cost(store, (0..store.len()).collect()));
+        }
+
+        let (dir, temporary) = match &self.work_dir {
+            Some(dir) => (dir.clone(), false),
+            None => {
+                let run = RUNS.fetch_add(1, Ordering::Relaxed);
+                let name = format!("cykl-concorde-{}-{}", std::process::id(), run);
+                (std::env::temp_dir().join(name), true)
+            }
+        };
+        std::fs::create_dir_all(&dir)?;
+
+        let result = self.run(store, &dir);
+        if temporary {
+            // The solution has been read, thus a directory that cannot be removed is only litter.
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+        result
+    }
+
+    fn run<M>(&self, store: &DataStore<M>, dir: &Path) -> Result<TourOrder, CyklError> {
+        let (instance, solution) = ("cykl.tsp", "cykl.sol");
+        let mut file = io::BufWriter::new(std::fs::File::create(dir.join(instance))?);
+        write_instance(store, self.scale, &mut file)?;
+        file.flush()?;
+        drop(file);
+
+        let mut command = Command::new(&self.binary);
+        command.current_dir(dir).arg("-x").arg("-o").arg(solution);
+        if let Some(seed) = self.seed {
+            command.arg("-s").arg(seed.to_string());
+        }
+        command.arg(instance);
+
+        let output = command.output()?;
+        if !output.status.success() {
+            return Err(CyklError::Io(io::Error::other(format!(
+                "Concorde failed with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))));
+        }
+
+        let content = std::fs::read_to_string(dir.join(solution))?;
+        let order = parse_solution(&content, store.len())?;
+        Ok(with_cost(store, order))
+    }
+}
+
+/// Writes the instance of a store in the TSPLIB format as the upper triangle of an explicit cost
+/// matrix, with costs multiplied by `scale` and rounded to integers.
+///
+/// Concorde only solves symmetric instances, thus costs that differ between both directions are
+/// rejected with [`CyklError::Asymmetric`].
+pub fn write_instance<M, W>(
+    store: &DataStore<M>,
+    scale: Scalar,
+    writer: &mut W,
+) -> Result<(), CyklError>
+where
+    W: Write,
+{
+    if let Some((from, to)) = interop::asymmetric_pair(store, scale) {
+        return Err(CyklError::Asymmetric { from, to });
+    }
+    interop::write_explicit(store, "cykl", scale, writer)
+}
+
+/// Parses a solution written by Concorde, which consists of the number of nodes followed by the
+/// zero-based node indices in the order of the tour.
+pub fn parse_solution(content: &str, len: usize) -> Result<Vec<usize>, CyklError> {
+    let mut tokens = content.split_whitespace().map(|token| {
+        token
+            .parse::<usize>()
+            .map_err(|_| CyklError::Parse(format!("Invalid node index '{}'", token)))
+    });
+
+    let n = tokens
+        .next()
+        .ok_or_else(|| CyklError::Parse("Empty solution".to_string()))??;
+    if n != len {
+        return Err(CyklError::LenMismatched {
+            expected: len,
+            received: n,
+        });
+    }
+
+    let order = tokens.collect::<Result<Vec<_>, _>>()?;
+    if order.len() != len {
+        return Err(CyklError::LenMismatched {
+            expected: len,
+            received: order.len(),
+        });
+    }
+
+    let mut seen = vec![false; len];
+    for &index in &order {
+        match seen.get_mut(index) {
+            Some(true) => return Err(CyklError::DuplicateNode(index)),
+            Some(flag) => *flag = true,
+            None => return Err(CyklError::IndexOutOfBounds { index, len }),
+        }
+    }
+
+    Ok(order)
+}
+
+/// Creates a tour order whose cost is the length of the closed tour in a store.
+fn with_cost<M>(store: &DataStore<M>, order: Vec<usize>) -> TourOrder {
+    let cost = cycle_cost(store, &order);
+    TourOrder::with_cost(order, cost)
+}
@@ -0,0 +1,365 @@
+#[cfg(feature = "config-toml")]
+use std::path::Path;
+use std::{convert::TryFrom, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alg::{registry::PipelineSpec, Objective, Scan, SolverKind, Termination},
+    CyklError, RunConfig, RunConfigBuilder,
+};
+
+impl RunConfig {
+    /// Reads a configuration from a TOML file, see [`RunConfig::from_toml_str`].
+    #[cfg(feature = "config-toml")]
+    pub fn from_toml<P: AsRef<Path>>(path: P) -> Result<Self, CyklError> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_toml_str(&content)
+    }
+
+    /// Parses a configuration from TOML.
+    ///
+    /// The top-level keys `cands`, `objective` (`"minimize"` or `"maximize"`), `scan` (`"first"`
+    /// or `"best"`) and `deterministic` are optional and default to the values of
+    /// [`RunConfigBuilder`]. The solver is described in a `[solver]` table by its `kind` and the
+    /// parameters of that kind:
+    ///
+    /// | `kind`                                          | parameters                          |
+    /// |-------------------------------------------------|-------------------------------------|
+    /// | `greedy`, `prize-collecting`, `generalized`     | `starters`                          |
+    /// | `late-acceptance`                               | `starters`, `history`, termination, `seed` |
+    /// | `adaptive`                                      | `starters`, `budget_ms`, `seed`     |
+    /// | `lns`                                           | `starters`, `n_remove`, termination, `seed` |
+    /// | `nearest-fragment`                              | `leaf_size`                         |
+    /// | `pipeline`                                      | `construct`, `improve`, `perturb`, `accept`, termination, `seed` |
+    ///
+    /// The phases of a `pipeline` are given by arrays of the names listed in
+    /// [`registry`](crate::alg::registry), which default to empty arrays, and `accept` defaults to
+    /// `"greedy"`. `starters` defaults to `[0]` and `seed` to `0`. The termination is given by
+    /// exactly one of `iterations`, `stagnation` or `time_limit_ms`. Unknown keys, values of the
+    /// wrong type and missing parameters are reported as [`CyklError::Parse`].
+    #[cfg(feature = "config-toml")]
+    pub fn from_toml_str(toml: &str) -> Result<Self, CyklError> {
+        let file: ConfigFile =
+            toml::from_str(toml).map_err(|err| CyklError::Parse(err.to_string()))?;
+        Self::try_from(file)
+    }
+
+    /// Parses a configuration from JSON with the same schema as [`RunConfig::from_toml_str`],
+    /// where the solver is given as the object `"solver"`.
+    #[cfg(feature = "config-json")]
+    pub fn from_json(json: &str) -> Result<Self, CyklError> {
+        let file: ConfigFile =
+            serde_json::from_str(json).map_err(|err| CyklError::Parse(err.to_string()))?;
+        Self::try_from(file)
+    }
+
+    /// Writes the configuration as TOML that [`RunConfig::from_toml_str`] reads back into a
+    /// configuration with the same parameters, e.g. to store it next to the results of an
+    /// experiment.
+    ///
+    /// Durations are written in whole milliseconds, and the cancellation token is not written.
+    /// Returns [`CyklError::Parse`] if a value does not fit into a TOML integer, e.g. a seed
+    /// above `i64::MAX`.
+    #[cfg(feature = "config-toml")]
+    pub fn to_toml(&self) -> Result<String, CyklError> {
+        toml::to_string(&ConfigFile::from(self)).map_err(|err| CyklError::Parse(err.to_string()))
+    }
+}
+
+/// Top-level table of a configuration file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    cands: Option<usize>,
+    #[serde(default, with = "ObjectiveName")]
+    objective: Objective,
+    #[serde(default, with = "ScanName")]
+    scan: Scan,
+    #[serde(default)]
+    deterministic: bool,
+    solver: Option<SolverTable>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Objective", rename_all = "lowercase")]
+enum ObjectiveName {
+    Minimize,
+    Maximize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Scan")]
+enum ScanName {
+    #[serde(rename = "first")]
+    FirstImprovement,
+    #[serde(rename = "best")]
+    BestImprovement,
+}
+
+/// `[solver]` table of a configuration file. Iterative solvers stop after exactly one of
+/// `iterations`, `stagnation` or `time_limit_ms`, which is checked when the table is converted.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+enum SolverTable {
+    Greedy {
+        #[serde(default = "default_starters")]
+        starters: Vec<usize>,
+    },
+    PrizeCollecting {
+        #[serde(default = "default_starters")]
+        starters: Vec<usize>,
+    },
+    Generalized {
+        #[serde(default = "default_starters")]
+        starters: Vec<usize>,
+    },
+    LateAcceptance {
+        #[serde(default = "default_starters")]
+        starters: Vec<usize>,
+        history: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        iterations: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stagnation: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        time_limit_ms: Option<u64>,
+        #[serde(default)]
+        seed: u64,
+    },
+    Adaptive {
+        #[serde(default = "default_starters")]
+        starters: Vec<usize>,
+        budget_ms: u64,
+        #[serde(default)]
+        seed: u64,
+    },
+    Lns {
+        #[serde(default = "default_starters")]
+        starters: Vec<usize>,
+        n_remove: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        iterations: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stagnation: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        time_limit_ms: Option<u64>,
+        #[serde(default)]
+        seed: u64,
+    },
+    NearestFragment {
+        leaf_size: usize,
+    },
+    Pipeline {
+        #[serde(default)]
+        construct: Vec<String>,
+        #[serde(default)]
+        improve: Vec<String>,
+        #[serde(default)]
+        perturb: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        accept: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        iterations: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stagnation: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        time_limit_ms: Option<u64>,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+fn default_starters() -> Vec<usize> {
+    vec![0]
+}
+
+/// Returns the termination given by exactly one of the termination keys of a solver.
+fn termination(
+    iterations: Option<usize>,
+    stagnation: Option<usize>,
+    time_limit_ms: Option<u64>,
+) -> Result<Termination, CyklError> {
+    let error = |msg: &str| CyklError::Parse(format!("solver: {}", msg));
+    match (iterations, stagnation, time_limit_ms) {
+        (Some(n), None, None) => Ok(Termination::Iterations(n)),
+        (None, Some(n), None) => Ok(Termination::Stagnation(n)),
+        (None, None, Some(ms)) => Ok(Termination::TimeLimit(Duration::from_millis(ms))),
+        (None, None, None) => Err(error(
+            "missing termination, expected one of iterations, stagnation, time_limit_ms",
+        )),
+        _ => Err(error(
+            "conflicting termination, expected only one of iterations, stagnation, time_limit_ms",
+        )),
+    }
+}
+
+/// Returns the values of the `iterations`, `stagnation` and `time_limit_ms` keys of a
+/// termination.
+#[cfg(feature = "config-toml")]
+fn termination_keys(termination: Termination) -> (Option<usize>, Option<usize>, Option<u64>) {
+    match termination {
+        Termination::Iterations(n) => (Some(n), None, None),
+        Termination::Stagnation(n) => (None, Some(n), None),
+        Termination::TimeLimit(limit) => (None, None, Some(limit.as_millis() as u64)),
+    }
+}
+
+impl TryFrom<ConfigFile> for RunConfig {
+    type Error = CyklError;
+
+    fn try_from(file: ConfigFile) -> Result<Self, Self::Error> {
+        let mut builder = RunConfigBuilder::new()
+            .objective(file.objective)
+            .scan(file.scan)
+            .deterministic(file.deterministic);
+        if let Some(cands) = file.cands {
+            if cands == 0 {
+                return Err(CyklError::Parse(
+                    "cands: expected at least one candidate".to_string(),
+                ));
+            }
+            builder = builder.cands(cands);
+        }
+
+        let solver = match file.solver {
+            None => return Ok(builder.build()),
+            Some(solver) => solver,
+        };
+        let solver = match solver {
+            SolverTable::Greedy { starters } => SolverKind::Greedy(starters),
+            SolverTable::PrizeCollecting { starters } => SolverKind::PrizeCollecting(starters),
+            SolverTable::Generalized { starters } => SolverKind::Generalized(starters),
+            SolverTable::LateAcceptance {
+                starters,
+                history,
+                iterations,
+                stagnation,
+                time_limit_ms,
+                seed,
+            } => SolverKind::LateAcceptance {
+                starters,
+                history,
+                termination: termination(iterations, stagnation, time_limit_ms)?,
+                seed,
+            },
+            SolverTable::Adaptive {
+                starters,
+                budget_ms,
+                seed,
+            } => SolverKind::Adaptive {
+                starters,
+                budget: Duration::from_millis(budget_ms),
+                seed,
+            },
+            SolverTable::Lns {
+                starters,
+                n_remove,
+                iterations,
+                stagnation,
+                time_limit_ms,
+                seed,
+            } => SolverKind::Lns {
+                starters,
+                n_remove,
+                termination: termination(iterations, stagnation, time_limit_ms)?,
+                seed,
+            },
+            SolverTable::NearestFragment { leaf_size } => SolverKind::NearestFragment { leaf_size },
+            SolverTable::Pipeline {
+                construct,
+                improve,
+                perturb,
+                accept,
+                iterations,
+                stagnation,
+                time_limit_ms,
+                seed,
+            } => SolverKind::Pipeline(PipelineSpec {
+                construct,
+                improve,
+                perturb,
+                accept,
+                termination: termination(iterations, stagnation, time_limit_ms)?,
+                objective: file.objective,
+                seed,
+            }),
+        };
+
+        Ok(builder.solver(solver).build())
+    }
+}
+
+#[cfg(feature = "config-toml")]
+impl From<&RunConfig> for ConfigFile {
+    fn from(config: &RunConfig) -> Self {
+        let solver = match config.solver().clone() {
+            SolverKind::Greedy(starters) => SolverTable::Greedy { starters },
+            SolverKind::PrizeCollecting(starters) => SolverTable::PrizeCollecting { starters },
+            SolverKind::Generalized(starters) => SolverTable::Generalized { starters },
+            SolverKind::LateAcceptance {
+                starters,
+                history,
+                termination,
+                seed,
+            } => {
+                let (iterations, stagnation, time_limit_ms) = termination_keys(termination);
+                SolverTable::LateAcceptance {
+                    starters,
+                    history,
+                    iterations,
+                    stagnation,
+                    time_limit_ms,
+                    seed,
+                }
+            }
+            SolverKind::Adaptive {
+                starters,
+                budget,
+                seed,
+            } => SolverTable::Adaptive {
+                starters,
+                budget_ms: budget.as_millis() as u64,
+                seed,
+            },
+            SolverKind::Lns {
+                starters,
+                n_remove,
+                termination,
+                seed,
+            } => {
+                let (iterations, stagnation, time_limit_ms) = termination_keys(termination);
+                SolverTable::Lns {
+                    starters,
+                    n_remove,
+                    iterations,
+                    stagnation,
+                    time_limit_ms,
+                    seed,
+                }
+            }
+            SolverKind::NearestFragment { leaf_size } => SolverTable::NearestFragment { leaf_size },
+            SolverKind::Pipeline(spec) => {
+                let (iterations, stagnation, time_limit_ms) = termination_keys(spec.termination);
+                SolverTable::Pipeline {
+                    construct: spec.construct,
+                    improve: spec.improve,
+                    perturb: spec.perturb,
+                    accept: spec.accept,
+                    iterations,
+                    stagnation,
+                    time_limit_ms,
+                    seed: spec.seed,
+                }
+            }
+        };
+
+        Self {
+            cands: Some(config.cands()),
+            objective: config.objective(),
+            scan: config.scan(),
+            deterministic: config.is_deterministic(),
+            solver: Some(solver),
+        }
+    }
+}
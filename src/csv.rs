@@ -0,0 +1,236 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use crate::{
+    data::{Metric, NodeKind},
+    CyklError, Model, Scalar,
+};
+
+/// Column of a CSV file, referred to either by its name in the header or by its position.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Column {
+    Name(String),
+    Index(usize),
+}
+
+impl From<&str> for Column {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_string())
+    }
+}
+
+impl From<usize> for Column {
+    fn from(index: usize) -> Self {
+        Self::Index(index)
+    }
+}
+
+/// Describes how the columns of a CSV file are mapped into nodes.
+///
+/// By default, the file is expected to have a header and to be delimited by commas, and the
+/// coordinates are read from the columns `x`, `y` and, for three-dimensional metrics, `z`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ColumnMap {
+    coords: [Column; 3],
+    id: Option<Column>,
+    demand: Option<Column>,
+    delimiter: char,
+    header: bool,
+}
+
+impl Default for ColumnMap {
+    fn default() -> Self {
+        Self {
+            coords: ["x".into(), "y".into(), "z".into()],
+            id: None,
+            demand: None,
+            delimiter: ',',
+            header: true,
+        }
+    }
+}
+
+impl ColumnMap {
+    /// Creates the default mapping, see [`ColumnMap`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the column that holds the first coordinate of nodes.
+    pub fn x<C: Into<Column>>(mut self, column: C) -> Self {
+        self.coords[0] = column.into();
+        self
+    }
+
+    /// Sets the column that holds the second coordinate of nodes.
+    pub fn y<C: Into<Column>>(mut self, column: C) -> Self {
+        self.coords[1] = column.into();
+        self
+    }
+
+    /// Sets the column that holds the third coordinate of nodes, which is only read for
+    /// three-dimensional metrics.
+    pub fn z<C: Into<Column>>(mut self, column: C) -> Self {
+        self.coords[2] = column.into();
+        self
+    }
+
+    /// Sets the column that holds the identifiers of nodes.
+    pub fn id<C: Into<Column>>(mut self, column: C) -> Self {
+        self.id = Some(column.into());
+        self
+    }
+
+    /// Sets the column that holds the demands of nodes.
+    pub fn demand<C: Into<Column>>(mut self, column: C) -> Self {
+        self.demand = Some(column.into());
+        self
+    }
+
+    /// Sets the character that separates the fields of a line.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets whether the first line of the file names the columns. Columns can only be referred to
+    /// by their names if the file has a header.
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+}
+
+/// Metadata of a node read from a CSV file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CsvNode {
+    /// Identifier of the node, if an ID column is mapped.
+    pub id: Option<String>,
+    /// Demand of the node, which is zero if no demand column is mapped.
+    pub demand: Scalar,
+}
+
+/// Creates a model from the rows of a CSV file, whose columns are mapped into nodes by `map`.
+///
+/// Fields may be enclosed in double quotes, in which case they may contain the delimiter. Empty
+/// lines are skipped.
+pub fn from_csv<P>(
+    path: P,
+    map: &ColumnMap,
+    metric: Metric,
+    groupsize: usize,
+) -> Result<Model<CsvNode>, CyklError>
+where
+    P: AsRef<Path>,
+{
+    read_csv(BufReader::new(File::open(path)?), map, metric, groupsize)
+}
+
+pub(crate) fn read_csv<R>(
+    reader: R,
+    map: &ColumnMap,
+    metric: Metric,
+    groupsize: usize,
+) -> Result<Model<CsvNode>, CyklError>
+where
+    R: BufRead,
+{
+    if metric.dim() == 0 || metric.dim() > map.coords.len() {
+        return Err(CyklError::MissingCoordinates);
+    }
+
+    let mut lines = reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()));
+
+    let header = if map.header {
+        match lines.next() {
+            Some((_, line)) => split(&line?, map.delimiter),
+            None => Vec::new(),
+        }
+    } else {
+        Vec::new()
+    };
+
+    let coords = map.coords[..metric.dim()]
+        .iter()
+        .map(|col| position(&header, col))
+        .collect::<Result<Vec<_>, _>>()?;
+    let id = map
+        .id
+        .as_ref()
+        .map(|col| position(&header, col))
+        .transpose()?;
+    let demand = map
+        .demand
+        .as_ref()
+        .map(|col| position(&header, col))
+        .transpose()?;
+
+    let mut model = Model::new(metric, groupsize);
+    for (line_no, line) in lines {
+        let fields = split(&line?, map.delimiter);
+        let field = |idx: usize| {
+            fields.get(idx).map(|s| s.trim()).ok_or_else(|| {
+                CyklError::Parse(format!("Line {} has no column {}", line_no + 1, idx))
+            })
+        };
+        let number = |idx: usize| {
+            let s = field(idx)?;
+            s.parse::<Scalar>().map_err(|_| {
+                CyklError::Parse(format!("Invalid number {:?} in line {}", s, line_no + 1))
+            })
+        };
+
+        let pos = coords
+            .iter()
+            .map(|idx| number(*idx))
+            .collect::<Result<Vec<_>, _>>()?;
+        let meta = CsvNode {
+            id: id.map(|idx| field(idx).map(str::to_string)).transpose()?,
+            demand: demand.map(number).transpose()?.unwrap_or(0.),
+        };
+
+        model.add(NodeKind::Target, pos, meta);
+    }
+    model.complete();
+
+    Ok(model)
+}
+
+fn position(header: &[String], column: &Column) -> Result<usize, CyklError> {
+    match column {
+        Column::Index(idx) => Ok(*idx),
+        Column::Name(name) => header
+            .iter()
+            .position(|h| h.trim() == name)
+            .ok_or_else(|| CyklError::Parse(format!("Column {} not found", name))),
+    }
+}
+
+/// Splits a line into its fields, removing the double quotes that enclose fields.
+fn split(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
@@ -1,9 +1,29 @@
-use std::{f64::consts::PI, ptr::NonNull};
+use std::{
+    cell::OnceCell,
+    collections::{BTreeMap, HashMap},
+    f64::consts::PI,
+    fmt::Display,
+    ptr::NonNull,
+};
 
-use crate::Scalar;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    oracle::cycle_cost,
+    spatial::{self, BoundingBox, KdTree},
+    tour::{free, to_nonnull, TourOrder},
+    CyklError, Scalar,
+};
 
 const EARTH_RADIUS: f64 = 6378.388;
 
+/// Mean radius of the earth in meters.
+const MEAN_EARTH_RADIUS: f64 = 6_371_008.8;
+/// Semi-major axis of the WGS-84 ellipsoid in meters.
+const WGS84_A: f64 = 6_378_137.;
+/// Flattening of the WGS-84 ellipsoid.
+const WGS84_F: f64 = 1. / 298.257_223_563;
+
 pub trait GetIndex {
     /// Returns a node's index.
     fn get(&self) -> usize;
@@ -16,25 +36,46 @@ impl GetIndex for usize {
 }
 
 impl GetIndex for NodeIndex {
+    // The cast is a no-op unless indices are stored as `u32`.
+    #[allow(clippy::unnecessary_cast)]
     fn get(&self) -> usize {
-        self.index
+        self.index as usize
     }
 }
 
+/// Integer type in which node indices are stored.
+///
+/// With the `u32-index` feature, indices take four bytes, which halves the size of [`NodeIndex`]
+/// and thus of the nodes of stores and tours, at the expense of limiting instances to `u32::MAX`
+/// nodes. The links between the vertices of a [`TwoLevelList`](crate::tour::TwoLevelList) shrink
+/// to four bytes as well, which limits its tours to 2^31 vertices.
+#[cfg(feature = "u32-index")]
+pub type RawIndex = u32;
+
+/// Integer type in which node indices are stored.
+#[cfg(not(feature = "u32-index"))]
+pub type RawIndex = usize;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct NodeIndex {
-    index: usize,
+    index: RawIndex,
     kind: NodeKind,
 }
 
 impl NodeIndex {
+    /// Creates an index, which must not exceed the range of [`RawIndex`].
     pub(crate) fn new(index: usize, kind: NodeKind) -> Self {
-        Self { index, kind }
+        debug_assert!(index as u64 <= RawIndex::MAX as u64);
+        Self {
+            index: index as RawIndex,
+            kind,
+        }
     }
 
     /// Returns the location's index.
+    #[allow(clippy::unnecessary_cast)]
     pub fn index(&self) -> usize {
-        self.index
+        self.index as usize
     }
 
     /// Returns the location's kind.
@@ -49,10 +90,224 @@ pub enum NodeKind {
     Target,
 }
 
+/// Policy for nodes that share their coordinates with previously added nodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DuplicatePolicy {
+    /// Duplicates are kept as distinct nodes connected by zero-cost edges.
+    #[default]
+    Keep,
+    /// Duplicates are not added. Instead, the index of the first node of the same kind with the
+    /// same coordinates is returned, and the metadata given for the duplicate is dropped.
+    ///
+    /// Nodes without coordinates, e.g. of [`Metric::Explicit`], are never merged.
+    Merge,
+    /// Duplicates are kept, but their coordinates are perturbed by at most the given amount in
+    /// each dimension when the costs are computed. The stored coordinates are not changed.
+    Jitter(Scalar),
+}
+
+/// Upper bound of the memory that an instance may occupy.
+///
+/// Instead of running out of memory, a store whose cost matrix would exceed the budget computes
+/// costs from the coordinates on demand, and solvers generate fewer candidates per node. The
+/// budget covers the store and the candidates, but not the tours built over the store.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MemoryBudget {
+    bytes: usize,
+}
+
+impl MemoryBudget {
+    /// Minimum number of candidates per node that is kept regardless of the budget.
+    pub const MIN_CANDS: usize = 2;
+
+    /// Creates a budget of the given number of bytes.
+    pub fn new(bytes: usize) -> Self {
+        Self { bytes }
+    }
+
+    /// Creates a budget without limit, under which all costs are cached.
+    pub fn unlimited() -> Self {
+        Self::new(usize::MAX)
+    }
+
+    /// Returns the number of bytes of the budget.
+    #[inline]
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Returns `true` if `additional` bytes fit into the budget when `used` bytes are already
+    /// occupied.
+    #[inline]
+    pub fn fits(&self, used: usize, additional: usize) -> bool {
+        used.saturating_add(additional) <= self.bytes
+    }
+
+    /// Returns the largest number of candidates per node, up to `k`, that fits into the budget
+    /// when `used` bytes are already occupied, but at least [`MemoryBudget::MIN_CANDS`].
+    pub fn max_cands(&self, n_nodes: usize, k: usize, used: usize) -> usize {
+        let per_cand = n_nodes.saturating_mul(CAND_BYTES).max(1);
+        let fitting = self.bytes.saturating_sub(used) / per_cand;
+        k.min(fitting.max(Self::MIN_CANDS))
+    }
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// Bytes occupied by a candidate of a node in a tour.
+const CAND_BYTES: usize = std::mem::size_of::<usize>();
+
+/// Unit in which the costs of a store are given, so that costs from different sources, such as
+/// travel times and travel distances, are not mixed up.
+///
+/// The unit describes the costs before they are multiplied by the scale of the store, see
+/// [`DataStore::set_scale`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// The costs have no particular unit, e.g. for Euclidean distances between abstract points.
+    #[default]
+    Unitless,
+    /// Travel distances in meters.
+    Meters,
+    /// Travel times in seconds.
+    Seconds,
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unitless => Ok(()),
+            Self::Meters => write!(f, "m"),
+            Self::Seconds => write!(f, "s"),
+        }
+    }
+}
+
+/// Named matrix of costs between all nodes of a store that is kept besides the costs used by the
+/// search, e.g. tolls next to travel times.
+///
+/// Layers can be reported for a tour with [`CostLayer::total`], added to an objective as a
+/// weighted penalty term, or combined into the costs of the store with
+/// [`DataStore::set_costs_from_layers`].
+#[derive(Clone, Debug)]
+pub struct CostLayer {
+    name: String,
+    unit: Unit,
+    len: usize,
+    costs: Vec<Scalar>,
+}
+
+impl CostLayer {
+    /// Returns the name of the layer.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the unit of the costs in the layer.
+    pub fn unit(&self) -> Unit {
+        self.unit
+    }
+
+    /// Returns the number of nodes the layer covers.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the layer covers no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the cost of travelling from node `a` to node `b`.
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` are out of bounds.
+    #[inline]
+    pub fn cost(&self, a: usize, b: usize) -> Scalar {
+        assert!(a < self.len && b < self.len, "Node index out of bounds");
+        self.costs[a * self.len + b]
+    }
+
+    /// Returns the sum of the costs along the edges of a closed tour.
+    ///
+    /// # Panics
+    /// Panics if the order contains an index that is out of bounds.
+    pub fn total(&self, order: &TourOrder) -> Scalar {
+        cycle_cost(self, order.order())
+    }
+}
+
+/// Transformation applied to the coordinates of nodes when they are added to a store.
+///
+/// Projections allow geographical instances to be solved with a fast planar metric such as
+/// [`Metric::Euc2d`], while the original coordinates remain accessible through
+/// [`DataStore::origin`]. All built-in projections expect latitude and longitude in decimal degrees
+/// and produce planar coordinates in meters.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Projection {
+    /// Coordinates are stored as given.
+    #[default]
+    Identity,
+    /// Spherical Web Mercator projection (EPSG:3857).
+    ///
+    /// Distances are stretched by a factor of `1 / cos(lat)`, thus this projection is only suitable
+    /// for instances spanning a small range of latitudes.
+    WebMercator,
+    /// Universal Transverse Mercator projection on the WGS-84 ellipsoid for a fixed zone.
+    ///
+    /// The distortion is below 0.1% within the zone.
+    Utm { zone: u8, north: bool },
+    /// Multiplies each coordinate by the weight of its axis, so that moves along heavier axes
+    /// cost more, e.g. slow Z moves of a drill. Coordinates are not expected in degrees.
+    AxisWeights([f64; 3]),
+    /// Projection defined by users.
+    Custom(fn(&[f64]) -> Vec<f64>),
+}
+
+impl Projection {
+    /// Returns the projected coordinates of a point.
+    pub fn project(&self, pos: &[f64]) -> Vec<f64> {
+        match self {
+            Self::Identity => pos.to_vec(),
+            Self::WebMercator => web_mercator(pos),
+            Self::Utm { zone, north } => utm(pos, *zone, *north),
+            Self::AxisWeights(weights) => pos
+                .iter()
+                .zip(weights.iter().chain(std::iter::repeat(&1.)))
+                .map(|(x, w)| x * w)
+                .collect(),
+            Self::Custom(f) => f(pos),
+        }
+    }
+
+    #[inline]
+    fn is_identity(&self) -> bool {
+        matches!(self, Self::Identity)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DataStore<M> {
     node: NodeStore,
     meta: Vec<M>,
+    policy: DuplicatePolicy,
+    budget: MemoryBudget,
+    // Lookup table from kinds and coordinates to nodes, only filled for the merge policy.
+    positions: HashMap<(NodeKind, Vec<u64>), NodeIndex>,
+    projection: Projection,
+    // Coordinates before projection, only filled if a projection is set.
+    origins: Vec<Vec<f64>>,
+    // Built on the first nearest-node query, and dropped whenever coordinates change.
+    tree: OnceCell<KdTree>,
+    unit: Unit,
+    layers: Vec<CostLayer>,
+    // Unscaled cost of the edges absent from a graph given by its edges, `None` for complete
+    // graphs.
+    forbidden: Option<Scalar>,
 }
 
 impl<M> DataStore<M> {
@@ -62,91 +317,765 @@ impl<M> DataStore<M> {
             metric,
             nodes: Vec::new(),
             coords: Vec::new(),
+            prizes: Vec::new(),
+            service: Vec::new(),
+            groups: Vec::new(),
             costs: Vec::with_capacity(0),
+            jittered: Vec::with_capacity(0),
+            scale: 1.,
+            refs: 1,
         };
 
         let node = NodeStore {
-            inner: NonNull::new(Box::leak(Box::new(inner))),
+            inner: to_nonnull(inner),
         };
 
         Self {
             node,
             meta: Vec::new(),
+            policy: DuplicatePolicy::default(),
+            budget: MemoryBudget::default(),
+            positions: HashMap::new(),
+            projection: Projection::default(),
+            origins: Vec::with_capacity(0),
+            tree: OnceCell::new(),
+            unit: Unit::default(),
+            layers: Vec::new(),
+            forbidden: None,
         }
     }
 
+    /// Creates a store of three-dimensional nodes whose cost is the Euclidean distance with each
+    /// axis weighted, as for drilling or printing paths where moves along some axes are slower.
+    ///
+    /// The weights are applied by [`Projection::AxisWeights`], so the coordinates as given remain
+    /// accessible through [`DataStore::origin`].
+    pub fn with_axis_weights(wx: f64, wy: f64, wz: f64) -> Self {
+        let mut store = Self::new(Metric::Euc3d);
+        store.set_projection(Projection::AxisWeights([wx, wy, wz]));
+        store
+    }
+
     pub fn with_capacity(metric: Metric, capacity: usize) -> Self {
         let inner = InnerStore {
             dim: metric.dim(),
             metric,
             nodes: Vec::with_capacity(capacity),
             coords: Vec::with_capacity(capacity * metric.dim()),
+            prizes: Vec::with_capacity(capacity),
+            service: Vec::with_capacity(capacity),
+            groups: Vec::with_capacity(capacity),
             costs: Vec::with_capacity(0),
+            jittered: Vec::with_capacity(0),
+            scale: 1.,
+            refs: 1,
         };
 
         let node = NodeStore {
-            inner: NonNull::new(Box::leak(Box::new(inner))),
+            inner: to_nonnull(inner),
         };
 
         Self {
             node,
             meta: Vec::with_capacity(capacity),
+            policy: DuplicatePolicy::default(),
+            budget: MemoryBudget::default(),
+            positions: HashMap::new(),
+            projection: Projection::default(),
+            origins: Vec::with_capacity(0),
+            tree: OnceCell::new(),
+            unit: Unit::default(),
+            layers: Vec::new(),
+            forbidden: None,
+        }
+    }
+
+    /// Creates a store for nodes given as latitude and longitude in decimal degrees, using the
+    /// [`Metric::Haversine`] distance in meters.
+    pub fn new_geo() -> Self {
+        let mut store = Self::new(Metric::Haversine);
+        store.unit = Unit::Meters;
+        store
+    }
+
+    /// Adds a node given as latitude and longitude in decimal degrees.
+    ///
+    /// Returns `None` if the metric of the store is not geodesic.
+    pub fn add_latlon(&mut self, kind: NodeKind, lat: f64, lon: f64, meta: M) -> Option<NodeIndex> {
+        if self.metric().is_geodesic() {
+            self.add(kind, vec![lat, lon], meta)
+        } else {
+            None
+        }
+    }
+
+    /// Sets the memory budget of the store, see [`MemoryBudget`].
+    ///
+    /// Must be called before the costs are computed.
+    pub fn set_memory_budget(&mut self, budget: MemoryBudget) {
+        self.budget = budget;
+    }
+
+    /// Returns the memory budget of the store.
+    pub fn memory_budget(&self) -> MemoryBudget {
+        self.budget
+    }
+
+    /// Returns the number of bytes allocated by the store, including the cost matrix if it is
+    /// cached.
+    pub fn memory_footprint(&self) -> usize {
+        let mut bytes = std::mem::size_of::<Self>()
+            + self.meta.capacity() * std::mem::size_of::<M>()
+            + self
+                .origins
+                .iter()
+                .map(|x| {
+                    std::mem::size_of::<Vec<f64>>() + x.capacity() * std::mem::size_of::<f64>()
+                })
+                .sum::<usize>()
+            + self
+                .positions
+                .keys()
+                .map(|(_, x)| {
+                    std::mem::size_of::<((NodeKind, Vec<u64>), NodeIndex)>()
+                        + x.capacity() * std::mem::size_of::<u64>()
+                })
+                .sum::<usize>()
+            + self
+                .layers
+                .iter()
+                .map(|x| {
+                    std::mem::size_of::<CostLayer>()
+                        + x.costs.capacity() * std::mem::size_of::<Scalar>()
+                })
+                .sum::<usize>();
+
+        if let Some(inner) = self.node.inner {
+            let inner = unsafe { inner.as_ref() };
+            bytes += std::mem::size_of::<InnerStore>()
+                + inner.nodes.capacity() * std::mem::size_of::<NodeIndex>()
+                + inner.coords.capacity() * std::mem::size_of::<Scalar>()
+                + inner.jittered.capacity() * std::mem::size_of::<Scalar>()
+                + inner.prizes.capacity() * std::mem::size_of::<Scalar>()
+                + inner.service.capacity() * std::mem::size_of::<Scalar>()
+                + inner.groups.capacity() * std::mem::size_of::<Option<usize>>()
+                + inner.costs.capacity() * std::mem::size_of::<Scalar>();
+        }
+
+        bytes
+    }
+
+    /// Returns `true` if the costs between all nodes are kept in a matrix, rather than computed
+    /// from the coordinates on demand.
+    pub fn is_matrix_cached(&self) -> bool {
+        match self.node.inner {
+            Some(inner) => unsafe { !inner.as_ref().costs.is_empty() },
+            None => false,
+        }
+    }
+
+    /// Returns the metric used to compute costs between nodes.
+    #[inline]
+    pub fn metric(&self) -> Metric {
+        match self.node.inner {
+            Some(inner) => unsafe { inner.as_ref().metric },
+            None => Metric::Undefined,
+        }
+    }
+
+    /// Sets the unit of the costs, see [`Unit`].
+    pub fn set_unit(&mut self, unit: Unit) {
+        self.unit = unit;
+    }
+
+    /// Returns the unit of the costs.
+    pub fn unit(&self) -> Unit {
+        self.unit
+    }
+
+    /// Sets the factor by which all costs are multiplied, e.g. `1. / 60.` to turn travel times in
+    /// seconds into minutes. Defaults to `1`.
+    ///
+    /// The factor applies to costs computed from coordinates as well as to explicit costs, and
+    /// may be set before or after they are computed.
+    ///
+    /// # Panics
+    /// Panics if the factor is not a positive finite number.
+    pub fn set_scale(&mut self, factor: Scalar) {
+        assert!(
+            factor.is_finite() && factor > 0.,
+            "Scale must be positive and finite"
+        );
+
+        if let Some(inner) = self.node.inner {
+            unsafe {
+                let ratio = factor / inner.as_ref().scale;
+                (*inner.as_ptr()).costs.iter_mut().for_each(|x| *x *= ratio);
+                (*inner.as_ptr()).scale = factor;
+            }
+        }
+    }
+
+    /// Returns the factor by which all costs are multiplied.
+    pub fn scale(&self) -> Scalar {
+        match self.node.inner {
+            Some(inner) => unsafe { inner.as_ref().scale },
+            None => 1.,
         }
     }
 
+    /// Sets the policy for nodes with duplicate coordinates.
+    ///
+    /// The policy must be set before nodes are added, since the merge policy is applied when a node
+    /// is added and the jitter policy is applied when costs are computed.
+    pub fn set_duplicate_policy(&mut self, policy: DuplicatePolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns the policy for nodes with duplicate coordinates.
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.policy
+    }
+
+    /// Sets the projection applied to the coordinates of nodes.
+    ///
+    /// The projection must be set before nodes are added.
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    /// Returns the projection applied to the coordinates of nodes.
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// Returns the coordinates of the node at the given index as they were given before the
+    /// projection.
+    pub fn origin(&self, index: usize) -> Option<&[f64]> {
+        if self.projection.is_identity() {
+            self.coord(index)
+        } else {
+            self.origins.get(index).map(|x| x.as_slice())
+        }
+    }
+
+    /// Adds a node and returns its index.
+    ///
+    /// Returns `None` if the index of the node would exceed the range of [`RawIndex`].
     #[inline]
     pub fn add(&mut self, kind: NodeKind, mut pos: Vec<f64>, meta: M) -> Option<NodeIndex> {
+        #[cfg(feature = "u32-index")]
+        if self.len() as u64 > RawIndex::MAX as u64 {
+            return None;
+        }
+
+        let policy = self.policy;
+        self.tree.take();
+        let positions = &mut self.positions;
+        let metas = &mut self.meta;
+        let origins = &mut self.origins;
+
+        if !self.projection.is_identity() {
+            let projected = self.projection.project(&pos);
+            origins.push(std::mem::replace(&mut pos, projected));
+        }
+
         self.node.inner.and_then(|inner| unsafe {
             if pos.len() != inner.as_ref().dim {
                 panic!("Len mismatched")
             }
 
+            if policy == DuplicatePolicy::Merge && !pos.is_empty() {
+                let key = (kind, to_key(&pos));
+                if let Some(node) = positions.get(&key) {
+                    origins.truncate(inner.as_ref().nodes.len());
+                    return Some(*node);
+                }
+
+                positions.insert(key, NodeIndex::new(inner.as_ref().nodes.len(), kind));
+            }
+
             let idx = inner.as_ref().nodes.len();
             let node = NodeIndex::new(idx, kind);
 
             (*inner.as_ptr()).nodes.push(node);
             (*inner.as_ptr()).coords.append(&mut pos);
-            self.meta.push(meta);
+            (*inner.as_ptr()).jittered = Vec::with_capacity(0);
+            (*inner.as_ptr()).prizes.push(0.);
+            (*inner.as_ptr()).service.push(0.);
+            (*inner.as_ptr()).groups.push(None);
+            metas.push(meta);
 
             inner.as_ref().nodes.get(idx).cloned()
         })
     }
 
+    /// Sets the prize collected by a tour that visits the node at the given index.
+    ///
+    /// Tours that skip the node pay its prize as a penalty. Nodes have no prize by default.
+    pub fn set_prize(&mut self, index: usize, prize: Scalar) -> Result<(), CyklError> {
+        let len = self.len();
+        match self.node.inner {
+            Some(inner) if index < len => {
+                unsafe { (*inner.as_ptr()).prizes[index] = prize };
+                Ok(())
+            }
+            _ => Err(CyklError::IndexOutOfBounds { index, len }),
+        }
+    }
+
+    /// Returns the prize of the node at the given index.
+    #[inline]
+    pub fn prize(&self, index: usize) -> Scalar {
+        self.node.prize(index)
+    }
+
+    /// Sets the time spent serving the node at the given index, in the unit of the costs.
+    ///
+    /// Service times count towards the duration of a tour, but not its length. Nodes need no
+    /// service by default.
+    pub fn set_service_time(&mut self, index: usize, time: Scalar) -> Result<(), CyklError> {
+        let len = self.len();
+        match self.node.inner {
+            Some(inner) if index < len => {
+                unsafe { (*inner.as_ptr()).service[index] = time };
+                Ok(())
+            }
+            _ => Err(CyklError::IndexOutOfBounds { index, len }),
+        }
+    }
+
+    /// Returns the service time of the node at the given index, or zero if there is no such node.
+    #[inline]
+    pub fn service_time(&self, index: usize) -> Scalar {
+        match self.node.inner {
+            Some(inner) => unsafe { inner.as_ref().service.get(index).copied().unwrap_or(0.) },
+            None => 0.,
+        }
+    }
+
+    /// Assigns the node at the given index to a group.
+    ///
+    /// In the generalised variant of the problem, a tour visits exactly one node of each group.
+    /// Nodes without a group are always visited.
+    pub fn set_group(&mut self, index: usize, group: usize) -> Result<(), CyklError> {
+        let len = self.len();
+        match self.node.inner {
+            Some(inner) if index < len => {
+                unsafe { (*inner.as_ptr()).groups[index] = Some(group) };
+                Ok(())
+            }
+            _ => Err(CyklError::IndexOutOfBounds { index, len }),
+        }
+    }
+
+    /// Returns the group of the node at the given index.
+    #[inline]
+    pub fn group(&self, index: usize) -> Option<usize> {
+        self.node.group(index)
+    }
+
+    /// Returns the members of all groups, ordered by group label.
+    ///
+    /// Each group is sorted in ascending order of node indices.
+    pub fn groups(&self) -> Vec<Vec<usize>> {
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for index in 0..self.len() {
+            if let Some(group) = self.group(index) {
+                groups.entry(group).or_default().push(index);
+            }
+        }
+        groups.into_values().collect()
+    }
+
+    /// Returns groups of nodes that share the same coordinates.
+    ///
+    /// Each group is sorted in ascending order of node indices and contains at least two nodes.
+    pub fn duplicates(&self) -> Vec<Vec<usize>> {
+        let mut groups: HashMap<Vec<u64>, Vec<usize>> = HashMap::new();
+
+        if let Some(inner) = self.node.inner {
+            unsafe {
+                let dim = inner.as_ref().dim;
+                if dim == 0 {
+                    return Vec::with_capacity(0);
+                }
+
+                for (idx, x) in inner.as_ref().coords.chunks(dim).enumerate() {
+                    groups.entry(to_key(x)).or_default().push(idx);
+                }
+            }
+        }
+
+        let mut result: Vec<Vec<usize>> = groups.into_values().filter(|v| v.len() > 1).collect();
+        result.sort();
+        result
+    }
+
     pub fn compute(&mut self) {
+        match self.policy {
+            DuplicatePolicy::Jitter(amount) => self.jitter(amount),
+            _ => {
+                if let Some(inner) = self.node.inner {
+                    unsafe { (*inner.as_ptr()).jittered = Vec::with_capacity(0) };
+                }
+            }
+        }
+
         if let Some(inner) = self.node.inner {
             unsafe {
                 let n_nodes = inner.as_ref().nodes.len();
                 let dim = inner.as_ref().dim;
 
+                // Explicit weights are given by users through `set_costs`.
+                if inner.as_ref().metric == Metric::Explicit {
+                    if inner.as_ref().costs.len() != n_nodes * n_nodes {
+                        (*inner.as_ptr()).costs = vec![0.; n_nodes * n_nodes];
+                    }
+                    return;
+                }
+
+                // Costs are computed on demand if the matrix exceeds the budget.
+                let matrix = n_nodes * n_nodes * std::mem::size_of::<Scalar>();
+                let used = self.memory_footprint()
+                    - inner.as_ref().costs.capacity() * std::mem::size_of::<Scalar>();
+                if !self.budget.fits(used, matrix) {
+                    (*inner.as_ptr()).costs = Vec::new();
+                    return;
+                }
+
+                let scale = inner.as_ref().scale;
+                let coords = inner.as_ref().cost_coords();
                 let mut result = vec![0.; n_nodes * n_nodes];
-                inner
-                    .as_ref()
-                    .coords
-                    .chunks(dim)
-                    .enumerate()
-                    .for_each(|(idx1, x1)| {
-                        let tmp = idx1 * n_nodes;
-                        inner
-                            .as_ref()
-                            .coords
-                            .chunks(dim)
-                            .enumerate()
-                            .for_each(|(idx2, x2)| {
-                                let pos = tmp + idx2;
-                                if idx1 != idx2 {
-                                    result[pos] = inner.as_ref().metric.cost(x1, x2);
-                                }
-                            })
-                    });
+                coords.chunks(dim).enumerate().for_each(|(idx1, x1)| {
+                    let tmp = idx1 * n_nodes;
+                    coords.chunks(dim).enumerate().for_each(|(idx2, x2)| {
+                        let pos = tmp + idx2;
+                        if idx1 != idx2 {
+                            result[pos] = inner.as_ref().metric.cost(x1, x2) * scale;
+                        }
+                    })
+                });
 
                 (*inner.as_ptr()).costs = result;
             }
         }
     }
 
+    /// Sets the full matrix of costs in row-major order, where the entry at `a * len + b` is the
+    /// cost of travelling from node `a` to node `b`.
+    ///
+    /// The costs may be asymmetric. They are kept by [`DataStore::compute`] if the metric is
+    /// [`Metric::Explicit`], and multiplied by the scale of the store.
+    pub fn set_costs(&mut self, mut costs: Vec<Scalar>) -> Result<(), CyklError> {
+        let len = self.len();
+        if costs.len() != len * len {
+            return Err(CyklError::LenMismatched {
+                expected: len * len,
+                received: costs.len(),
+            });
+        }
+
+        if let Some(inner) = self.node.inner {
+            unsafe {
+                let scale = inner.as_ref().scale;
+                if scale != 1. {
+                    costs.iter_mut().for_each(|x| *x *= scale);
+                }
+                (*inner.as_ptr()).costs = costs;
+            }
+        }
+        self.forbidden = None;
+
+        Ok(())
+    }
+
+    /// Sets the costs of a graph that is given by its undirected edges as `(a, b, cost)`, and
+    /// forbids all other edges.
+    ///
+    /// Absent edges are given a cost above that of any cycle which uses only edges of the graph,
+    /// thus constructions and improving moves avoid them whenever possible, see
+    /// [`DataStore::missing_edge`]. The metric should be [`Metric::Explicit`], so that
+    /// [`DataStore::compute`] keeps the costs.
+    pub fn set_edges(&mut self, edges: &[(usize, usize, Scalar)]) -> Result<(), CyklError> {
+        let len = self.len();
+        if let Some((a, b, _)) = edges.iter().find(|(a, b, _)| *a >= len || *b >= len) {
+            return Err(CyklError::IndexOutOfBounds {
+                index: if *a >= len { *a } else { *b },
+                len,
+            });
+        }
+
+        let max_cost = edges.iter().fold(0., |acc: Scalar, x| acc.max(x.2));
+        let forbidden = len as Scalar * max_cost + 1.;
+
+        let mut costs = vec![forbidden; len * len];
+        for ii in 0..len {
+            costs[ii * len + ii] = 0.;
+        }
+        for (a, b, cost) in edges {
+            costs[a * len + b] = *cost;
+            costs[b * len + a] = *cost;
+        }
+
+        self.set_costs(costs)?;
+        self.forbidden = Some(forbidden);
+        Ok(())
+    }
+
+    /// Returns `true` if the graph of the store has an edge between two distinct nodes, which is
+    /// always the case unless the store was given by its edges with [`DataStore::set_edges`].
+    pub fn has_edge(&self, a: usize, b: usize) -> bool {
+        match self.forbidden {
+            Some(forbidden) => a != b && self.cost(&a, &b) < forbidden * self.scale(),
+            None => a != b,
+        }
+    }
+
+    /// Returns the first edge of a tour that is absent from the graph of the store, or `None` if
+    /// the tour is a Hamiltonian cycle of the graph.
+    pub fn missing_edge(&self, order: &TourOrder) -> Option<(usize, usize)> {
+        self.forbidden?;
+        let nodes = order.order();
+        if nodes.len() < 2 {
+            return None;
+        }
+
+        (0..nodes.len())
+            .map(|ii| (nodes[ii], nodes[(ii + 1) % nodes.len()]))
+            .find(|(a, b)| !self.has_edge(*a, *b))
+    }
+
+    /// Sets the full matrix of costs given in a unit, see [`DataStore::set_costs`].
+    ///
+    /// Returns [`CyklError::UnitMismatch`] if the store already has a different unit other than
+    /// [`Unit::Unitless`], which prevents e.g. travel times from replacing travel distances.
+    /// Otherwise, the unit of the store is set to the given unit.
+    pub fn set_costs_in(&mut self, costs: Vec<Scalar>, unit: Unit) -> Result<(), CyklError> {
+        if self.unit != Unit::Unitless && self.unit != unit {
+            return Err(CyklError::UnitMismatch {
+                expected: self.unit,
+                found: unit,
+            });
+        }
+
+        self.set_costs(costs)?;
+        self.unit = unit;
+        Ok(())
+    }
+
+    /// Adds a named layer of costs in row-major order, like [`DataStore::set_costs`], or replaces
+    /// the layer of the same name.
+    ///
+    /// Layers are not used by the search unless they are combined into the costs of the store
+    /// with [`DataStore::set_costs_from_layers`]. They are not multiplied by the scale of the store.
+    pub fn add_layer(
+        &mut self,
+        name: &str,
+        unit: Unit,
+        costs: Vec<Scalar>,
+    ) -> Result<(), CyklError> {
+        let len = self.len();
+        if costs.len() != len * len {
+            return Err(CyklError::LenMismatched {
+                expected: len * len,
+                received: costs.len(),
+            });
+        }
+
+        let layer = CostLayer {
+            name: name.to_string(),
+            unit,
+            len,
+            costs,
+        };
+        match self.layers.iter_mut().find(|x| x.name == name) {
+            Some(old) => *old = layer,
+            None => self.layers.push(layer),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the layer of the given name.
+    pub fn layer(&self, name: &str) -> Option<&CostLayer> {
+        self.layers.iter().find(|x| x.name == name)
+    }
+
+    /// Returns all layers in the order in which they were added.
+    pub fn layers(&self) -> &[CostLayer] {
+        &self.layers
+    }
+
+    /// Returns the total of each layer along a closed tour, e.g. to report the distance and tolls
+    /// of a tour that was optimised for time.
+    pub fn layer_totals(&self, order: &TourOrder) -> Vec<(&str, Scalar)> {
+        self.layers
+            .iter()
+            .map(|x| (x.name(), x.total(order)))
+            .collect()
+    }
+
+    /// Sets the costs of the store to the weighted sum of layers, given as pairs of layer names and
+    /// weights, so that the search optimises the combination.
+    ///
+    /// The unit of the store becomes the unit of the layers if they all share it, and
+    /// [`Unit::Unitless`] otherwise. As with [`DataStore::set_costs`], the costs are only kept by
+    /// [`DataStore::compute`] if the metric is [`Metric::Explicit`].
+    ///
+    /// Returns [`CyklError::UnknownLayer`] if a name does not refer to any layer.
+    pub fn set_costs_from_layers(&mut self, weights: &[(&str, Scalar)]) -> Result<(), CyklError> {
+        let len = self.len();
+        let mut costs = vec![0.; len * len];
+        let mut units = Vec::with_capacity(weights.len());
+
+        for &(name, weight) in weights {
+            let layer = self
+                .layer(name)
+                .ok_or_else(|| CyklError::UnknownLayer(name.to_string()))?;
+            if layer.len != len {
+                return Err(CyklError::LenMismatched {
+                    expected: len,
+                    received: layer.len,
+                });
+            }
+
+            for (x, y) in costs.iter_mut().zip(&layer.costs) {
+                *x += weight * y;
+            }
+            units.push(layer.unit);
+        }
+
+        self.set_costs(costs)?;
+        self.unit = match units.split_first() {
+            Some((first, rest)) if rest.iter().all(|x| x == first) => *first,
+            _ => Unit::Unitless,
+        };
+
+        Ok(())
+    }
+
+    /// Perturbs the coordinates from which costs are computed for all but the first node in each
+    /// group of duplicates. The coordinates of the nodes themselves are left untouched.
+    fn jitter(&mut self, amount: Scalar) {
+        let groups = self.duplicates();
+
+        if let Some(inner) = self.node.inner {
+            unsafe {
+                let dim = inner.as_ref().dim;
+                let mut rng = StdRng::seed_from_u64(inner.as_ref().nodes.len() as u64);
+                let mut jittered = Vec::with_capacity(0);
+
+                if !groups.is_empty() {
+                    jittered = inner.as_ref().coords.clone();
+                    for idx in groups.iter().flat_map(|group| group.iter().skip(1)) {
+                        for x in &mut jittered[idx * dim..(idx + 1) * dim] {
+                            *x += rng.gen_range(-amount..=amount);
+                        }
+                    }
+                }
+
+                (*inner.as_ptr()).jittered = jittered;
+            }
+        }
+    }
+
+    /// Returns the number of coordinates per node.
+    #[inline]
+    pub fn dim(&self) -> usize {
+        match self.node.inner {
+            Some(inner) => unsafe { inner.as_ref().dim },
+            None => 0,
+        }
+    }
+
+    /// Returns a hash of the instance computed from the metric, the coordinates of all nodes and,
+    /// for explicit weights, the cost matrix.
+    ///
+    /// The hash does not depend on the platform or the run. It is embedded in saved tours,
+    /// candidate sets and search states, so that loading them for another instance fails with
+    /// [`CyklError::InstanceMismatch`].
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash = fnv1a(FNV_OFFSET, &[self.metric() as u8]);
+        hash = fnv1a(hash, &(self.len() as u64).to_le_bytes());
+
+        if let Some(inner) = self.node.inner {
+            unsafe {
+                for x in &inner.as_ref().coords {
+                    hash = fnv1a(hash, &x.to_le_bytes());
+                }
+
+                if inner.as_ref().metric == Metric::Explicit {
+                    for x in &inner.as_ref().costs {
+                        hash = fnv1a(hash, &x.to_le_bytes());
+                    }
+                } else if inner.as_ref().scale != 1. {
+                    hash = fnv1a(hash, &inner.as_ref().scale.to_le_bytes());
+                }
+            }
+        }
+
+        hash
+    }
+
+    /// Returns the coordinates of the node at the given index as `(x, y, z)`, where missing
+    /// dimensions are zero.
+    ///
+    /// Coordinates are returned as they are stored, i.e. after the projection, see
+    /// [`DataStore::origin`]. Returns `None` if the index is out of bounds or the metric has no
+    /// coordinates.
+    pub fn coords(&self, index: usize) -> Option<(Scalar, Scalar, Scalar)> {
+        let pos = self.coord(index).filter(|pos| !pos.is_empty())?;
+        let at = |dim: usize| pos.get(dim).copied().unwrap_or(0.);
+        Some((at(0), at(1), at(2)))
+    }
+
+    /// Iterates over the coordinates of all nodes in the order of their indices, see
+    /// [`DataStore::coords`].
+    pub fn iter_coords(&self) -> impl Iterator<Item = (Scalar, Scalar, Scalar)> + '_ {
+        (0..self.len()).filter_map(move |index| self.coords(index))
+    }
+
+    /// Returns the box that encloses the coordinates of all nodes, or `None` if the nodes have no
+    /// coordinates.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        spatial::bounding_box(self)
+    }
+
+    /// Returns the mean of the coordinates of all nodes, or `None` if the nodes have no
+    /// coordinates.
+    pub fn centroid(&self) -> Option<(Scalar, Scalar, Scalar)> {
+        spatial::centroid(self)
+    }
+
+    /// Returns the index of the node whose coordinates are nearest to a point given as
+    /// `(x, y, z)`, or `None` if the nodes have no coordinates.
+    ///
+    /// The point is compared to the stored, i.e. projected, coordinates by the Euclidean distance.
+    /// A [`KdTree`] is built on the first query in O(N log N) time, after which each query takes
+    /// O(log N) time on average.
+    pub fn nearest_to(&self, point: (Scalar, Scalar, Scalar)) -> Option<usize> {
+        self.tree.get_or_init(|| KdTree::new(self)).nearest(point)
+    }
+
+    /// Collects the coordinates of all nodes in the order of their indices, see
+    /// [`DataStore::coords`].
+    pub fn to_vec(&self) -> Vec<(Scalar, Scalar, Scalar)> {
+        self.iter_coords().collect()
+    }
+
+    /// Returns the coordinates of the node at the given index.
+    #[inline]
+    pub(crate) fn coord(&self, index: usize) -> Option<&[Scalar]> {
+        self.node.inner.and_then(|inner| unsafe {
+            let dim = inner.as_ref().dim;
+            (*inner.as_ptr()).coords.get(index * dim..(index + 1) * dim)
+        })
+    }
+
     #[inline]
     pub(crate) fn store(&self) -> NodeStore {
-        self.node
+        self.node.clone()
     }
 
     #[inline]
@@ -168,22 +1097,104 @@ impl<M> DataStore<M> {
     }
 }
 
-#[derive(Clone, Copy, Debug, Hash)]
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Feeds bytes into a 64-bit FNV-1a hash.
+#[inline]
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |hash, b| (hash ^ *b as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// Converts coordinates into a hashable key, treating `-0.0` and `0.0` as equal.
+#[inline]
+fn to_key(pos: &[f64]) -> Vec<u64> {
+    pos.iter().map(|x| (x + 0.).to_bits()).collect()
+}
+
+/// Handle to the nodes of a [`DataStore`], which is shared with the tours created from the store.
+///
+/// The nodes are freed when the last handle is dropped.
+#[derive(Debug, Hash)]
 pub(crate) struct NodeStore {
     inner: Option<NonNull<InnerStore>>,
 }
 
+impl Clone for NodeStore {
+    fn clone(&self) -> Self {
+        if let Some(inner) = self.inner {
+            unsafe { (*inner.as_ptr()).refs += 1 };
+        }
+
+        Self { inner: self.inner }
+    }
+}
+
+impl Drop for NodeStore {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            unsafe {
+                (*inner.as_ptr()).refs -= 1;
+                if (*inner.as_ptr()).refs == 0 {
+                    free(inner);
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 struct InnerStore {
     dim: usize,
     metric: Metric,
     nodes: Vec<NodeIndex>,
     coords: Vec<Scalar>,
+    prizes: Vec<Scalar>,
+    // Time spent at each node, in the unit of the costs.
+    service: Vec<Scalar>,
+    groups: Vec<Option<usize>>,
     // Compute and store all cost in a big vec.
     // This simplifies implementation and interface but comes with huge cost for memory,
     // especially when we also need to save extra things for alpha-nearness scheme.
-    // Need optimisation for large problems, but later.
+    // Left empty if the matrix exceeds the memory budget, in which case costs are computed on
+    // demand.
     costs: Vec<Scalar>,
+    // Coordinates from which costs are computed if duplicates are jittered, left empty otherwise
+    // so that costs are computed from `coords`.
+    jittered: Vec<Scalar>,
+    // Factor by which all costs are multiplied.
+    scale: Scalar,
+    // Number of handles sharing the store.
+    refs: usize,
+}
+
+impl InnerStore {
+    /// Returns the coordinates from which costs are computed.
+    #[inline]
+    fn cost_coords(&self) -> &[Scalar] {
+        if self.jittered.is_empty() {
+            &self.coords
+        } else {
+            &self.jittered
+        }
+    }
+
+    /// Returns the cost between two distinct nodes from the matrix, or computes it from their
+    /// coordinates if the matrix is not cached.
+    #[inline]
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        if self.costs.is_empty() {
+            let coords = self.cost_coords();
+            self.metric.cost(
+                &coords[a * self.dim..(a + 1) * self.dim],
+                &coords[b * self.dim..(b + 1) * self.dim],
+            ) * self.scale
+        } else {
+            self.costs[a * self.nodes.len() + b]
+        }
+    }
 }
 
 impl NodeStore {
@@ -204,6 +1215,24 @@ impl NodeStore {
         }
     }
 
+    /// Returns the prize of the node at the given index, or zero if there is no such node.
+    #[inline]
+    pub fn prize(&self, index: usize) -> Scalar {
+        match self.inner {
+            Some(inner) => unsafe { inner.as_ref().prizes.get(index).copied().unwrap_or(0.) },
+            None => 0.,
+        }
+    }
+
+    /// Returns the group of the node at the given index.
+    #[inline]
+    pub fn group(&self, index: usize) -> Option<usize> {
+        match self.inner {
+            Some(inner) => unsafe { inner.as_ref().groups.get(index).copied().flatten() },
+            None => None,
+        }
+    }
+
     #[inline]
     pub fn cost<I>(&self, a: &I, b: &I) -> f64
     where
@@ -213,13 +1242,40 @@ impl NodeStore {
             0.
         } else {
             match self.inner {
-                Some(inner) => unsafe {
-                    inner.as_ref().costs[a.get() * inner.as_ref().nodes.len() + b.get()]
-                },
+                Some(inner) => unsafe { inner.as_ref().cost(a.get(), b.get()) },
                 None => 0.,
             }
         }
     }
+
+    /// Writes the costs between the given pairs of node indices into `out`.
+    ///
+    /// Unlike repeated calls to [`NodeStore::cost`], the cost matrix is looked up only once. Pairs
+    /// of identical nodes have zero cost.
+    ///
+    /// # Panics
+    /// Panics if `pairs` and `out` differ in length or an index is out of bounds.
+    pub fn costs(&self, pairs: &[(usize, usize)], out: &mut [Scalar]) {
+        assert_eq!(
+            pairs.len(),
+            out.len(),
+            "Mismatched lengths of pairs and output"
+        );
+
+        let inner = match self.inner {
+            Some(inner) => unsafe { inner.as_ref() },
+            None => {
+                out.iter_mut().for_each(|x| *x = 0.);
+                return;
+            }
+        };
+        let len = inner.nodes.len();
+
+        for (x, &(a, b)) in out.iter_mut().zip(pairs) {
+            assert!(a < len && b < len, "Node index out of bounds");
+            *x = if a == b { 0. } else { inner.cost(a, b) };
+        }
+    }
 }
 
 impl<'s> IntoIterator for &'s NodeStore {
@@ -261,6 +1317,12 @@ pub enum Metric {
     Xray1,
     /// Special distance function for crystallography problems of version 2.
     Xray2,
+    /// Great-circle distance in meters between points given as latitude and longitude in decimal
+    /// degrees.
+    Haversine,
+    /// Geodesic distance in meters on the WGS-84 ellipsoid between points given as latitude and
+    /// longitude in decimal degrees.
+    Vincenty,
     /// Distance function defined by users.
     Custom,
     /// No distance function is given.
@@ -276,7 +1338,9 @@ impl Metric {
             | Metric::Man2d
             | Metric::Ceil2d
             | Metric::Geo
-            | Metric::Att => 2,
+            | Metric::Att
+            | Metric::Haversine
+            | Metric::Vincenty => 2,
             Metric::Euc3d | Metric::Max3d | Metric::Man3d | Metric::Xray1 | Metric::Xray2 => 3,
             Metric::Custom => todo!(),
             Metric::Undefined => 0,
@@ -296,9 +1360,17 @@ impl Metric {
             Self::Att => att(a, b),
             Self::Xray1 => xray1(a, b),
             Self::Xray2 => xray2(a, b),
+            Self::Haversine => haversine(a, b),
+            Self::Vincenty => vincenty(a, b),
             _ => 0.,
         }
     }
+
+    /// Returns `true` if the metric expects nodes given as latitude and longitude in decimal
+    /// degrees.
+    pub fn is_geodesic(&self) -> bool {
+        matches!(self, Self::Haversine | Self::Vincenty)
+    }
 }
 
 /// Calculates the 2D-Euclidean distance between two points.
@@ -375,6 +1447,133 @@ pub fn geo(a: &[f64], b: &[f64]) -> f64 {
     EARTH_RADIUS * q4 + 1.
 }
 
+/// Calculates the great-circle distance in meters between two points given as latitude and
+/// longitude in decimal degrees.
+///
+/// Unlike [`geo`], the coordinates are not interpreted in the TSPLIB format and the result is not
+/// rounded.
+#[inline]
+pub fn haversine(a: &[f64], b: &[f64]) -> f64 {
+    let (lat_a, lat_b) = (a[0].to_radians(), b[0].to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (b[1] - a[1]).to_radians();
+
+    let h = (0.5 * d_lat).sin().powi(2) + lat_a.cos() * lat_b.cos() * (0.5 * d_lon).sin().powi(2);
+    2. * MEAN_EARTH_RADIUS * h.sqrt().min(1.).asin()
+}
+
+/// Calculates the geodesic distance in meters between two points on the WGS-84 ellipsoid given as
+/// latitude and longitude in decimal degrees, using the inverse formula of Vincenty.
+///
+/// The formula is accurate to within millimeters, but might fail to converge for nearly antipodal
+/// points. In that case, the result of [`haversine`] is returned instead.
+pub fn vincenty(a: &[f64], b: &[f64]) -> f64 {
+    let b_axis = WGS84_A * (1. - WGS84_F);
+    let l = (b[1] - a[1]).to_radians();
+    let u1 = ((1. - WGS84_F) * a[0].to_radians().tan()).atan();
+    let u2 = ((1. - WGS84_F) * b[0].to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..200 {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+        .sqrt();
+
+        if sin_sigma == 0. {
+            // Coincident points.
+            return 0.;
+        }
+
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos_sq_alpha = 1. - sin_alpha * sin_alpha;
+        let cos_2sigma_m = if cos_sq_alpha == 0. {
+            // Both points lie on the equator.
+            0.
+        } else {
+            cos_sigma - 2. * sin_u1 * sin_u2 / cos_sq_alpha
+        };
+
+        let c = WGS84_F / 16. * cos_sq_alpha * (4. + WGS84_F * (4. - 3. * cos_sq_alpha));
+        let lambda_prev = lambda;
+        lambda = l
+            + (1. - c)
+                * WGS84_F
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2sigma_m + c * cos_sigma * (-1. + 2. * cos_2sigma_m.powi(2))));
+
+        if (lambda - lambda_prev).abs() < 1e-12 {
+            let u_sq = cos_sq_alpha * (WGS84_A.powi(2) - b_axis.powi(2)) / b_axis.powi(2);
+            let k_a = 1. + u_sq / 16384. * (4096. + u_sq * (-768. + u_sq * (320. - 175. * u_sq)));
+            let k_b = u_sq / 1024. * (256. + u_sq * (-128. + u_sq * (74. - 47. * u_sq)));
+            let delta_sigma = k_b
+                * sin_sigma
+                * (cos_2sigma_m
+                    + k_b / 4.
+                        * (cos_sigma * (-1. + 2. * cos_2sigma_m.powi(2))
+                            - k_b / 6.
+                                * cos_2sigma_m
+                                * (-3. + 4. * sin_sigma.powi(2))
+                                * (-3. + 4. * cos_2sigma_m.powi(2))));
+
+            return b_axis * k_a * (sigma - delta_sigma);
+        }
+    }
+
+    haversine(a, b)
+}
+
+/// Projects latitude and longitude in decimal degrees onto the spherical Web Mercator plane.
+fn web_mercator(pos: &[f64]) -> Vec<f64> {
+    let (lat, lon) = (pos[0].to_radians(), pos[1].to_radians());
+    vec![WGS84_A * lon, WGS84_A * (0.25 * PI + 0.5 * lat).tan().ln()]
+}
+
+/// Projects latitude and longitude in decimal degrees onto the plane of the given UTM zone.
+///
+/// Uses the series expansion given by J. Snyder (1987), "Map Projections: A Working Manual".
+fn utm(pos: &[f64], zone: u8, north: bool) -> Vec<f64> {
+    const K0: f64 = 0.9996;
+
+    let e2 = WGS84_F * (2. - WGS84_F);
+    let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+    let ep2 = e2 / (1. - e2);
+
+    let lat = pos[0].to_radians();
+    let lon0 = (f64::from(zone) * 6. - 183.).to_radians();
+    let (sin_lat, cos_lat) = lat.sin_cos();
+
+    let n = WGS84_A / (1. - e2 * sin_lat * sin_lat).sqrt();
+    let t = lat.tan().powi(2);
+    let c = ep2 * cos_lat * cos_lat;
+    let a = cos_lat * (pos[1].to_radians() - lon0);
+    let m = WGS84_A
+        * ((1. - e2 / 4. - 3. * e4 / 64. - 5. * e6 / 256.) * lat
+            - (3. * e2 / 8. + 3. * e4 / 32. + 45. * e6 / 1024.) * (2. * lat).sin()
+            + (15. * e4 / 256. + 45. * e6 / 1024.) * (4. * lat).sin()
+            - (35. * e6 / 3072.) * (6. * lat).sin());
+
+    let x = K0
+        * n
+        * (a + (1. - t + c) * a.powi(3) / 6.
+            + (5. - 18. * t + t * t + 72. * c - 58. * ep2) * a.powi(5) / 120.)
+        + 500_000.;
+    let y = K0
+        * (m + n
+            * lat.tan()
+            * (a * a / 2.
+                + (5. - t + 9. * c + 4. * c * c) * a.powi(4) / 24.
+                + (61. - 58. * t + t * t + 600. * c - 330. * ep2) * a.powi(6) / 720.));
+
+    vec![x, if north { y } else { y + 10_000_000. }]
+}
+
 #[inline]
 fn to_geo_coord(x: f64) -> f64 {
     let deg = x.trunc();
@@ -0,0 +1,133 @@
+use std::fmt::Display;
+
+use crate::{data::Unit, tour::UpdateTourError};
+
+/// Crate-wide error type returned by fallible operations.
+#[derive(Debug)]
+pub enum CyklError {
+    /// An index does not refer to any node in a container.
+    IndexOutOfBounds { index: usize, len: usize },
+
+    /// A node appears more than once in a tour order.
+    DuplicateNode(usize),
+
+    /// The node at the second index is not the direct successor of the node at the first index.
+    NotSuccessor { from: usize, to: usize },
+
+    /// An input file could not be parsed.
+    Parse(String),
+
+    /// Reading from or writing to a file failed.
+    Io(std::io::Error),
+
+    /// The coordinates of nodes are required but not available, e.g. for explicit weights.
+    MissingCoordinates,
+
+    /// The length of an input does not match the number of elements it describes.
+    LenMismatched { expected: usize, received: usize },
+
+    /// An operation on a tour failed.
+    Tour(UpdateTourError),
+
+    /// A stored artifact was created for an instance other than the current one, as told by their
+    /// fingerprints.
+    InstanceMismatch { expected: u64, found: u64 },
+
+    /// The operation was stopped by a [`CancellationToken`](crate::CancellationToken) before it
+    /// produced a result.
+    Cancelled,
+
+    /// The node at the index cannot be served within the given limits, e.g. because its demand
+    /// exceeds the capacity of a vehicle.
+    Infeasible(usize),
+
+    /// Costs in one unit were combined with costs in another unit.
+    UnitMismatch { expected: Unit, found: Unit },
+
+    /// A name does not refer to any layer of costs.
+    UnknownLayer(String),
+
+    /// A name does not refer to any phase or acceptance strategy of a registry.
+    UnknownPhase(String),
+
+    /// No tour was found that uses only edges of the graph, as the best tour found travels from
+    /// the first node to the second one without an edge between them.
+    MissingEdge { from: usize, to: usize },
+
+    /// The cost from the first node to the second one differs from the cost back, which a solver
+    /// for symmetric instances cannot represent.
+    Asymmetric { from: usize, to: usize },
+
+    /// A solver running on another thread panicked with the given message.
+    Panicked(String),
+}
+
+impl Display for CyklError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IndexOutOfBounds { index, len } => {
+                write!(f, "Index {} is out of bounds for length {}", index, len)
+            }
+            Self::DuplicateNode(index) => write!(f, "Node {} appears more than once", index),
+            Self::NotSuccessor { from, to } => {
+                write!(f, "Node {} is not the successor of node {}", to, from)
+            }
+            Self::Parse(msg) => write!(f, "Failed to parse input: {}", msg),
+            Self::Io(err) => write!(f, "I/O error: {}", err),
+            Self::MissingCoordinates => write!(f, "Nodes have no coordinates"),
+            Self::LenMismatched { expected, received } => {
+                write!(f, "Expected {} elements, received {}", expected, received)
+            }
+            Self::Tour(err) => write!(f, "Tour operation failed: {:?}", err),
+            Self::InstanceMismatch { expected, found } => write!(
+                f,
+                "Instance fingerprint {:016x} does not match {:016x}",
+                found, expected
+            ),
+            Self::Cancelled => write!(f, "Operation was cancelled"),
+            Self::Infeasible(index) => {
+                write!(f, "Node {} cannot be served within the limits", index)
+            }
+            Self::UnitMismatch { expected, found } => {
+                // Unitless costs display as an empty suffix, thus they are named explicitly.
+                let costs = |unit: &Unit| match unit {
+                    Unit::Unitless => "unitless costs".to_string(),
+                    unit => format!("costs in {}", unit),
+                };
+                write!(
+                    f,
+                    "Cannot combine {} with {}",
+                    costs(found),
+                    costs(expected)
+                )
+            }
+            Self::UnknownLayer(name) => write!(f, "No layer of costs named {}", name),
+            Self::UnknownPhase(name) => write!(f, "No phase named {}", name),
+            Self::MissingEdge { from, to } => {
+                write!(
+                    f,
+                    "No Hamiltonian cycle found, missing edge ({}, {})",
+                    from, to
+                )
+            }
+            Self::Asymmetric { from, to } => {
+                write!(f, "Costs between nodes {} and {} are asymmetric", from, to)
+            }
+            Self::Panicked(msg) => write!(f, "Solver panicked: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CyklError {}
+
+impl From<std::io::Error> for CyklError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<UpdateTourError> for CyklError {
+    fn from(err: UpdateTourError) -> Self {
+        Self::Tour(err)
+    }
+}
@@ -0,0 +1,176 @@
+//! Running solvers on the cross product of instances, configurations and seeds, e.g. for
+//! benchmarks.
+//!
+//! An [`Experiment`] names its instances and configurations, and builds the model of an instance
+//! from its name on the worker thread that solves it, like [`batch::solve_all`]. The reports of an
+//! experiment can be written with [`write_csv`] as a tidy table with one row per run.
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{batch, tour::TourOrder, CyklError, Model, RunConfig, Scalar};
+
+/// Outcome of one run of an experiment.
+#[derive(Debug)]
+pub struct SolveReport {
+    /// Name of the instance.
+    pub instance: String,
+    /// Name of the configuration.
+    pub config: String,
+    /// Seed with which the solver was run.
+    pub seed: u64,
+    /// Tour found in the run, `None` if the instance has no nodes, or the error raised while
+    /// building or solving the instance.
+    pub result: Result<Option<TourOrder>, CyklError>,
+    /// Time spent on building and solving the instance.
+    pub elapsed: Duration,
+}
+
+impl SolveReport {
+    /// Returns the cost of the tour found, if any.
+    pub fn cost(&self) -> Option<Scalar> {
+        match &self.result {
+            Ok(Some(order)) => Some(order.cost()),
+            _ => None,
+        }
+    }
+}
+
+/// Cross product of instances, configurations and seeds, each combination of which is solved once.
+#[derive(Clone, Debug)]
+pub struct Experiment {
+    instances: Vec<String>,
+    configs: Vec<(String, RunConfig)>,
+    seeds: Vec<u64>,
+    parallelism: usize,
+}
+
+impl Default for Experiment {
+    fn default() -> Self {
+        Self {
+            instances: Vec::new(),
+            configs: Vec::new(),
+            seeds: vec![0],
+            parallelism: 1,
+        }
+    }
+}
+
+impl Experiment {
+    /// Creates an experiment without instances and configurations, which runs each combination
+    /// with the seed `0` on the calling thread.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an instance, which is passed by its name to the function that builds its model.
+    pub fn with_instance(mut self, name: &str) -> Self {
+        self.instances.push(name.to_string());
+        self
+    }
+
+    /// Adds a named configuration.
+    pub fn with_config(mut self, name: &str, config: RunConfig) -> Self {
+        self.configs.push((name.to_string(), config));
+        self
+    }
+
+    /// Sets the seeds with which each instance is solved for each configuration, see
+    /// [`RunConfig::with_seed`].
+    pub fn with_seeds(mut self, seeds: &[u64]) -> Self {
+        self.seeds = seeds.to_vec();
+        self
+    }
+
+    /// Sets the number of threads on which runs are solved. Defaults to one.
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Returns the number of runs of the experiment.
+    pub fn len(&self) -> usize {
+        self.instances.len() * self.configs.len() * self.seeds.len()
+    }
+
+    /// Returns `true` if the experiment has no runs.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Solves every combination of instance, configuration and seed, and returns one report per
+    /// run, ordered by instance, then configuration, then seed.
+    ///
+    /// The model of an instance is created by `build(name)` for every run. Errors of single runs
+    /// are reported without stopping the others.
+    pub fn run<M, F>(&self, build: F) -> Vec<SolveReport>
+    where
+        F: Fn(&str) -> Result<Model<M>, CyklError> + Sync,
+    {
+        let n_seeds = self.seeds.len();
+        let n_configs = self.configs.len();
+
+        batch::map_indexed(self.len(), self.parallelism, |index| {
+            let instance = &self.instances[index / (n_configs * n_seeds)];
+            let (name, config) = &self.configs[(index / n_seeds) % n_configs];
+            let seed = self.seeds[index % n_seeds];
+            let config = config.clone().with_seed(seed);
+
+            let start = Instant::now();
+            let result = build(instance).and_then(|mut model| model.solve(&config));
+            SolveReport {
+                instance: instance.clone(),
+                config: name.clone(),
+                seed,
+                result,
+                elapsed: start.elapsed(),
+            }
+        })
+    }
+}
+
+/// Writes reports as CSV with a header and the columns `instance`, `config`, `seed`, `status`,
+/// `nodes`, `cost` and `elapsed_ms`.
+///
+/// The status is `ok`, `empty` for instances without nodes, or the message of the error of the
+/// run, in which case `nodes` and `cost` are left empty.
+pub fn write_csv<W>(reports: &[SolveReport], writer: &mut W) -> Result<(), CyklError>
+where
+    W: Write,
+{
+    writeln!(writer, "instance,config,seed,status,nodes,cost,elapsed_ms")?;
+    for report in reports {
+        let (status, nodes, cost) = match &report.result {
+            Ok(Some(order)) => (
+                "ok".to_string(),
+                order.len().to_string(),
+                order.cost().to_string(),
+            ),
+            Ok(None) => ("empty".to_string(), "0".to_string(), String::new()),
+            Err(err) => (err.to_string(), String::new(), String::new()),
+        };
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{:.3}",
+            escape(&report.instance),
+            escape(&report.config),
+            report.seed,
+            escape(&status),
+            nodes,
+            cost,
+            report.elapsed.as_secs_f64() * 1000.
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Quotes a field that contains separators, quotes or line breaks.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,226 @@
+//! Input and output files of the LKH solver by K. Helsgaun.
+//!
+//! [`write_par_and_problem`] writes an instance and a parameter file, with which LKH is run as
+//! `LKH cykl.par`. The tour written by LKH is read with [`read_tour`], e.g. to compare it with a
+//! tour found by this crate.
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    alg::{SolverKind, Termination},
+    data::DataStore,
+    load_tour,
+    tour::TourOrder,
+    CyklError, RunConfig, Scalar,
+};
+
+use super::write_explicit;
+
+/// Parameters of an LKH run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LkhConfig {
+    runs: usize,
+    max_candidates: usize,
+    max_trials: Option<usize>,
+    seed: u64,
+    time_limit: Option<Duration>,
+    scale: Scalar,
+}
+
+impl LkhConfig {
+    /// Creates parameters for a single run with five candidates per node, which are the defaults
+    /// of LKH.
+    pub fn new() -> Self {
+        Self {
+            runs: 1,
+            max_candidates: 5,
+            max_trials: None,
+            seed: 1,
+            time_limit: None,
+            scale: 1.,
+        }
+    }
+
+    /// Sets the number of runs.
+    pub fn with_runs(mut self, runs: usize) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    /// Sets the number of candidates per node.
+    pub fn with_max_candidates(mut self, max_candidates: usize) -> Self {
+        self.max_candidates = max_candidates;
+        self
+    }
+
+    /// Sets the number of trials per run. Defaults to the number of nodes, as in LKH.
+    pub fn with_max_trials(mut self, max_trials: usize) -> Self {
+        self.max_trials = Some(max_trials);
+        self
+    }
+
+    /// Sets the seed of the random number generator.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the time limit of the whole search.
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Sets the factor by which costs are multiplied before they are rounded to the integers that
+    /// LKH works with. Defaults to `1`.
+    pub fn with_scale(mut self, scale: Scalar) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Returns the factor by which costs are multiplied.
+    pub fn scale(&self) -> Scalar {
+        self.scale
+    }
+}
+
+impl Default for LkhConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Takes over the number of candidates, and the seed and time limit of the solver if it has any.
+impl From<&RunConfig> for LkhConfig {
+    fn from(config: &RunConfig) -> Self {
+        let result = Self::new().with_max_candidates(config.cands());
+        match *config.solver() {
+            SolverKind::LateAcceptance {
+                termination, seed, ..
+            }
+            | SolverKind::Lns {
+                termination, seed, ..
+            } => match termination {
+                Termination::TimeLimit(limit) => result.with_seed(seed).with_time_limit(limit),
+                _ => result.with_seed(seed),
+            },
+            SolverKind::Adaptive { budget, seed, .. } => {
+                result.with_seed(seed).with_time_limit(budget)
+            }
+            _ => result,
+        }
+    }
+}
+
+/// Paths of the files written by [`write_par_and_problem`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LkhFiles {
+    /// Parameter file, which is passed to LKH.
+    pub par: PathBuf,
+    /// Instance in the TSPLIB format.
+    pub problem: PathBuf,
+    /// Tour file that LKH writes its best tour to.
+    pub tour: PathBuf,
+}
+
+/// Writes the instance of a store as `cykl.tsp` and a parameter file `cykl.par` that refers to it
+/// into a directory, which is created if it does not exist.
+///
+/// The instance is an explicit cost matrix, see [`LkhConfig::with_scale`]. LKH writes its best tour to
+/// `cykl.tour` in the same directory.
+///
+/// The parameter file refers to the other files by their canonical paths, since LKH resolves
+/// relative paths against its working directory rather than the directory of the parameter file.
+pub fn write_par_and_problem<M, P>(
+    store: &DataStore<M>,
+    config: &LkhConfig,
+    dir: P,
+) -> Result<LkhFiles, CyklError>
+where
+    P: AsRef<Path>,
+{
+    std::fs::create_dir_all(&dir)?;
+    let dir = dir.as_ref().canonicalize()?;
+    let files = LkhFiles {
+        par: dir.join("cykl.par"),
+        problem: dir.join("cykl.tsp"),
+        tour: dir.join("cykl.tour"),
+    };
+
+    let mut problem = io::BufWriter::new(std::fs::File::create(&files.problem)?);
+    write_explicit(store, "cykl", config.scale, &mut problem)?;
+    problem.flush()?;
+
+    let mut par = String::new();
+    par.push_str(&format!("PROBLEM_FILE = {}\n", files.problem.display()));
+    par.push_str(&format!("TOUR_FILE = {}\n", files.tour.display()));
+    par.push_str(&format!("RUNS = {}\n", config.runs));
+    par.push_str(&format!("MAX_CANDIDATES = {}\n", config.max_candidates));
+    if let Some(max_trials) = config.max_trials {
+        par.push_str(&format!("MAX_TRIALS = {}\n", max_trials));
+    }
+    par.push_str(&format!("SEED = {}\n", config.seed));
+    if let Some(limit) = config.time_limit {
+        par.push_str(&format!("TIME_LIMIT = {}\n", limit.as_secs_f64()));
+    }
+    std::fs::write(&files.par, par)?;
+
+    Ok(files)
+}
+
+/// Tour read from a file written by LKH.
+#[derive(Clone, Debug)]
+pub struct LkhTour {
+    order: TourOrder,
+    length: Option<i64>,
+}
+
+impl LkhTour {
+    /// Returns the tour, whose cost is measured with the costs of the store.
+    pub fn order(&self) -> &TourOrder {
+        &self.order
+    }
+
+    /// Returns the length reported by LKH, which is measured in the scaled and rounded costs of
+    /// the instance, or `None` if the file does not report a length.
+    pub fn length(&self) -> Option<i64> {
+        self.length
+    }
+
+    /// Consumes the tour and returns its order.
+    pub fn into_order(self) -> TourOrder {
+        self.order
+    }
+}
+
+/// Reads a tour file written by LKH for the instance of a store.
+///
+/// The file is read like a tour saved by [`save_tour`](crate::save_tour), and the length is taken
+/// from the `COMMENT : Length = ...` line that LKH writes.
+pub fn read_tour<M, P>(store: &DataStore<M>, path: P) -> Result<LkhTour, CyklError>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let order = load_tour(store, path)?;
+
+    let content = std::fs::read_to_string(path)?;
+    let length = content
+        .lines()
+        .take_while(|line| line.trim() != "TOUR_SECTION")
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| key.trim() == "COMMENT")
+        .find_map(|(_, value)| value.trim().strip_prefix("Length"))
+        .map(|value| {
+            value
+                .trim_start_matches(|c: char| c == '=' || c.is_whitespace())
+                .parse::<i64>()
+                .map_err(|err| CyklError::Parse(format!("{:?}", err)))
+        })
+        .transpose()?;
+
+    Ok(LkhTour { order, length })
+}
@@ -0,0 +1,66 @@
+//! Exchange of instances and tours with other TSP solvers.
+use std::io::Write;
+
+use crate::{data::DataStore, CyklError, Scalar};
+
+pub mod lkh;
+pub mod permutation;
+
+/// Returns a pair of nodes whose costs, multiplied by `scale` and rounded to integers, differ
+/// between both directions, or `None` if the rounded costs are symmetric.
+pub(crate) fn asymmetric_pair<M>(store: &DataStore<M>, scale: Scalar) -> Option<(usize, usize)> {
+    let len = store.len();
+    let rounded = |a: usize, b: usize| (store.cost(&a, &b) * scale).round() as i64;
+    (0..len)
+        .flat_map(|a| (a + 1..len).map(move |b| (a, b)))
+        .find(|&(a, b)| rounded(a, b) != rounded(b, a))
+}
+
+/// Writes the instance of a store in the TSPLIB format as an explicit cost matrix, with costs
+/// multiplied by `scale` and rounded to integers.
+///
+/// Symmetric costs are written as the upper triangle of the matrix of a `TSP`. Costs that differ
+/// between both directions are written as the full matrix of an `ATSP`, as the upper triangle would
+/// silently drop the costs against the order of the nodes.
+///
+/// An explicit matrix is understood by all TSPLIB readers regardless of the metric of the store.
+pub(crate) fn write_explicit<M, W>(
+    store: &DataStore<M>,
+    name: &str,
+    scale: Scalar,
+    writer: &mut W,
+) -> Result<(), CyklError>
+where
+    W: Write,
+{
+    let len = store.len();
+    let rounded = |a: usize, b: usize| (store.cost(&a, &b) * scale).round() as i64;
+    let symmetric = asymmetric_pair(store, scale).is_none();
+    writeln!(writer, "NAME : {}", name)?;
+    writeln!(writer, "TYPE : {}", if symmetric { "TSP" } else { "ATSP" })?;
+    writeln!(writer, "DIMENSION : {}", len)?;
+    writeln!(writer, "EDGE_WEIGHT_TYPE : EXPLICIT")?;
+    writeln!(
+        writer,
+        "EDGE_WEIGHT_FORMAT : {}",
+        if symmetric {
+            "UPPER_ROW"
+        } else {
+            "FULL_MATRIX"
+        }
+    )?;
+    writeln!(writer, "EDGE_WEIGHT_SECTION")?;
+
+    for a in 0..len {
+        let cols = if symmetric { a + 1..len } else { 0..len };
+        let row: Vec<String> = cols
+            .map(|b| if a == b { 0 } else { rounded(a, b) }.to_string())
+            .collect();
+        if !row.is_empty() {
+            writeln!(writer, "{}", row.join(" "))?;
+        }
+    }
+
+    writeln!(writer, "EOF")?;
+    Ok(())
+}
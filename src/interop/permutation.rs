@@ -0,0 +1,86 @@
+//! Tours given as permutations in the formats of other tools, e.g. to start from a tour found
+//! elsewhere.
+//!
+//! All parsed tours are validated against the store: they must visit every node exactly once.
+use crate::{
+    data::DataStore,
+    model::parse_tour,
+    tour::{validate_order, TourOrder},
+    CyklError,
+};
+
+use crate::oracle::cycle_cost;
+
+/// Encoding of a permutation of the nodes of a store.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PermutationFormat {
+    /// Node ids starting from one, separated by whitespace or commas, e.g. `1 3 2`.
+    OneBased,
+    /// JSON array of node indices starting from zero, e.g. `[0, 2, 1]`.
+    Json,
+    /// Tour file in the TSPLIB format as written by LKH, see [`load_tour`](crate::load_tour).
+    Lkh,
+    /// Route printed by the examples of Google OR-tools, e.g. `Route for vehicle 0:` followed by
+    /// `0 -> 2 -> 1 -> 0`, with node indices starting from zero. The return to the first node is
+    /// dropped, and lines without arrows are ignored.
+    OrTools,
+}
+
+/// Parses a permutation of the nodes of a store into a tour order, whose cost is measured with the
+/// costs of the store.
+///
+/// Returns [`CyklError::Parse`] if a node cannot be read, and the errors of
+/// [`Tour::try_apply`](crate::tour::Tour::try_apply) if the permutation does not visit every node
+/// of the store exactly once.
+pub fn parse_permutation<M>(
+    store: &DataStore<M>,
+    text: &str,
+    format: PermutationFormat,
+) -> Result<TourOrder, CyklError> {
+    let order = match format {
+        PermutationFormat::OneBased => split_indices(text, |c| c.is_whitespace() || c == ',', 1)?,
+        PermutationFormat::Json => {
+            let inner = text
+                .trim()
+                .strip_prefix('[')
+                .and_then(|x| x.strip_suffix(']'))
+                .ok_or_else(|| CyklError::Parse("Expected a JSON array".to_string()))?;
+            split_indices(inner, |c| c.is_whitespace() || c == ',', 0)?
+        }
+        PermutationFormat::Lkh => parse_tour(store, text)?.order().clone(),
+        PermutationFormat::OrTools => {
+            let mut order = Vec::new();
+            for line in text.lines().filter(|line| line.contains("->")) {
+                let mut route =
+                    split_indices(line, |c| c.is_whitespace() || c == '-' || c == '>', 0)?;
+                if route.len() > 1 && route.first() == route.last() {
+                    route.pop();
+                }
+                order.append(&mut route);
+            }
+            order
+        }
+    };
+
+    let mut order = TourOrder::with_ord(order);
+    validate_order(&order, store.len())?;
+    order.set_cost(cycle_cost(store, order.order()));
+    Ok(order)
+}
+
+/// Splits a text into node indices, which are given starting from `base`.
+fn split_indices<F>(text: &str, separator: F, base: usize) -> Result<Vec<usize>, CyklError>
+where
+    F: Fn(char) -> bool,
+{
+    text.split(separator)
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            let id: usize = token
+                .parse()
+                .map_err(|err| CyklError::Parse(format!("{:?} in {:?}", err, token)))?;
+            id.checked_sub(base)
+                .ok_or_else(|| CyklError::Parse(format!("Node id {} below {}", id, base)))
+        })
+        .collect()
+}
@@ -4,12 +4,70 @@ pub mod tour;
 
 pub type Scalar = f64;
 
+mod error;
+pub use error::CyklError;
+
+mod cancel;
+pub use cancel::CancellationToken;
+
+#[cfg(any(feature = "config-toml", feature = "config-json"))]
+mod config;
+
 mod model;
+pub use model::load_tour;
 pub use model::load_tsp;
+pub use model::save_tour;
 pub use model::Model;
 pub use model::RunConfig;
 pub use model::RunConfigBuilder;
 
 pub mod data;
 
+mod csv;
+pub use csv::{from_csv, Column, ColumnMap, CsvNode};
+
+#[cfg(feature = "routing")]
+mod routing;
+#[cfg(feature = "routing")]
+pub use routing::TableAnnotation;
+
+#[cfg(feature = "arrow")]
+mod columnar;
+#[cfg(feature = "arrow")]
+pub use columnar::{from_arrow, from_parquet};
+
+pub mod viz;
+
+pub mod transform;
+
+pub mod stats;
+
+pub mod spatial;
+
+pub mod cluster;
+
+pub mod split;
+
+pub mod overlay;
+
+pub mod oracle;
+
+pub mod scenario;
+
+pub mod robustness;
+
+pub mod interop;
+
+pub mod batch;
+
+pub mod experiments;
+
+pub mod autotune;
+
+#[cfg(feature = "async")]
+pub mod service;
+
+#[cfg(feature = "concorde")]
+pub mod concorde;
+
 mod tests;
@@ -1,11 +1,31 @@
-use std::{collections::HashSet, path::Path};
+use std::{collections::HashSet, path::Path, sync::Arc};
 
 use tspf::TspBuilder;
 
+#[cfg(feature = "routing")]
+use crate::routing::{parse_table, TableAnnotation};
 use crate::{
-    alg::{cand_gen_nn, solvers::solve_greedy, SolverKind},
-    data::{DataStore, Metric, NodeIndex, NodeKind},
-    tour::{TourOrder, TwoLevelList},
+    alg::{
+        acceptance::Greedy,
+        adaptive::Adaptive,
+        cand_gen_nn_with,
+        incumbent::{Incumbent, Parallelism},
+        lkh::enumerate_2opt,
+        lns::Lns,
+        nearest_fragment,
+        registry::{PipelineSpec, Registry},
+        solvers::{
+            descend, solve_generalized, solve_greedy, solve_lahc_reporting, solve_prize_collecting,
+        },
+        Objective, Scan, SolverKind,
+    },
+    data::{
+        DataStore, DuplicatePolicy, MemoryBudget, Metric, NodeIndex, NodeKind, Projection, Unit,
+    },
+    oracle::cycle_cost,
+    overlay::CostOverlay,
+    tour::{Tour, TourBuilder, TourOrder, TwoLevelList},
+    CancellationToken, CyklError, Scalar,
 };
 
 #[derive(Debug)]
@@ -30,6 +50,12 @@ impl<M> Model<M> {
         }
     }
 
+    /// Creates a model for nodes given as latitude and longitude in decimal degrees, using the
+    /// [`Metric::Haversine`] distance in meters.
+    pub fn new_geo(groupsize: usize) -> Self {
+        Self::new(Metric::Haversine, groupsize)
+    }
+
     pub fn with_capacity(
         metric: Metric,
         groupsize: usize,
@@ -45,6 +71,140 @@ impl<M> Model<M> {
         }
     }
 
+    /// Sets the size of the segments of the tours created by the model, see
+    /// [`TourBuilder::groupsize`].
+    pub fn set_groupsize(&mut self, groupsize: usize) {
+        self.groupsize = groupsize;
+    }
+
+    /// Returns the size of the segments of the tours created by the model.
+    pub fn groupsize(&self) -> usize {
+        self.groupsize
+    }
+
+    /// Sets the policy for nodes with duplicate coordinates.
+    ///
+    /// Must be called before any node is added to the model.
+    pub fn set_duplicate_policy(&mut self, policy: DuplicatePolicy) {
+        self.store.set_duplicate_policy(policy);
+    }
+
+    /// Sets the projection applied to the coordinates of nodes.
+    ///
+    /// Must be called before any node is added to the model.
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.store.set_projection(projection);
+    }
+
+    /// Sets the unit of the costs, see [`Unit`].
+    pub fn set_unit(&mut self, unit: Unit) {
+        self.store.set_unit(unit);
+    }
+
+    /// Sets the factor by which all costs are multiplied, see [`DataStore::set_scale`].
+    ///
+    /// # Panics
+    /// Panics if the factor is not a positive finite number.
+    pub fn set_scale(&mut self, factor: Scalar) {
+        self.store.set_scale(factor);
+    }
+
+    /// Sets the memory budget of the model, see [`MemoryBudget`].
+    ///
+    /// Must be called before the model is completed. Over budget, costs are computed on demand
+    /// instead of being cached, and fewer candidates than configured are generated per node.
+    pub fn set_memory_budget(&mut self, budget: MemoryBudget) {
+        self.store.set_memory_budget(budget);
+    }
+
+    /// Returns the number of bytes allocated by the nodes of the model and their costs.
+    pub fn memory_footprint(&self) -> usize {
+        self.store.memory_footprint()
+    }
+
+    /// Sets the prize collected by visiting the node at the given index.
+    ///
+    /// Nodes with prizes may be skipped by [`SolverKind::PrizeCollecting`], at the expense of
+    /// their prizes.
+    pub fn set_prize(&mut self, index: usize, prize: Scalar) -> Result<(), CyklError> {
+        self.store.set_prize(index, prize)
+    }
+
+    /// Sets the time spent serving the node at the given index, which counts towards
+    /// [`TourOrder::duration`] but not the length of a tour.
+    pub fn set_service_time(&mut self, index: usize, time: Scalar) -> Result<(), CyklError> {
+        self.store.set_service_time(index, time)
+    }
+
+    /// Assigns the node at the given index to a group.
+    ///
+    /// [`SolverKind::Generalized`] visits exactly one node of each group.
+    pub fn set_group(&mut self, index: usize, group: usize) -> Result<(), CyklError> {
+        self.store.set_group(index, group)
+    }
+
+    /// Assigns the nodes of each part of a partition to the group numbered by the position of the
+    /// part, e.g. the clusters computed with [`cluster::kmeans`](crate::cluster::kmeans).
+    pub fn set_groups(&mut self, partition: &[Vec<usize>]) -> Result<(), CyklError> {
+        for (group, part) in partition.iter().enumerate() {
+            for index in part {
+                self.store.set_group(*index, group)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the full matrix of costs for models with [`Metric::Explicit`], see
+    /// [`DataStore::set_costs`].
+    pub fn set_costs(&mut self, costs: Vec<Scalar>) -> Result<(), CyklError> {
+        self.store.set_costs(costs)
+    }
+
+    /// Sets the costs of a graph given by its edges, see [`DataStore::set_edges`].
+    ///
+    /// Solving fails with [`CyklError::MissingEdge`] if no tour is found that uses only edges of
+    /// the graph.
+    pub fn set_edges(&mut self, edges: &[(usize, usize, Scalar)]) -> Result<(), CyklError> {
+        self.store.set_edges(edges)
+    }
+
+    /// Adds a named layer of costs, see [`DataStore::add_layer`].
+    pub fn add_layer(
+        &mut self,
+        name: &str,
+        unit: Unit,
+        costs: Vec<Scalar>,
+    ) -> Result<(), CyklError> {
+        self.store.add_layer(name, unit, costs)
+    }
+
+    /// Sets the costs of a model with [`Metric::Explicit`] to the weighted sum of layers, see
+    /// [`DataStore::set_costs_from_layers`].
+    pub fn set_costs_from_layers(&mut self, weights: &[(&str, Scalar)]) -> Result<(), CyklError> {
+        self.store.set_costs_from_layers(weights)
+    }
+
+    /// Sets the costs of a model with [`Metric::Explicit`] from the table response of a routing
+    /// engine such as OSRM or Valhalla, so that costs along road networks can be used directly.
+    ///
+    /// The table must have one row and column for each node of the model, in the order in which the
+    /// nodes were added. Returns [`CyklError::UnitMismatch`] if the model already has costs in
+    /// another unit, e.g. when durations are given for a model of distances.
+    #[cfg(feature = "routing")]
+    pub fn costs_from_osrm_json(
+        &mut self,
+        json: &[u8],
+        annotation: TableAnnotation,
+    ) -> Result<(), CyklError> {
+        let (_, costs) = parse_table(json, annotation)?;
+        self.store.set_costs_in(costs, annotation.unit())
+    }
+
+    /// Returns the store that holds the nodes of the model.
+    pub fn store(&self) -> &DataStore<M> {
+        &self.store
+    }
+
     #[inline]
     pub fn n_depots(&self) -> usize {
         self.depots.len()
@@ -82,31 +242,300 @@ impl<M> Model<M> {
         }
     }
 
-    // TODO: should return status and/or result.
-    pub fn solve(&mut self, config: &RunConfig) -> Option<TourOrder> {
+    /// Adds a node given as latitude and longitude in decimal degrees.
+    ///
+    /// Returns `None` if the model is already completed or its metric is not geodesic.
+    pub fn add_latlon(&mut self, kind: NodeKind, lat: f64, lon: f64, meta: M) -> Option<NodeIndex> {
+        if self.store.metric().is_geodesic() {
+            self.add(kind, vec![lat, lon], meta)
+        } else {
+            None
+        }
+    }
+
+    // TODO: should return status.
+    pub fn solve(&mut self, config: &RunConfig) -> Result<Option<TourOrder>, CyklError> {
+        self.solve_with(config, None)
+    }
+
+    /// Solves the model like [`Model::solve`], and offers the constructed tour and each better
+    /// tour of the improvement phase to `incumbent` as soon as it is found.
+    pub(crate) fn solve_with(
+        &mut self,
+        config: &RunConfig,
+        incumbent: Option<&Arc<Incumbent>>,
+    ) -> Result<Option<TourOrder>, CyklError> {
+        let offer = |order: &TourOrder| {
+            if let Some(incumbent) = incumbent {
+                incumbent.offer(order);
+            }
+        };
         self.complete();
 
-        let mut tour = TwoLevelList::new(&self.store, self.groupsize);
-        cand_gen_nn(&mut tour, config.cands);
+        let mut tour = TourBuilder::new()
+            .groupsize(self.groupsize)
+            .build(&self.store)?;
+        let token = &config.cancellation;
+        let cands = self.store.memory_budget().max_cands(
+            self.store.len(),
+            config.cands,
+            self.store.memory_footprint(),
+        );
+        cand_gen_nn_with(&mut tour, cands, config.objective, token)?;
+
+        let starters = match config.solver {
+            SolverKind::Greedy(ref starters)
+            | SolverKind::PrizeCollecting(ref starters)
+            | SolverKind::Generalized(ref starters)
+            | SolverKind::LateAcceptance { ref starters, .. }
+            | SolverKind::Adaptive { ref starters, .. }
+            | SolverKind::Lns { ref starters, .. } => starters,
+            SolverKind::NearestFragment { leaf_size } => {
+                let order = nearest_fragment(&self.store, leaf_size);
+                if order.is_empty() {
+                    return Ok(None);
+                }
+
+                tour.apply_with(&order, token, |_| {})?;
+                offer(&order);
+                return self.check_edges(order).map(Some);
+            }
+            SolverKind::Pipeline(ref spec) => {
+                let order = run_pipeline(&mut tour, config, spec, incumbent)?;
+                return self.check_edges(order).map(Some);
+            }
+        };
+
+        if token.is_cancelled() {
+            return Err(CyklError::Cancelled);
+        }
 
-        let result = match config.solver {
-            SolverKind::Greedy(ref starters) => solve_greedy(&mut tour, starters),
+        let constructed = match solve_greedy(&mut tour, starters, config.objective)? {
+            Some(order) => order,
+            None => return Ok(None),
         };
 
-        result.unwrap()
+        offer(&constructed);
+        if token.is_cancelled() {
+            return Ok(Some(constructed));
+        }
+
+        improve(&mut tour, config, constructed, incumbent)
+            .and_then(|order| self.check_edges(order))
+            .map(Some)
+    }
+
+    /// Returns [`CyklError::MissingEdge`] if a tour uses an edge that is absent from the graph of
+    /// the store.
+    fn check_edges(&self, order: TourOrder) -> Result<TourOrder, CyklError> {
+        match self.store.missing_edge(&order) {
+            Some((from, to)) => Err(CyklError::MissingEdge { from, to }),
+            None => Ok(order),
+        }
+    }
+
+    /// Re-optimises a tour under the costs adjusted by an overlay, starting from the given tour,
+    /// and returns the improved tour with its cost under the overlay.
+    ///
+    /// The improvement phase of the solver of the configuration is run on the given tour. Solvers
+    /// without an improvement phase descend with 2-opt moves instead.
+    pub(crate) fn reoptimize(
+        &mut self,
+        config: &RunConfig,
+        overlay: CostOverlay,
+        start: &TourOrder,
+    ) -> Result<TourOrder, CyklError> {
+        self.complete();
+
+        let mut tour = TourBuilder::new()
+            .groupsize(self.groupsize)
+            .overlay(overlay)
+            .build(&self.store)?;
+        let cands = self.store.memory_budget().max_cands(
+            self.store.len(),
+            config.cands,
+            self.store.memory_footprint(),
+        );
+        cand_gen_nn_with(&mut tour, cands, config.objective, &config.cancellation)?;
+        tour.apply(start)?;
+
+        let initial = tour.tour_order();
+        match config.solver {
+            SolverKind::Greedy(_) | SolverKind::NearestFragment { .. } => {
+                descend(&mut tour, |tour, node, moves| {
+                    moves.extend(enumerate_2opt(tour, node, node.candidates()))
+                });
+                Ok(tour.tour_order())
+            }
+            _ => improve(&mut tour, config, initial, None),
+        }
     }
 }
 
+/// Runs the improvement phase of the solver of a configuration on a constructed tour, and offers
+/// each new best tour to the incumbent.
+fn improve(
+    tour: &mut TwoLevelList,
+    config: &RunConfig,
+    constructed: TourOrder,
+    incumbent: Option<&Arc<Incumbent>>,
+) -> Result<TourOrder, CyklError> {
+    let token = &config.cancellation;
+    let result = match config.solver {
+        SolverKind::PrizeCollecting(_) => solve_prize_collecting(tour, config.objective)?,
+        SolverKind::Generalized(_) => solve_generalized(tour, config.objective)?,
+        SolverKind::LateAcceptance {
+            history,
+            termination,
+            seed,
+            ..
+        } => solve_lahc_reporting(
+            tour,
+            history,
+            termination,
+            seed,
+            config.objective,
+            token,
+            incumbent.map(Arc::as_ref),
+        ),
+        SolverKind::Adaptive { budget, seed, .. } => {
+            let mut adaptive = Adaptive::new(seed)
+                .with_scan(config.scan)
+                .with_objective(config.objective)
+                .with_cancellation(token.clone());
+            if let Some(incumbent) = incumbent {
+                adaptive = adaptive.with_incumbent(Arc::clone(incumbent));
+            }
+            adaptive.run(tour, budget)?
+        }
+        SolverKind::Lns {
+            n_remove,
+            termination,
+            seed,
+            ..
+        } => {
+            let mut lns = Lns::new(n_remove, seed).with_cancellation(token.clone());
+            if let Some(incumbent) = incumbent {
+                lns = lns.with_incumbent(Arc::clone(incumbent));
+            }
+            lns.run_with_objective(tour, &mut Greedy, termination, &config.objective)?
+        }
+        // Re-optimisation starts from a given tour, which construction phases would discard.
+        SolverKind::Pipeline(ref spec) => run_pipeline(
+            tour,
+            config,
+            &PipelineSpec {
+                construct: Vec::new(),
+                ..spec.clone()
+            },
+            incumbent,
+        )?,
+        SolverKind::Greedy(_) | SolverKind::NearestFragment { .. } => constructed,
+    };
+
+    Ok(result)
+}
+
+/// Builds the pipeline of a specification from the built-in phases and runs it on a tour, and
+/// offers each new best tour to the incumbent.
+fn run_pipeline(
+    tour: &mut TwoLevelList,
+    config: &RunConfig,
+    spec: &PipelineSpec,
+    incumbent: Option<&Arc<Incumbent>>,
+) -> Result<TourOrder, CyklError> {
+    let mut pipeline = Registry::new()
+        .build(spec)?
+        .objective(config.objective)
+        .with_cancellation(config.cancellation.clone());
+    if let Some(incumbent) = incumbent {
+        pipeline = pipeline.with_incumbent(Arc::clone(incumbent));
+    }
+    pipeline.run(tour)
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct RunConfig {
-    cands: usize,
+    pub(crate) cands: usize,
     solver: SolverKind,
+    objective: Objective,
+    scan: Scan,
+    deterministic: bool,
+    pub(crate) cancellation: CancellationToken,
+}
+
+impl RunConfig {
+    /// Returns the number of candidates per node.
+    pub fn cands(&self) -> usize {
+        self.cands
+    }
+
+    /// Returns the solver.
+    pub fn solver(&self) -> &SolverKind {
+        &self.solver
+    }
+
+    /// Returns the direction of the optimisation.
+    pub fn objective(&self) -> Objective {
+        self.objective
+    }
+
+    /// Returns the strategy with which local search drivers choose among improving moves.
+    pub fn scan(&self) -> Scan {
+        self.scan
+    }
+
+    /// Returns `true` if parallel runs of the configuration must not depend on the scheduling of
+    /// their threads, see [`RunConfigBuilder::deterministic`].
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Returns the distribution of independent starts over `n_threads` threads for this
+    /// configuration, e.g. for [`multi_start_with`](crate::alg::incumbent::multi_start_with).
+    pub fn parallelism(&self, n_threads: usize) -> Parallelism {
+        Parallelism::new(n_threads).deterministic(self.deterministic)
+    }
+
+    /// Returns the seed of the solver, or `None` if the solver has no random number generator.
+    pub fn seed(&self) -> Option<u64> {
+        match &self.solver {
+            SolverKind::LateAcceptance { seed, .. }
+            | SolverKind::Adaptive { seed, .. }
+            | SolverKind::Lns { seed, .. } => Some(*seed),
+            SolverKind::Pipeline(spec) => Some(spec.seed),
+            SolverKind::Greedy(_)
+            | SolverKind::PrizeCollecting(_)
+            | SolverKind::Generalized(_)
+            | SolverKind::NearestFragment { .. } => None,
+        }
+    }
+
+    /// Returns the configuration with the seed of its solver replaced by `seed`. Solvers without a
+    /// random number generator are left unchanged.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        match &mut self.solver {
+            SolverKind::LateAcceptance { seed: s, .. }
+            | SolverKind::Adaptive { seed: s, .. }
+            | SolverKind::Lns { seed: s, .. } => *s = seed,
+            SolverKind::Pipeline(spec) => spec.seed = seed,
+            SolverKind::Greedy(_)
+            | SolverKind::PrizeCollecting(_)
+            | SolverKind::Generalized(_)
+            | SolverKind::NearestFragment { .. } => {}
+        }
+        self
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct RunConfigBuilder {
     cands: Option<usize>,
     solver: Option<SolverKind>,
+    objective: Option<Objective>,
+    scan: Option<Scan>,
+    deterministic: Option<bool>,
+    cancellation: Option<CancellationToken>,
 }
 
 impl RunConfigBuilder {
@@ -126,20 +555,60 @@ impl RunConfigBuilder {
         self
     }
 
+    /// Sets the direction of the optimisation. Defaults to [`Objective::Minimize`].
+    pub fn objective(mut self, objective: Objective) -> Self {
+        self.objective = Some(objective);
+        self
+    }
+
+    /// Sets the strategy with which local search drivers choose among the improving moves of a
+    /// node. Defaults to [`Scan::FirstImprovement`].
+    pub fn scan(mut self, scan: Scan) -> Self {
+        self.scan = Some(scan);
+        self
+    }
+
+    /// Sets whether parallel runs must produce bit-identical tours for the same seeds and number of
+    /// threads. Defaults to `false`.
+    ///
+    /// [`solve_multi_start`](crate::batch::solve_multi_start) distributes its starts as given by
+    /// [`RunConfig::parallelism`], thus deterministic runs reduce the tours of their starts in a
+    /// fixed order, see [`Parallelism::deterministic`]. [`Model::solve`] runs on the calling
+    /// thread and is unaffected, whereas solvers with a time limit stay dependent on the speed of
+    /// the machine in either mode.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = Some(deterministic);
+        self
+    }
+
+    /// Sets a token with which the caller can stop the run, see [`CancellationToken`].
+    ///
+    /// The token is checked during candidate generation and in each iteration of the improvement
+    /// phase. A run cancelled before its construction phase fails with [`CyklError::Cancelled`],
+    /// otherwise it returns the best tour found so far.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     pub fn build(self) -> RunConfig {
         RunConfig {
             cands: self.cands.unwrap_or(10),
             solver: self.solver.unwrap_or_else(|| SolverKind::Greedy(vec![0])),
+            objective: self.objective.unwrap_or_default(),
+            scan: self.scan.unwrap_or_default(),
+            deterministic: self.deterministic.unwrap_or(false),
+            cancellation: self.cancellation.unwrap_or_default(),
         }
     }
 }
 
-pub fn load_tsp<P>(path: P, groupsize: usize) -> Model<usize>
+pub fn load_tsp<P>(path: P, groupsize: usize) -> Result<Model<usize>, CyklError>
 where
     P: AsRef<Path>,
 {
-    // TODO: catch err.
-    let mut tsp = TspBuilder::parse_path(path).unwrap();
+    let mut tsp =
+        TspBuilder::parse_path(path).map_err(|err| CyklError::Parse(format!("{:?}", err)))?;
 
     let metric = match tsp.weight_kind() {
         tspf::WeightKind::Explicit => Metric::Explicit,
@@ -179,5 +648,117 @@ where
 
     model.complete();
 
-    model
+    Ok(model)
+}
+
+/// Prefix of the comment in which [`save_tour`] stores the fingerprint of the instance.
+const FINGERPRINT_COMMENT: &str = "cykl fingerprint ";
+
+/// Writes a tour order into a file in the TSPLIB tour format.
+///
+/// The fingerprint of the instance is written into the comment of the file and checked by
+/// [`load_tour`].
+pub fn save_tour<M, P>(store: &DataStore<M>, order: &TourOrder, path: P) -> Result<(), CyklError>
+where
+    P: AsRef<Path>,
+{
+    let mut content = String::new();
+    content.push_str("TYPE : TOUR\n");
+    content.push_str(&format!(
+        "COMMENT : {}{:016x}\n",
+        FINGERPRINT_COMMENT,
+        store.fingerprint()
+    ));
+    content.push_str(&format!("DIMENSION : {}\n", order.len()));
+    content.push_str("TOUR_SECTION\n");
+    for index in order.order() {
+        content.push_str(&format!("{}\n", index + 1));
+    }
+    content.push_str("-1\nEOF\n");
+
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// Reads a tour order from a file in the TSPLIB tour format.
+///
+/// Returns [`CyklError::InstanceMismatch`] if the file was written by [`save_tour`] for another
+/// instance. Tours without fingerprint, e.g. from other solvers, are checked only for their
+/// dimension and node indices.
+pub fn load_tour<M, P>(store: &DataStore<M>, path: P) -> Result<TourOrder, CyklError>
+where
+    P: AsRef<Path>,
+{
+    let content = std::fs::read_to_string(path)?;
+    parse_tour(store, &content)
+}
+
+/// Parses a tour order in the TSPLIB tour format, see [`load_tour`].
+pub(crate) fn parse_tour<M>(store: &DataStore<M>, content: &str) -> Result<TourOrder, CyklError> {
+    let mut lines = content.lines().map(str::trim);
+
+    for line in lines.by_ref() {
+        if line == "TOUR_SECTION" {
+            break;
+        }
+
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+
+        match key {
+            "COMMENT" => {
+                if let Some(hex) = value.strip_prefix(FINGERPRINT_COMMENT) {
+                    let found = u64::from_str_radix(hex, 16)
+                        .map_err(|err| CyklError::Parse(format!("{:?}", err)))?;
+                    if found != store.fingerprint() {
+                        return Err(CyklError::InstanceMismatch {
+                            expected: store.fingerprint(),
+                            found,
+                        });
+                    }
+                }
+            }
+            "DIMENSION" => {
+                let dim: usize = value
+                    .parse()
+                    .map_err(|err| CyklError::Parse(format!("{:?}", err)))?;
+                if dim != store.len() {
+                    return Err(CyklError::LenMismatched {
+                        expected: store.len(),
+                        received: dim,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut visited = vec![false; store.len()];
+    let mut order = Vec::with_capacity(store.len());
+    for token in lines.flat_map(str::split_whitespace) {
+        if token == "-1" || token == "EOF" {
+            break;
+        }
+
+        let id: usize = token
+            .parse()
+            .map_err(|err| CyklError::Parse(format!("{:?}", err)))?;
+        let index = id.wrapping_sub(1);
+        match visited.get_mut(index) {
+            Some(true) => return Err(CyklError::DuplicateNode(index)),
+            Some(x) => *x = true,
+            None => {
+                return Err(CyklError::IndexOutOfBounds {
+                    index,
+                    len: store.len(),
+                })
+            }
+        }
+        order.push(index);
+    }
+
+    let cost = cycle_cost(store, &order);
+    Ok(TourOrder::with_cost(order, cost))
 }
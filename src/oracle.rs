@@ -0,0 +1,439 @@
+//! Sources of travel costs between nodes, which tours and solvers query instead of a concrete
+//! store.
+//!
+//! A [`DistanceOracle`] only answers how many nodes there are and what travelling between two of
+//! them costs. Besides [`DataStore`] and [`CostLayer`], the module provides oracles on bare
+//! coordinates ([`CoordOracle`]), on an explicit matrix ([`MatrixOracle`]), with the adjustments
+//! of an overlay ([`Overlaid`]) and on a function ([`FnOracle`]). With the `mmap` feature,
+//! [`MmapOracle`] reads a matrix from a memory-mapped file, so that instances whose matrix exceeds
+//! the memory are left to the page cache. With the `routing` feature, [`RemoteOracle`] fetches and
+//! caches the costs of a [`TableService`] such as the table API of OSRM ([`OsrmService`]). An
+//! oracle replaces the costs of the store in a tour with
+//! [`TwoLevelList::attach_oracle`](crate::tour::TwoLevelList::attach_oracle).
+//!
+//! A tour is created either from a store, whose costs an attached oracle replaces, or from an
+//! oracle alone with
+//! [`TourBuilder::build_from_oracle`](crate::tour::TourBuilder::build_from_oracle).
+//! Solvers only query the costs of the tour. Code that reads other attributes of the nodes, such
+//! as their coordinates, kinds or prizes, still reads them from the store, thus a tour created
+//! from an oracle alone has nodes without coordinates, all of which are targets.
+#[cfg(feature = "mmap")]
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+use std::{fmt, rc::Rc, sync::Arc};
+
+use crate::{
+    data::{CostLayer, DataStore, Metric},
+    overlay::CostOverlay,
+    CyklError, Scalar,
+};
+
+#[cfg(feature = "routing")]
+pub use crate::routing::{OsrmService, RemoteOracle, TableService};
+
+/// Source of the costs of travelling between the nodes of an instance.
+pub trait DistanceOracle {
+    /// Returns the number of nodes.
+    fn len(&self) -> usize;
+
+    /// Returns the cost of travelling from node `a` to node `b`.
+    ///
+    /// # Panics
+    /// Implementations may panic if `a` or `b` are out of bounds.
+    fn cost(&self, a: usize, b: usize) -> Scalar;
+
+    /// Returns `true` if there are no nodes.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Makes the oracle ready to answer every query, e.g. by fetching the costs from a remote
+    /// service, and returns the errors of doing so.
+    ///
+    /// Tours call it when the oracle is attached, so that their queries cannot fail. Oracles whose
+    /// costs are always available keep the default, which does nothing.
+    fn prepare(&self) -> Result<(), CyklError> {
+        Ok(())
+    }
+}
+
+/// Returns the cost of the closed tour that visits the nodes in the given order.
+pub(crate) fn cycle_cost<O>(oracle: &O, order: &[usize]) -> Scalar
+where
+    O: DistanceOracle + ?Sized,
+{
+    match (order.first(), order.last()) {
+        (Some(first), Some(last)) => {
+            order
+                .windows(2)
+                .map(|w| oracle.cost(w[0], w[1]))
+                .sum::<Scalar>()
+                + oracle.cost(*last, *first)
+        }
+        _ => 0.,
+    }
+}
+
+/// Oracle shared with a tour, see
+/// [`TwoLevelList::attach_oracle`](crate::tour::TwoLevelList::attach_oracle).
+pub type SharedOracle = Arc<dyn DistanceOracle + Send + Sync>;
+
+impl fmt::Debug for dyn DistanceOracle + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DistanceOracle")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<M> DistanceOracle for DataStore<M> {
+    fn len(&self) -> usize {
+        DataStore::len(self)
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        DataStore::cost(self, &a, &b)
+    }
+}
+
+impl DistanceOracle for CostLayer {
+    fn len(&self) -> usize {
+        CostLayer::len(self)
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        CostLayer::cost(self, a, b)
+    }
+}
+
+impl<O: DistanceOracle + ?Sized> DistanceOracle for &O {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        (**self).cost(a, b)
+    }
+
+    fn prepare(&self) -> Result<(), CyklError> {
+        (**self).prepare()
+    }
+}
+
+impl<O: DistanceOracle + ?Sized> DistanceOracle for Box<O> {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        (**self).cost(a, b)
+    }
+
+    fn prepare(&self) -> Result<(), CyklError> {
+        (**self).prepare()
+    }
+}
+
+impl<O: DistanceOracle + ?Sized> DistanceOracle for Rc<O> {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        (**self).cost(a, b)
+    }
+
+    fn prepare(&self) -> Result<(), CyklError> {
+        (**self).prepare()
+    }
+}
+
+impl<O: DistanceOracle + ?Sized> DistanceOracle for Arc<O> {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        (**self).cost(a, b)
+    }
+
+    fn prepare(&self) -> Result<(), CyklError> {
+        (**self).prepare()
+    }
+}
+
+/// Costs computed on demand from the coordinates of the nodes with a metric.
+#[derive(Clone, Debug)]
+pub struct CoordOracle {
+    metric: Metric,
+    dim: usize,
+    coords: Vec<Scalar>,
+}
+
+impl CoordOracle {
+    /// Creates an oracle on the coordinates of the nodes, which must all have the same number of
+    /// dimensions.
+    pub fn new(metric: Metric, coords: &[Vec<Scalar>]) -> Result<Self, CyklError> {
+        let dim = coords.first().map_or(0, |pos| pos.len());
+        let mut flat = Vec::with_capacity(coords.len() * dim);
+        for pos in coords {
+            if pos.len() != dim {
+                return Err(CyklError::LenMismatched {
+                    expected: dim,
+                    received: pos.len(),
+                });
+            }
+            flat.extend_from_slice(pos);
+        }
+
+        Ok(Self {
+            metric,
+            dim,
+            coords: flat,
+        })
+    }
+}
+
+impl DistanceOracle for CoordOracle {
+    fn len(&self) -> usize {
+        self.coords.len().checked_div(self.dim).unwrap_or(0)
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        let at = |index: usize| &self.coords[index * self.dim..(index + 1) * self.dim];
+        self.metric.cost(at(a), at(b))
+    }
+}
+
+/// Costs read from an explicit matrix in row-major order.
+#[derive(Clone, Debug)]
+pub struct MatrixOracle {
+    len: usize,
+    costs: Vec<Scalar>,
+}
+
+impl MatrixOracle {
+    /// Creates an oracle on a square matrix of `len` rows in row-major order.
+    pub fn new(len: usize, costs: Vec<Scalar>) -> Result<Self, CyklError> {
+        if costs.len() != len * len {
+            return Err(CyklError::LenMismatched {
+                expected: len * len,
+                received: costs.len(),
+            });
+        }
+
+        Ok(Self { len, costs })
+    }
+}
+
+impl DistanceOracle for MatrixOracle {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        assert!(a < self.len && b < self.len, "Node index out of bounds");
+        self.costs[a * self.len + b]
+    }
+}
+
+/// Costs of another oracle with the adjustments of an overlay applied.
+#[derive(Clone, Debug)]
+pub struct Overlaid<O> {
+    base: O,
+    overlay: CostOverlay,
+}
+
+impl<O: DistanceOracle> Overlaid<O> {
+    /// Creates an oracle that adjusts the costs of `base` with `overlay`. Pairs of nodes without
+    /// an adjustment keep the costs of `base`.
+    pub fn new(base: O, overlay: CostOverlay) -> Self {
+        Self { base, overlay }
+    }
+
+    /// Returns the overlay.
+    pub fn overlay(&self) -> &CostOverlay {
+        &self.overlay
+    }
+
+    /// Returns the overlay for modification.
+    pub fn overlay_mut(&mut self) -> &mut CostOverlay {
+        &mut self.overlay
+    }
+}
+
+impl<O: DistanceOracle> DistanceOracle for Overlaid<O> {
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        let base = self.base.cost(a, b);
+        self.overlay.adjust(a, b, base).unwrap_or(base)
+    }
+
+    fn prepare(&self) -> Result<(), CyklError> {
+        self.base.prepare()
+    }
+}
+
+/// Costs returned by a function, e.g. one that reads a memory-mapped matrix or queries and caches
+/// the responses of a remote service.
+#[derive(Clone)]
+pub struct FnOracle<F> {
+    len: usize,
+    f: F,
+}
+
+impl<F> fmt::Debug for FnOracle<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnOracle").field("len", &self.len).finish()
+    }
+}
+
+impl<F> FnOracle<F>
+where
+    F: Fn(usize, usize) -> Scalar,
+{
+    /// Creates an oracle on `len` nodes, whose costs are `f(a, b)`.
+    pub fn new(len: usize, f: F) -> Self {
+        Self { len, f }
+    }
+}
+
+impl<F> DistanceOracle for FnOracle<F>
+where
+    F: Fn(usize, usize) -> Scalar,
+{
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        (self.f)(a, b)
+    }
+}
+
+/// Magic bytes at the start of a matrix file of [`MmapOracle`].
+#[cfg(feature = "mmap")]
+const MATRIX_MAGIC: &[u8; 8] = b"CYKLMTRX";
+
+/// Version of the matrix file format of [`MmapOracle`].
+#[cfg(feature = "mmap")]
+const MATRIX_VERSION: u32 = 1;
+
+/// Length of the header of a matrix file: magic bytes, version and number of nodes.
+#[cfg(feature = "mmap")]
+const MATRIX_HEADER: usize = 20;
+
+/// Costs read from a matrix file that is mapped into memory.
+///
+/// The operating system loads the pages of the matrix when they are read and may evict them
+/// again, thus the matrix may exceed the memory of the machine. Files are written with
+/// [`MmapOracle::write`].
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MmapOracle {
+    len: usize,
+    map: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl MmapOracle {
+    /// Writes the costs of an oracle into a file that [`MmapOracle::open`] maps.
+    ///
+    /// The binary format consists of the magic bytes `CYKLMTRX`, the format version and the
+    /// number of nodes, followed by the costs in row-major order. All numbers are little-endian,
+    /// costs take eight bytes.
+    pub fn write<O, P>(oracle: &O, path: P) -> Result<(), CyklError>
+    where
+        O: DistanceOracle + ?Sized,
+        P: AsRef<Path>,
+    {
+        oracle.prepare()?;
+
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(MATRIX_MAGIC)?;
+        w.write_all(&MATRIX_VERSION.to_le_bytes())?;
+        w.write_all(&(oracle.len() as u64).to_le_bytes())?;
+        for a in 0..oracle.len() {
+            for b in 0..oracle.len() {
+                w.write_all(&oracle.cost(a, b).to_le_bytes())?;
+            }
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Maps a file written by [`MmapOracle::write`].
+    ///
+    /// Returns [`CyklError::Parse`] if the file is not a matrix file or its length does not match
+    /// the number of nodes in its header. The file must not be modified while it is mapped.
+    pub fn open<P>(path: P) -> Result<Self, CyklError>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path)?;
+        // SAFETY: The map is only read, and the caller must not modify the file while it is
+        // mapped, as documented above.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+
+        let header = map
+            .get(..MATRIX_HEADER)
+            .ok_or_else(|| CyklError::Parse("not a matrix file".to_string()))?;
+        if &header[..8] != MATRIX_MAGIC {
+            return Err(CyklError::Parse("not a matrix file".to_string()));
+        }
+
+        let mut version = [0; 4];
+        version.copy_from_slice(&header[8..12]);
+        let version = u32::from_le_bytes(version);
+        if version != MATRIX_VERSION {
+            return Err(CyklError::Parse(format!(
+                "unsupported matrix file version {}",
+                version
+            )));
+        }
+
+        let mut len = [0; 8];
+        len.copy_from_slice(&header[12..20]);
+        let len = u64::from_le_bytes(len);
+        let expected = usize::try_from(len)
+            .ok()
+            .and_then(|len| len.checked_mul(len))
+            .and_then(|n_costs| n_costs.checked_mul(std::mem::size_of::<Scalar>()));
+        if expected != Some(map.len() - MATRIX_HEADER) {
+            return Err(CyklError::Parse(format!(
+                "matrix file of {} bytes does not hold the costs of {} nodes",
+                map.len(),
+                len
+            )));
+        }
+
+        Ok(Self {
+            len: len as usize,
+            map,
+        })
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl DistanceOracle for MmapOracle {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        assert!(a < self.len && b < self.len, "Node index out of bounds");
+        let size = std::mem::size_of::<Scalar>();
+        let offset = MATRIX_HEADER + (a * self.len + b) * size;
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&self.map[offset..offset + size]);
+        Scalar::from_le_bytes(bytes)
+    }
+}
@@ -0,0 +1,101 @@
+//! Temporary overrides of edge costs on top of a [`DataStore`].
+use std::collections::HashMap;
+
+use crate::{data::DataStore, tour::Edge, Scalar};
+
+/// Overrides the costs of selected edges without changing the store they belong to.
+///
+/// An overlay holds two kinds of adjustments per edge: a replacement of the base cost, e.g. a
+/// prohibitively high cost for a forbidden edge or another cost in a scenario analysis, and a
+/// penalty that is added on top, e.g. the penalties of guided local search. Edges are undirected,
+/// thus overriding `(a, b)` also overrides `(b, a)`.
+///
+/// An overlay is attached to a tour with [`TwoLevelList::attach_overlay`], after which
+/// [`Tour::distance`] and the tour length include the adjustments.
+///
+/// [`TwoLevelList::attach_overlay`]: crate::tour::TwoLevelList::attach_overlay
+/// [`Tour::distance`]: crate::tour::Tour::distance
+#[derive(Clone, Debug, Default)]
+pub struct CostOverlay {
+    costs: HashMap<Edge, Scalar>,
+    penalties: HashMap<Edge, Scalar>,
+}
+
+impl CostOverlay {
+    /// Creates an overlay that keeps all base costs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the cost of an edge.
+    pub fn set(&mut self, a: usize, b: usize, cost: Scalar) {
+        self.costs.insert(Edge::new(a, b), cost);
+    }
+
+    /// Adds a penalty to the cost of an edge, accumulating with earlier penalties.
+    pub fn add_penalty(&mut self, a: usize, b: usize, penalty: Scalar) {
+        *self.penalties.entry(Edge::new(a, b)).or_insert(0.) += penalty;
+    }
+
+    /// Returns the accumulated penalty of an edge.
+    pub fn penalty(&self, a: usize, b: usize) -> Scalar {
+        self.penalties.get(&Edge::new(a, b)).copied().unwrap_or(0.)
+    }
+
+    /// Restores the base cost of an edge and removes its penalty.
+    pub fn remove(&mut self, a: usize, b: usize) {
+        self.costs.remove(&Edge::new(a, b));
+        self.penalties.remove(&Edge::new(a, b));
+    }
+
+    /// Removes all adjustments.
+    pub fn clear(&mut self) {
+        self.costs.clear();
+        self.penalties.clear();
+    }
+
+    /// Returns the number of edges whose cost is adjusted.
+    pub fn len(&self) -> usize {
+        self.costs.len()
+            + self
+                .penalties
+                .keys()
+                .filter(|edge| !self.costs.contains_key(edge))
+                .count()
+    }
+
+    /// Returns `true` if no edge cost is adjusted.
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty() && self.penalties.is_empty()
+    }
+
+    /// Returns `true` if the cost of an edge is adjusted.
+    pub fn contains(&self, a: usize, b: usize) -> bool {
+        let edge = Edge::new(a, b);
+        self.costs.contains_key(&edge) || self.penalties.contains_key(&edge)
+    }
+
+    /// Returns the adjusted cost of an edge between two distinct nodes, where `base` is the cost
+    /// of the edge in the store, or `None` if the edge is not adjusted.
+    #[inline]
+    pub(crate) fn adjust(&self, a: usize, b: usize, base: Scalar) -> Option<Scalar> {
+        if self.is_empty() || a == b {
+            return None;
+        }
+
+        let edge = Edge::new(a, b);
+        match (self.costs.get(&edge), self.penalties.get(&edge)) {
+            (None, None) => None,
+            (cost, penalty) => Some(cost.copied().unwrap_or(base) + penalty.copied().unwrap_or(0.)),
+        }
+    }
+
+    /// Returns the cost of an edge in a store with the adjustments of the overlay applied.
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` are out of bounds.
+    pub fn cost<M>(&self, store: &DataStore<M>, a: usize, b: usize) -> Scalar {
+        let base = store.cost(&a, &b);
+        self.adjust(a, b, base).unwrap_or(base)
+    }
+}
@@ -0,0 +1,177 @@
+//! Robustness of tours against uncertain edge costs, e.g. travel times that vary from day to day.
+//!
+//! [`evaluate`] draws random perturbations of all edge costs, measures a tour under each of them,
+//! and compares it to the tours re-optimised for the perturbed costs. A robust tour stays close to
+//! the re-optimised tours across the samples, even if it is not the shortest one for the expected
+//! costs.
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    oracle::cycle_cost,
+    oracle::Overlaid,
+    overlay::CostOverlay,
+    tour::{validate_order, TourOrder},
+    CyklError, Model, RunConfig, Scalar,
+};
+
+/// Random model of the deviation of an edge cost from its value in the store.
+///
+/// Edge costs are perturbed independently of each other by a factor drawn for each edge.
+/// Perturbed costs are never negative.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Noise {
+    /// Multiplies each cost by a factor drawn uniformly from `[1 - spread, 1 + spread]`.
+    Uniform(Scalar),
+    /// Multiplies each cost by a factor drawn from a normal distribution with mean one and the
+    /// given coefficient of variation.
+    Gaussian(Scalar),
+}
+
+impl Noise {
+    /// Draws the factor by which a cost is multiplied.
+    fn factor<R>(&self, rng: &mut R) -> Scalar
+    where
+        R: Rng,
+    {
+        let factor = match self {
+            Self::Uniform(spread) => 1. + spread * (2. * rng.gen::<Scalar>() - 1.),
+            Self::Gaussian(cv) => {
+                // Box-Muller transform of two uniform samples, the first one in (0, 1].
+                let u = 1. - rng.gen::<Scalar>();
+                let v = rng.gen::<Scalar>();
+                let z = (-2. * u.ln()).sqrt() * (2. * std::f64::consts::PI * v).cos();
+                1. + cv * z
+            }
+        };
+        factor.max(0.)
+    }
+}
+
+/// Costs of a tour under perturbed edge costs, see [`evaluate`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Robustness {
+    /// Cost of the evaluated tour in each sample.
+    pub costs: Vec<Scalar>,
+    /// Cost of the tour re-optimised for each sample.
+    pub reoptimized: Vec<Scalar>,
+    /// Rank of the evaluated tour in each sample among itself and all re-optimised tours, where
+    /// rank 1 means that no other tour is shorter in the sample.
+    pub ranks: Vec<usize>,
+}
+
+impl Robustness {
+    /// Returns the number of samples.
+    pub fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    /// Returns `true` if no samples were drawn.
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty()
+    }
+
+    /// Returns the mean cost of the evaluated tour over all samples.
+    pub fn mean_cost(&self) -> Scalar {
+        mean_of(self.costs.iter().copied(), self.len())
+    }
+
+    /// Returns the standard deviation of the cost of the evaluated tour over all samples.
+    pub fn std_cost(&self) -> Scalar {
+        let mean = self.mean_cost();
+        mean_of(self.costs.iter().map(|x| (x - mean).powi(2)), self.len()).sqrt()
+    }
+
+    /// Returns the mean excess of the cost of the evaluated tour over the re-optimised tour.
+    pub fn mean_regret(&self) -> Scalar {
+        mean_of(
+            self.costs
+                .iter()
+                .zip(&self.reoptimized)
+                .map(|(cost, best)| cost - best),
+            self.len(),
+        )
+    }
+
+    /// Returns the mean rank of the evaluated tour.
+    pub fn mean_rank(&self) -> Scalar {
+        mean_of(self.ranks.iter().map(|x| *x as Scalar), self.len())
+    }
+
+    /// Returns the fraction of samples in which no other tour is shorter than the evaluated tour.
+    pub fn best_fraction(&self) -> Scalar {
+        mean_of(
+            self.ranks.iter().map(|x| if *x == 1 { 1. } else { 0. }),
+            self.len(),
+        )
+    }
+}
+
+/// Measures how a tour performs if the edge costs of a model deviate randomly from the store.
+///
+/// For each of `n_samples` samples, the costs of all edges are perturbed according to `noise`,
+/// and the tour is re-optimised for the perturbed costs with the improvement phase of the
+/// configured solver, like in [`scenario::compare`](crate::scenario::compare). The tour is then
+/// ranked in each sample against itself and the re-optimised tours of all samples. Samples are
+/// drawn from a generator seeded with `seed`, so that evaluations are reproducible.
+///
+/// Each sample perturbs all pairs of nodes, and ranking compares all tours in all samples, thus
+/// the evaluation takes O(S * N^2 + S^2 * N) time for S samples besides the re-optimisation.
+///
+/// Returns an error if the tour does not visit every node of the model exactly once.
+pub fn evaluate<M>(
+    model: &mut Model<M>,
+    tour: &TourOrder,
+    noise: Noise,
+    n_samples: usize,
+    config: &RunConfig,
+    seed: u64,
+) -> Result<Robustness, CyklError> {
+    model.complete();
+    validate_order(tour, model.n_nodes())?;
+
+    let mut reoptimized = Vec::with_capacity(n_samples);
+    let mut result = Robustness::default();
+    for sample in 0..n_samples {
+        let overlay = perturb(model, noise, seed, sample);
+        let order = model.reoptimize(config, overlay, tour)?;
+        result.reoptimized.push(order.cost());
+        reoptimized.push(order);
+    }
+
+    for sample in 0..n_samples {
+        let costs = Overlaid::new(model.store(), perturb(model, noise, seed, sample));
+        let cost = cycle_cost(&costs, tour.order());
+        let shorter = reoptimized
+            .iter()
+            .filter(|order| cycle_cost(&costs, order.order()) < cost)
+            .count();
+        result.costs.push(cost);
+        result.ranks.push(shorter + 1);
+    }
+
+    Ok(result)
+}
+
+/// Draws the perturbed costs of a sample.
+fn perturb<M>(model: &Model<M>, noise: Noise, seed: u64, sample: usize) -> CostOverlay {
+    let mut rng = StdRng::seed_from_u64(seed.wrapping_add(sample as u64));
+    let store = model.store();
+    let mut overlay = CostOverlay::new();
+    for a in 0..store.len() {
+        for b in a + 1..store.len() {
+            overlay.set(a, b, store.cost(&a, &b) * noise.factor(&mut rng));
+        }
+    }
+    overlay
+}
+
+fn mean_of<I>(values: I, len: usize) -> Scalar
+where
+    I: Iterator<Item = Scalar>,
+{
+    if len == 0 {
+        0.
+    } else {
+        values.sum::<Scalar>() / len as Scalar
+    }
+}
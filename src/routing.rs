@@ -0,0 +1,541 @@
+//! Cost matrices of routing engines, either imported from their table responses or queried on
+//! demand.
+//!
+//! [`TableService`] requests the costs between sources and targets, e.g. from the table API of
+//! OSRM with [`OsrmService`]. [`RemoteOracle`] fetches the rows of the matrix from a service as
+//! tours query them, in batches, and caches them, so that instances can be solved on road costs
+//! without fetching the whole matrix up front. Full table responses of OSRM and Valhalla are
+//! parsed into the matrix of a model with
+//! [`Model::costs_from_osrm_json`](crate::Model::costs_from_osrm_json).
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{Read, Write},
+    net::TcpStream,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Condvar, Mutex, MutexGuard, OnceLock,
+    },
+    time::Duration,
+};
+
+use serde_json::Value;
+
+use crate::{data::Unit, oracle::DistanceOracle, CyklError, Scalar};
+
+/// Annotation of a table response of a routing engine that is used as costs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TableAnnotation {
+    /// Travel times in seconds.
+    Duration,
+    /// Travel distances in meters.
+    Distance,
+}
+
+impl TableAnnotation {
+    /// Returns the unit of the costs parsed for this annotation.
+    pub fn unit(&self) -> Unit {
+        match self {
+            Self::Duration => Unit::Seconds,
+            Self::Distance => Unit::Meters,
+        }
+    }
+}
+
+/// Parses the cost matrix from the table response of OSRM (`durations`/`distances`) or Valhalla
+/// (`sources_to_targets`), and returns it in row-major order.
+///
+/// Valhalla reports distances in kilometres by default, which are converted into meters. Pairs
+/// of nodes without a route are reported as an error, since they would leave the tour infeasible.
+pub(crate) fn parse_table(
+    json: &[u8],
+    annotation: TableAnnotation,
+) -> Result<(usize, Vec<Scalar>), CyklError> {
+    let (n_rows, n_cols, costs) = parse_block(json, annotation)?;
+    if n_rows != n_cols {
+        return Err(CyklError::LenMismatched {
+            expected: n_rows,
+            received: n_cols,
+        });
+    }
+
+    Ok((n_rows, costs))
+}
+
+/// Parses a table response like [`parse_table`] for any number of sources and targets, and
+/// returns the number of rows and columns along with the costs.
+fn parse_block(
+    json: &[u8],
+    annotation: TableAnnotation,
+) -> Result<(usize, usize, Vec<Scalar>), CyklError> {
+    let root: Value =
+        serde_json::from_slice(json).map_err(|err| CyklError::Parse(format!("{:?}", err)))?;
+
+    let osrm_key = match annotation {
+        TableAnnotation::Duration => "durations",
+        TableAnnotation::Distance => "distances",
+    };
+
+    if let Some(rows) = root.get(osrm_key) {
+        matrix(rows, |entry| entry.as_f64())
+    } else if let Some(rows) = root.get("sources_to_targets") {
+        let (key, scale) = match annotation {
+            TableAnnotation::Duration => ("time", 1.),
+            TableAnnotation::Distance => ("distance", 1000.),
+        };
+        matrix(rows, |entry| {
+            entry.get(key).and_then(Value::as_f64).map(|x| x * scale)
+        })
+    } else {
+        Err(CyklError::Parse(format!(
+            "Neither {} nor sources_to_targets found",
+            osrm_key
+        )))
+    }
+}
+
+/// Collects a matrix from an array of rows of equal length, reading each entry with `f`.
+fn matrix<F>(rows: &Value, f: F) -> Result<(usize, usize, Vec<Scalar>), CyklError>
+where
+    F: Fn(&Value) -> Option<Scalar>,
+{
+    let rows = rows
+        .as_array()
+        .ok_or_else(|| CyklError::Parse("Table is not an array".to_string()))?;
+    let n_rows = rows.len();
+    let n_cols = match rows.first().and_then(Value::as_array) {
+        Some(row) => row.len(),
+        None => 0,
+    };
+
+    let mut costs = Vec::with_capacity(n_rows * n_cols);
+    for (ii, row) in rows.iter().enumerate() {
+        let row = row
+            .as_array()
+            .ok_or_else(|| CyklError::Parse(format!("Row {} is not an array", ii)))?;
+        if row.len() != n_cols {
+            return Err(CyklError::LenMismatched {
+                expected: n_cols,
+                received: row.len(),
+            });
+        }
+
+        for (jj, entry) in row.iter().enumerate() {
+            let cost = f(entry)
+                .ok_or_else(|| CyklError::Parse(format!("No route from {} to {}", ii, jj)))?;
+            costs.push(cost);
+        }
+    }
+
+    Ok((n_rows, n_cols, costs))
+}
+
+/// Service that answers blocks of the cost matrix, e.g. the table API of a routing engine.
+pub trait TableService {
+    /// Returns the costs from each of the sources to each of the targets in row-major order.
+    fn table(&self, sources: &[usize], targets: &[usize]) -> Result<Vec<Scalar>, CyklError>;
+}
+
+impl<F> TableService for F
+where
+    F: Fn(&[usize], &[usize]) -> Result<Vec<Scalar>, CyklError>,
+{
+    fn table(&self, sources: &[usize], targets: &[usize]) -> Result<Vec<Scalar>, CyklError> {
+        self(sources, targets)
+    }
+}
+
+/// Table service of an OSRM server, queried over plain HTTP.
+///
+/// Each request carries the coordinates of the sources and targets it asks for, thus the service
+/// suits instances of up to a few thousand nodes, depending on the limits of the server.
+#[derive(Clone, Debug)]
+pub struct OsrmService {
+    addr: String,
+    profile: String,
+    coords: Vec<(Scalar, Scalar)>,
+    annotation: TableAnnotation,
+    timeout: Option<Duration>,
+}
+
+impl OsrmService {
+    /// Creates a service on the server at `addr`, e.g. `localhost:5000`, for nodes at the given
+    /// longitudes and latitudes, which asks for durations with the `driving` profile.
+    pub fn new(addr: &str, coords: Vec<(Scalar, Scalar)>) -> Self {
+        Self {
+            addr: addr.to_string(),
+            profile: "driving".to_string(),
+            coords,
+            annotation: TableAnnotation::Duration,
+            timeout: None,
+        }
+    }
+
+    /// Sets the profile of the server used for routing. Defaults to `driving`.
+    pub fn with_profile(mut self, profile: &str) -> Self {
+        self.profile = profile.to_string();
+        self
+    }
+
+    /// Sets the annotation used as costs. Defaults to durations.
+    pub fn with_annotation(mut self, annotation: TableAnnotation) -> Self {
+        self.annotation = annotation;
+        self
+    }
+
+    /// Sets the timeout of reading from and writing to the server. Defaults to none.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        self.coords.len()
+    }
+
+    /// Returns `true` if there are no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.coords.is_empty()
+    }
+
+    /// Returns the path of the table request, in which each node appears once.
+    fn path(&self, sources: &[usize], targets: &[usize]) -> Result<String, CyklError> {
+        let mut slots = BTreeMap::new();
+        let mut nodes = Vec::new();
+        let mut slot = |index: usize| -> Result<usize, CyklError> {
+            if index >= self.coords.len() {
+                return Err(CyklError::IndexOutOfBounds {
+                    index,
+                    len: self.coords.len(),
+                });
+            }
+            Ok(*slots.entry(index).or_insert_with(|| {
+                nodes.push(index);
+                nodes.len() - 1
+            }))
+        };
+        let sources = sources
+            .iter()
+            .map(|&x| slot(x))
+            .collect::<Result<Vec<_>, _>>()?;
+        let targets = targets
+            .iter()
+            .map(|&x| slot(x))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let join = |slots: &[usize]| {
+            slots
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        let coords = nodes
+            .iter()
+            .map(|&index| format!("{},{}", self.coords[index].0, self.coords[index].1))
+            .collect::<Vec<_>>()
+            .join(";");
+        let annotation = match self.annotation {
+            TableAnnotation::Duration => "duration",
+            TableAnnotation::Distance => "distance",
+        };
+
+        Ok(format!(
+            "/table/v1/{}/{}?sources={}&destinations={}&annotations={}",
+            self.profile,
+            coords,
+            join(&sources),
+            join(&targets),
+            annotation
+        ))
+    }
+}
+
+impl TableService for OsrmService {
+    fn table(&self, sources: &[usize], targets: &[usize]) -> Result<Vec<Scalar>, CyklError> {
+        if sources.is_empty() || targets.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let path = self.path(sources, targets)?;
+        let body = http_get(&self.addr, &path, self.timeout)?;
+        let (n_rows, n_cols, costs) = parse_block(&body, self.annotation)?;
+        if n_rows != sources.len() || n_cols != targets.len() {
+            return Err(CyklError::LenMismatched {
+                expected: sources.len() * targets.len(),
+                received: n_rows * n_cols,
+            });
+        }
+
+        Ok(costs)
+    }
+}
+
+/// Sends a GET request over HTTP/1.0 and returns the body of a successful response.
+fn http_get(addr: &str, path: &str, timeout: Option<Duration>) -> Result<Vec<u8>, CyklError> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    write!(
+        stream,
+        "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\n\r\n",
+        path, addr
+    )?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| CyklError::Parse("Malformed HTTP response".to_string()))?;
+    let status = String::from_utf8_lossy(&response[..split])
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(CyklError::Parse(format!(
+            "Table request failed: {}",
+            status
+        )));
+    }
+
+    Ok(response.split_off(split + 4))
+}
+
+/// Oracle that fetches rows of the cost matrix from a [`TableService`] on demand and caches them.
+///
+/// A query for an uncached row fetches it together with the following uncached rows in a single
+/// request of up to [`batch_size`](Self::with_batch_size) sources. Rows in flight are marked, so
+/// that concurrent queries for them wait for that request instead of sending their own, while
+/// queries for cached rows proceed without waiting for any request.
+///
+/// Since [`DistanceOracle::cost`] cannot fail, a query whose row cannot be fetched returns
+/// infinity and keeps the error for [`take_error`](Self::take_error). Until the error is taken,
+/// queries for uncached rows return infinity without contacting the service. Tours
+/// [`prepare`](DistanceOracle::prepare) the oracle when it is attached, which fetches the first
+/// row to check that the service is reachable and fails the attachment on errors. Since later
+/// rows may still fail, callers check [`take_error`](Self::take_error) after solving, or fetch
+/// all rows when the oracle is prepared with [`with_prefetch`](Self::with_prefetch).
+pub struct RemoteOracle<S> {
+    service: S,
+    len: usize,
+    batch_size: usize,
+    prefetch: bool,
+    rows: Vec<OnceLock<Box<[Scalar]>>>,
+    // Rows that a request is fetching.
+    in_flight: Mutex<Vec<bool>>,
+    fetched: Condvar,
+    error: Mutex<Option<CyklError>>,
+    n_requests: AtomicUsize,
+}
+
+impl<S> fmt::Debug for RemoteOracle<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteOracle")
+            .field("len", &self.len)
+            .field("batch_size", &self.batch_size)
+            .field("n_requests", &self.n_requests.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl<S: TableService> RemoteOracle<S> {
+    /// Creates an oracle on `len` nodes that fetches rows of 16 sources at a time.
+    pub fn new(service: S, len: usize) -> Self {
+        Self {
+            service,
+            len,
+            batch_size: 16,
+            prefetch: false,
+            rows: (0..len).map(|_| OnceLock::new()).collect(),
+            in_flight: Mutex::new(vec![false; len]),
+            fetched: Condvar::new(),
+            error: Mutex::new(None),
+            n_requests: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sets the maximum number of sources per request. Defaults to 16.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets whether preparing the oracle fetches the whole matrix, which takes as many rows as
+    /// there are nodes, so that no query can fail afterwards. Defaults to `false`, in which case
+    /// only the first row is fetched.
+    pub fn with_prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Returns the service.
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+
+    /// Returns the number of requests sent to the service.
+    pub fn n_requests(&self) -> usize {
+        self.n_requests.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of cached rows.
+    pub fn n_cached(&self) -> usize {
+        self.rows.iter().filter(|row| row.get().is_some()).count()
+    }
+
+    /// Returns and clears the error of a query that could not fetch its row, after which queries
+    /// contact the service again.
+    pub fn take_error(&self) -> Option<CyklError> {
+        self.lock_error().take()
+    }
+
+    /// Fetches the rows of the given sources that are not cached yet.
+    pub fn prefetch(&self, sources: &[usize]) -> Result<(), CyklError> {
+        if let Some(&index) = sources.iter().find(|&&index| index >= self.len) {
+            return Err(CyklError::IndexOutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+
+        let mut missing: Vec<usize> = sources
+            .iter()
+            .copied()
+            .filter(|&index| self.rows[index].get().is_none())
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+
+        for chunk in missing.chunks(self.batch_size) {
+            let batch = self.claim(&mut self.lock_in_flight(), chunk.iter().copied());
+            self.fetch(&batch)?;
+        }
+
+        // Rows that concurrent queries were fetching are awaited, and fetched again if those
+        // queries failed.
+        for &index in missing.iter() {
+            self.fetch_row(index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetches every row that is not cached yet, i.e. the whole matrix.
+    pub fn prefetch_all(&self) -> Result<(), CyklError> {
+        self.prefetch(&(0..self.len).collect::<Vec<_>>())
+    }
+
+    /// Fetches the row of a source with the following uncached rows, unless another query does so,
+    /// in which case it waits for that query.
+    fn fetch_row(&self, index: usize) -> Result<(), CyklError> {
+        let mut in_flight = self.lock_in_flight();
+        while in_flight[index] {
+            in_flight = self
+                .fetched
+                .wait(in_flight)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+        if self.rows[index].get().is_some() {
+            return Ok(());
+        }
+
+        let batch = self.claim(&mut in_flight, (index..self.len).chain(0..index));
+        drop(in_flight);
+        self.fetch(&batch)
+    }
+
+    /// Marks up to a batch of the given rows that are neither cached nor in flight as in flight,
+    /// and returns them.
+    fn claim<I>(&self, in_flight: &mut [bool], candidates: I) -> Vec<usize>
+    where
+        I: Iterator<Item = usize>,
+    {
+        let batch: Vec<usize> = candidates
+            .filter(|&index| !in_flight[index] && self.rows[index].get().is_none())
+            .take(self.batch_size)
+            .collect();
+        for &index in batch.iter() {
+            in_flight[index] = true;
+        }
+        batch
+    }
+
+    /// Fetches the rows of claimed sources without holding a lock, then releases the claims.
+    fn fetch(&self, sources: &[usize]) -> Result<(), CyklError> {
+        if sources.is_empty() {
+            return Ok(());
+        }
+
+        let result = self.request(sources);
+        let mut in_flight = self.lock_in_flight();
+        for &source in sources {
+            in_flight[source] = false;
+        }
+        drop(in_flight);
+        self.fetched.notify_all();
+        result
+    }
+
+    fn request(&self, sources: &[usize]) -> Result<(), CyklError> {
+        let targets: Vec<usize> = (0..self.len).collect();
+        self.n_requests.fetch_add(1, Ordering::Relaxed);
+        let costs = self.service.table(sources, &targets)?;
+        if costs.len() != sources.len() * self.len {
+            return Err(CyklError::LenMismatched {
+                expected: sources.len() * self.len,
+                received: costs.len(),
+            });
+        }
+
+        for (&source, row) in sources.iter().zip(costs.chunks(self.len.max(1))) {
+            let _ = self.rows[source].set(row.into());
+        }
+
+        Ok(())
+    }
+
+    fn lock_in_flight(&self) -> MutexGuard<'_, Vec<bool>> {
+        self.in_flight.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn lock_error(&self) -> MutexGuard<'_, Option<CyklError>> {
+        self.error.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<S: TableService> DistanceOracle for RemoteOracle<S> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn cost(&self, a: usize, b: usize) -> Scalar {
+        assert!(a < self.len && b < self.len, "Node index out of bounds");
+        if let Some(row) = self.rows[a].get() {
+            return row[b];
+        }
+
+        let mut error = self.lock_error();
+        if error.is_none() {
+            drop(error);
+            match self.fetch_row(a) {
+                Ok(()) => return self.rows[a].get().map_or(Scalar::INFINITY, |row| row[b]),
+                Err(err) => {
+                    error = self.lock_error();
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        Scalar::INFINITY
+    }
+
+    fn prepare(&self) -> Result<(), CyklError> {
+        if self.prefetch {
+            self.prefetch_all()
+        } else {
+            self.prefetch(&[0][..self.len.min(1)])
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Comparison of tours under alternative edge costs, e.g. to analyse how a tour changes if a road
+//! doubles in cost.
+use crate::{overlay::CostOverlay, tour::TourOrder, viz, CyklError, Model, RunConfig, Scalar};
+
+/// Tour re-optimised under the costs of one scenario, see [`compare`].
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    tour: TourOrder,
+    baseline_cost: Scalar,
+    delta: Scalar,
+    changed_edges: usize,
+}
+
+impl Scenario {
+    /// Returns the re-optimised tour, whose cost is measured under the scenario.
+    pub fn tour(&self) -> &TourOrder {
+        &self.tour
+    }
+
+    /// Returns the cost of the re-optimised tour under the scenario.
+    pub fn cost(&self) -> Scalar {
+        self.tour.cost()
+    }
+
+    /// Returns the cost of the baseline tour under the scenario, i.e. if the tour is not adapted.
+    pub fn baseline_cost(&self) -> Scalar {
+        self.baseline_cost
+    }
+
+    /// Returns the difference between the cost of the re-optimised tour and the cost of the
+    /// baseline tour without the overlay.
+    pub fn delta(&self) -> Scalar {
+        self.delta
+    }
+
+    /// Returns the amount by which re-optimising reduces the cost of the baseline tour under the
+    /// scenario.
+    pub fn savings(&self) -> Scalar {
+        self.baseline_cost - self.cost()
+    }
+
+    /// Returns the number of edges of the re-optimised tour that are not part of the baseline
+    /// tour.
+    pub fn changed_edges(&self) -> usize {
+        self.changed_edges
+    }
+}
+
+/// Baseline tour and the tours re-optimised for each scenario, see [`compare`].
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    baseline: TourOrder,
+    scenarios: Vec<Scenario>,
+}
+
+impl Comparison {
+    /// Returns the tour solved without an overlay.
+    pub fn baseline(&self) -> &TourOrder {
+        &self.baseline
+    }
+
+    /// Returns the results of the scenarios in the order of their overlays.
+    pub fn scenarios(&self) -> &[Scenario] {
+        &self.scenarios
+    }
+}
+
+/// Solves a model, then re-optimises the tour under each overlay and compares the results.
+///
+/// Each scenario starts from the baseline tour and runs the improvement phase of the configured
+/// solver with the costs of its overlay, see [`CostOverlay`]. Solvers without an improvement phase
+/// descend with 2-opt moves instead. The store of the model is not modified.
+///
+/// Returns `None` if the model has no tour.
+pub fn compare<M>(
+    model: &mut Model<M>,
+    overlays: &[CostOverlay],
+    config: &RunConfig,
+) -> Result<Option<Comparison>, CyklError> {
+    let baseline = match model.solve(config)? {
+        Some(order) => order,
+        None => return Ok(None),
+    };
+    let baseline_edges = viz::edges(&baseline);
+
+    let mut scenarios = Vec::with_capacity(overlays.len());
+    for overlay in overlays {
+        let baseline_cost = path_cost(model, overlay, &baseline);
+        let tour = model.reoptimize(config, overlay.clone(), &baseline)?;
+
+        scenarios.push(Scenario {
+            delta: tour.cost() - baseline.cost(),
+            changed_edges: viz::edges(&tour).difference(&baseline_edges).count(),
+            baseline_cost,
+            tour,
+        });
+    }
+
+    Ok(Some(Comparison {
+        baseline,
+        scenarios,
+    }))
+}
+
+/// Returns the cost of a closed tour under an overlay.
+fn path_cost<M>(model: &Model<M>, overlay: &CostOverlay, order: &TourOrder) -> Scalar {
+    let nodes = order.order();
+    (0..nodes.len())
+        .map(|ii| overlay.cost(model.store(), nodes[ii], nodes[(ii + 1) % nodes.len()]))
+        .sum()
+}
@@ -0,0 +1,147 @@
+//! Asynchronous interface for embedding the solver into services.
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures::{channel::mpsc as stream, Stream};
+
+use crate::{alg::incumbent::Incumbent, tour::TourOrder, CyklError, Model, RunConfig};
+
+pub use crate::CancellationToken;
+
+/// Tour that is better than all tours previously reported by [`solve_async`].
+#[derive(Clone, Debug)]
+pub struct Improvement {
+    /// The improved tour.
+    pub order: TourOrder,
+    /// Time elapsed since the solver was started.
+    pub elapsed: Duration,
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Fixed pool of threads on which [`solve_async`] runs the solvers, so that blocking solver work
+/// stays off the executor of the caller and concurrent requests do not spawn a thread each.
+///
+/// The pool has as many threads as the machine has cores, and further jobs wait in a queue.
+struct BlockingPool {
+    sender: Mutex<mpsc::Sender<Job>>,
+}
+
+impl BlockingPool {
+    fn global() -> &'static BlockingPool {
+        static POOL: OnceLock<BlockingPool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            let n_threads = thread::available_parallelism().map_or(1, |n| n.get());
+            for ii in 0..n_threads {
+                let receiver = Arc::clone(&receiver);
+                thread::Builder::new()
+                    .name(format!("cykl-solver-{}", ii))
+                    .spawn(move || loop {
+                        // The lock is released before the job runs.
+                        let job = match receiver.lock() {
+                            Ok(receiver) => receiver.recv(),
+                            Err(_) => break,
+                        };
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("Failed to spawn a solver thread");
+            }
+
+            BlockingPool {
+                sender: Mutex::new(sender),
+            }
+        })
+    }
+
+    fn spawn(&self, job: Job) {
+        if let Ok(sender) = self.sender.lock() {
+            // The workers never drop the receiver, thus sending cannot fail.
+            let _ = sender.send(job);
+        }
+    }
+}
+
+/// Solves a model on a blocking thread pool and reports the improvements of the best tour as a
+/// stream.
+///
+/// Since models cannot be moved between threads, the model is created by `build` on the solver
+/// thread, like in [`solve_all`](crate::batch::solve_all). The solver reports the tour after its
+/// construction phase, and then each tour of its improvement phase that is better than all tours
+/// reported before with respect to the objective of the configuration, as soon as it is found.
+/// The stream ends once the solver has finished or was cancelled. If `build` or the solver fails,
+/// the error is the last item of the stream; a panic in `build` or the solver is reported as
+/// [`CyklError::Panicked`].
+///
+/// Cancellation is cooperative: the token replaces the token of the configuration, thus the solver
+/// checks it during candidate generation and in each iteration of the improvement phase. Once the
+/// token is cancelled, no further improvements are reported.
+pub fn solve_async<M, F>(
+    build: F,
+    mut config: RunConfig,
+    token: CancellationToken,
+) -> impl Stream<Item = Result<Improvement, CyklError>> + Unpin
+where
+    F: FnOnce() -> Result<Model<M>, CyklError> + Send + 'static,
+{
+    let (tx, rx) = stream::unbounded();
+    config.cancellation = token.clone();
+
+    BlockingPool::global().spawn(Box::new(move || {
+        if token.is_cancelled() {
+            return;
+        }
+
+        let start = Instant::now();
+        let incumbent = {
+            let (tx, token) = (tx.clone(), token.clone());
+            Arc::new(
+                Incumbent::with_objective(config.objective()).on_improvement(move |order| {
+                    if !token.is_cancelled() {
+                        // The caller may have dropped the stream, in which case nothing is left
+                        // to do.
+                        let _ = tx.unbounded_send(Ok(Improvement {
+                            order: order.clone(),
+                            elapsed: start.elapsed(),
+                        }));
+                    }
+                }),
+            )
+        };
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            build().and_then(|mut model| model.solve_with(&config, Some(&incumbent)))
+        }))
+        .unwrap_or_else(|panic| Err(CyklError::Panicked(panic_message(panic.as_ref()))));
+
+        match result {
+            // The final tour is only reported if the solver did not offer it already.
+            Ok(Some(order)) => {
+                incumbent.offer(&order);
+            }
+            Ok(None) | Err(CyklError::Cancelled) => {}
+            Err(err) => {
+                let _ = tx.unbounded_send(Err(err));
+            }
+        }
+    }));
+
+    rx
+}
+
+/// Returns the message of a panic payload, if it is a string.
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|msg| msg.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
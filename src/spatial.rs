@@ -0,0 +1,382 @@
+//! Spatial summaries and queries over the coordinates of nodes.
+use std::cmp::Ordering;
+
+use crate::{data::DataStore, Scalar};
+
+/// Axis-aligned box that encloses the coordinates of a set of nodes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    /// Smallest coordinates as `(x, y, z)`.
+    pub min: (Scalar, Scalar, Scalar),
+    /// Largest coordinates as `(x, y, z)`.
+    pub max: (Scalar, Scalar, Scalar),
+}
+
+impl BoundingBox {
+    /// Returns the extent of the box along each axis.
+    pub fn size(&self) -> (Scalar, Scalar, Scalar) {
+        (
+            self.max.0 - self.min.0,
+            self.max.1 - self.min.1,
+            self.max.2 - self.min.2,
+        )
+    }
+
+    /// Returns the centre of the box.
+    pub fn center(&self) -> (Scalar, Scalar, Scalar) {
+        (
+            (self.min.0 + self.max.0) / 2.,
+            (self.min.1 + self.max.1) / 2.,
+            (self.min.2 + self.max.2) / 2.,
+        )
+    }
+
+    /// Returns `true` if the point lies inside the box or on its border.
+    pub fn contains(&self, point: (Scalar, Scalar, Scalar)) -> bool {
+        (self.min.0..=self.max.0).contains(&point.0)
+            && (self.min.1..=self.max.1).contains(&point.1)
+            && (self.min.2..=self.max.2).contains(&point.2)
+    }
+}
+
+/// Returns the box that encloses the coordinates of all nodes of a store, or `None` if the nodes
+/// have no coordinates.
+pub fn bounding_box<M>(store: &DataStore<M>) -> Option<BoundingBox> {
+    let mut coords = store.iter_coords();
+    let first = coords.next()?;
+
+    Some(coords.fold(
+        BoundingBox {
+            min: first,
+            max: first,
+        },
+        |bbox, (x, y, z)| BoundingBox {
+            min: (bbox.min.0.min(x), bbox.min.1.min(y), bbox.min.2.min(z)),
+            max: (bbox.max.0.max(x), bbox.max.1.max(y), bbox.max.2.max(z)),
+        },
+    ))
+}
+
+/// Returns the mean of the coordinates of all nodes of a store, or `None` if the nodes have no
+/// coordinates.
+pub fn centroid<M>(store: &DataStore<M>) -> Option<(Scalar, Scalar, Scalar)> {
+    let (count, sum) = store
+        .iter_coords()
+        .fold((0usize, (0., 0., 0.)), |(count, sum), (x, y, z)| {
+            (count + 1, (sum.0 + x, sum.1 + y, sum.2 + z))
+        });
+
+    if count == 0 {
+        return None;
+    }
+
+    let n = count as Scalar;
+    Some((sum.0 / n, sum.1 / n, sum.2 / n))
+}
+
+/// k-d tree over the coordinates of the nodes of a store.
+///
+/// The tree is stored implicitly: the node at the middle of each range of `nodes` splits the range
+/// along the axis with the largest extent. Queries measure the Euclidean distance between the
+/// coordinates, regardless of the metric of the store.
+///
+/// Nodes inserted after the tree was built are kept in a list that queries scan, and removed nodes
+/// are skipped by queries, until the tree is rebuilt once either grows too large.
+#[derive(Clone, Debug, Default)]
+pub struct KdTree {
+    nodes: Vec<usize>,
+    axes: Vec<usize>,
+    coords: Vec<[Scalar; 3]>,
+    // Nodes inserted since the last build.
+    extra: Vec<usize>,
+    // Nodes of the implicit tree that were removed since the last build.
+    removed: Vec<bool>,
+    n_removed: usize,
+}
+
+impl KdTree {
+    /// Builds a tree over all nodes with coordinates in O(N log N) time.
+    pub fn new<M>(store: &DataStore<M>) -> Self {
+        let coords: Vec<[Scalar; 3]> = (0..store.len())
+            .map(|index| {
+                store
+                    .coords(index)
+                    .map_or([Scalar::NAN; 3], |(x, y, z)| [x, y, z])
+            })
+            .collect();
+
+        let mut nodes: Vec<usize> = (0..coords.len())
+            .filter(|index| !coords[*index][0].is_nan())
+            .collect();
+        let mut axes = vec![0; nodes.len()];
+        build(&coords, &mut nodes, &mut axes);
+
+        let removed = vec![false; coords.len()];
+        Self {
+            nodes,
+            axes,
+            coords,
+            extra: Vec::new(),
+            removed,
+            n_removed: 0,
+        }
+    }
+
+    /// Returns the number of nodes in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len() + self.extra.len() - self.n_removed
+    }
+
+    /// Returns `true` if the tree contains no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the tree contains the node at the given index.
+    pub fn contains(&self, index: usize) -> bool {
+        self.coords.get(index).is_some_and(|pos| !pos[0].is_nan()) && !self.removed[index]
+    }
+
+    /// Returns the coordinates of a node of the tree as `(x, y, z)`.
+    pub fn position(&self, index: usize) -> Option<(Scalar, Scalar, Scalar)> {
+        if self.contains(index) {
+            let pos = &self.coords[index];
+            Some((pos[0], pos[1], pos[2]))
+        } else {
+            None
+        }
+    }
+
+    /// Adds the node at the given index with its coordinates, or moves it if it is already in the
+    /// tree.
+    pub fn insert(&mut self, index: usize, point: (Scalar, Scalar, Scalar)) {
+        if index >= self.coords.len() {
+            self.coords.resize(index + 1, [Scalar::NAN; 3]);
+            self.removed.resize(index + 1, false);
+        }
+
+        let known = !self.coords[index][0].is_nan();
+        self.coords[index] = [point.0, point.1, point.2];
+        if known {
+            // The position of the node in the implicit tree depends on its coordinates.
+            if self.removed[index] {
+                self.removed[index] = false;
+                self.n_removed -= 1;
+            }
+            self.rebuild();
+        } else {
+            self.extra.push(index);
+            if self.extra.len() * self.extra.len() > self.nodes.len() {
+                self.rebuild();
+            }
+        }
+    }
+
+    /// Removes the node at the given index and returns `true` if it was in the tree.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if !self.contains(index) {
+            return false;
+        }
+
+        if let Some(pos) = self.extra.iter().position(|x| *x == index) {
+            self.extra.swap_remove(pos);
+            self.coords[index] = [Scalar::NAN; 3];
+        } else {
+            self.removed[index] = true;
+            self.n_removed += 1;
+            if 2 * self.n_removed > self.nodes.len() {
+                self.rebuild();
+            }
+        }
+        true
+    }
+
+    /// Returns the index of the node nearest to a point given as `(x, y, z)`, or `None` if the tree
+    /// is empty. Ties are broken by the lower index.
+    pub fn nearest(&self, point: (Scalar, Scalar, Scalar)) -> Option<usize> {
+        self.k_nearest(point, 1).first().copied()
+    }
+
+    /// Returns the indices of the `k` nodes nearest to a point given as `(x, y, z)`, ordered by
+    /// their distance to the point. Ties are broken by the lower index.
+    pub fn k_nearest(&self, point: (Scalar, Scalar, Scalar), k: usize) -> Vec<usize> {
+        self.k_nearest_where(point, k, |_| true)
+    }
+
+    /// Returns the indices of the `k` nodes nearest to a point given as `(x, y, z)` for which the
+    /// predicate holds, ordered by their distance to the point. Ties are broken by the lower index.
+    pub fn k_nearest_where<F>(
+        &self,
+        point: (Scalar, Scalar, Scalar),
+        k: usize,
+        pred: F,
+    ) -> Vec<usize>
+    where
+        F: Fn(usize) -> bool,
+    {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let point = [point.0, point.1, point.2];
+        let mut best = Vec::with_capacity(k + 1);
+        self.search(&point, 0, self.nodes.len(), k, &pred, &mut best);
+        for index in self.extra.iter().filter(|index| pred(**index)) {
+            offer(&mut best, k, (dist2(&self.coords[*index], &point), *index));
+        }
+        best.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Returns the indices of all nodes within a distance of `radius` from a point given as
+    /// `(x, y, z)`, in no particular order.
+    pub fn within(&self, point: (Scalar, Scalar, Scalar), radius: Scalar) -> Vec<usize> {
+        let point = [point.0, point.1, point.2];
+        let mut result = Vec::new();
+        self.range(&point, 0, self.nodes.len(), radius * radius, &mut result);
+        result.extend(
+            self.extra
+                .iter()
+                .filter(|index| dist2(&self.coords[**index], &point) <= radius * radius),
+        );
+        result
+    }
+
+    /// Builds the implicit tree again over all nodes.
+    fn rebuild(&mut self) {
+        for index in 0..self.coords.len() {
+            if self.removed[index] {
+                self.coords[index] = [Scalar::NAN; 3];
+                self.removed[index] = false;
+            }
+        }
+
+        self.nodes = (0..self.coords.len())
+            .filter(|index| !self.coords[*index][0].is_nan())
+            .collect();
+        self.axes = vec![0; self.nodes.len()];
+        self.extra.clear();
+        self.n_removed = 0;
+        build(&self.coords, &mut self.nodes, &mut self.axes);
+    }
+
+    fn search<F>(
+        &self,
+        point: &[Scalar; 3],
+        lo: usize,
+        hi: usize,
+        k: usize,
+        pred: &F,
+        best: &mut Vec<(Scalar, usize)>,
+    ) where
+        F: Fn(usize) -> bool,
+    {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        let index = self.nodes[mid];
+        let pos = &self.coords[index];
+        if !self.removed[index] && pred(index) {
+            offer(best, k, (dist2(pos, point), index));
+        }
+
+        let axis = self.axes[mid];
+        let delta = point[axis] - pos[axis];
+        let (near, far) = if delta < 0. {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+
+        self.search(point, near.0, near.1, k, pred, best);
+        let pruned = best.len() == k && matches!(best.last(), Some((d, _)) if delta * delta > *d);
+        if !pruned {
+            self.search(point, far.0, far.1, k, pred, best);
+        }
+    }
+
+    fn range(&self, point: &[Scalar; 3], lo: usize, hi: usize, r2: Scalar, out: &mut Vec<usize>) {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        let index = self.nodes[mid];
+        let pos = &self.coords[index];
+        if !self.removed[index] && dist2(pos, point) <= r2 {
+            out.push(index);
+        }
+
+        let axis = self.axes[mid];
+        let delta = point[axis] - pos[axis];
+        if delta < 0. || delta * delta <= r2 {
+            self.range(point, lo, mid, r2, out);
+        }
+        if delta >= 0. || delta * delta <= r2 {
+            self.range(point, mid + 1, hi, r2, out);
+        }
+    }
+}
+
+/// Returns the squared Euclidean distance between two points.
+#[inline]
+fn dist2(a: &[Scalar; 3], b: &[Scalar; 3]) -> Scalar {
+    (0..3).map(|ii| (a[ii] - b[ii]).powi(2)).sum()
+}
+
+/// Inserts a node into the list of the `k` nearest nodes found so far, which is ordered by
+/// distance and index.
+#[inline]
+fn offer(best: &mut Vec<(Scalar, usize)>, k: usize, item: (Scalar, usize)) {
+    if best.len() == k && !before(&item, &best[k - 1]) {
+        return;
+    }
+
+    let pos = best
+        .iter()
+        .position(|x| before(&item, x))
+        .unwrap_or(best.len());
+    best.insert(pos, item);
+    best.truncate(k);
+}
+
+#[inline]
+fn before(a: &(Scalar, usize), b: &(Scalar, usize)) -> bool {
+    a.0 < b.0 || (a.0 == b.0 && a.1 < b.1)
+}
+
+/// Arranges the nodes into an implicit tree and records the split axis of each position.
+fn build(coords: &[[Scalar; 3]], nodes: &mut [usize], axes: &mut [usize]) {
+    if nodes.len() <= 1 {
+        return;
+    }
+
+    let (mut min, mut max) = ([Scalar::MAX; 3], [Scalar::MIN; 3]);
+    for pos in nodes.iter().map(|n| &coords[*n]) {
+        for (dim, x) in pos.iter().enumerate() {
+            min[dim] = min[dim].min(*x);
+            max[dim] = max[dim].max(*x);
+        }
+    }
+
+    let mut axis = 0;
+    for dim in 1..3 {
+        if max[dim] - min[dim] > max[axis] - min[axis] {
+            axis = dim;
+        }
+    }
+
+    let mid = nodes.len() / 2;
+    nodes.select_nth_unstable_by(mid, |a, b| {
+        coords[*a][axis]
+            .partial_cmp(&coords[*b][axis])
+            .unwrap_or(Ordering::Equal)
+    });
+    axes[mid] = axis;
+
+    let (left, right) = nodes.split_at_mut(mid);
+    let (left_axes, right_axes) = axes.split_at_mut(mid);
+    build(coords, left, left_axes);
+    build(coords, &mut right[1..], &mut right_axes[1..]);
+}
@@ -0,0 +1,176 @@
+//! Splitting of a giant tour into vehicle trips.
+use crate::{data::DataStore, tour::TourOrder, CyklError, Scalar};
+
+/// Limits of a single trip, see [`split`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TripLimits {
+    capacity: Option<Scalar>,
+    max_length: Option<Scalar>,
+    max_duration: Option<Scalar>,
+}
+
+impl TripLimits {
+    /// Creates limits that allow any trip.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the total demand of the nodes visited by a trip.
+    pub fn capacity(mut self, capacity: Scalar) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Limits the length of a trip, including the legs from and to the depot.
+    pub fn max_length(mut self, max_length: Scalar) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Limits the duration of a trip, which is its length plus the service times of the nodes
+    /// it visits, see [`DataStore::set_service_time`].
+    pub fn max_duration(mut self, max_duration: Scalar) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+}
+
+/// Trips computed by [`split`].
+#[derive(Clone, Debug)]
+pub struct Trips {
+    trips: Vec<TourOrder>,
+    cost: Scalar,
+}
+
+impl Trips {
+    /// Returns the trips. Each trip starts at the depot, and its cost includes the return to the
+    /// depot.
+    pub fn trips(&self) -> &[TourOrder] {
+        &self.trips
+    }
+
+    /// Returns the total length of all trips.
+    pub fn cost(&self) -> Scalar {
+        self.cost
+    }
+
+    /// Consumes the result and returns the trips.
+    pub fn into_trips(self) -> Vec<TourOrder> {
+        self.trips
+    }
+}
+
+/// Partitions a giant tour into trips from and to a depot with Prins' Split algorithm.
+///
+/// Each trip serves a run of consecutive nodes of the giant tour, starting where the giant tour
+/// is cut after the depot, which may or may not be part of the order. Among all such partitions
+/// whose trips respect the limits, the one with the shortest total length is found as a shortest
+/// path in the auxiliary acyclic graph whose arcs are the feasible trips. `demands` holds the
+/// demand of each node of the store. The algorithm takes O(N * B) time, where B is the largest
+/// number of nodes per trip.
+///
+/// Returns [`CyklError::Infeasible`] if a single node cannot be served within the limits.
+///
+/// See C. Prins (2004), "A simple and effective evolutionary algorithm for the vehicle routing
+/// problem".
+pub fn split<M>(
+    store: &DataStore<M>,
+    order: &TourOrder,
+    depot: usize,
+    demands: &[Scalar],
+    limits: TripLimits,
+) -> Result<Trips, CyklError> {
+    let len = store.len();
+    if demands.len() != len {
+        return Err(CyklError::LenMismatched {
+            expected: len,
+            received: demands.len(),
+        });
+    }
+    if let Some(index) = order
+        .order()
+        .iter()
+        .chain(std::iter::once(&depot))
+        .find(|index| **index >= len)
+    {
+        return Err(CyklError::IndexOutOfBounds { index: *index, len });
+    }
+
+    // Nodes in the order of the giant tour, starting after the depot.
+    let start = order
+        .order()
+        .iter()
+        .position(|x| *x == depot)
+        .map_or(0, |pos| pos + 1);
+    let nodes: Vec<usize> = order.order()[start..]
+        .iter()
+        .chain(order.order()[..start].iter())
+        .copied()
+        .filter(|x| *x != depot)
+        .collect();
+
+    let capacity = limits.capacity.unwrap_or(Scalar::INFINITY);
+    let max_length = limits.max_length.unwrap_or(Scalar::INFINITY);
+    let max_duration = limits.max_duration.unwrap_or(Scalar::INFINITY);
+
+    // Cost of the best partition of the first `ii` nodes, and the start of its last trip.
+    let n = nodes.len();
+    let mut best = vec![Scalar::INFINITY; n + 1];
+    let mut pred = vec![0; n + 1];
+    best[0] = 0.;
+
+    for ii in 0..n {
+        if best[ii] == Scalar::INFINITY {
+            continue;
+        }
+
+        let (mut load, mut path, mut service) = (0., 0., 0.);
+        for jj in ii..n {
+            load += demands[nodes[jj]];
+            service += store.service_time(nodes[jj]);
+            path += if jj == ii {
+                store.cost(&depot, &nodes[jj])
+            } else {
+                store.cost(&nodes[jj - 1], &nodes[jj])
+            };
+
+            // Load, outbound path and service only grow with further nodes.
+            if load > capacity || path > max_length || path + service > max_duration {
+                break;
+            }
+
+            let trip = path + store.cost(&nodes[jj], &depot);
+            if trip <= max_length
+                && trip + service <= max_duration
+                && best[ii] + trip < best[jj + 1]
+            {
+                best[jj + 1] = best[ii] + trip;
+                pred[jj + 1] = ii;
+            }
+        }
+    }
+
+    if best[n] == Scalar::INFINITY {
+        let index = (0..n)
+            .find(|ii| best[*ii + 1] == Scalar::INFINITY)
+            .map_or(depot, |ii| nodes[ii]);
+        return Err(CyklError::Infeasible(index));
+    }
+
+    let mut trips = Vec::new();
+    let mut end = n;
+    while end > 0 {
+        let begin = pred[end];
+        let mut trip = Vec::with_capacity(end - begin + 1);
+        trip.push(depot);
+        trip.extend_from_slice(&nodes[begin..end]);
+        trips.push(TourOrder::with_cost(trip, best[end] - best[begin]));
+        end = begin;
+    }
+    trips.reverse();
+
+    Ok(Trips {
+        trips,
+        cost: best[n],
+    })
+}
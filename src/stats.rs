@@ -0,0 +1,553 @@
+//! Statistics over tours and collections of tours.
+use std::{collections::HashMap, fmt};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    data::{DataStore, Metric, Unit},
+    oracle::cycle_cost,
+    tour::{Tour, TourNode, TourOrder},
+    viz, Scalar,
+};
+
+/// Number of subgradient steps used to approximate the Held-Karp bound.
+const HK_ITERATIONS: usize = 100;
+
+/// Diversity of a population of tours.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Diversity {
+    /// Average fraction of edges of one tour that are missing in another tour, over all pairs of
+    /// tours. Ranges from 0 if all tours share the same edges to 1 if no two tours share an edge.
+    pub edge_distance: Scalar,
+    /// Shannon entropy of the frequencies of edges in the population, normalised to range from 0
+    /// if all tours share the same edges to 1 if no two tours share an edge.
+    pub entropy: Scalar,
+}
+
+/// Measures how much the tours of a population differ from each other.
+///
+/// Tours are compared by their undirected edges, so that rotated and reflected orders are
+/// considered equal. Populations with fewer than two non-empty tours have a diversity of zero.
+pub fn diversity(population: &[TourOrder]) -> Diversity {
+    let edges: Vec<_> = population
+        .iter()
+        .map(viz::edges)
+        .filter(|edges| !edges.is_empty())
+        .collect();
+
+    let n_tours = edges.len();
+    if n_tours < 2 {
+        return Diversity::default();
+    }
+
+    let mut distance = 0.;
+    for (ii, a) in edges.iter().enumerate() {
+        for b in edges.iter().skip(ii + 1) {
+            let shared = a.intersection(b).count();
+            distance += 1. - shared as Scalar / a.len().max(b.len()) as Scalar;
+        }
+    }
+    let n_pairs = (n_tours * (n_tours - 1) / 2) as Scalar;
+
+    let mut frequencies = HashMap::new();
+    for edge in edges.iter().flatten() {
+        *frequencies.entry(*edge).or_insert(0_usize) += 1;
+    }
+
+    let total = frequencies.values().sum::<usize>() as Scalar;
+    let entropy: Scalar = frequencies
+        .values()
+        .map(|count| {
+            let p = *count as Scalar / total;
+            -p * p.ln()
+        })
+        .sum();
+
+    // A population of identical tours has the entropy ln(n_edges), a population of disjoint tours
+    // has the entropy ln(n_edges * n_tours).
+    let avg_edges = total / n_tours as Scalar;
+    let entropy = ((entropy - avg_edges.ln()) / (n_tours as Scalar).ln()).clamp(0., 1.);
+
+    Diversity {
+        edge_distance: distance / n_pairs,
+        entropy,
+    }
+}
+
+/// Measures how much of a previous tour is preserved in a new tour, e.g. between the solutions of
+/// consecutive days.
+///
+/// Returns the fraction of the undirected edges of `old`, i.e. of its pairs of adjacent nodes,
+/// that are also part of `new`. Ranges from 0 if no edge is preserved to 1 if all edges are
+/// preserved. A previous tour with fewer than two nodes has no edges and is always preserved.
+pub fn stability(old: &TourOrder, new: &TourOrder) -> Scalar {
+    let old = viz::edges(old);
+    if old.is_empty() {
+        return 1.;
+    }
+
+    let new = viz::edges(new);
+    old.intersection(&new).count() as Scalar / old.len() as Scalar
+}
+
+/// Distribution of the edge lengths of a tour over buckets of equal width.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EdgeHistogram {
+    /// Length of the shortest edge, which is the lower end of the first bucket.
+    pub min: Scalar,
+    /// Length of the longest edge, which is the upper end of the last bucket.
+    pub max: Scalar,
+    /// Number of edges in each bucket.
+    pub counts: Vec<usize>,
+}
+
+impl EdgeHistogram {
+    /// Returns the width of a bucket.
+    pub fn bucket_width(&self) -> Scalar {
+        if self.counts.is_empty() {
+            0.
+        } else {
+            (self.max - self.min) / self.counts.len() as Scalar
+        }
+    }
+}
+
+/// Counts the edges of a tour by their lengths in `n_buckets` buckets of equal width.
+///
+/// The buckets range from the shortest to the longest edge. Tours with fewer than two nodes have
+/// no edges, in which case all counts are zero.
+pub fn edge_histogram<T>(tour: &T, n_buckets: usize) -> EdgeHistogram
+where
+    T: Tour,
+{
+    let lengths: Vec<Scalar> = tour.edges().map(|(_, _, length)| length).collect();
+    let mut result = EdgeHistogram {
+        counts: vec![0; n_buckets],
+        ..EdgeHistogram::default()
+    };
+
+    if lengths.is_empty() || n_buckets == 0 {
+        return result;
+    }
+
+    result.min = lengths.iter().copied().fold(Scalar::MAX, Scalar::min);
+    result.max = lengths.iter().copied().fold(Scalar::MIN, Scalar::max);
+
+    let width = result.bucket_width();
+    for length in lengths {
+        let idx = if width > 0. {
+            (((length - result.min) / width) as usize).min(n_buckets - 1)
+        } else {
+            0
+        };
+        result.counts[idx] += 1;
+    }
+
+    result
+}
+
+/// Measures how closely two nodes of a tour are related, as used by Shaw's removal heuristic.
+///
+/// The measure combines the distance between the nodes, relative to the average edge of the tour,
+/// with the number of edges between them along the tour, relative to half the tour. It ranges from
+/// 1 for a node and itself towards 0 for nodes that are far apart in both respects. Returns 0 if
+/// one of the nodes is not part of the tour.
+pub fn relatedness<T>(tour: &T, a: usize, b: usize) -> Scalar
+where
+    T: Tour,
+{
+    let n = tour.n_included();
+    if !tour.is_included(a) || !tour.is_included(b) {
+        return 0.;
+    }
+
+    match (
+        tour.order_index(a),
+        tour.order_index(b),
+        tour.distance_at(&a, &b),
+    ) {
+        (Some(pos_a), Some(pos_b), Ok(distance)) => relatedness_of(
+            n,
+            pos_a.max(pos_b) - pos_a.min(pos_b),
+            distance,
+            tour.total_distance() / n as Scalar,
+        ),
+        _ => 0.,
+    }
+}
+
+/// Returns the relatedness of two nodes that are `hops` positions apart in a tour of `n` nodes,
+/// from their distance and the mean edge of the tour, see [`relatedness`].
+pub(crate) fn relatedness_of(n: usize, hops: usize, distance: Scalar, mean_edge: Scalar) -> Scalar {
+    let hops = hops.min(n - hops) as Scalar / (n as Scalar / 2.).max(1.);
+    let distance = if mean_edge > 0. {
+        distance / mean_edge
+    } else {
+        0.
+    };
+
+    1. / (1. + distance + hops)
+}
+
+/// Distribution of the lengths of random tours, see [`sample_random_tours`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TourSample {
+    /// Number of nodes visited by each tour.
+    pub n_nodes: usize,
+    /// Lengths of the sampled tours in ascending order.
+    pub lengths: Vec<Scalar>,
+    /// Mean length.
+    pub mean: Scalar,
+    /// Standard deviation of the lengths.
+    pub std: Scalar,
+}
+
+impl TourSample {
+    /// Returns the `q`-quantile of the lengths for `q` in `[0, 1]`, interpolating linearly between
+    /// neighbouring samples, or zero if there are no samples.
+    pub fn quantile(&self, q: Scalar) -> Scalar {
+        match self.lengths.len() {
+            0 => 0.,
+            len => {
+                let pos = q.clamp(0., 1.) * (len - 1) as Scalar;
+                let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+                let frac = pos - lo as Scalar;
+                self.lengths[lo] + frac * (self.lengths[hi] - self.lengths[lo])
+            }
+        }
+    }
+
+    /// Returns the mean length of an edge of a random tour.
+    pub fn mean_edge(&self) -> Scalar {
+        if self.n_nodes < 2 {
+            0.
+        } else {
+            self.mean / self.n_nodes as Scalar
+        }
+    }
+
+    /// Returns the temperature at which simulated annealing accepts a move that lengthens the
+    /// tour by the mean edge length of a random tour with probability `acceptance` in `(0, 1)`.
+    ///
+    /// The temperature thus scales with the costs of an instance, see
+    /// [`SimulatedAnnealing::new`](crate::alg::acceptance::SimulatedAnnealing::new).
+    pub fn temperature(&self, acceptance: Scalar) -> Scalar {
+        -self.mean_edge() / acceptance.ln()
+    }
+}
+
+/// Measures the lengths of `n` tours that visit all nodes of a store in a uniformly random order.
+///
+/// The lengths of random tours are a baseline against which the improvement of a heuristic can be
+/// judged, e.g. by the ratio of a tour length to the mean, and they calibrate parameters that
+/// depend on the scale of the costs.
+pub fn sample_random_tours<M, R>(store: &DataStore<M>, n: usize, rng: &mut R) -> TourSample
+where
+    R: Rng,
+{
+    let mut order: Vec<usize> = (0..store.len()).collect();
+    let mut lengths: Vec<Scalar> = (0..n)
+        .map(|_| {
+            order.shuffle(rng);
+            cycle_cost(store, &order)
+        })
+        .collect();
+    lengths.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    if lengths.is_empty() {
+        return TourSample {
+            n_nodes: order.len(),
+            ..TourSample::default()
+        };
+    }
+
+    let mean = lengths.iter().sum::<Scalar>() / n as Scalar;
+    let var = lengths.iter().map(|x| (x - mean).powi(2)).sum::<Scalar>() / n as Scalar;
+    TourSample {
+        n_nodes: order.len(),
+        lengths,
+        mean,
+        std: var.sqrt(),
+    }
+}
+
+/// Diagnostics that explain the quality of a tour.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QualityReport {
+    /// Number of nodes visited by the tour.
+    pub n_nodes: usize,
+    /// Total length of the tour.
+    pub length: Scalar,
+    /// Average length of an edge.
+    pub mean_edge: Scalar,
+    /// Length of the longest edge.
+    pub max_edge: Scalar,
+    /// Number of pairs of edges that cross each other. Only available for planar Euclidean
+    /// metrics, where an optimal tour has no crossings.
+    pub crossings: Option<usize>,
+    /// Weight of a minimum spanning tree over the visited nodes, which is a lower bound of the
+    /// optimal tour length.
+    pub mst_weight: Scalar,
+    /// Approximation of the Held-Karp bound, which is a tighter lower bound of the optimal tour
+    /// length.
+    pub held_karp_bound: Scalar,
+    /// Unit of all lengths, before they were scaled by the store.
+    pub unit: Unit,
+    /// Factor by which the store multiplied its costs, see
+    /// [`DataStore::scale`](crate::data::DataStore::scale). The lengths are only given in `unit`
+    /// if the factor is 1.
+    pub scale: Scalar,
+}
+
+impl QualityReport {
+    /// Returns the relative excess of the tour length over the Held-Karp bound, which is an upper
+    /// bound of the gap to the optimal tour.
+    pub fn gap(&self) -> Scalar {
+        if self.held_karp_bound > 0. {
+            self.length / self.held_karp_bound - 1.
+        } else {
+            0.
+        }
+    }
+}
+
+impl fmt::Display for QualityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "nodes:      {}", self.n_nodes)?;
+        if self.scale == 1. {
+            writeln!(f, "length:     {}{}", self.length, self.unit)?;
+        } else if self.unit == Unit::Unitless {
+            writeln!(f, "length:     {} (scaled by {})", self.length, self.scale)?;
+        } else {
+            writeln!(
+                f,
+                "length:     {} ({} scaled by {})",
+                self.length, self.unit, self.scale
+            )?;
+        }
+        writeln!(f, "mean edge:  {}", self.mean_edge)?;
+        writeln!(f, "max edge:   {}", self.max_edge)?;
+        if let Some(crossings) = self.crossings {
+            writeln!(f, "crossings:  {}", crossings)?;
+        }
+        writeln!(f, "mst weight: {}", self.mst_weight)?;
+        write!(
+            f,
+            "hk bound:   {} (gap {:.2}%)",
+            self.held_karp_bound,
+            100. * self.gap()
+        )
+    }
+}
+
+/// Summarises the edges of a tour and compares its length to lower bounds of the optimal tour.
+///
+/// The crossings are counted by comparing all pairs of edges, and the bounds are computed over all
+/// pairs of visited nodes. The report thus takes O(N^2) time per subgradient step, and is intended
+/// for diagnosing instances of moderate size.
+pub fn quality_report<T, M>(tour: &T, store: &DataStore<M>) -> QualityReport
+where
+    T: Tour,
+{
+    let order = tour.tour_order();
+    let nodes = order.order();
+    let (edges, lengths): (Vec<_>, Vec<_>) = tour.edges().map(|(a, b, d)| ((a, b), d)).unzip();
+
+    let length: Scalar = lengths.iter().sum();
+    let crossings = match store.metric() {
+        Metric::Euc2d | Metric::Ceil2d => Some(count_crossings(store, &edges)),
+        _ => None,
+    };
+
+    QualityReport {
+        n_nodes: nodes.len(),
+        length,
+        mean_edge: if lengths.is_empty() {
+            0.
+        } else {
+            length / lengths.len() as Scalar
+        },
+        max_edge: lengths.iter().copied().fold(0., Scalar::max),
+        crossings,
+        mst_weight: spanning_tree(tour, nodes, &vec![0.; nodes.len()]).0,
+        held_karp_bound: held_karp(tour, nodes, length),
+        unit: store.unit(),
+        scale: store.scale(),
+    }
+}
+
+/// Approximates the Held-Karp bound of the nodes visited by a tour, which is a lower bound of the
+/// length of any tour over these nodes.
+///
+/// The bound is computed with subgradient optimisation on minimum 1-trees, as in
+/// [`quality_report`], which takes O(N^2) time per step.
+pub fn held_karp_bound<T>(tour: &T) -> Scalar
+where
+    T: Tour,
+{
+    let order = tour.tour_order();
+    held_karp(tour, order.order(), order.cost())
+}
+
+fn count_crossings<M>(store: &DataStore<M>, edges: &[(usize, usize)]) -> usize {
+    let pos = |index: usize| match store.coord(index) {
+        Some(pos) if pos.len() >= 2 => (pos[0], pos[1]),
+        _ => (0., 0.),
+    };
+
+    let mut result = 0;
+    for (ii, &(a, b)) in edges.iter().enumerate() {
+        for &(c, d) in edges.iter().skip(ii + 1) {
+            // Edges sharing a node meet but do not cross.
+            if a == c || a == d || b == c || b == d {
+                continue;
+            }
+
+            if intersect(pos(a), pos(b), pos(c), pos(d)) {
+                result += 1;
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns `true` if the segments [a, b] and [c, d] properly intersect.
+fn intersect(
+    a: (Scalar, Scalar),
+    b: (Scalar, Scalar),
+    c: (Scalar, Scalar),
+    d: (Scalar, Scalar),
+) -> bool {
+    let orient = |p: (Scalar, Scalar), q: (Scalar, Scalar), r: (Scalar, Scalar)| {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    };
+
+    let (o1, o2) = (orient(a, b, c), orient(a, b, d));
+    let (o3, o4) = (orient(c, d, a), orient(c, d, b));
+    o1 * o2 < 0. && o3 * o4 < 0.
+}
+
+/// Computes a minimum spanning tree over the nodes with the costs `d(a, b) + pi[a] + pi[b]`, and
+/// returns its weight together with the degree of each node.
+fn spanning_tree<T>(tour: &T, nodes: &[usize], pi: &[Scalar]) -> (Scalar, Vec<usize>)
+where
+    T: Tour,
+{
+    let len = nodes.len();
+    let mut degrees = vec![0; len];
+    if len < 2 {
+        return (0., degrees);
+    }
+
+    // The nodes are taken from the order of the tour, thus all of them have a vertex.
+    let vertices: Vec<TourNode> = nodes.iter().filter_map(|x| tour.get(*x)).collect();
+    let cost = |ii: usize, jj: usize| tour.distance(&vertices[ii], &vertices[jj]) + pi[ii] + pi[jj];
+
+    let mut in_tree = vec![false; len];
+    let mut dist: Vec<Scalar> = (0..len).map(|ii| cost(0, ii)).collect();
+    let mut parent = vec![0; len];
+    let mut weight = 0.;
+    in_tree[0] = true;
+
+    for _ in 1..len {
+        let mut next = 0;
+        let mut d = Scalar::MAX;
+        for ii in 0..len {
+            if !in_tree[ii] && dist[ii] < d {
+                d = dist[ii];
+                next = ii;
+            }
+        }
+
+        in_tree[next] = true;
+        weight += d;
+        degrees[next] += 1;
+        degrees[parent[next]] += 1;
+
+        for ii in 0..len {
+            if !in_tree[ii] {
+                let d = cost(next, ii);
+                if d < dist[ii] {
+                    dist[ii] = d;
+                    parent[ii] = next;
+                }
+            }
+        }
+    }
+
+    (weight, degrees)
+}
+
+/// Approximates the Held-Karp bound with a subgradient ascent over minimum 1-trees, which consist
+/// of a spanning tree over all nodes except the first one and the two cheapest edges of the first
+/// node.
+fn held_karp<T>(tour: &T, nodes: &[usize], upper: Scalar) -> Scalar
+where
+    T: Tour,
+{
+    let len = nodes.len();
+    if len < 3 {
+        return upper;
+    }
+
+    let vertices: Vec<TourNode> = nodes.iter().filter_map(|x| tour.get(*x)).collect();
+    let mut pi = vec![0.; len];
+    let mut best = Scalar::MIN;
+    let mut step_size = 2.;
+    let mut stagnation = 0;
+
+    for _ in 0..HK_ITERATIONS {
+        let (tree, tree_degrees) = spanning_tree(tour, &nodes[1..], &pi[1..]);
+
+        let mut degrees = vec![0; len];
+        degrees[1..].copy_from_slice(&tree_degrees);
+
+        // The two cheapest edges of the first node close the 1-tree.
+        let mut closing: Vec<(Scalar, usize)> = (1..len)
+            .map(|ii| {
+                (
+                    tour.distance(&vertices[0], &vertices[ii]) + pi[0] + pi[ii],
+                    ii,
+                )
+            })
+            .collect();
+        closing.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        for &(_, ii) in closing.iter().take(2) {
+            degrees[ii] += 1;
+        }
+        degrees[0] = 2;
+
+        let weight = tree + closing[0].0 + closing[1].0 - 2. * pi.iter().sum::<Scalar>();
+        if weight > best {
+            best = weight;
+            stagnation = 0;
+        } else {
+            stagnation += 1;
+            if stagnation >= 10 {
+                step_size /= 2.;
+                stagnation = 0;
+            }
+        }
+
+        let norm: usize = degrees
+            .iter()
+            .map(|x| (*x as isize - 2).pow(2) as usize)
+            .sum();
+        if norm == 0 {
+            // The 1-tree is a tour, hence the bound is optimal.
+            break;
+        }
+
+        let t = step_size * (upper - weight).max(0.) / norm as Scalar;
+        if t <= 0. {
+            break;
+        }
+
+        for (p, degree) in pi.iter_mut().zip(degrees.iter()) {
+            *p += t * (*degree as Scalar - 2.);
+        }
+    }
+
+    best.min(upper)
+}
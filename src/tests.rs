@@ -1,7 +1,29 @@
 #![cfg(test)]
 use float_cmp::approx_eq;
 
-use crate::data::{DataStore, Metric, NodeKind};
+use crate::{
+    alg::{
+        acceptance::Greedy,
+        cand_gen_nn,
+        incumbent::Incumbent,
+        lns::Lns,
+        objective::{Penalized, Penalty, Stability, TourObjective, TurnPenalty},
+        solvers::{solve_greedy, SearchState},
+        CandidateSet, Objective, SolverKind, Termination,
+    },
+    cluster,
+    data::{
+        DataStore, DuplicatePolicy, GetIndex, MemoryBudget, Metric, NodeIndex, NodeKind,
+        Projection, Unit,
+    },
+    interop::lkh::{self, LkhConfig},
+    overlay::CostOverlay,
+    scenario,
+    split::{self, TripLimits},
+    stats,
+    tour::{Tour, TourOrder, TwoLevelList},
+    transform, viz, CancellationToken, CyklError, Model, RunConfigBuilder,
+};
 
 #[test]
 fn test_metric() {
@@ -28,3 +50,2590 @@ fn test_metric() {
         }
     }
 }
+
+#[test]
+fn test_memory_budget() {
+    let len = 20;
+    let create = |budget| {
+        let mut ds = DataStore::with_capacity(Metric::Euc2d, len);
+        ds.set_memory_budget(budget);
+        for ii in 0..len {
+            ds.add(NodeKind::Target, vec![ii as f64, (ii * ii % 7) as f64], ());
+        }
+        ds.compute();
+        ds
+    };
+
+    let cached = create(MemoryBudget::unlimited());
+    assert!(cached.is_matrix_cached());
+    let matrix = len * len * std::mem::size_of::<f64>();
+    assert!(cached.memory_footprint() > matrix);
+
+    let budget = MemoryBudget::new(cached.memory_footprint() - 1);
+    let on_demand = create(budget);
+    assert!(!on_demand.is_matrix_cached());
+    assert_eq!(
+        cached.memory_footprint() - matrix,
+        on_demand.memory_footprint()
+    );
+    for ii in 0..len {
+        for jj in 0..len {
+            assert_eq!(cached.cost(&ii, &jj), on_demand.cost(&ii, &jj));
+        }
+    }
+
+    let used = on_demand.memory_footprint();
+    assert_eq!(10, MemoryBudget::unlimited().max_cands(len, 10, used));
+    assert_eq!(
+        3,
+        MemoryBudget::new(used + 3 * len * std::mem::size_of::<usize>()).max_cands(len, 10, used)
+    );
+    assert_eq!(
+        MemoryBudget::MIN_CANDS,
+        MemoryBudget::new(used).max_cands(len, 10, used)
+    );
+
+    let mut model = Model::new(Metric::Euc2d, 4);
+    model.set_memory_budget(MemoryBudget::new(1));
+    for ii in 0..len {
+        model.add(NodeKind::Target, vec![ii as f64, (ii * ii % 7) as f64], ());
+    }
+    let config = RunConfigBuilder::new()
+        .cands(8)
+        .solver(SolverKind::Greedy(vec![0]))
+        .build();
+    let result = model.solve(&config).unwrap().unwrap();
+    assert_eq!(len, result.len());
+    assert!(!model.store().is_matrix_cached());
+
+    let mut tour = TwoLevelList::new(model.store(), 4);
+    cand_gen_nn(&mut tour, 4, Objective::Minimize);
+    let set = CandidateSet::from_tour(&tour, model.store());
+    assert!(set.memory_footprint() >= len * 4 * std::mem::size_of::<usize>());
+}
+
+#[test]
+fn test_autotune() {
+    use crate::autotune;
+
+    let mut model = Model::<()>::new(Metric::Euc2d, 4);
+    let config = RunConfigBuilder::new().build();
+    let tuning = autotune::probe(&mut model, &config, std::time::Duration::from_secs(1)).unwrap();
+    assert!(tuning.probes.is_empty());
+    assert_eq!(4, model.groupsize());
+    assert_eq!(config, tuning.config);
+
+    let mut model = Model::new(Metric::Euc2d, 4);
+    for ii in 0..50 {
+        let cluster = (ii % 5) as f64 * 100.;
+        model.add(
+            NodeKind::Target,
+            vec![cluster + (ii % 7) as f64, cluster + (ii % 3) as f64],
+            (),
+        );
+    }
+    let tuning = autotune::probe(&mut model, &config, std::time::Duration::from_secs(60)).unwrap();
+    assert_eq!(9, tuning.probes.len());
+    let best = tuning
+        .probes
+        .iter()
+        .map(|x| x.cost)
+        .fold(f64::INFINITY, f64::min);
+    let chosen = tuning
+        .probes
+        .iter()
+        .find(|x| x.groupsize == tuning.groupsize && x.cands == tuning.config.cands())
+        .unwrap();
+    assert_eq!(best, chosen.cost);
+    assert_eq!(tuning.groupsize, model.groupsize());
+
+    // At least one probe runs, even without budget.
+    let tuning = autotune::probe(&mut model, &config, std::time::Duration::from_secs(0)).unwrap();
+    assert_eq!(1, tuning.probes.len());
+
+    // The budget bounds the probes of a solver that would run much longer on its own.
+    let slow = RunConfigBuilder::new()
+        .solver(SolverKind::Adaptive {
+            starters: vec![0],
+            budget: std::time::Duration::from_secs(60),
+            seed: 1,
+        })
+        .build();
+    let began = std::time::Instant::now();
+    let tuning = autotune::probe(&mut model, &slow, std::time::Duration::from_millis(300)).unwrap();
+    assert!(began.elapsed() < std::time::Duration::from_secs(10));
+    assert!(!tuning.probes.is_empty());
+
+    // A failing probe leaves the groupsize of the model as it was.
+    let mut model = Model::new(Metric::Explicit, 3);
+    for _ in 0..4 {
+        model.add(NodeKind::Target, Vec::new(), ());
+    }
+    model
+        .set_edges(&[(0, 1, 1.), (1, 2, 1.), (2, 3, 1.)])
+        .unwrap();
+    assert!(matches!(
+        autotune::probe(&mut model, &config, std::time::Duration::from_secs(1)),
+        Err(CyklError::MissingEdge { .. })
+    ));
+    assert_eq!(3, model.groupsize());
+}
+
+#[test]
+fn test_solve_all() {
+    use crate::batch;
+
+    let build = |ii: usize| {
+        if ii == 3 {
+            return Err(CyklError::MissingCoordinates);
+        }
+
+        let mut model = Model::new(Metric::Euc2d, 4);
+        for jj in 0..(5 + ii) {
+            let angle = jj as f64 * 2. * std::f64::consts::PI / (5 + ii) as f64;
+            model.add(NodeKind::Target, vec![angle.cos(), angle.sin()], ());
+        }
+        Ok(model)
+    };
+    let config = RunConfigBuilder::new()
+        .cands(4)
+        .solver(SolverKind::Greedy(vec![0]))
+        .build();
+
+    let serial = batch::solve_all(12, build, &config, 1);
+    let parallel = batch::solve_all(12, build, &config, 4);
+    assert_eq!(12, parallel.len());
+    for (ii, (a, b)) in serial.iter().zip(&parallel).enumerate() {
+        assert_eq!(ii, b.index);
+        if ii == 3 {
+            assert!(matches!(b.result, Err(CyklError::MissingCoordinates)));
+            continue;
+        }
+
+        let a = a.result.as_ref().unwrap().as_ref().unwrap();
+        let b = b.result.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(5 + ii, b.len());
+        assert_eq!(a, b);
+        assert!(approx_eq!(f64, a.cost(), b.cost(), epsilon = 1e-10));
+    }
+}
+
+#[test]
+fn test_solve_multi_start() {
+    use crate::batch;
+
+    let build = |_: usize| {
+        let mut model = Model::new(Metric::Euc2d, 4);
+        for ii in 0..16 {
+            let pos = vec![((ii * 7) % 16) as f64, ((ii * 5) % 11) as f64];
+            model.add(NodeKind::Target, pos, ());
+        }
+        Ok(model)
+    };
+    let config = |objective| {
+        RunConfigBuilder::new()
+            .cands(4)
+            .solver(SolverKind::LateAcceptance {
+                starters: vec![0],
+                history: 4,
+                termination: Termination::Iterations(100),
+                seed: 5,
+            })
+            .objective(objective)
+            .build()
+    };
+
+    // Start 0 runs the configuration as given, thus the best start is at least as good.
+    for objective in [Objective::Minimize, Objective::Maximize] {
+        let single = build(0)
+            .unwrap()
+            .solve(&config(objective))
+            .unwrap()
+            .unwrap();
+        let best = batch::solve_multi_start(6, build, &config(objective), 3)
+            .unwrap()
+            .unwrap();
+        assert_eq!(16, best.len());
+        assert!(!objective.is_better(single.cost(), best.cost()));
+    }
+
+    // Deterministic runs keep the tour of the lowest of tying starts on any number of threads.
+    let deterministic = RunConfigBuilder::new()
+        .cands(4)
+        .solver(config(Objective::Minimize).solver().clone())
+        .deterministic(true)
+        .build();
+    let expected = batch::solve_multi_start(8, build, &deterministic, 1).unwrap();
+    for n_threads in [2, 3, 8] {
+        let received = batch::solve_multi_start(8, build, &deterministic, n_threads).unwrap();
+        assert_eq!(expected, received);
+    }
+
+    let failing = |_: usize| -> Result<Model<()>, CyklError> { Err(CyklError::MissingCoordinates) };
+    assert!(matches!(
+        batch::solve_multi_start(4, failing, &config(Objective::Minimize), 2),
+        Err(CyklError::MissingCoordinates)
+    ));
+}
+
+#[test]
+fn test_experiment() {
+    use crate::experiments::{self, Experiment};
+
+    let build = |name: &str| {
+        let len = match name {
+            "ring-8" => 8,
+            "ring-12" => 12,
+            _ => return Err(CyklError::UnknownLayer(name.to_string())),
+        };
+        let mut model = Model::new(Metric::Euc2d, 4);
+        for jj in 0..len {
+            let angle = jj as f64 * 2. * std::f64::consts::PI / len as f64;
+            model.add(NodeKind::Target, vec![angle.cos(), angle.sin()], ());
+        }
+        Ok(model)
+    };
+    let lns = RunConfigBuilder::new()
+        .cands(4)
+        .solver(SolverKind::Lns {
+            starters: vec![0],
+            n_remove: 2,
+            termination: Termination::Iterations(20),
+            seed: 0,
+        })
+        .build();
+    assert_eq!(
+        &SolverKind::Lns {
+            starters: vec![0],
+            n_remove: 2,
+            termination: Termination::Iterations(20),
+            seed: 9,
+        },
+        lns.clone().with_seed(9).solver()
+    );
+
+    let experiment = Experiment::new()
+        .with_instance("ring-8")
+        .with_instance("ring-12")
+        .with_instance("a,b")
+        .with_config("greedy", RunConfigBuilder::new().cands(4).build())
+        .with_config("lns", lns)
+        .with_seeds(&[1, 2]);
+    assert_eq!(12, experiment.len());
+
+    let serial = experiment.run(build);
+    let parallel = experiment.clone().with_parallelism(3).run(build);
+    assert_eq!(12, parallel.len());
+    for (a, b) in serial.iter().zip(&parallel) {
+        assert_eq!(
+            (&a.instance, &a.config, a.seed),
+            (&b.instance, &b.config, b.seed)
+        );
+        assert_eq!(a.cost(), b.cost());
+    }
+    assert_eq!(
+        ("ring-12", "greedy", 2),
+        (
+            parallel[5].instance.as_str(),
+            parallel[5].config.as_str(),
+            parallel[5].seed
+        )
+    );
+    for report in &parallel[..8] {
+        let n = if report.instance == "ring-8" { 8. } else { 12. };
+        let perimeter = 2. * n * (std::f64::consts::PI / n).sin();
+        assert!(approx_eq!(
+            f64,
+            perimeter,
+            report.cost().unwrap(),
+            epsilon = 1e-9
+        ));
+    }
+    assert!(parallel[8..]
+        .iter()
+        .all(|report| matches!(report.result, Err(CyklError::UnknownLayer(_)))));
+
+    let mut csv = Vec::new();
+    experiments::write_csv(&parallel, &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(13, lines.len());
+    assert_eq!(
+        "instance,config,seed,status,nodes,cost,elapsed_ms",
+        lines[0]
+    );
+    assert!(lines[1].starts_with("ring-8,greedy,1,ok,8,"));
+    assert!(lines[9].starts_with("\"a,b\",greedy,1,"));
+}
+
+#[test]
+fn test_raw_index() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 2);
+    let a = ds.add(NodeKind::Depot, vec![0., 0.], ()).unwrap();
+    let b = ds.add(NodeKind::Target, vec![1., 0.], ()).unwrap();
+    assert_eq!((0, 1), (a.index(), b.get()));
+    assert_eq!(NodeKind::Depot, a.kind());
+
+    let expected = if cfg!(feature = "u32-index") { 8 } else { 16 };
+    assert_eq!(expected, std::mem::size_of::<NodeIndex>());
+}
+
+#[test]
+fn test_coords() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 3);
+    for pos in [[0., 1.], [2., 3.], [4., 5.]] {
+        ds.add(NodeKind::Target, pos.to_vec(), ());
+    }
+    assert_eq!(Some((2., 3., 0.)), ds.coords(1));
+    assert_eq!(None, ds.coords(3));
+    assert_eq!(vec![(0., 1., 0.), (2., 3., 0.), (4., 5., 0.)], ds.to_vec());
+    assert_eq!(3, ds.iter_coords().count());
+
+    let mut ds = DataStore::with_capacity(Metric::Euc3d, 1);
+    ds.add(NodeKind::Target, vec![1., 2., 3.], ());
+    assert_eq!(Some((1., 2., 3.)), ds.coords(0));
+
+    let mut ds = DataStore::with_capacity(Metric::Explicit, 1);
+    ds.add(NodeKind::Target, Vec::new(), ());
+    assert!(ds.coords(0).is_none());
+    assert!(ds.to_vec().is_empty());
+}
+
+#[test]
+fn test_unit_and_scale() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 2);
+    ds.add(NodeKind::Target, vec![0., 0.], ());
+    ds.add(NodeKind::Target, vec![3., 4.], ());
+    ds.set_scale(2.);
+    ds.compute();
+    assert_eq!(10., ds.cost(&0, &1));
+    ds.set_scale(0.5);
+    assert_eq!(2.5, ds.cost(&0, &1));
+
+    ds.set_memory_budget(MemoryBudget::new(0));
+    ds.compute();
+    assert!(!ds.is_matrix_cached());
+    assert_eq!(2.5, ds.cost(&0, &1));
+    assert_eq!(Unit::Unitless, ds.unit());
+    assert_eq!(Unit::Meters, DataStore::<()>::new_geo().unit());
+
+    let mut ds = DataStore::with_capacity(Metric::Explicit, 2);
+    ds.add(NodeKind::Target, Vec::new(), ());
+    ds.add(NodeKind::Target, Vec::new(), ());
+    ds.set_scale(1. / 60.);
+    ds.set_costs_in(vec![0., 120., 60., 0.], Unit::Seconds)
+        .unwrap();
+    ds.compute();
+    assert_eq!(2., ds.cost(&0, &1));
+    assert_eq!(1., ds.cost(&1, &0));
+    assert_eq!(Unit::Seconds, ds.unit());
+
+    assert!(matches!(
+        ds.set_costs_in(vec![0.; 4], Unit::Meters),
+        Err(CyklError::UnitMismatch {
+            expected: Unit::Seconds,
+            found: Unit::Meters
+        })
+    ));
+    assert_eq!(2., ds.cost(&0, &1));
+
+    let tour = TwoLevelList::new(&ds, 2);
+    let report = stats::quality_report(&tour, &ds);
+    assert_eq!(Unit::Seconds, report.unit);
+    assert_eq!(1. / 60., report.scale);
+    assert!(report
+        .to_string()
+        .contains(&format!("length:     3 (s scaled by {})\n", 1. / 60.)));
+    assert_eq!(
+        "Cannot combine costs in m with costs in s",
+        ds.set_costs_in(vec![0.; 4], Unit::Meters)
+            .unwrap_err()
+            .to_string()
+    );
+}
+
+#[test]
+fn test_sparse_graph() {
+    let len = 8;
+    let sparse = |edges: &[(usize, usize, f64)]| {
+        let mut model = Model::new(Metric::Explicit, 3);
+        for _ in 0..len {
+            model.add(NodeKind::Target, Vec::new(), ());
+        }
+        model.set_edges(edges).unwrap();
+        model
+    };
+
+    // The expensive cycle 0-3-6-1-4-7-2-5 is joined by the cheap path 0-1-2-3-4-5-6-7.
+    let mut edges: Vec<_> = (0..len)
+        .map(|ii| (ii * 3 % len, (ii + 1) * 3 % len, 10.))
+        .collect();
+    edges.extend((0..len - 1).map(|ii| (ii, ii + 1, 1.)));
+
+    let mut model = sparse(&edges);
+    assert!(model.store().has_edge(0, 3));
+    assert!(!model.store().has_edge(0, 2));
+    assert!(!model.store().has_edge(0, 0));
+    let config = RunConfigBuilder::new()
+        .cands(4)
+        .solver(SolverKind::Lns {
+            starters: vec![0],
+            n_remove: 3,
+            termination: Termination::Iterations(100),
+            seed: 1,
+        })
+        .build();
+    let result = model.solve(&config).unwrap().unwrap();
+    assert_eq!(len, result.len());
+    assert!(model.store().missing_edge(&result).is_none());
+    assert!(result.cost() <= 80.);
+
+    // A path has no Hamiltonian cycle.
+    let mut model = sparse(&edges[len..]);
+    assert!(matches!(
+        model.solve(&config),
+        Err(CyklError::MissingEdge { .. })
+    ));
+    assert!(matches!(
+        model.store().clone().set_edges(&[(0, len, 1.)]),
+        Err(CyklError::IndexOutOfBounds { .. })
+    ));
+}
+
+#[test]
+fn test_cost_layers() {
+    let mut ds = DataStore::with_capacity(Metric::Explicit, 3);
+    for _ in 0..3 {
+        ds.add(NodeKind::Target, Vec::new(), ());
+    }
+    let time = vec![0., 10., 20., 10., 0., 10., 20., 10., 0.];
+    let distance = vec![0., 5., 5., 5., 0., 8., 5., 8., 0.];
+    let toll = vec![0., 0., 3., 0., 0., 1., 3., 1., 0.];
+    ds.add_layer("time", Unit::Seconds, time).unwrap();
+    ds.add_layer("distance", Unit::Meters, distance).unwrap();
+    ds.add_layer("toll", Unit::Unitless, vec![0.; 4])
+        .unwrap_err();
+    ds.add_layer("toll", Unit::Unitless, toll).unwrap();
+    assert_eq!(3, ds.layers().len());
+
+    ds.set_costs_from_layers(&[("time", 1.)]).unwrap();
+    ds.compute();
+    assert_eq!(20., ds.cost(&2, &0));
+    assert_eq!(Unit::Seconds, ds.unit());
+
+    let order = TourOrder::with_nat_ord(3);
+    assert_eq!(
+        vec![("time", 40.), ("distance", 18.), ("toll", 4.)],
+        ds.layer_totals(&order)
+    );
+
+    let objective =
+        Penalized::new(Objective::Minimize).with_penalty(2., ds.layer("toll").unwrap().clone());
+    assert_eq!(
+        48.,
+        objective.evaluate(&TourOrder::with_cost(vec![0, 1, 2], 40.))
+    );
+
+    ds.set_costs_from_layers(&[("time", 1.), ("toll", 10.)])
+        .unwrap();
+    assert_eq!(50., ds.cost(&2, &0));
+    assert_eq!(Unit::Unitless, ds.unit());
+    assert!(matches!(
+        ds.set_costs_from_layers(&[("fuel", 1.)]),
+        Err(CyklError::UnknownLayer(_))
+    ));
+}
+
+#[test]
+fn test_spatial_summary() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 100);
+    let pos = |ii: usize| vec![((ii * 37) % 101) as f64, ((ii * 53) % 97) as f64];
+    for ii in 0..100 {
+        ds.add(NodeKind::Target, pos(ii), ());
+    }
+
+    let bbox = ds.bounding_box().unwrap();
+    let (xs, ys): (Vec<f64>, Vec<f64>) = (0..100).map(|ii| (pos(ii)[0], pos(ii)[1])).unzip();
+    let fold = |v: &[f64], f: fn(f64, f64) -> f64| v.iter().copied().reduce(f).unwrap();
+    assert_eq!((fold(&xs, f64::min), fold(&ys, f64::min), 0.), bbox.min);
+    assert_eq!((fold(&xs, f64::max), fold(&ys, f64::max), 0.), bbox.max);
+    assert!(bbox.contains(bbox.center()));
+
+    let (cx, cy, _) = ds.centroid().unwrap();
+    assert!(approx_eq!(
+        f64,
+        xs.iter().sum::<f64>() / 100.,
+        cx,
+        epsilon = 1e-10
+    ));
+    assert!(approx_eq!(
+        f64,
+        ys.iter().sum::<f64>() / 100.,
+        cy,
+        epsilon = 1e-10
+    ));
+
+    for query in [
+        (0., 0., 0.),
+        (50.5, 20.25, 0.),
+        (200., -3., 0.),
+        (37., 53., 0.),
+    ] {
+        let brute = (0..100)
+            .min_by(|a, b| {
+                let d = |ii: usize| (pos(ii)[0] - query.0).powi(2) + (pos(ii)[1] - query.1).powi(2);
+                d(*a).partial_cmp(&d(*b)).unwrap().then(a.cmp(b))
+            })
+            .unwrap();
+        assert_eq!(Some(brute), ds.nearest_to(query), "{:?}", query);
+    }
+
+    // Adding a node invalidates the tree.
+    let index = ds.add(NodeKind::Target, vec![500., 500.], ()).unwrap();
+    assert_eq!(Some(index.index()), ds.nearest_to((499., 499., 0.)));
+
+    let ds = DataStore::<()>::new(Metric::Euc2d);
+    assert!(ds.bounding_box().is_none());
+    assert!(ds.centroid().is_none());
+    assert!(ds.nearest_to((0., 0., 0.)).is_none());
+}
+
+#[test]
+fn test_warm_candidates() {
+    use crate::spatial::KdTree;
+
+    let k = 5;
+    let pos = |ii: usize| vec![(ii * 37 % 101) as f64, (ii * 53 % 97) as f64];
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 200);
+    for ii in 0..150 {
+        ds.add(NodeKind::Target, pos(ii), ());
+    }
+
+    let mut tree = KdTree::new(&ds);
+    let mut set = CandidateSet::from_kd_tree(&ds, &tree, k);
+    let brute = |active: &[usize], index: usize| {
+        let d =
+            |c: usize| (pos(c)[0] - pos(index)[0]).powi(2) + (pos(c)[1] - pos(index)[1]).powi(2);
+        let mut others: Vec<_> = active.iter().copied().filter(|c| *c != index).collect();
+        others.sort_by(|a, b| d(*a).partial_cmp(&d(*b)).unwrap().then(a.cmp(b)));
+        others.truncate(k);
+        others
+    };
+
+    for ii in 150..200 {
+        ds.add(NodeKind::Target, pos(ii), ());
+        set.insert_node(&ds, &mut tree, ii, k).unwrap();
+    }
+    assert_eq!(200, tree.len());
+    assert_eq!(CandidateSet::from_kd_tree(&ds, &KdTree::new(&ds), k), set);
+
+    let mut active: Vec<usize> = (0..200).collect();
+    for ii in (0..200).step_by(7) {
+        assert!(set.remove_node(&mut tree, ii, k));
+        active.retain(|x| *x != ii);
+    }
+    assert!(!set.remove_node(&mut tree, 0, k));
+    // Inserting a node that is already in the tree leaves no duplicates in the other lists.
+    set.insert_node(&ds, &mut tree, 1, k).unwrap();
+    assert_eq!(active.len(), tree.len());
+    for index in 0..200 {
+        let expected = if active.contains(&index) {
+            brute(&active, index)
+        } else {
+            Vec::new()
+        };
+        assert_eq!(&expected[..], set.candidates(index).unwrap(), "{}", index);
+    }
+
+    assert!(matches!(
+        set.insert_node(&ds, &mut tree, 200, k),
+        Err(CyklError::IndexOutOfBounds { .. })
+    ));
+}
+
+#[test]
+fn test_hnsw_candidates() {
+    use crate::{
+        alg::{CandidateSetBuilder, HnswParams, NeighborIndex},
+        spatial::KdTree,
+    };
+
+    let k = 8;
+    let len = 400;
+    let pos = |ii: usize| vec![(ii * 37 % 211) as f64, (ii * 53 % 197) as f64];
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, len);
+    for ii in 0..len {
+        ds.add(NodeKind::Target, pos(ii), ());
+    }
+
+    let exact = CandidateSetBuilder::new().k(k).build(&ds);
+    assert_eq!(CandidateSet::from_kd_tree(&ds, &KdTree::new(&ds), k), exact);
+
+    let params = HnswParams::new().m(8).ef_search(32).seed(3);
+    let approx = CandidateSetBuilder::new()
+        .k(k)
+        .neighbor_index(NeighborIndex::Hnsw(params))
+        .build(&ds);
+    assert_eq!(ds.fingerprint(), approx.fingerprint());
+    let mut hits = 0;
+    for index in 0..len {
+        let cands = approx.candidates(index).unwrap();
+        assert_eq!(k, cands.len());
+        assert!(!cands.contains(&index));
+        assert!(cands
+            .windows(2)
+            .all(|w| ds.cost(&index, &w[0]) <= ds.cost(&index, &w[1])));
+        let expected = exact.candidates(index).unwrap();
+        hits += cands.iter().filter(|c| expected.contains(c)).count();
+    }
+    // Ties between equally distant neighbours count as misses.
+    assert!(hits as f64 >= 0.9 * (len * k) as f64, "{}", hits);
+
+    // Explicit costs have no coordinates for a tree, but serve the graph.
+    let n = 30;
+    let mut explicit = DataStore::with_capacity(Metric::Explicit, n);
+    for _ in 0..n {
+        explicit.add(NodeKind::Target, Vec::new(), ());
+    }
+    let costs = (0..n * n)
+        .map(|ii| ((ii / n) as f64 - (ii % n) as f64).abs())
+        .collect();
+    explicit.set_costs(costs).unwrap();
+    let set = CandidateSetBuilder::new()
+        .k(2)
+        .neighbor_index(NeighborIndex::Hnsw(HnswParams::new()))
+        .build(&explicit);
+    assert_eq!(Some(&[4, 6][..]), set.candidates(5));
+    assert_eq!(Some(&[1, 2][..]), set.candidates(0));
+    let tree = CandidateSetBuilder::new().k(2).build(&explicit);
+    assert_eq!(Some(&[][..]), tree.candidates(5));
+}
+
+#[test]
+fn test_clustering() {
+    let mut model = Model::new(Metric::Euc2d, 4);
+    // Three blobs of five nodes each.
+    for (cx, cy) in [(0., 0.), (100., 0.), (0., 100.)] {
+        for ii in 0..5 {
+            let pos = vec![cx + (ii % 3) as f64, cy + (ii / 3) as f64];
+            model.add(NodeKind::Target, pos, ());
+        }
+    }
+
+    let mut clusters = cluster::kmeans(model.store(), 3, 20, 7);
+    clusters.sort();
+    assert_eq!(
+        vec![
+            (0..5).collect::<Vec<_>>(),
+            (5..10).collect(),
+            (10..15).collect()
+        ],
+        clusters
+    );
+    assert_eq!(clusters, {
+        let mut again = cluster::kmeans(model.store(), 3, 20, 7);
+        again.sort();
+        again
+    });
+    assert_eq!(1, cluster::kmeans(model.store(), 1, 20, 7).len());
+    assert!(cluster::kmeans(model.store(), 0, 20, 7).is_empty());
+
+    let cells = cluster::grid(model.store(), 50.);
+    assert_eq!(
+        vec![
+            (0..5).collect::<Vec<_>>(),
+            (5..10).collect(),
+            (10..15).collect()
+        ],
+        cells
+    );
+    assert_eq!(1, cluster::grid(model.store(), 0.).len());
+    assert_eq!(15, cluster::grid(model.store(), 0.5).len());
+
+    model.set_groups(&cells).unwrap();
+    assert_eq!(cells, model.store().groups());
+    assert!(model.set_groups(&[vec![15]]).is_err());
+}
+
+#[test]
+fn test_split() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 7);
+    for ii in 0..7 {
+        ds.add(NodeKind::Target, vec![ii as f64, 0.], ());
+    }
+    ds.compute();
+
+    let giant = TourOrder::with_ord(vec![3, 4, 5, 6, 0, 1, 2]);
+    let mut demands = vec![1.; 7];
+    demands[0] = 0.;
+    let trips = |demands: &[f64], limits| split::split(&ds, &giant, 0, demands, limits);
+
+    let result = trips(&demands, TripLimits::new().capacity(3.)).unwrap();
+    assert_eq!(18., result.cost());
+    let orders: Vec<_> = result.trips().iter().map(|t| t.order().clone()).collect();
+    assert_eq!(vec![vec![0, 1, 2, 3], vec![0, 4, 5, 6]], orders);
+    assert_eq!(
+        vec![6., 12.],
+        result.trips().iter().map(|t| t.cost()).collect::<Vec<_>>()
+    );
+
+    let result = trips(&demands, TripLimits::new().capacity(2.).max_length(12.)).unwrap();
+    assert_eq!(24., result.cost());
+    assert_eq!(3, result.trips().len());
+
+    // Without limits, a single trip serves all nodes.
+    assert_eq!(1, trips(&demands, TripLimits::new()).unwrap().trips().len());
+
+    assert!(matches!(
+        trips(&demands, TripLimits::new().max_length(10.)),
+        Err(CyklError::Infeasible(6))
+    ));
+    demands[3] = 5.;
+    assert!(matches!(
+        trips(&demands, TripLimits::new().capacity(3.)),
+        Err(CyklError::Infeasible(3))
+    ));
+    assert!(matches!(
+        trips(&demands[1..], TripLimits::new()),
+        Err(CyklError::LenMismatched { .. })
+    ));
+}
+
+#[test]
+fn test_service_time() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 7);
+    for ii in 0..7 {
+        ds.add(NodeKind::Target, vec![ii as f64, 0.], ());
+    }
+    ds.compute();
+    for ii in 1..7 {
+        ds.set_service_time(ii, 1.).unwrap();
+    }
+    assert_eq!(0., ds.service_time(0));
+    assert!(ds.set_service_time(7, 1.).is_err());
+
+    let giant = TourOrder::with_ord((0..7).collect());
+    assert_eq!(18., giant.duration(&ds));
+
+    let demands = vec![0.; 7];
+    let result = split::split(
+        &ds,
+        &giant,
+        0,
+        &demands,
+        TripLimits::new().max_duration(13.),
+    )
+    .unwrap();
+    assert_eq!(26., result.cost());
+    let orders: Vec<_> = result.trips().iter().map(|t| t.order().clone()).collect();
+    assert_eq!(vec![vec![0, 1, 2], vec![0, 3, 4, 5], vec![0, 6]], orders);
+    assert!(result.trips().iter().all(|t| t.duration(&ds) <= 13.));
+
+    assert!(matches!(
+        split::split(
+            &ds,
+            &giant,
+            0,
+            &demands,
+            TripLimits::new().max_duration(12.)
+        ),
+        Err(CyklError::Infeasible(6))
+    ));
+}
+
+#[test]
+fn test_cost_overlay() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 4);
+    for pos in [[0., 0.], [1., 0.], [1., 1.], [0., 1.]] {
+        ds.add(NodeKind::Target, pos.to_vec(), ());
+    }
+    ds.compute();
+
+    let mut overlay = CostOverlay::new();
+    assert!(overlay.is_empty());
+    overlay.set(1, 0, 10.);
+    overlay.add_penalty(2, 3, 0.5);
+    overlay.add_penalty(3, 2, 0.5);
+    assert_eq!(2, overlay.len());
+    assert!(overlay.contains(0, 1));
+    assert_eq!(1., overlay.penalty(2, 3));
+    assert_eq!(10., overlay.cost(&ds, 0, 1));
+    assert_eq!(1., overlay.cost(&ds, 1, 2));
+
+    let mut tour = TwoLevelList::new(&ds, 2);
+    assert_eq!(4., tour.total_distance());
+    assert!(tour.attach_overlay(overlay).is_none());
+    assert_eq!(14., tour.total_distance());
+    assert_eq!(14., tour.tour_order().cost());
+    assert_eq!(10., tour.distance_at(&1, &0).unwrap());
+    assert_eq!(2., tour.distance_at(&3, &2).unwrap());
+
+    let mut out = [0.; 3];
+    tour.distances(&[(0, 1), (1, 2), (2, 3)], &mut out);
+    assert_eq!([10., 1., 2.], out);
+
+    tour.update_overlay(|overlay| overlay.remove(0, 1));
+    assert_eq!(5., tour.total_distance());
+    assert_eq!(1, tour.overlay().unwrap().len());
+
+    // The store keeps its costs.
+    assert_eq!(1., ds.cost(&0, &1));
+    assert!(tour.detach_overlay().is_some());
+    assert_eq!(4., tour.total_distance());
+}
+
+#[test]
+fn test_distance_oracle() {
+    use crate::{
+        oracle::{CoordOracle, DistanceOracle, FnOracle, MatrixOracle, Overlaid},
+        tour::TourBuilder,
+    };
+    use std::sync::Arc;
+
+    let square = [[0., 0.], [1., 0.], [1., 1.], [0., 1.]];
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 4);
+    for pos in square {
+        ds.add(NodeKind::Target, pos.to_vec(), ());
+    }
+    let coords: Vec<Vec<f64>> = square.iter().map(|pos| pos.to_vec()).collect();
+    let on_coords = CoordOracle::new(Metric::Euc2d, &coords).unwrap();
+    assert_eq!(4, on_coords.len());
+    for (a, b) in [(0, 1), (0, 2), (3, 1)] {
+        assert_eq!(DistanceOracle::cost(&ds, a, b), on_coords.cost(a, b));
+    }
+    assert!(CoordOracle::new(Metric::Euc2d, &[vec![0., 0.], vec![1.]]).is_err());
+
+    // Travelling clockwise costs more than counter-clockwise.
+    let costs = (0..16)
+        .map(|ii| match (ii / 4, ii % 4) {
+            (a, b) if a == b => 0.,
+            (a, b) if (a + 1) % 4 == b => 1.,
+            _ => 3.,
+        })
+        .collect();
+    let matrix = Arc::new(MatrixOracle::new(4, costs).unwrap());
+    let mut tour = TourBuilder::new()
+        .groupsize(2)
+        .oracle(matrix.clone())
+        .build(&ds)
+        .unwrap();
+    assert_eq!(4., tour.total_distance());
+    assert_eq!(3., tour.distance_at(&1, &0).unwrap());
+    let mut out = [0.; 2];
+    tour.distances(&[(0, 1), (2, 1)], &mut out);
+    assert_eq!([1., 3.], out);
+    assert!(tour.detach_oracle().is_some());
+    assert_eq!(4., tour.total_distance());
+    assert_eq!(1., tour.distance_at(&1, &0).unwrap());
+
+    let mut overlay = CostOverlay::new();
+    overlay.set(0, 1, 5.);
+    let overlaid = Overlaid::new(matrix.clone(), overlay);
+    assert_eq!(5., overlaid.cost(0, 1));
+    assert_eq!(3., overlaid.cost(2, 1));
+    let base = matrix.clone();
+    let double = FnOracle::new(4, move |a, b| 2. * base.cost(a, b));
+    assert!(tour.attach_oracle(Arc::new(double)).unwrap().is_none());
+    assert_eq!(8., tour.total_distance());
+
+    let small = FnOracle::new(3, |_, _| 1.);
+    assert!(matches!(
+        tour.attach_oracle(Arc::new(small)),
+        Err(CyklError::LenMismatched {
+            expected: 4,
+            received: 3
+        })
+    ));
+
+    // Tours can be created from an oracle without a store.
+    let mut tour = TourBuilder::new()
+        .groupsize(2)
+        .build_from_oracle(matrix.clone())
+        .unwrap();
+    assert_eq!(4, tour.len());
+    assert_eq!(4., tour.total_distance());
+    tour.apply(&TourOrder::with_ord(vec![3, 2, 1, 0])).unwrap();
+    assert_eq!(12., tour.total_distance());
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_mmap_oracle() {
+    use crate::oracle::{DistanceOracle, FnOracle, MmapOracle};
+
+    let path = std::env::temp_dir().join(format!("cykl-matrix-{}.bin", std::process::id()));
+    let oracle = FnOracle::new(5, |a, b| (10 * a + b) as f64);
+    MmapOracle::write(&oracle, &path).unwrap();
+
+    let mapped = MmapOracle::open(&path).unwrap();
+    assert_eq!(5, mapped.len());
+    assert_eq!(12., mapped.cost(1, 2));
+    assert_eq!(43., mapped.cost(4, 3));
+
+    // Truncated files and other files are refused.
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &bytes[..bytes.len() - 8]).unwrap();
+    assert!(matches!(MmapOracle::open(&path), Err(CyklError::Parse(_))));
+    std::fs::write(&path, b"not a matrix file at all").unwrap();
+    assert!(matches!(MmapOracle::open(&path), Err(CyklError::Parse(_))));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "routing")]
+#[test]
+fn test_remote_oracle() {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::Mutex,
+        thread,
+    };
+
+    use crate::oracle::{DistanceOracle, OsrmService, RemoteOracle, TableService};
+
+    let requests = Mutex::new(Vec::new());
+    let service = |sources: &[usize], targets: &[usize]| {
+        requests.lock().unwrap().push(sources.to_vec());
+        Ok(sources
+            .iter()
+            .flat_map(|&a| targets.iter().map(move |&b| (10 * a + b) as f64))
+            .collect())
+    };
+    let oracle = RemoteOracle::new(service, 10).with_batch_size(4);
+    assert_eq!(12., oracle.cost(1, 2));
+    assert_eq!(43., oracle.cost(4, 3));
+    assert_eq!(29., oracle.cost(2, 9));
+    assert_eq!(vec![vec![1, 2, 3, 4]], *requests.lock().unwrap());
+
+    // Cached rows are skipped when fetching ahead.
+    oracle.prefetch(&[9, 0, 3, 9]).unwrap();
+    assert_eq!(vec![0, 9], requests.lock().unwrap()[1]);
+    assert_eq!(8., oracle.cost(0, 8));
+    oracle.prefetch_all().unwrap();
+    assert_eq!(3, oracle.n_requests());
+    assert_eq!(10, oracle.n_cached());
+    assert!(oracle.prefetch(&[10]).is_err());
+
+    let failing = RemoteOracle::new(
+        |_: &[usize], _: &[usize]| Err(CyklError::Parse("Unavailable".to_string())),
+        3,
+    );
+    assert!(matches!(failing.prefetch_all(), Err(CyklError::Parse(_))));
+
+    // Queries that cannot fetch their row return infinity and keep the error, without contacting
+    // the service again until the error is taken.
+    assert_eq!(f64::INFINITY, failing.cost(0, 1));
+    assert_eq!(f64::INFINITY, failing.cost(2, 1));
+    assert_eq!(2, failing.n_requests());
+    assert!(matches!(failing.take_error(), Some(CyklError::Parse(_))));
+    assert_eq!(f64::INFINITY, failing.cost(2, 1));
+    assert_eq!(3, failing.n_requests());
+
+    // Tours check the first row when the oracle is attached, and refuse oracles that cannot fetch
+    // it.
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 3);
+    for ii in 0..3 {
+        ds.add(NodeKind::Target, vec![ii as f64, 0.], ());
+    }
+    let mut tour = crate::tour::TourBuilder::new().build(&ds).unwrap();
+    let distance = tour.total_distance();
+    assert!(matches!(
+        tour.attach_oracle(std::sync::Arc::new(failing)),
+        Err(CyklError::Parse(_))
+    ));
+    assert_eq!(distance, tour.total_distance());
+    let remote = std::sync::Arc::new(RemoteOracle::new(
+        |sources: &[usize], targets: &[usize]| Ok(vec![1.; sources.len() * targets.len()]),
+        3,
+    ));
+    remote.prepare().unwrap();
+    assert_eq!(1, remote.n_cached());
+    tour.attach_oracle(remote.clone()).unwrap();
+    assert_eq!(3., tour.total_distance());
+
+    // The whole matrix is only fetched on request.
+    let remote = std::sync::Arc::new(
+        RemoteOracle::new(
+            |sources: &[usize], targets: &[usize]| Ok(vec![1.; sources.len() * targets.len()]),
+            3,
+        )
+        .with_batch_size(1)
+        .with_prefetch(true),
+    );
+    remote.prepare().unwrap();
+    assert_eq!(3, remote.n_requests());
+    assert_eq!(3, remote.n_cached());
+
+    // A request in flight neither blocks queries for cached rows nor is sent twice.
+    let (release, wait) = std::sync::mpsc::channel::<()>();
+    let wait = Mutex::new(wait);
+    let blocking = RemoteOracle::new(
+        |sources: &[usize], targets: &[usize]| {
+            if sources.contains(&2) {
+                wait.lock().unwrap().recv().unwrap();
+            }
+            Ok(vec![1.; sources.len() * targets.len()])
+        },
+        4,
+    )
+    .with_batch_size(1);
+    blocking.prefetch(&[0]).unwrap();
+    thread::scope(|scope| {
+        let first = scope.spawn(|| blocking.cost(2, 1));
+        while blocking.n_requests() < 2 {
+            thread::yield_now();
+        }
+        let second = scope.spawn(|| blocking.cost(2, 3));
+        assert_eq!(1., blocking.cost(0, 1));
+        release.send(()).unwrap();
+        assert_eq!(1., first.join().unwrap());
+        assert_eq!(1., second.join().unwrap());
+    });
+    assert_eq!(2, blocking.n_requests());
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        let body = r#"{"code":"Ok","durations":[[0,7,9]]}"#;
+        write!(
+            stream,
+            "HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+        line
+    });
+    let osrm = OsrmService::new(&addr, vec![(13.4, 52.5), (13.5, 52.5), (13.4, 52.6)]);
+    assert_eq!(vec![0., 7., 9.], osrm.table(&[1], &[1, 0, 2]).unwrap());
+    assert!(server.join().unwrap().starts_with(
+        "GET /table/v1/driving/13.5,52.5;13.4,52.5;13.4,52.6?sources=0&destinations=0;1;2"
+    ));
+}
+
+#[test]
+fn test_scenario_compare() {
+    let mut model = Model::new(Metric::Euc2d, 3);
+    for ii in 0..8 {
+        model.add(NodeKind::Target, vec![(ii % 4) as f64, (ii / 4) as f64], ());
+    }
+
+    for solver in [
+        SolverKind::Greedy(vec![0]),
+        SolverKind::Lns {
+            starters: vec![0],
+            n_remove: 3,
+            termination: Termination::Iterations(100),
+            seed: 1,
+        },
+    ] {
+        let config = RunConfigBuilder::new().cands(5).solver(solver).build();
+        let baseline = model.solve(&config).unwrap().unwrap();
+        let (a, b) = (baseline.order()[0], baseline.order()[1]);
+
+        let mut expensive = CostOverlay::new();
+        expensive.set(a, b, 100.);
+        let overlays = [CostOverlay::new(), expensive];
+        let comparison = scenario::compare(&mut model, &overlays, &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(baseline.order(), comparison.baseline().order());
+        assert_eq!(2, comparison.scenarios().len());
+
+        let unchanged = &comparison.scenarios()[0];
+        assert!(approx_eq!(f64, baseline.cost(), unchanged.baseline_cost()));
+        assert!(unchanged.delta() <= 1e-9);
+        assert!(unchanged.savings() >= 0.);
+
+        let changed = &comparison.scenarios()[1];
+        let d = model.store().cost(&a, &b);
+        assert!(approx_eq!(
+            f64,
+            baseline.cost() - d + 100.,
+            changed.baseline_cost(),
+            epsilon = 1e-9
+        ));
+        assert!(changed.cost() < 100.);
+        assert!(changed.savings() > 0.);
+        assert!(changed.delta() > 0.);
+        assert!(changed.changed_edges() > 0);
+        assert_eq!(8, changed.tour().len());
+    }
+}
+
+#[test]
+fn test_robustness() {
+    use crate::robustness::{self, Noise};
+
+    let mut model = Model::new(Metric::Euc2d, 3);
+    for ii in 0..8 {
+        model.add(NodeKind::Target, vec![(ii % 4) as f64, (ii / 4) as f64], ());
+    }
+    let config = RunConfigBuilder::new().cands(5).build();
+    let tour = model.solve(&config).unwrap().unwrap();
+
+    // Without noise, the tour is measured at its own cost and cannot be beaten by re-optimising.
+    let exact = robustness::evaluate(&mut model, &tour, Noise::Uniform(0.), 3, &config, 1).unwrap();
+    assert_eq!(3, exact.len());
+    assert!(approx_eq!(
+        f64,
+        tour.cost(),
+        exact.mean_cost(),
+        epsilon = 1e-9
+    ));
+    assert!(exact.std_cost() < 1e-9);
+    assert!(exact.mean_regret().abs() < 1e-9);
+    assert_eq!(1., exact.best_fraction());
+
+    for noise in [Noise::Uniform(0.5), Noise::Gaussian(0.3)].iter().copied() {
+        let result = robustness::evaluate(&mut model, &tour, noise, 10, &config, 7).unwrap();
+        assert_eq!(10, result.ranks.len());
+        assert!(result.std_cost() > 0.);
+        assert!(result.mean_regret() >= -1e-9);
+        assert!(result.ranks.iter().all(|rank| (1..=11).contains(rank)));
+        assert!(result.mean_rank() >= 1.);
+        assert_eq!(
+            result,
+            robustness::evaluate(&mut model, &tour, noise, 10, &config, 7).unwrap()
+        );
+    }
+
+    let short = TourOrder::with_ord(vec![0, 1, 2]);
+    assert!(robustness::evaluate(&mut model, &short, Noise::Uniform(0.1), 1, &config, 1).is_err());
+}
+
+#[test]
+fn test_lkh_files() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 4);
+    for pos in [[0., 0.], [3., 0.], [3., 4.], [0., 4.]] {
+        ds.add(NodeKind::Target, pos.to_vec(), ());
+    }
+    ds.compute();
+
+    let config = RunConfigBuilder::new()
+        .cands(7)
+        .solver(SolverKind::Adaptive {
+            starters: vec![0],
+            budget: std::time::Duration::from_millis(1500),
+            seed: 42,
+        })
+        .build();
+    let dir = std::env::temp_dir().join(format!("cykl-test-lkh-{}", std::process::id()));
+    let files = lkh::write_par_and_problem(&ds, &LkhConfig::from(&config), &dir).unwrap();
+
+    assert!(files.problem.is_absolute() && files.tour.is_absolute());
+    let par = std::fs::read_to_string(&files.par).unwrap();
+    assert!(par.contains(&format!("PROBLEM_FILE = {}\n", files.problem.display())));
+    assert!(par.contains(&format!("TOUR_FILE = {}\n", files.tour.display())));
+    assert!(par.contains("MAX_CANDIDATES = 7\nSEED = 42\nTIME_LIMIT = 1.5\n"));
+
+    let problem = std::fs::read_to_string(&files.problem).unwrap();
+    assert!(problem.contains("DIMENSION : 4\n"));
+    assert!(problem.contains("EDGE_WEIGHT_SECTION\n3 5 4\n4 5\n3\nEOF\n"));
+
+    std::fs::write(
+        &files.tour,
+        "NAME : cykl.14.tour\nCOMMENT : Length = 14\nCOMMENT : Found by LKH\nTYPE : TOUR\n\
+         DIMENSION : 4\nTOUR_SECTION\n1\n4\n3\n2\n-1\nEOF\n",
+    )
+    .unwrap();
+    let tour = lkh::read_tour(&ds, &files.tour).unwrap();
+    assert_eq!(&vec![0, 3, 2, 1], tour.order().order());
+    assert_eq!(14., tour.order().cost());
+    assert_eq!(Some(14), tour.length());
+
+    std::fs::write(&files.tour, "TOUR_SECTION\n1\n2\n3\n4\n-1\n").unwrap();
+    assert_eq!(None, lkh::read_tour(&ds, &files.tour).unwrap().length());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_write_explicit_asymmetric() {
+    let mut ds = DataStore::with_capacity(Metric::Explicit, 3);
+    for _ in 0..3 {
+        ds.add(NodeKind::Target, Vec::new(), ());
+    }
+    ds.set_costs(vec![0., 1., 2., 1., 0., 3., 4., 3., 0.])
+        .unwrap();
+    ds.compute();
+
+    assert_eq!(Some((0, 2)), crate::interop::asymmetric_pair(&ds, 1.));
+    let mut problem = Vec::new();
+    crate::interop::write_explicit(&ds, "cykl", 1., &mut problem).unwrap();
+    let problem = String::from_utf8(problem).unwrap();
+    assert!(problem.contains(
+        "TYPE : ATSP
+"
+    ));
+    assert!(problem.contains(
+        "EDGE_WEIGHT_FORMAT : FULL_MATRIX
+"
+    ));
+    assert!(problem.contains("EDGE_WEIGHT_SECTION\n0 1 2\n1 0 3\n4 3 0\nEOF\n"));
+
+    // Differences lost by rounding do not make the instance asymmetric.
+    assert_eq!(None, crate::interop::asymmetric_pair(&ds, 0.1));
+}
+
+#[test]
+fn test_parse_permutation() {
+    use crate::interop::permutation::{parse_permutation, PermutationFormat};
+
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 4);
+    for pos in [[0., 0.], [3., 0.], [3., 4.], [0., 4.]] {
+        ds.add(NodeKind::Target, pos.to_vec(), ());
+    }
+    ds.compute();
+
+    let inputs = [
+        (PermutationFormat::OneBased, "1 4\n3, 2\n"),
+        (PermutationFormat::Json, " [0, 3,2 ,1]\n"),
+        (
+            PermutationFormat::Lkh,
+            "NAME : x\nTYPE : TOUR\nDIMENSION : 4\nTOUR_SECTION\n1\n4\n3\n2\n-1\nEOF\n",
+        ),
+        (
+            PermutationFormat::OrTools,
+            "Objective: 14\nRoute for vehicle 0:\n 0 -> 3 -> 2 -> 1 -> 0\nDistance of the route: 14m\n",
+        ),
+    ];
+    for (format, text) in inputs.iter() {
+        let order = parse_permutation(&ds, text, *format).unwrap();
+        assert_eq!(&vec![0, 3, 2, 1], order.order(), "{:?}", format);
+        assert_eq!(14., order.cost());
+    }
+
+    assert!(matches!(
+        parse_permutation(&ds, "1 2 3", PermutationFormat::OneBased),
+        Err(CyklError::Tour(_))
+    ));
+    assert!(matches!(
+        parse_permutation(&ds, "[0, 1, 1, 2]", PermutationFormat::Json),
+        Err(CyklError::DuplicateNode(1))
+    ));
+    assert!(matches!(
+        parse_permutation(&ds, "[0, 1, 2, 4]", PermutationFormat::Json),
+        Err(CyklError::IndexOutOfBounds { index: 4, len: 4 })
+    ));
+    assert!(matches!(
+        parse_permutation(&ds, "0 1 2 3", PermutationFormat::OneBased),
+        Err(CyklError::Parse(_))
+    ));
+    assert!(matches!(
+        parse_permutation(&ds, "0, 1, 2, 3", PermutationFormat::Json),
+        Err(CyklError::Parse(_))
+    ));
+}
+
+#[test]
+fn test_duplicates() {
+    let points = vec![
+        vec![0., 0.],
+        vec![1., 1.],
+        vec![0., 0.],
+        vec![2., 2.],
+        vec![1., 1.],
+        vec![-0., 0.],
+    ];
+
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, points.len());
+    for pt in &points {
+        ds.add(NodeKind::Target, pt.clone(), ());
+    }
+    assert_eq!(vec![vec![0, 2, 5], vec![1, 4]], ds.duplicates());
+
+    let mut ds = DataStore::new(Metric::Euc2d);
+    ds.set_duplicate_policy(DuplicatePolicy::Merge);
+    let indices: Vec<usize> = points
+        .iter()
+        .map(|pt| ds.add(NodeKind::Target, pt.clone(), ()).unwrap().index())
+        .collect();
+    assert_eq!(vec![0, 1, 0, 2, 1, 0], indices);
+    assert_eq!(3, ds.len());
+    assert!(ds.duplicates().is_empty());
+
+    // Nodes of different kinds are not merged, nor are nodes without coordinates.
+    let depot = ds.add(NodeKind::Depot, points[0].clone(), ()).unwrap();
+    assert_eq!(3, depot.index());
+    assert_eq!(Some(depot), ds.add(NodeKind::Depot, points[0].clone(), ()));
+    let mut ds = DataStore::new(Metric::Explicit);
+    ds.set_duplicate_policy(DuplicatePolicy::Merge);
+    for ii in 0..3 {
+        let node = ds.add(NodeKind::Target, Vec::new(), ()).unwrap();
+        assert_eq!(ii, node.index());
+    }
+
+    let mut ds = DataStore::new(Metric::Euc2d);
+    ds.set_duplicate_policy(DuplicatePolicy::Jitter(1e-3));
+    for pt in &points {
+        ds.add(NodeKind::Target, pt.clone(), ());
+    }
+    ds.compute();
+    // Only the costs are perturbed, the coordinates are kept.
+    assert_eq!(vec![vec![0, 2, 5], vec![1, 4]], ds.duplicates());
+    assert_eq!(Some((0., 0., 0.)), ds.coords(2));
+
+    for ii in 0..points.len() {
+        for jj in 0..points.len() {
+            if ii != jj {
+                assert!(ds.cost(&ii, &jj) > 0.);
+            }
+        }
+    }
+    assert!(ds.cost(&0, &2) < 1e-2);
+}
+
+#[test]
+fn test_greedy_with_duplicates() {
+    let mut ds = DataStore::new(Metric::Euc2d);
+    for ii in 0..12 {
+        ds.add(NodeKind::Target, vec![(ii % 4) as f64; 2], ());
+    }
+    ds.compute();
+
+    let mut tour = TwoLevelList::new(&ds, 4);
+    cand_gen_nn(&mut tour, 2, Objective::Minimize);
+    let result = solve_greedy(&mut tour, &[0, 5], Objective::Minimize)
+        .unwrap()
+        .unwrap();
+    assert_eq!(12, result.len());
+    assert!(approx_eq!(
+        f64,
+        6. * 2_f64.sqrt(),
+        result.cost(),
+        epsilon = 1e-10
+    ));
+}
+
+#[test]
+fn test_diff_svg() {
+    let mut ds = DataStore::new(Metric::Euc2d);
+    for pt in &[[0., 0.], [1., 0.], [1., 1.], [0., 1.]] {
+        ds.add(NodeKind::Target, pt.to_vec(), ());
+    }
+    ds.compute();
+
+    let old = TourOrder::with_ord(vec![0, 1, 2, 3]);
+    let new = TourOrder::with_ord(vec![0, 2, 1, 3]);
+
+    let path = std::env::temp_dir().join("cykl_test_diff.svg");
+    assert!(viz::diff_svg(&ds, &old, &new, &path).is_ok());
+    let svg = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(svg.starts_with("<svg"));
+    assert_eq!(4, svg.matches("<circle").count());
+    // Shared edges: (0, 3), (1, 2).
+    assert_eq!(2, svg.matches("#9e9e9e").count());
+    // Removed edges: (0, 1), (2, 3).
+    assert_eq!(2, svg.matches("#d32f2f").count());
+    // Added edges: (0, 2), (1, 3).
+    assert_eq!(2, svg.matches("#388e3c").count());
+
+    let ds = DataStore::<()>::new(Metric::Explicit);
+    assert!(matches!(
+        viz::diff_svg(&ds, &old, &new, &path),
+        Err(CyklError::MissingCoordinates)
+    ));
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn test_tour_png() {
+    let mut ds = DataStore::new(Metric::Euc2d);
+    for pt in &[[0., 0.], [1., 0.], [1., 1.], [0., 1.]] {
+        ds.add(NodeKind::Target, pt.to_vec(), ());
+    }
+    ds.compute();
+
+    let path = std::env::temp_dir().join("cykl_test_tour.png");
+    assert!(viz::tour_png(&ds, &TourOrder::with_nat_ord(4), &path).is_ok());
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(&[0x89, b'P', b'N', b'G'], &bytes[..4]);
+}
+
+#[test]
+fn test_geodesic_metrics() {
+    // One degree along a meridian.
+    let d = Metric::Haversine.cost(&[10., 20.], &[11., 20.]);
+    assert!(approx_eq!(f64, 111_195.08, d, epsilon = 1e-2));
+
+    // Flinders Peak to Buninyong, the reference example of Vincenty's paper.
+    let flinders = [
+        -(37. + 57. / 60. + 3.7203 / 3600.),
+        144. + 25. / 60. + 29.5244 / 3600.,
+    ];
+    let buninyong = [
+        -(37. + 39. / 60. + 10.1561 / 3600.),
+        143. + 55. / 60. + 35.3839 / 3600.,
+    ];
+    let d = Metric::Vincenty.cost(&flinders, &buninyong);
+    assert!(approx_eq!(f64, 54_972.271, d, epsilon = 1e-3));
+    assert!(approx_eq!(
+        f64,
+        0.,
+        Metric::Vincenty.cost(&flinders, &flinders)
+    ));
+
+    let mut ds = DataStore::new_geo();
+    assert!(ds.add_latlon(NodeKind::Target, 52.52, 13.405, ()).is_some());
+    assert!(ds
+        .add_latlon(NodeKind::Target, 48.8566, 2.3522, ())
+        .is_some());
+    ds.compute();
+    assert!((ds.cost(&0, &1) - 877_460.).abs() < 1_000.);
+
+    let mut ds = DataStore::new(Metric::Euc2d);
+    assert!(ds.add_latlon(NodeKind::Target, 52.52, 13.405, ()).is_none());
+}
+
+#[test]
+fn test_projection() {
+    // Points around Berlin, which lies in UTM zone 33N.
+    let points = [
+        [52.52, 13.405],
+        [52.50, 13.45],
+        [52.55, 13.30],
+        [52.45, 13.38],
+    ];
+
+    let mut ds = DataStore::new(Metric::Euc2d);
+    ds.set_projection(Projection::Utm {
+        zone: 33,
+        north: true,
+    });
+    for pt in &points {
+        ds.add(NodeKind::Target, pt.to_vec(), ());
+    }
+    ds.compute();
+
+    for ii in 0..points.len() {
+        assert_eq!(&points[ii][..], ds.origin(ii).unwrap());
+        for jj in 0..points.len() {
+            let exp = Metric::Vincenty.cost(&points[ii], &points[jj]);
+            assert!((exp - ds.cost(&ii, &jj)).abs() <= 1e-3 * exp);
+        }
+    }
+
+    // The central meridian of a zone is mapped onto the false easting.
+    let x = Projection::Utm {
+        zone: 31,
+        north: true,
+    }
+    .project(&[0., 3.]);
+    assert!(approx_eq!(f64, 500_000., x[0], epsilon = 1e-6));
+    assert!(approx_eq!(f64, 0., x[1], epsilon = 1e-6));
+
+    let x = Projection::WebMercator.project(&[0., 180.]);
+    assert!(approx_eq!(f64, 20_037_508.342789244, x[0], epsilon = 1e-6));
+
+    // Moves along Z cost five times as much as planar moves.
+    let mut ds = DataStore::with_axis_weights(1., 1., 5.);
+    ds.add(NodeKind::Target, vec![0., 0., 0.], ());
+    ds.add(NodeKind::Target, vec![3., 4., 0.], ());
+    ds.add(NodeKind::Target, vec![0., 0., 1.], ());
+    ds.compute();
+    assert_eq!(5., ds.cost(&0, &1));
+    assert_eq!(5., ds.cost(&0, &2));
+    assert_eq!(&[0., 0., 1.][..], ds.origin(2).unwrap());
+
+    let ds = DataStore::<()>::new(Metric::Euc2d);
+    assert!(ds.origin(0).is_none());
+}
+
+#[test]
+fn test_cancellation() {
+    let mut model = Model::new(Metric::Euc2d, 3);
+    for ii in 0..6 {
+        model.add(NodeKind::Target, vec![ii as f64, (ii % 2) as f64], ());
+    }
+
+    let token = CancellationToken::new();
+    assert_eq!(token, token.clone());
+    assert_ne!(token, CancellationToken::new());
+    let expired = token.with_deadline(std::time::Instant::now());
+    assert!(expired.is_cancelled() && !token.is_cancelled());
+    assert_eq!(token, expired);
+
+    let solver = SolverKind::Adaptive {
+        starters: vec![0],
+        budget: std::time::Duration::from_secs(60),
+        seed: 1,
+    };
+    let config = RunConfigBuilder::new()
+        .cands(2)
+        .solver(solver)
+        .cancellation(token.clone())
+        .build();
+
+    token.cancel();
+    assert!(matches!(model.solve(&config), Err(CyklError::Cancelled)));
+
+    // A token cancelled after the construction phase stops the time-budgeted improvement phase.
+    let token = CancellationToken::new();
+    let config = RunConfigBuilder::new()
+        .cands(2)
+        .solver(SolverKind::LateAcceptance {
+            starters: vec![0],
+            history: 4,
+            termination: Termination::TimeLimit(std::time::Duration::from_secs(60)),
+            seed: 1,
+        })
+        .cancellation(token.clone())
+        .build();
+    let incumbent = {
+        let token = token.clone();
+        std::sync::Arc::new(Incumbent::new().on_improvement(move |_| token.cancel()))
+    };
+    let result = model
+        .solve_with(&config, Some(&incumbent))
+        .unwrap()
+        .unwrap();
+    assert_eq!(6, result.len());
+    assert_eq!(
+        Some(result.cost()),
+        incumbent.best().map(|best| best.cost())
+    );
+}
+
+#[test]
+fn test_objective() {
+    let mut model = Model::new(Metric::Euc2d, 3);
+    for ii in 0..6 {
+        model.add(NodeKind::Target, vec![ii as f64, 0.], ());
+    }
+
+    let solve = |model: &mut Model<()>, objective| {
+        let config = RunConfigBuilder::new()
+            .cands(2)
+            .solver(SolverKind::Greedy(vec![0]))
+            .objective(objective)
+            .build();
+        model.solve(&config).unwrap().unwrap()
+    };
+
+    let shortest = solve(&mut model, Objective::Minimize);
+    assert!(approx_eq!(f64, 10., shortest.cost(), epsilon = 1e-10));
+
+    let longest = solve(&mut model, Objective::Maximize);
+    assert_eq!(&vec![0, 5, 1, 4, 2, 3], longest.order());
+    assert!(approx_eq!(f64, 18., longest.cost(), epsilon = 1e-10));
+
+    assert!(Objective::Maximize.is_better(longest.cost(), shortest.cost()));
+    assert!(Objective::Minimize.is_better(shortest.cost(), longest.cost()));
+
+    // The improvement phases keep the direction, instead of shortening the longest tour.
+    let solvers = vec![
+        SolverKind::LateAcceptance {
+            starters: vec![0],
+            history: 4,
+            termination: Termination::Iterations(200),
+            seed: 1,
+        },
+        SolverKind::Adaptive {
+            starters: vec![0],
+            budget: std::time::Duration::from_millis(20),
+            seed: 1,
+        },
+        SolverKind::Lns {
+            starters: vec![0],
+            n_remove: 2,
+            termination: Termination::Iterations(50),
+            seed: 1,
+        },
+    ];
+    for solver in solvers {
+        let config = RunConfigBuilder::new()
+            .cands(2)
+            .solver(solver.clone())
+            .objective(Objective::Maximize)
+            .build();
+        let longest = model.solve(&config).unwrap().unwrap();
+        assert!(
+            approx_eq!(f64, 18., longest.cost(), epsilon = 1e-10),
+            "{:?}",
+            solver
+        );
+    }
+}
+
+#[test]
+fn test_prize_collecting() {
+    let solve = |prize, objective| {
+        let mut model = Model::new(Metric::Euc2d, 2);
+        for pt in &[[0., 0.], [1., 0.], [1., 1.], [0., 1.], [50., 0.]] {
+            model.add(NodeKind::Target, pt.to_vec(), ());
+        }
+        for index in 0..4 {
+            model.set_prize(index, 100.).unwrap();
+        }
+        model.set_prize(4, prize).unwrap();
+        assert!(matches!(
+            model.set_prize(5, 1.),
+            Err(CyklError::IndexOutOfBounds { index: 5, len: 5 })
+        ));
+
+        let config = RunConfigBuilder::new()
+            .cands(2)
+            .solver(SolverKind::PrizeCollecting(vec![0]))
+            .objective(objective)
+            .build();
+        model.solve(&config).unwrap().unwrap()
+    };
+
+    // The detour to the remote node costs more than its prize.
+    let result = solve(10., Objective::Minimize);
+    assert_eq!(4, result.len());
+    assert!(!result.order().contains(&4));
+    assert!(approx_eq!(f64, 14., result.cost(), epsilon = 1e-10));
+
+    // When maximising, the detour is worth more than the prize.
+    let longest = solve(10., Objective::Maximize);
+    assert!(longest.order().contains(&4));
+    assert!(Objective::Maximize.is_better(longest.cost(), result.cost()));
+
+    let result = solve(200., Objective::Minimize);
+    assert_eq!(5, result.len());
+    assert!(approx_eq!(
+        f64,
+        53. + 2501_f64.sqrt(),
+        result.cost(),
+        epsilon = 1e-10
+    ));
+}
+
+#[test]
+fn test_generalized() {
+    let mut model = Model::new(Metric::Euc2d, 2);
+    let points = [
+        [10., 10.],
+        [0., 0.],
+        [10., 0.],
+        [1., 0.],
+        [0., 10.],
+        [0., 1.],
+        [5., 5.],
+    ];
+    for pt in &points {
+        model.add(NodeKind::Target, pt.to_vec(), ());
+    }
+    for index in 0..6 {
+        model.set_group(index, index / 2).unwrap();
+    }
+    assert!(model.set_group(7, 0).is_err());
+
+    let config = RunConfigBuilder::new()
+        .cands(3)
+        .solver(SolverKind::Generalized(vec![0]))
+        .build();
+    let result = model.solve(&config).unwrap().unwrap();
+
+    let mut order = result.order().clone();
+    order.sort_unstable();
+    assert_eq!(vec![1, 3, 5, 6], order);
+
+    let expected = 2. + 2. * 41_f64.sqrt();
+    assert!(approx_eq!(f64, expected, result.cost(), epsilon = 1e-10));
+
+    let config = RunConfigBuilder::new()
+        .cands(3)
+        .solver(SolverKind::Generalized(vec![0]))
+        .objective(Objective::Maximize)
+        .build();
+    let longest = model.solve(&config).unwrap().unwrap();
+    assert_eq!(4, longest.len());
+    assert!(Objective::Maximize.is_better(longest.cost(), result.cost()));
+}
+
+#[test]
+fn test_late_acceptance() {
+    let mut model = Model::new(Metric::Euc2d, 4);
+    for ii in 0..16 {
+        let pos = vec![((ii * 7) % 16) as f64, ((ii * 5) % 11) as f64];
+        model.add(NodeKind::Target, pos, ());
+    }
+
+    let greedy = model
+        .solve(&RunConfigBuilder::new().cands(5).build())
+        .unwrap()
+        .unwrap();
+
+    let config = RunConfigBuilder::new()
+        .cands(5)
+        .solver(SolverKind::LateAcceptance {
+            starters: vec![0],
+            history: 10,
+            termination: Termination::Iterations(2000),
+            seed: 5,
+        })
+        .build();
+    let result = model.solve(&config).unwrap().unwrap();
+
+    assert_eq!(16, result.len());
+    assert!(result.cost() <= greedy.cost());
+}
+
+#[test]
+fn test_noon_bean() {
+    let mut ds = DataStore::new(Metric::Euc2d);
+    let points = [[0., 0.], [5., 5.], [4., 0.], [1., 1.], [0., 4.], [6., 1.]];
+    for pt in &points {
+        ds.add(NodeKind::Target, pt.to_vec(), ());
+    }
+    ds.compute();
+
+    let groups = vec![vec![0, 1], vec![2, 3], vec![4, 5]];
+    let cost = |store: &DataStore<()>, order: &[usize]| {
+        (0..order.len())
+            .map(|ii| store.cost(&order[ii], &order[(ii + 1) % order.len()]))
+            .sum::<f64>()
+    };
+
+    let mut gtsp_best = f64::MAX;
+    for a in &groups[0] {
+        for b in &groups[1] {
+            for c in &groups[2] {
+                gtsp_best = gtsp_best.min(cost(&ds, &[*a, *b, *c]));
+            }
+        }
+    }
+
+    let nb = transform::noon_bean(&ds, &groups).unwrap();
+    assert_eq!(3, nb.n_clusters());
+
+    // Enumerates all tours of the transformed instance that start at node 0.
+    let mut atsp_best = (f64::MAX, Vec::new());
+    let mut stack = vec![vec![0]];
+    while let Some(prefix) = stack.pop() {
+        if prefix.len() == points.len() {
+            let c = cost(nb.store(), &prefix);
+            if c < atsp_best.0 {
+                atsp_best = (c, prefix);
+            }
+            continue;
+        }
+
+        for next in (0..points.len()).filter(|x| !prefix.contains(x)) {
+            let mut v = prefix.clone();
+            v.push(next);
+            stack.push(v);
+        }
+    }
+
+    let order = nb.map_back(&TourOrder::with_ord(atsp_best.1));
+    assert_eq!(3, order.len());
+    assert!(approx_eq!(
+        f64,
+        gtsp_best,
+        cost(&ds, order.order()),
+        epsilon = 1e-9
+    ));
+    assert!(approx_eq!(
+        f64,
+        gtsp_best + 3. * nb.penalty(),
+        atsp_best.0,
+        epsilon = 1e-9
+    ));
+
+    assert!(matches!(
+        transform::noon_bean(&ds, &[vec![0, 1], vec![1]]),
+        Err(CyklError::DuplicateNode(1))
+    ));
+}
+
+#[test]
+fn test_diversity() {
+    let div = stats::diversity(&[TourOrder::with_nat_ord(5)]);
+    assert_eq!(stats::Diversity::default(), div);
+
+    // Rotated and reflected tours share all edges.
+    let same = [
+        TourOrder::with_ord(vec![0, 1, 2, 3, 4]),
+        TourOrder::with_ord(vec![2, 3, 4, 0, 1]),
+        TourOrder::with_ord(vec![4, 3, 2, 1, 0]),
+    ];
+    let div = stats::diversity(&same);
+    assert!(approx_eq!(f64, 0., div.edge_distance, epsilon = 1e-10));
+    assert!(approx_eq!(f64, 0., div.entropy, epsilon = 1e-10));
+
+    let disjoint = [
+        TourOrder::with_ord(vec![0, 1, 2, 3, 4]),
+        TourOrder::with_ord(vec![0, 2, 4, 1, 3]),
+    ];
+    let div = stats::diversity(&disjoint);
+    assert!(approx_eq!(f64, 1., div.edge_distance, epsilon = 1e-10));
+    assert!(approx_eq!(f64, 1., div.entropy, epsilon = 1e-10));
+
+    // The tours share the edges (0, 1), (1, 2) and (3, 4).
+    let partial = [
+        TourOrder::with_ord(vec![0, 1, 2, 3, 4]),
+        TourOrder::with_ord(vec![0, 1, 2, 4, 3]),
+    ];
+    let div = stats::diversity(&partial);
+    assert!(approx_eq!(f64, 0.4, div.edge_distance, epsilon = 1e-10));
+    assert!(div.entropy > 0. && div.entropy < 1.);
+}
+
+#[test]
+fn test_stability() {
+    let old = TourOrder::with_ord(vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(
+        1.,
+        stats::stability(&old, &TourOrder::with_ord(vec![3, 2, 1, 0, 5, 4]))
+    );
+    assert_eq!(1., stats::stability(&TourOrder::with_nat_ord(1), &old));
+
+    // A 2-opt move replaces the edges (1, 2) and (4, 5).
+    let new = TourOrder::with_ord(vec![0, 1, 4, 3, 2, 5]);
+    assert!(approx_eq!(f64, 4. / 6., stats::stability(&old, &new)));
+    assert_eq!(2., Stability::new(&old).penalty(&new));
+    assert_eq!(0., stats::stability(&old, &TourOrder::default()));
+
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 8);
+    for ii in 0..8 {
+        let angle = ii as f64 * std::f64::consts::PI / 4.;
+        ds.add(NodeKind::Target, vec![angle.cos(), angle.sin()], ());
+    }
+    ds.compute();
+
+    let reference = TourOrder::with_ord(vec![0, 2, 1, 3, 4, 6, 5, 7]);
+    let solve = |weight| {
+        let mut tour = TwoLevelList::new(&ds, 3);
+        cand_gen_nn(&mut tour, 5, Objective::Minimize);
+        tour.apply(&reference).unwrap();
+        let objective =
+            Penalized::new(Objective::Minimize).with_penalty(weight, Stability::new(&reference));
+        Lns::new(3, 7)
+            .run_with_objective(
+                &mut tour,
+                &mut Greedy,
+                Termination::Iterations(100),
+                &objective,
+            )
+            .unwrap()
+    };
+
+    assert!(stats::stability(&reference, &solve(0.)) < 1.);
+    assert_eq!(1., stats::stability(&reference, &solve(1000.)));
+}
+
+#[test]
+fn test_turn_penalty() {
+    use std::f64::consts::PI;
+
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 5);
+    for pos in [[0., 0.], [1., 0.], [1., 1.], [0., 1.], [2., 0.]].iter() {
+        ds.add(NodeKind::Target, pos.to_vec(), ());
+    }
+    ds.compute();
+
+    let turns = TurnPenalty::new(&ds);
+    assert!(approx_eq!(f64, 0., turns.angle(0, 1, 4)));
+    assert!(approx_eq!(f64, PI, turns.angle(0, 1, 0)));
+    assert!(approx_eq!(f64, PI / 2., turns.angle(0, 1, 2)));
+
+    let square = TourOrder::with_ord(vec![0, 1, 2, 3]);
+    let crossed = TourOrder::with_ord(vec![0, 2, 1, 3]);
+    assert!(approx_eq!(f64, 2. * PI, turns.penalty(&square)));
+    assert!(approx_eq!(f64, 3. * PI, turns.penalty(&crossed)));
+    assert_eq!(0., turns.penalty(&TourOrder::with_ord(vec![0, 1])));
+
+    let sharp = turns.with_threshold(PI / 2.);
+    assert!(approx_eq!(f64, 0., sharp.penalty(&square)));
+    assert!(approx_eq!(f64, PI, sharp.penalty(&crossed)));
+
+    let objective = Penalized::new(Objective::Minimize).with_penalty(1., sharp);
+    let mut order = square.clone();
+    order.set_cost(4.);
+    assert!(approx_eq!(f64, 4., objective.evaluate(&order)));
+}
+
+#[test]
+fn test_nearest_fragment() {
+    let mut model = Model::new(Metric::Euc2d, 4);
+    for ii in 0..16 {
+        let pos = vec![((ii * 7) % 16) as f64, ((ii * 5) % 11) as f64];
+        model.add(NodeKind::Target, pos, ());
+    }
+
+    let config = RunConfigBuilder::new()
+        .cands(5)
+        .solver(SolverKind::NearestFragment { leaf_size: 4 })
+        .build();
+    let result = model.solve(&config).unwrap().unwrap();
+
+    assert_eq!(16, result.len());
+    assert!(result.cost() > 0.);
+}
+
+#[test]
+fn test_sample_random_tours() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let len = 12;
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, len);
+    for ii in 0..len {
+        let angle = ii as f64 * 2. * std::f64::consts::PI / len as f64;
+        ds.add(NodeKind::Target, vec![angle.cos(), angle.sin()], ());
+    }
+    ds.compute();
+    let optimal = crate::oracle::cycle_cost(&ds, &(0..len).collect::<Vec<_>>());
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let sample = stats::sample_random_tours(&ds, 200, &mut rng);
+    assert_eq!(len, sample.n_nodes);
+    assert_eq!(200, sample.lengths.len());
+    assert!(sample.lengths.windows(2).all(|w| w[0] <= w[1]));
+    assert!(sample.lengths[0] >= optimal - 1e-9);
+    assert!(approx_eq!(
+        f64,
+        sample.lengths.iter().sum::<f64>() / 200.,
+        sample.mean,
+        epsilon = 1e-9
+    ));
+    assert!(sample.std > 0.);
+
+    assert_eq!(sample.lengths[0], sample.quantile(0.));
+    assert_eq!(sample.lengths[199], sample.quantile(1.));
+    let median = sample.quantile(0.5);
+    assert!(sample.lengths[99] <= median && median <= sample.lengths[100]);
+    assert!(approx_eq!(
+        f64,
+        sample.mean / len as f64 / 2_f64.ln(),
+        sample.temperature(0.5)
+    ));
+
+    let empty = stats::sample_random_tours(&ds, 0, &mut rng);
+    assert!(empty.lengths.is_empty());
+    assert_eq!(0., empty.quantile(0.5));
+}
+
+#[test]
+fn test_quality_report() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 4);
+    for pos in [[0., 0.], [1., 0.], [1., 1.], [0., 1.]] {
+        ds.add(NodeKind::Target, pos.to_vec(), ());
+    }
+    ds.compute();
+
+    let mut tour = TwoLevelList::new(&ds, 2);
+    tour.apply(&TourOrder::with_ord(vec![0, 2, 1, 3])).unwrap();
+
+    let histogram = stats::edge_histogram(&tour, 2);
+    assert_eq!(vec![2, 2], histogram.counts);
+    assert!(approx_eq!(f64, 1., histogram.min, epsilon = 1e-10));
+    assert!(approx_eq!(
+        f64,
+        2_f64.sqrt(),
+        histogram.max,
+        epsilon = 1e-10
+    ));
+
+    let report = stats::quality_report(&tour, &ds);
+    assert_eq!(4, report.n_nodes);
+    assert_eq!(Some(1), report.crossings);
+    assert!(approx_eq!(f64, 3., report.mst_weight, epsilon = 1e-10));
+    assert!(approx_eq!(f64, 4., report.held_karp_bound, epsilon = 1e-6));
+    assert!(report.gap() > 0.);
+
+    tour.apply(&TourOrder::with_nat_ord(4)).unwrap();
+    let report = stats::quality_report(&tour, &ds);
+    assert_eq!(Some(0), report.crossings);
+    assert!(approx_eq!(f64, 1., report.max_edge, epsilon = 1e-10));
+    assert!(approx_eq!(f64, 0., report.gap(), epsilon = 1e-6));
+}
+
+#[test]
+fn test_relatedness() {
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 8);
+    for ii in 0..8 {
+        let angle = ii as f64 * std::f64::consts::PI / 4.;
+        ds.add(NodeKind::Target, vec![angle.cos(), angle.sin()], ());
+    }
+    ds.compute();
+
+    let mut tour = TwoLevelList::new(&ds, 4);
+    tour.apply(&TourOrder::with_nat_ord(8)).unwrap();
+
+    assert!(approx_eq!(
+        f64,
+        1.,
+        stats::relatedness(&tour, 3, 3),
+        epsilon = 1e-10
+    ));
+    let near = stats::relatedness(&tour, 3, 4);
+    let far = stats::relatedness(&tour, 3, 6);
+    assert!(near > far && far > 0.);
+    assert!(approx_eq!(
+        f64,
+        near,
+        stats::relatedness(&tour, 4, 3),
+        epsilon = 1e-10
+    ));
+
+    // Nodes at the ends of the order are adjacent along the tour.
+    assert!(approx_eq!(
+        f64,
+        stats::relatedness(&tour, 0, 1),
+        stats::relatedness(&tour, 0, 7),
+        epsilon = 1e-10
+    ));
+
+    tour.exclude(6).unwrap();
+    assert_eq!(0., stats::relatedness(&tour, 3, 6));
+}
+
+#[test]
+fn test_candidate_set() {
+    let mut ds = DataStore::new(Metric::Euc2d);
+    for ii in 0..8 {
+        ds.add(NodeKind::Target, vec![ii as f64, (ii % 3) as f64], ());
+    }
+    ds.compute();
+
+    let mut tour = TwoLevelList::new(&ds, 3);
+    cand_gen_nn(&mut tour, 3, Objective::Minimize);
+    let set = CandidateSet::from_tour(&tour, &ds);
+
+    let path = std::env::temp_dir().join("cykl_test_candidates.bin");
+    set.save(&path).unwrap();
+    let loaded = CandidateSet::load(&path, &ds);
+
+    let mut other = DataStore::new(Metric::Euc2d);
+    for ii in 0..8 {
+        other.add(NodeKind::Target, vec![ii as f64, 0.], ());
+    }
+    other.compute();
+    let mismatched = CandidateSet::load(&path, &other);
+
+    // A corrupted number of candidates is rejected instead of reserving memory for it.
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[28..32].copy_from_slice(&u32::MAX.to_le_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+    let corrupted = CandidateSet::load(&path, &ds);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(corrupted, Err(CyklError::Parse(_))));
+
+    let loaded = loaded.unwrap();
+    assert_eq!(set, loaded);
+    assert!(matches!(
+        mismatched,
+        Err(CyklError::InstanceMismatch { .. })
+    ));
+
+    let mut fresh = TwoLevelList::new(&ds, 3);
+    loaded.apply(&mut fresh).unwrap();
+    for (a, b) in tour.itr().zip(fresh.itr()) {
+        let expected: Vec<_> = a.candidates().iter().map(|c| c.index()).collect();
+        let received: Vec<_> = b.candidates().iter().map(|c| c.index()).collect();
+        assert_eq!(expected, received);
+    }
+}
+
+#[test]
+fn test_candidate_set_symmetrize() {
+    let mut ds = DataStore::new(Metric::Euc2d);
+    for ii in 0..10 {
+        ds.add(
+            NodeKind::Target,
+            vec![(ii * ii) as f64, (ii % 4) as f64],
+            (),
+        );
+    }
+    ds.compute();
+
+    let mut tour = TwoLevelList::new(&ds, 3);
+    cand_gen_nn(&mut tour, 3, Objective::Minimize);
+    let mut set = CandidateSet::from_tour(&tour, &ds);
+    set.symmetrize(&ds, 4);
+
+    for a in 0..set.len() {
+        let cands = set.candidates(a).unwrap();
+        assert!(cands.len() <= 4);
+        assert!(!cands.contains(&a));
+        for b in cands {
+            assert!(set.candidates(*b).unwrap().contains(&a));
+        }
+    }
+
+    // The nearest neighbour of each node is ranked first by both endpoints and thus kept.
+    for a in 0..set.len() {
+        let nearest = tour.get(a).unwrap().candidates()[0].index().get();
+        assert!(set.candidates(a).unwrap().contains(&nearest));
+    }
+}
+
+#[test]
+fn test_fingerprint() {
+    let create = |offset: f64| {
+        let mut ds = DataStore::new(Metric::Euc2d);
+        for ii in 0..6 {
+            ds.add(
+                NodeKind::Target,
+                vec![ii as f64 + offset, (ii % 2) as f64],
+                (),
+            );
+        }
+        ds.compute();
+        ds
+    };
+    let ds = create(0.);
+    let other = create(0.5);
+    assert_eq!(ds.fingerprint(), create(0.).fingerprint());
+    assert_ne!(ds.fingerprint(), other.fingerprint());
+
+    let order = TourOrder::with_ord(vec![0, 2, 4, 5, 3, 1]);
+    let path = std::env::temp_dir().join("cykl_test_fingerprint.tour");
+    crate::save_tour(&ds, &order, &path).unwrap();
+    let loaded = crate::load_tour(&ds, &path);
+    let mismatched = crate::load_tour(&other, &path);
+    std::fs::remove_file(&path).unwrap();
+
+    let loaded = loaded.unwrap();
+    assert_eq!(order.order(), loaded.order());
+    assert!(loaded.cost() > 0.);
+    assert!(matches!(
+        mismatched,
+        Err(CyklError::InstanceMismatch { .. })
+    ));
+
+    let tour = TwoLevelList::new(&ds, 3);
+    let state = SearchState::new(&tour, Greedy, 0);
+    assert!(state.verify(&other).is_ok());
+    let state = state.with_fingerprint(&ds);
+    assert!(state.verify(&ds).is_ok());
+    assert!(matches!(
+        state.verify(&other),
+        Err(CyklError::InstanceMismatch { .. })
+    ));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_solve_async() {
+    use crate::service::{solve_async, CancellationToken};
+    use futures::executor::block_on_stream;
+
+    let build = || {
+        let mut model = Model::new(Metric::Euc2d, 8);
+        for ii in 0..64 {
+            let pos = vec![((ii * 37) % 101) as f64, ((ii * 53) % 89) as f64];
+            model.add(NodeKind::Target, pos, ());
+        }
+        Ok(model)
+    };
+    let config = RunConfigBuilder::new()
+        .cands(5)
+        .solver(SolverKind::LateAcceptance {
+            starters: vec![0],
+            history: 10,
+            termination: Termination::Iterations(2000),
+            seed: 5,
+        })
+        .build();
+
+    let token = CancellationToken::new();
+    let improvements: Vec<_> = block_on_stream(solve_async(build, config.clone(), token.clone()))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    // Besides the constructed tour, the tours found during the improvement phase are reported.
+    assert!(improvements.len() > 2);
+    assert!(improvements
+        .windows(2)
+        .all(|w| w[1].order.cost() < w[0].order.cost()));
+    let solved = build().unwrap().solve(&config).unwrap().unwrap();
+    assert_eq!(solved.cost(), improvements.last().unwrap().order.cost());
+
+    // Failures are reported as the last item instead of silently ending the stream.
+    let failed: Vec<_> = block_on_stream(solve_async(
+        || -> Result<Model<()>, CyklError> { panic!("broken instance") },
+        config.clone(),
+        CancellationToken::new(),
+    ))
+    .collect();
+    assert!(matches!(&failed[..], [Err(CyklError::Panicked(msg))] if msg == "broken instance"));
+
+    token.cancel();
+    assert_eq!(
+        0,
+        block_on_stream(solve_async(build, config, token)).count()
+    );
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_from_arrow() {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{Float64Array, Int32Array},
+        record_batch::RecordBatch,
+    };
+
+    let x = Float64Array::from(vec![0., 3., 3.]);
+    let y = Int32Array::from(vec![0, 0, 4]);
+    let batch =
+        RecordBatch::try_from_iter(vec![("x", Arc::new(x) as _), ("y", Arc::new(y) as _)]).unwrap();
+
+    let model = crate::from_arrow(&batch, Metric::Euc2d, 4).unwrap();
+    assert_eq!(3, model.store().len());
+    assert!(approx_eq!(f64, 5., model.store().cost(&0, &2)));
+
+    assert!(matches!(
+        crate::from_arrow(&batch, Metric::Euc3d, 4),
+        Err(CyklError::MissingCoordinates)
+    ));
+}
+
+#[test]
+fn test_read_csv() {
+    use crate::csv::{read_csv, ColumnMap};
+
+    let data = "name;lon;lat;demand\n\"a;1\";0;0;2\n\nb;3;0;1.5\nc;3;4;0\n";
+    let map = ColumnMap::new()
+        .x("lon")
+        .y("lat")
+        .id("name")
+        .demand("demand")
+        .delimiter(';');
+    let model = read_csv(data.as_bytes(), &map, Metric::Euc2d, 4).unwrap();
+    assert_eq!(3, model.store().len());
+    assert!(approx_eq!(f64, 5., model.store().cost(&0, &2)));
+
+    let map = ColumnMap::new().x(1).y(2).header(false);
+    let model = read_csv("a,0,0\nb,3,4\n".as_bytes(), &map, Metric::Euc2d, 4).unwrap();
+    assert!(approx_eq!(f64, 5., model.store().cost(&0, &1)));
+
+    assert!(matches!(
+        read_csv("x,y\n1,a\n".as_bytes(), &ColumnMap::new(), Metric::Euc2d, 4),
+        Err(CyklError::Parse(_))
+    ));
+}
+
+#[test]
+fn test_tour_gpx_kml() {
+    let mut ds = DataStore::new_geo();
+    for (lat, lon) in &[(48.1, 11.5), (48.2, 11.6), (48.3, 11.4)] {
+        ds.add_latlon(NodeKind::Target, *lat, *lon, ());
+    }
+    ds.compute();
+    let order = TourOrder::with_ord(vec![0, 2, 1]);
+
+    let path = std::env::temp_dir().join("cykl_test_tour.gpx");
+    viz::tour_gpx(&ds, &order, &path).unwrap();
+    let gpx = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(4, gpx.matches("<trkpt").count());
+    assert!(gpx.contains("<trkpt lat=\"48.3\" lon=\"11.4\">"));
+
+    let path = std::env::temp_dir().join("cykl_test_tour.kml");
+    viz::tour_kml(&ds, &order, &path).unwrap();
+    let kml = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(kml.contains("11.5,48.1\n11.4,48.3\n11.6,48.2\n11.5,48.1\n"));
+
+    let mut ds = DataStore::new(Metric::Euc2d);
+    ds.add(NodeKind::Target, vec![0., 0.], ());
+    assert!(matches!(
+        viz::tour_gpx(&ds, &TourOrder::with_nat_ord(1), &path),
+        Err(CyklError::MissingCoordinates)
+    ));
+}
+
+#[cfg(feature = "routing")]
+#[test]
+fn test_costs_from_osrm_json() {
+    use crate::TableAnnotation;
+
+    let build = || {
+        let mut model = Model::new(Metric::Explicit, 4);
+        for _ in 0..3 {
+            model.add(NodeKind::Target, Vec::new(), ());
+        }
+        model
+    };
+
+    let osrm = br#"{"code":"Ok","durations":[[0,10,20],[11,0,5],[21,6,0]]}"#;
+    let mut model = build();
+    model
+        .costs_from_osrm_json(osrm, TableAnnotation::Duration)
+        .unwrap();
+    model.complete();
+    assert!(approx_eq!(f64, 5., model.store().cost(&1, &2)));
+    assert!(model
+        .costs_from_osrm_json(osrm, TableAnnotation::Distance)
+        .is_err());
+
+    let valhalla = br#"{"sources_to_targets":[
+        [{"distance":0,"time":0},{"distance":1.5,"time":9},{"distance":2,"time":12}],
+        [{"distance":1.5,"time":9},{"distance":0,"time":0},{"distance":0.5,"time":4}],
+        [{"distance":2,"time":12},{"distance":0.5,"time":4},{"distance":0,"time":0}]]}"#;
+    let mut model = build();
+    model
+        .costs_from_osrm_json(valhalla, TableAnnotation::Distance)
+        .unwrap();
+    model.complete();
+    assert!(approx_eq!(f64, 500., model.store().cost(&1, &2)));
+
+    let unreachable = br#"{"durations":[[0,null],[3,0]]}"#;
+    assert!(matches!(
+        Model::<()>::new(Metric::Explicit, 4)
+            .costs_from_osrm_json(unreachable, TableAnnotation::Duration),
+        Err(CyklError::Parse(_))
+    ));
+}
+
+#[cfg(all(feature = "concorde", unix))]
+#[test]
+fn test_concorde() {
+    use std::os::unix::fs::PermissionsExt;
+
+    use crate::concorde::{self, Concorde};
+
+    let mut ds = DataStore::with_capacity(Metric::Euc2d, 4);
+    for pos in [[0., 0.], [1.5, 0.], [1.5, 1.], [0., 1.]] {
+        ds.add(NodeKind::Target, pos.to_vec(), ());
+    }
+    ds.compute();
+
+    let mut instance = Vec::new();
+    concorde::write_instance(&ds, 2., &mut instance).unwrap();
+    let instance = String::from_utf8(instance).unwrap();
+    assert!(instance.contains("DIMENSION : 4\n"));
+    assert!(instance.contains("EDGE_WEIGHT_SECTION\n3 4 2\n2 4\n3\nEOF\n"));
+
+    let mut asym = DataStore::with_capacity(Metric::Explicit, 2);
+    asym.add(NodeKind::Target, Vec::new(), ());
+    asym.add(NodeKind::Target, Vec::new(), ());
+    asym.set_costs(vec![0., 1., 2., 0.]).unwrap();
+    asym.compute();
+    assert!(matches!(
+        concorde::write_instance(&asym, 1., &mut Vec::new()),
+        Err(CyklError::Asymmetric { from: 0, to: 1 })
+    ));
+
+    assert_eq!(
+        vec![0, 3, 2, 1],
+        concorde::parse_solution("4\n0 3 2\n1\n", 4).unwrap()
+    );
+    assert!(matches!(
+        concorde::parse_solution("4\n0 3 3 1", 4),
+        Err(CyklError::DuplicateNode(3))
+    ));
+    assert!(matches!(
+        concorde::parse_solution("3\n0 1 2", 4),
+        Err(CyklError::LenMismatched { .. })
+    ));
+    assert!(matches!(
+        concorde::parse_solution("4\n0 x", 4),
+        Err(CyklError::Parse(_))
+    ));
+
+    // A stand-in for Concorde that writes a fixed tour into the file given by `-o`.
+    let dir = std::env::temp_dir().join(format!("cykl-test-concorde-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let binary = dir.join("concorde");
+    std::fs::write(
+        &binary,
+        "#!/bin/sh\nwhile [ \"$1\" != \"-o\" ]; do shift; done\nprintf '4\\n0 3 2 1\\n' > \"$2\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let order = Concorde::new(&binary).with_seed(1).solve(&ds).unwrap();
+    assert_eq!(&vec![0, 3, 2, 1], order.order());
+    assert!(approx_eq!(f64, 5., order.cost()));
+
+    let failing = Concorde::new(dir.join("missing")).solve(&ds);
+    assert!(matches!(failing, Err(CyklError::Io(_))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "config-toml")]
+#[test]
+fn test_run_config_toml() {
+    let config = crate::RunConfig::from_toml_str(
+        r#"
+        # Experiment 12
+        cands = 8
+        scan = "best"
+        deterministic = true
+
+        [solver]
+        kind = "lns"
+        starters = [0, 3]
+        n_remove = 4
+        stagnation = 1_000
+        seed = 42 # fixed for reproduction
+        "#,
+    )
+    .unwrap();
+    assert_eq!(8, config.cands());
+    assert_eq!(Objective::Minimize, config.objective());
+    assert_eq!(crate::alg::Scan::BestImprovement, config.scan());
+    assert!(config.is_deterministic());
+    assert_eq!(
+        &SolverKind::Lns {
+            starters: vec![0, 3],
+            n_remove: 4,
+            termination: Termination::Stagnation(1000),
+            seed: 42,
+        },
+        config.solver()
+    );
+    // Cancellation tokens compare by identity, so only the parameters are compared.
+    let same = |a: &crate::RunConfig, b: &crate::RunConfig| {
+        a.cands() == b.cands()
+            && a.solver() == b.solver()
+            && a.objective() == b.objective()
+            && a.scan() == b.scan()
+            && a.is_deterministic() == b.is_deterministic()
+    };
+    let reread = crate::RunConfig::from_toml_str(&config.to_toml().unwrap()).unwrap();
+    assert!(same(&config, &reread));
+
+    let default = crate::RunConfig::from_toml_str("").unwrap();
+    assert!(same(&RunConfigBuilder::new().build(), &default));
+    let reread = crate::RunConfig::from_toml_str(&default.to_toml().unwrap()).unwrap();
+    assert!(same(&default, &reread));
+
+    let pipeline = crate::RunConfig::from_toml_str(
+        r#"
+        objective = "maximize"
+
+        [solver]
+        kind = "pipeline"
+        construct = ["greedy"]
+        improve = ["2opt", "oropt"]
+        accept = "sa"
+        time_limit_ms = 200
+        "#,
+    )
+    .unwrap();
+    let reread = crate::RunConfig::from_toml_str(&pipeline.to_toml().unwrap()).unwrap();
+    assert!(same(&pipeline, &reread));
+
+    // Seeds beyond the integers of TOML cannot be written.
+    assert!(matches!(
+        config.clone().with_seed(u64::MAX).to_toml(),
+        Err(CyklError::Parse(_))
+    ));
+
+    let message = |toml: &str| match crate::RunConfig::from_toml_str(toml) {
+        Err(CyklError::Parse(msg)) => msg,
+        other => panic!("expected a parse error, got {:?}", other),
+    };
+    assert!(message("cand = 3").contains("unknown field `cand`"));
+    assert!(message("cands = \"3\"").contains("invalid type: string \"3\""));
+    assert!(message("cands = 0").contains("expected at least one candidate"));
+    assert!(message("objective = \"min\"").contains("expected `minimize` or `maximize`"));
+    assert!(message("[solver]\nkind = \"lns\"\nn_remove = 2").contains("missing termination"));
+    assert!(message("[solver]\nkind = \"lns\"\niterations = 2").contains("`n_remove`"));
+    assert!(message("[solver]\nkind = \"greedy\"\nseed = 1").contains("unknown field `seed`"));
+    assert!(
+        message("[solver]\nkind = \"lns\"\nn_remove = 2\niterations = 1\nstagnation = 1")
+            .contains("conflicting termination")
+    );
+    assert!(message("[solver]\nkind = \"greedy\"\nstarters = [0, -1]").contains("-1"));
+    assert!(message("cands = 3\ncands = 4").contains("duplicate key"));
+
+    assert!(matches!(
+        crate::RunConfig::from_toml("missing.toml"),
+        Err(CyklError::Io(_))
+    ));
+}
+
+#[cfg(feature = "config-json")]
+#[test]
+fn test_run_config_pipeline() {
+    let config = crate::RunConfig::from_json(
+        r#"{"solver": {"kind": "pipeline", "construct": ["greedy"], "improve": ["2opt", "oropt"],
+            "perturb": ["double-bridge"], "accept": "sa", "iterations": 20, "seed": 5}}"#,
+    )
+    .unwrap();
+    let names = |names: &[&str]| names.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    let spec = crate::alg::registry::PipelineSpec {
+        construct: names(&["greedy"]),
+        improve: names(&["2opt", "oropt"]),
+        perturb: names(&["double-bridge"]),
+        accept: Some("sa".to_string()),
+        termination: Termination::Iterations(20),
+        objective: Objective::Minimize,
+        seed: 5,
+    };
+    assert_eq!(&SolverKind::Pipeline(spec), config.solver());
+
+    let len = 12;
+    let mut model = Model::new(Metric::Euc2d, 4);
+    for ii in 0..len {
+        model.add(NodeKind::Target, vec![ii as f64, (ii * ii % 7) as f64], ());
+    }
+    let result = model.solve(&config).unwrap().unwrap();
+    assert_eq!(len, result.len());
+    assert_eq!(result, model.solve(&config).unwrap().unwrap());
+
+    let unknown = crate::RunConfig::from_json(
+        r#"{"solver": {"kind": "pipeline", "improve": ["3opt"], "iterations": 1}}"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        model.solve(&unknown),
+        Err(CyklError::UnknownPhase(name)) if name == "3opt"
+    ));
+    assert!(matches!(
+        crate::RunConfig::from_json(
+            r#"{"solver": {"kind": "pipeline", "improve": [2], "iterations": 1}}"#
+        ),
+        Err(CyklError::Parse(msg)) if msg.contains("expected a string")
+    ));
+}
+
+#[cfg(feature = "config-json")]
+#[test]
+fn test_run_config_json() {
+    let config = crate::RunConfig::from_json(
+        r#"{"cands": 5, "deterministic": true,
+            "solver": {"kind": "adaptive", "budget_ms": 1500, "seed": 7}}"#,
+    )
+    .unwrap();
+    assert_eq!(5, config.cands());
+    assert!(config.is_deterministic());
+    assert_eq!(
+        &SolverKind::Adaptive {
+            starters: vec![0],
+            budget: std::time::Duration::from_millis(1500),
+            seed: 7,
+        },
+        config.solver()
+    );
+
+    let default = crate::RunConfig::from_json("{}").unwrap();
+    assert_eq!(RunConfigBuilder::new().build().solver(), default.solver());
+    assert!(!default.is_deterministic());
+
+    let message = |json: &str| match crate::RunConfig::from_json(json) {
+        Err(CyklError::Parse(msg)) => msg,
+        other => panic!("expected a parse error, got {:?}", other),
+    };
+    assert!(
+        message(r#"{"solver": {"kind": "adaptive", "budget_ms": 1.5}}"#)
+            .contains("invalid type: floating point `1.5`")
+    );
+    assert!(message(r#"{"cand": 3}"#).contains("unknown field `cand`"));
+    assert!(message(r#"{"scan": "fast"}"#).contains("expected `first` or `best`"));
+    assert!(message(r#"{"solver": {"kind": "tabu"}}"#).contains("unknown variant `tabu`"));
+    assert!(
+        message(r#"{"solver": {"kind": "greedy", "seed": 1}}"#).contains("unknown field `seed`")
+    );
+    assert!(
+        message(r#"{"solver": {"kind": "lns", "n_remove": 2}}"#).contains("missing termination")
+    );
+    assert!(matches!(
+        crate::RunConfig::from_json("[1, 2]"),
+        Err(CyklError::Parse(_))
+    ));
+}
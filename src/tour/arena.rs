@@ -0,0 +1,218 @@
+use std::{cell::RefCell, ptr::NonNull};
+
+use crate::data::{NodeIndex, NodeKind};
+
+use super::node::InnerNode;
+
+/// Number of vertices below which tours share a block.
+const MIN_BLOCK_LEN: usize = 256;
+
+thread_local! {
+    static ARENA: RefCell<Arena> = RefCell::new(Arena::default());
+}
+
+/// Contiguous vertices of a tour, allocated by [`alloc`].
+#[derive(Debug)]
+pub(super) struct Run {
+    pub(super) base: NonNull<InnerNode>,
+    pub(super) len: usize,
+}
+
+impl Run {
+    /// Returns a run without vertices.
+    pub(super) fn empty() -> Self {
+        Self {
+            base: NonNull::dangling(),
+            len: 0,
+        }
+    }
+
+    /// Returns the vertex at the given offset of the run.
+    ///
+    /// # Safety
+    /// The offset must be smaller than the length of the run.
+    #[inline]
+    pub(super) unsafe fn at(&self, offset: usize) -> NonNull<InnerNode> {
+        NonNull::new_unchecked(self.base.as_ptr().add(offset))
+    }
+}
+
+/// Released vertices of a block of the arena.
+#[derive(Debug)]
+struct Free {
+    block: usize,
+    base: NonNull<InnerNode>,
+    len: usize,
+}
+
+/// Vertices of the tours of a thread.
+///
+/// Tours give their vertices back to the arena when they are dropped or repacked. Released
+/// vertices are reused by later tours of the thread, and a block is freed as soon as all of its
+/// vertices are released. Vertices are referenced by [`TourNode`](super::TourNode)s, which are
+/// plain pointers, thus using a handle after its vertex was released is undefined behaviour.
+#[derive(Debug, Default)]
+struct Arena {
+    blocks: Vec<NonNull<[InnerNode]>>,
+    // Released vertices as runs of a block, which are split and merged again as tours come and go.
+    free: Vec<Free>,
+    n_live: usize,
+}
+
+impl Arena {
+    fn alloc(&mut self, vertices: Vec<InnerNode>) -> Run {
+        let len = vertices.len();
+        if len == 0 {
+            return Run::empty();
+        }
+        self.n_live += len;
+
+        // Takes the smallest released run that fits.
+        let fit = self
+            .free
+            .iter()
+            .enumerate()
+            .filter(|(_, free)| free.len >= len)
+            .min_by_key(|(_, free)| free.len)
+            .map(|(pos, _)| pos);
+
+        match fit {
+            Some(pos) => unsafe {
+                let free = self.free.swap_remove(pos);
+                if free.len > len {
+                    self.free.push(Free {
+                        block: free.block,
+                        base: NonNull::new_unchecked(free.base.as_ptr().add(len)),
+                        len: free.len - len,
+                    });
+                }
+                for (offset, vertex) in vertices.into_iter().enumerate() {
+                    *free.base.as_ptr().add(offset) = vertex;
+                }
+                Run {
+                    base: free.base,
+                    len,
+                }
+            },
+            None => {
+                // Small tours share a block, whose spare vertices are released from the start.
+                let mut vertices = vertices;
+                let n_spare = MIN_BLOCK_LEN.saturating_sub(len);
+                vertices.extend(
+                    (0..n_spare).map(|_| InnerNode::new(NodeIndex::new(0, NodeKind::Target))),
+                );
+
+                let block = NonNull::from(Box::leak(vertices.into_boxed_slice()));
+                let base: NonNull<InnerNode> = block.cast();
+                if n_spare > 0 {
+                    self.free.push(Free {
+                        block: self.blocks.len(),
+                        base: unsafe { NonNull::new_unchecked(base.as_ptr().add(len)) },
+                        len: n_spare,
+                    });
+                }
+                self.blocks.push(block);
+                Run { base, len }
+            }
+        }
+    }
+
+    unsafe fn release(&mut self, run: Run) {
+        if run.len == 0 {
+            return;
+        }
+
+        let (mut beg, mut end) = (run.base.as_ptr(), run.base.as_ptr().add(run.len));
+        let block = match self.blocks.iter().position(|block| {
+            let first: *mut InnerNode = block.as_ptr().cast();
+            first <= beg && end <= first.add(block.len())
+        }) {
+            Some(block) => block,
+            None => {
+                debug_assert!(false, "Run not allocated by this arena");
+                return;
+            }
+        };
+        self.n_live -= run.len;
+
+        // Drops the candidates and all other data of the released vertices.
+        for offset in 0..run.len {
+            let vertex = run.at(offset);
+            *vertex.as_ptr() = InnerNode::new((*vertex.as_ptr()).index);
+        }
+
+        // Merges the run with the adjacent released runs of its block.
+        self.free.retain(|free| {
+            let (b, e) = (free.base.as_ptr(), free.base.as_ptr().add(free.len));
+            if free.block != block {
+                true
+            } else if e == beg {
+                beg = b;
+                false
+            } else if b == end {
+                end = e;
+                false
+            } else {
+                true
+            }
+        });
+
+        let len = end.offset_from(beg) as usize;
+        if len < self.blocks[block].len() {
+            self.free.push(Free {
+                block,
+                base: NonNull::new_unchecked(beg),
+                len,
+            });
+            return;
+        }
+
+        // The whole block is released, thus it is freed, and the released runs of the last block
+        // follow it to its new position.
+        drop(Box::from_raw(self.blocks.swap_remove(block).as_ptr()));
+        let moved = self.blocks.len();
+        for free in self.free.iter_mut().filter(|free| free.block == moved) {
+            free.block = block;
+        }
+    }
+
+    fn n_reserved(&self) -> usize {
+        self.blocks.iter().map(|block| block.len()).sum()
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        for block in self.blocks.drain(..) {
+            unsafe { drop(Box::from_raw(block.as_ptr())) };
+        }
+    }
+}
+
+/// Places the given vertices next to each other in the arena of the current thread.
+pub(super) fn alloc(vertices: Vec<InnerNode>) -> Run {
+    ARENA.with(|arena| arena.borrow_mut().alloc(vertices))
+}
+
+/// Gives the vertices of a run back to the arena of the current thread.
+///
+/// # Safety
+/// The run must come from [`alloc`] on the current thread and must not be released before.
+pub(super) unsafe fn release(run: Run) {
+    // Tours dropped while the thread ends may outlive the arena, which frees the vertices itself.
+    let _ = ARENA.try_with(|arena| arena.borrow_mut().release(run));
+}
+
+/// Returns the number of vertices held by the tours of the current thread.
+///
+/// Tours cannot be sent to other threads, thus the count returns to its previous value once all
+/// tours created in between are dropped, which tests can use to detect leaks.
+pub fn live_vertices() -> usize {
+    ARENA.with(|arena| arena.borrow().n_live)
+}
+
+/// Returns the number of vertices the current thread has allocated for tours, including those
+/// released by dropped tours and kept for reuse until their blocks are released as a whole.
+pub fn reserved_vertices() -> usize {
+    ARENA.with(|arena| arena.borrow().n_reserved())
+}
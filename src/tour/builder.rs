@@ -0,0 +1,107 @@
+use crate::{
+    data::{DataStore, Metric, NodeKind},
+    oracle::SharedOracle,
+    overlay::CostOverlay,
+    CyklError,
+};
+
+use super::{ReversalPolicy, Tour, TourOrder, TwoLevelList};
+
+/// Collects the options of a [`TwoLevelList`] and creates it in one step, so that new options
+/// do not require further constructor variants.
+///
+/// Only options that vary between tours are set here. The width of node indices and links is
+/// chosen for the whole crate with the `u32-index` feature. Tours always share the nodes of their
+/// store, which they keep alive, and always keep the prefix costs of their segments up to date.
+/// Tours are closed cycles, whose open paths are measured with [`Tour::path_length`].
+#[derive(Clone, Debug, Default)]
+pub struct TourBuilder {
+    groupsize: Option<usize>,
+    policy: Option<ReversalPolicy>,
+    overlay: Option<CostOverlay>,
+    oracle: Option<SharedOracle>,
+    order: Option<TourOrder>,
+}
+
+impl TourBuilder {
+    /// Creates a builder with the default options, see the setters.
+    pub fn new() -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    /// Sets the number of vertices per segment. Defaults to the square root of the number of
+    /// nodes, which balances the costs of walking through segments and through vertices.
+    pub fn groupsize(mut self, groupsize: usize) -> Self {
+        self.groupsize = Some(groupsize);
+        self
+    }
+
+    /// Sets the policy for reversing whole segments. Defaults to [`ReversalPolicy::Lazy`].
+    pub fn reversal_policy(mut self, policy: ReversalPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Sets an overlay whose adjusted costs replace the costs of the store, see
+    /// [`TwoLevelList::attach_overlay`].
+    pub fn overlay(mut self, overlay: CostOverlay) -> Self {
+        self.overlay = Some(overlay);
+        self
+    }
+
+    /// Sets an oracle whose costs replace the costs of the store, see
+    /// [`TwoLevelList::attach_oracle`].
+    pub fn oracle(mut self, oracle: SharedOracle) -> Self {
+        self.oracle = Some(oracle);
+        self
+    }
+
+    /// Sets the initial order of the tour. Defaults to the order in which the nodes were added to
+    /// the store.
+    pub fn order(mut self, order: TourOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Creates a tour over the nodes of a store.
+    ///
+    /// Returns an error if the initial order does not visit every node of the store exactly once.
+    pub fn build<M>(self, store: &DataStore<M>) -> Result<TwoLevelList, CyklError> {
+        let groupsize = match self.groupsize {
+            Some(groupsize) => groupsize,
+            None => (store.len() as f64).sqrt().ceil() as usize,
+        };
+
+        let mut tour = TwoLevelList::unbuilt(store, groupsize.max(1))
+            .with_reversal_policy(self.policy.unwrap_or_default());
+
+        // The overlay and the oracle are set before the tour is built, so that the tour length is
+        // computed with their costs by the single build into the initial order.
+        tour.set_cost_sources(self.overlay, self.oracle)?;
+        let order = self
+            .order
+            .unwrap_or_else(|| TourOrder::with_nat_ord(store.len()));
+        tour.try_apply(&order)?;
+
+        Ok(tour)
+    }
+
+    /// Creates a tour over the nodes of an oracle, whose costs the tour uses in place of a store,
+    /// see [`TourBuilder::oracle`], which this oracle replaces.
+    ///
+    /// The nodes have no coordinates and are all targets. Code that needs a store, e.g. to read
+    /// coordinates, requires a tour created with [`TourBuilder::build`].
+    pub fn build_from_oracle(mut self, oracle: SharedOracle) -> Result<TwoLevelList, CyklError> {
+        // The tour keeps the nodes alive, and takes its costs from the oracle, thus the store
+        // neither needs a cost matrix nor outlives the tour.
+        let mut store = DataStore::with_capacity(Metric::Explicit, oracle.len());
+        for _ in 0..oracle.len() {
+            store.add(NodeKind::Target, Vec::new(), ());
+        }
+
+        self.oracle = Some(oracle);
+        self.build(&store)
+    }
+}
@@ -0,0 +1,115 @@
+//! Compact binary encoding of tour orders.
+//!
+//! An encoded order starts with a version byte, followed by the number of nodes as a varint, the
+//! cost as a little-endian `f64`, and the nodes. Each node is stored as the zigzag-encoded
+//! difference to the previous node in LEB128 varint format, starting from zero. Tours improved by
+//! local search mostly connect nearby nodes, which are often added next to each other, thus most
+//! differences fit in one or two bytes instead of eight.
+use std::convert::TryFrom;
+
+use crate::{CyklError, Scalar};
+
+use super::TourOrder;
+
+const VERSION: u8 = 1;
+
+impl TourOrder {
+    /// Encodes the order and its cost into a compact binary form, see [`TourOrder::from_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(self.order.len() * 2 + 16);
+        result.push(VERSION);
+        write_varint(&mut result, self.order.len() as u64);
+        result.extend_from_slice(&self.cost.to_le_bytes());
+
+        let mut prev = 0;
+        for &index in &self.order {
+            write_varint(&mut result, zigzag(index as i64 - prev as i64));
+            prev = index;
+        }
+
+        result
+    }
+
+    /// Decodes an order encoded by [`TourOrder::to_bytes`].
+    ///
+    /// Returns [`CyklError::Parse`] if the bytes are truncated, contain trailing data, were
+    /// written by an unknown version of the encoding, or decode to node indices that do not fit
+    /// into `usize`. Malformed input never panics.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CyklError> {
+        let (&version, mut rest) = bytes
+            .split_first()
+            .ok_or_else(|| CyklError::Parse("Empty tour encoding".to_string()))?;
+        if version != VERSION {
+            return Err(CyklError::Parse(format!(
+                "Unknown tour encoding version {}",
+                version
+            )));
+        }
+
+        // Every node takes at least one byte, which bounds the length before it is allocated.
+        let len = usize::try_from(read_varint(&mut rest)?).map_err(|_| truncated())?;
+        if rest.len() < 8 || rest.len() - 8 < len {
+            return Err(truncated());
+        }
+        let (cost, mut rest) = rest.split_at(8);
+        let mut cost_bytes = [0; 8];
+        cost_bytes.copy_from_slice(cost);
+
+        let mut order = Vec::with_capacity(len);
+        let mut prev = 0_i64;
+        for _ in 0..len {
+            let index = prev
+                .checked_add(unzigzag(read_varint(&mut rest)?))
+                .ok_or_else(|| CyklError::Parse("Node index exceeds 64 bits".to_string()))?;
+            let node = usize::try_from(index)
+                .map_err(|_| CyklError::Parse(format!("Invalid node index {}", index)))?;
+            order.push(node);
+            prev = index;
+        }
+
+        if !rest.is_empty() {
+            return Err(CyklError::Parse(format!(
+                "{} trailing bytes after tour encoding",
+                rest.len()
+            )));
+        }
+
+        Ok(Self::with_cost(order, Scalar::from_le_bytes(cost_bytes)))
+    }
+}
+
+fn truncated() -> CyklError {
+    CyklError::Parse("Truncated tour encoding".to_string())
+}
+
+#[inline]
+fn zigzag(x: i64) -> u64 {
+    ((x << 1) ^ (x >> 63)) as u64
+}
+
+#[inline]
+fn unzigzag(x: u64) -> i64 {
+    (x >> 1) as i64 ^ -((x & 1) as i64)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut x: u64) {
+    while x >= 0x80 {
+        out.push((x as u8) | 0x80);
+        x >>= 7;
+    }
+    out.push(x as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, CyklError> {
+    let mut result = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or_else(truncated)?;
+        *bytes = rest;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+
+    Err(CyklError::Parse("Varint exceeds 64 bits".to_string()))
+}
@@ -0,0 +1,46 @@
+//! Arithmetic on positions in a cycle, such as the positions of nodes in a tour.
+//!
+//! Positions range from `0` to `n - 1`, and position `n - 1` is followed by position `0`. These
+//! are the primitives needed to reason about segments of a tour when writing custom moves, e.g.
+//! on the positions returned by [`Tour::order_index`](super::Tour::order_index).
+
+/// Returns `true` if `mid` lies on the way from `from` to `to` when walking forward through a
+/// cycle. Both ends are included.
+///
+/// If `from` is greater than `to`, the way wraps around the end of the cycle, e.g. `1` lies between
+/// `5` and `3`. The length of the cycle does not matter as long as all values lie inside it.
+pub fn between<T>(from: T, mid: T, to: T) -> bool
+where
+    T: PartialEq + PartialOrd,
+{
+    if from <= to {
+        from <= mid && mid <= to
+    } else {
+        !(to < mid && mid < from)
+    }
+}
+
+/// Returns the number of forward steps from position `a` to position `b` in a cycle of length `n`.
+///
+/// The steps backward are `cyclic_distance(b, a, n)`, thus the shorter way between two positions
+/// is the minimum of both.
+///
+/// # Panics
+/// Panics if `n` is zero.
+#[inline]
+pub fn cyclic_distance(a: usize, b: usize, n: usize) -> usize {
+    (b % n + n - a % n) % n
+}
+
+/// Returns the positions from `from` to `to`, both included, when walking forward through a cycle
+/// of length `n`.
+///
+/// The range wraps around the end of the cycle if `from` is greater than `to`. If `from` equals
+/// `to`, the range consists of that single position.
+///
+/// # Panics
+/// Panics if `n` is zero.
+pub fn wrapped_range(from: usize, to: usize, n: usize) -> impl Iterator<Item = usize> {
+    let start = from % n;
+    (0..=cyclic_distance(start, to, n)).map(move |step| (start + step) % n)
+}
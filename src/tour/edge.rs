@@ -0,0 +1,93 @@
+use std::fmt::Display;
+
+use crate::{data::DataStore, Scalar};
+
+/// Undirected edge between two nodes, identified by their indices.
+///
+/// The indices are stored with the smaller one first, so that `Edge::new(a, b)` and
+/// `Edge::new(b, a)` are equal and hash alike.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Edge(usize, usize);
+
+impl Edge {
+    pub fn new(a: usize, b: usize) -> Self {
+        if a <= b {
+            Self(a, b)
+        } else {
+            Self(b, a)
+        }
+    }
+
+    /// Returns the smaller index of the two endpoints.
+    #[inline]
+    pub fn low(&self) -> usize {
+        self.0
+    }
+
+    /// Returns the larger index of the two endpoints.
+    #[inline]
+    pub fn high(&self) -> usize {
+        self.1
+    }
+
+    /// Returns both endpoints, the smaller index first.
+    #[inline]
+    pub fn nodes(&self) -> (usize, usize) {
+        (self.0, self.1)
+    }
+
+    /// Returns `true` if the node at the index is an endpoint of this edge.
+    #[inline]
+    pub fn contains(&self, index: usize) -> bool {
+        self.0 == index || self.1 == index
+    }
+
+    /// Returns the endpoint opposite to the node at the index, or `None` if it is not an endpoint.
+    #[inline]
+    pub fn other(&self, index: usize) -> Option<usize> {
+        if self.0 == index {
+            Some(self.1)
+        } else if self.1 == index {
+            Some(self.0)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a key that identifies this edge, e.g. for tabu lists, penalty maps and frequency
+    /// counters keyed by integers.
+    ///
+    /// The key packs the smaller index into the upper and the larger index into the lower 32 bits,
+    /// thus it does not depend on the platform or the run, and is unique for indices below 2^32.
+    #[inline]
+    pub fn key(&self) -> u64 {
+        ((self.0 as u64) << 32) | (self.1 as u64 & 0xffff_ffff)
+    }
+
+    /// Returns the edge identified by a key, see [`Edge::key`].
+    #[inline]
+    pub fn from_key(key: u64) -> Self {
+        Self((key >> 32) as usize, (key & 0xffff_ffff) as usize)
+    }
+
+    /// Returns the cost of this edge in a store, measured from the smaller to the larger index.
+    ///
+    /// # Panics
+    /// Panics if an endpoint is out of bounds.
+    #[inline]
+    pub fn length<M>(&self, store: &DataStore<M>) -> Scalar {
+        store.cost(&self.0, &self.1)
+    }
+}
+
+impl From<(usize, usize)> for Edge {
+    fn from((a, b): (usize, usize)) -> Self {
+        Self::new(a, b)
+    }
+}
+
+impl Display for Edge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.0, self.1)
+    }
+}
@@ -1,38 +1,115 @@
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
+
 use getset::{CopyGetters, Getters};
+use rand::Rng;
 
-use crate::data::GetIndex;
-use crate::Scalar;
+use crate::data::{DataStore, GetIndex, NodeIndex};
+use crate::{CyklError, Scalar};
 
 mod tll;
-pub use tll::TwoLevelList;
+pub use tll::{ReversalPolicy, TwoLevelList};
+
+mod builder;
+pub use builder::TourBuilder;
+
+mod codec;
+
+pub mod cyclic;
+
+mod edge;
+pub use edge::Edge;
+
+mod arena;
+pub use arena::{live_vertices, reserved_vertices};
 
 mod node;
 pub use node::exclude;
 pub use node::NodeStatus;
 pub use node::TourNode;
+pub(crate) use node::{free, to_nonnull};
 
 mod error;
 pub use error::UpdateTourError;
 
+mod partial;
+pub use partial::PartialTour;
+
+mod pool;
+pub use pool::SolutionPool;
+
 pub mod tests;
 
 // #[enum_dispatch(TourImpltor)]
 pub trait Tour {
     /// Rearranges the tour's vertices according to the given order.
-    // TODO: should return Result<()>.
+    ///
+    /// Returns [`UpdateTourError::TourLenMismatched`] if the order does not have the same length
+    /// as the tour, and [`UpdateTourError::InvalidTourOrder`] if it contains an out-of-bounds or a
+    /// duplicate index. The tour is left unchanged in both cases.
     fn apply(&mut self, order: &TourOrder) -> Result<(), UpdateTourError>;
 
+    /// Rearranges the tour's vertices according to the given order.
+    ///
+    /// Unlike [`Tour::apply`], the order is validated before the tour is modified. An error is
+    /// returned if the order does not have the same length as the tour, or if it contains an
+    /// out-of-bounds or a duplicate index.
+    fn try_apply(&mut self, order: &TourOrder) -> Result<(), CyklError> {
+        validate_order(order, self.len())?;
+        self.apply(order).map_err(CyklError::from)
+    }
+
     /// Returns true iff the tour, starting at the vertex `from`, arrives at the vertex `mid`
     /// before reaching the vertex `to` in its forward traversal.
     fn between(&self, from: &TourNode, mid: &TourNode, to: &TourNode) -> bool;
 
     /// Returns true iff the tour, starting at the vertex `from_index`, arrives at the vertex `mid_index`
     /// before reaching the vertex `to_index` in its forward traversal.
-    fn between_at(&self, from_index: usize, mid_index: usize, to_index: usize) -> bool;
+    fn between_at(&self, from_index: usize, mid_index: usize, to_index: usize) -> bool {
+        match (
+            self.get(from_index),
+            self.get(mid_index),
+            self.get(to_index),
+        ) {
+            (Some(from), Some(mid), Some(to)) => self.between(&from, &mid, &to),
+            _ => false,
+        }
+    }
 
+    /// Fallible version of [`Tour::between_at`].
+    ///
+    /// Returns an error if any index is out of bounds.
+    fn try_between_at(
+        &self,
+        from_index: usize,
+        mid_index: usize,
+        to_index: usize,
+    ) -> Result<bool, CyklError> {
+        match (
+            self.get(from_index),
+            self.get(mid_index),
+            self.get(to_index),
+        ) {
+            (Some(from), Some(mid), Some(to)) => Ok(self.between(&from, &mid, &to)),
+            _ => Err(out_of_bounds(
+                self.len(),
+                &[from_index, mid_index, to_index],
+            )),
+        }
+    }
+
+    /// Calculates the distance between two vertices of the tour.
+    ///
+    /// Use [`Tour::distance_at`] for indices that are not known to belong to the tour.
+    ///
+    /// # Panics
+    /// Panics if either vertex is not known to the tour.
     #[inline]
     fn distance(&self, a: &TourNode, b: &TourNode) -> Scalar {
         self.distance_at(&a.index(), &b.index())
+            .unwrap_or_else(|err| panic!("{}", err))
     }
 
     /// Calculates the distance between two nodes at the given index.
@@ -41,12 +118,118 @@ pub trait Tour {
     /// * a - The index from the container of the tail node in the arc.
     /// * b - The index from the container of the head node in the arc.
     ///
-    /// # Panics
-    /// Panics if `a` or `b` are out of bounds.
-    fn distance_at<I>(&self, a: &I, b: &I) -> Scalar
+    /// Returns an error if `a` or `b` are out of bounds.
+    fn distance_at<I>(&self, a: &I, b: &I) -> Result<Scalar, CyklError>
     where
         I: GetIndex + PartialEq + Eq;
 
+    /// Calculates the distances between the given pairs of node indices and writes them into
+    /// `out`.
+    ///
+    /// Evaluating the gains of a batch of moves this way is cheaper than calling
+    /// [`Tour::distance_at`] for each pair, since implementations can look up their costs at once.
+    ///
+    /// # Panics
+    /// Panics if `pairs` and `out` differ in length or if an index is out of bounds. Use
+    /// [`Tour::distance_at`] for indices that are not known to belong to the tour.
+    fn distances(&self, pairs: &[(usize, usize)], out: &mut [Scalar]) {
+        assert_eq!(
+            pairs.len(),
+            out.len(),
+            "Mismatched lengths of pairs and output"
+        );
+        for (x, (a, b)) in out.iter_mut().zip(pairs) {
+            *x = self
+                .distance_at(a, b)
+                .unwrap_or_else(|err| panic!("{}", err));
+        }
+    }
+
+    /// Returns the total cost of the path from the vertex at `from_index` to the vertex at
+    /// `to_index` in the forward traversal of the tour.
+    ///
+    /// The cost of a path is the sum of the distances of all edges lying on that path. Returns
+    /// `None` if either index is out of bounds or refers to a vertex that is excluded from the
+    /// tour.
+    ///
+    /// The default implementation walks along the path and thus takes linear time.
+    fn path_length(&self, from_index: usize, to_index: usize) -> Option<Scalar> {
+        let (mut node, to) = (self.get(from_index)?, self.get(to_index)?);
+
+        let mut result = 0.;
+        // A vertex that is not reached within a full round is not on the tour.
+        for _ in 0..self.len() {
+            if node == to {
+                return Some(result);
+            }
+            let next = self.successor(&node)?;
+            result += self.distance(&node, &next);
+            node = next;
+        }
+
+        None
+    }
+
+    /// Returns the indices of the vertices on the path from the vertex at `from_index` to the
+    /// vertex at `to_index`, including both ends, walking along the tour in the given direction.
+    ///
+    /// With [`PathDirection::Shorter`], the path with fewer vertices is returned, and the forward
+    /// path on a tie. If either index is out of bounds or refers to an excluded vertex, an empty
+    /// vector is returned.
+    ///
+    /// The default implementation walks along the path and thus takes linear time in its length.
+    fn extract_path(
+        &self,
+        from_index: usize,
+        to_index: usize,
+        direction: PathDirection,
+    ) -> Vec<usize> {
+        let (from, to) = match (self.get(from_index), self.get(to_index)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Vec::new(),
+        };
+
+        if !self.is_included(from_index) || !self.is_included(to_index) {
+            return Vec::new();
+        }
+
+        let step = |node: &TourNode, forward: bool| {
+            if forward {
+                self.successor(node)
+            } else {
+                self.predecessor(node)
+            }
+        };
+
+        // Walks in both directions in lockstep if the shorter path is requested, so that the
+        // walk stops once the shorter path is found.
+        let directions: &[bool] = match direction {
+            PathDirection::Forward => &[true],
+            PathDirection::Backward => &[false],
+            PathDirection::Shorter => &[true, false],
+        };
+        let mut walks: Vec<(TourNode, Vec<usize>)> = directions
+            .iter()
+            .map(|_| (from, vec![from_index]))
+            .collect();
+
+        loop {
+            for (ii, (node, path)) in walks.iter_mut().enumerate() {
+                if *node == to {
+                    return std::mem::take(path);
+                }
+
+                match step(node, directions[ii]) {
+                    Some(next) => {
+                        path.push(self.index_of(&next));
+                        *node = next;
+                    }
+                    None => return Vec::new(),
+                }
+            }
+        }
+    }
+
     /// Permutate the tour's order by replacing the edges `(from_a, to_a)` and `(from_b, to_b)`
     /// by the new edges `(from_a, from_b)` and `(to_a, to_b)`.
     ///
@@ -60,22 +243,155 @@ pub trait Tour {
     /// * to_a - The index from the container of the head node in the first arc.
     /// * from_b - The index from the container of the tail node in the second arc.
     /// * to_b - The index from the container of the head node in the second arc.
-    fn flip_at(&mut self, from_a: usize, to_a: usize, from_b: usize, to_b: usize);
+    fn flip_at(&mut self, from_a: usize, to_a: usize, from_b: usize, to_b: usize) {
+        if let (Some(fa), Some(ta), Some(fb), Some(tb)) = (
+            self.get(from_a),
+            self.get(to_a),
+            self.get(from_b),
+            self.get(to_b),
+        ) {
+            self.flip(&fa, &ta, &fb, &tb);
+        }
+    }
 
     fn flip(&mut self, from_a: &TourNode, to_a: &TourNode, from_b: &TourNode, to_b: &TourNode);
 
+    /// Fallible version of [`Tour::flip_at`].
+    ///
+    /// Returns an error if any index is out of bounds, or if `to_a` and `to_b` are not the direct
+    /// successors of `from_a` and `from_b`. The tour is left untouched in case of an error.
+    fn try_flip_at(
+        &mut self,
+        from_a: usize,
+        to_a: usize,
+        from_b: usize,
+        to_b: usize,
+    ) -> Result<(), CyklError> {
+        let (fa, ta, fb, tb) = match (
+            self.get(from_a),
+            self.get(to_a),
+            self.get(from_b),
+            self.get(to_b),
+        ) {
+            (Some(fa), Some(ta), Some(fb), Some(tb)) => (fa, ta, fb, tb),
+            _ => return Err(out_of_bounds(self.len(), &[from_a, to_a, from_b, to_b])),
+        };
+
+        if self.successor(&fa) != Some(ta) {
+            return Err(CyklError::NotSuccessor {
+                from: from_a,
+                to: to_a,
+            });
+        }
+
+        if self.successor(&fb) != Some(tb) {
+            return Err(CyklError::NotSuccessor {
+                from: from_b,
+                to: to_b,
+            });
+        }
+
+        self.flip(&fa, &ta, &fb, &tb);
+        Ok(())
+    }
+
+    /// Applies a sequence of flips as a single operation, e.g. the chain of flips that makes up
+    /// one step of the Lin-Kernighan heuristic.
+    ///
+    /// Each flip is validated as in [`Tour::try_flip_at`] against the tour left by the flips
+    /// before it. If a flip is invalid, the flips applied so far are undone in reverse order and
+    /// its error is returned. The tour then forms the same cycle as before, although it may be
+    /// traversed in the opposite direction.
+    fn apply_moves(&mut self, moves: &[FlipSpec]) -> Result<(), CyklError> {
+        for (ii, spec) in moves.iter().enumerate() {
+            if let Err(err) = self.try_flip_at(spec.from_a, spec.to_a, spec.from_b, spec.to_b) {
+                for applied in moves[..ii].iter().rev() {
+                    undo_flip(self, applied);
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
     /// Returns a reference to a vertex representing a node in this tour.
     ///
     /// If a node is registered in the container of this tour, returns the reference to its
     /// corresponding vertex, otherwise returns `None`.
     fn get(&self, index: usize) -> Option<TourNode>;
 
+    /// Returns the index and kind of the node at the given index in the container, or `None` if
+    /// the index is out of bounds.
+    ///
+    /// Unlike `get(index).unwrap().index()`, this neither panics nor hands out the vertex.
+    #[inline]
+    fn node(&self, index: usize) -> Option<NodeIndex> {
+        self.get(index).map(|node| node.index())
+    }
+
+    /// Returns the index in the container of the node represented by a vertex.
+    ///
+    /// # Panics
+    /// Panics if the vertex does not belong to a node, like [`TourNode::index`].
+    #[inline]
+    fn index_of(&self, node: &TourNode) -> usize {
+        node.index().get()
+    }
+
+    /// Returns the vertex at the given position in the forward traversal of the tour.
+    ///
+    /// Positions are counted from the vertex at index `0`, which always has the position `0`. Thus
+    /// the result is consistent with the order returned by [`Tour::tour_order`]. If `pos` is out of
+    /// bounds, returns `None`.
+    ///
+    /// The default implementation walks along the tour and thus takes linear time.
+    fn at_position(&self, pos: usize) -> Option<TourNode> {
+        if pos >= self.len() {
+            return None;
+        }
+
+        let mut node = self.get(0)?;
+        for _ in 0..pos {
+            node = self.successor(&node)?;
+        }
+
+        Some(node)
+    }
+
+    /// Returns the position of the vertex at the given index in the forward traversal of the tour.
+    ///
+    /// This is the inverse operation of [`Tour::at_position`]. If `index` is out of bounds, returns
+    /// `None`.
+    ///
+    /// The default implementation walks along the tour and thus takes linear time.
+    fn order_index(&self, index: usize) -> Option<usize> {
+        let target = self.get(index)?;
+        let mut node = self.get(0)?;
+
+        for pos in 0..self.len() {
+            if node == target {
+                return Some(pos);
+            }
+            node = self.successor(&node)?;
+        }
+
+        None
+    }
+
     /// Returns the relation between two nodes.
     ///
     /// If ```base``` precedes ```targ```, [`NodeRel::Predecessor`] is returned.
     /// And if ```base``` is ```targ```'s successor, [`NodeRel::Successor`] is returned instead.
     /// Otherwise, the two nodes are not neighbours which results in [`NodeRel::None`].
-    fn relation(&self, base: &TourNode, targ: &TourNode) -> NodeRel;
+    fn relation(&self, base: &TourNode, targ: &TourNode) -> NodeRel {
+        if self.successor(base).as_ref() == Some(targ) {
+            NodeRel::Predecessor
+        } else if self.predecessor(base).as_ref() == Some(targ) {
+            NodeRel::Successor
+        } else {
+            NodeRel::None
+        }
+    }
 
     /// Returns a reference to a vertex which is the `kin`'s direct successor in the forward
     /// traversal of the tour.
@@ -95,7 +411,9 @@ pub trait Tour {
     ///
     /// Since a tour is a cycle, the direct successor of the last vertex is the first vertex
     /// in the forward traversal of the tour.
-    fn successor_at(&self, kin_index: usize) -> Option<TourNode>;
+    fn successor_at(&self, kin_index: usize) -> Option<TourNode> {
+        self.get(kin_index).and_then(|kin| self.successor(&kin))
+    }
 
     /// Returns a reference to a vertex which is the direct predecessor of the vertex at the given
     /// index in the forward traversal of the tour.
@@ -115,14 +433,30 @@ pub trait Tour {
     ///
     /// Since a tour is a cycle, the direct predecessor of the first vertex is the last vertex
     /// in the forward traversal of the tour.
-    fn predecessor_at(&self, kin_index: usize) -> Option<TourNode>;
+    fn predecessor_at(&self, kin_index: usize) -> Option<TourNode> {
+        self.get(kin_index).and_then(|kin| self.predecessor(&kin))
+    }
 
     /// Reverses a tour entirely.
     fn rev(&mut self);
 
+    /// Rearranges the vertices in memory to follow the tour, so that walking through the tour is
+    /// friendlier to caches, e.g. after the initial tour is constructed.
+    ///
+    /// The default implementation does nothing, for tours whose layout does not depend on the order.
+    fn repack(&mut self) {}
+
     /// Returns the node order of a tour.
     fn tour_order(&self) -> TourOrder;
 
+    /// Writes the node order of a tour into `order`, e.g. to reuse its buffer in a loop.
+    ///
+    /// The default implementation replaces `order` with [`Tour::tour_order`], whereas
+    /// implementations may reuse the buffer of `order` without allocating.
+    fn tour_order_into(&self, order: &mut TourOrder) {
+        *order = self.tour_order();
+    }
+
     /// Returns the total distance for a tour order if such order is applied.
     ///
     /// The function will only calculate the distance and will not cause any structural changes.
@@ -135,13 +469,203 @@ pub trait Tour {
     fn len(&self) -> usize;
 
     /// Returns ```true``` if the tour contains no nodes.
-    fn is_empty(&self) -> bool;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
     /// Returns the total distance of completely traversing through the tour.
     fn total_distance(&self) -> Scalar;
 
     /// Returns the iterator over all nodes stored in a tour.
     fn itr(&self) -> TourIter;
+
+    /// Returns an iterator over the edges of the tour in the order returned by
+    /// [`Tour::tour_order`], each as the indices of a node and its successor together with the
+    /// cost between them.
+    ///
+    /// A tour with two nodes has two edges connecting them, a tour with fewer nodes has no edges.
+    fn edges(&self) -> TourEdges<'_, Self> {
+        TourEdges::new(self)
+    }
+
+    /// Returns an iterator over the keys of the undirected edges of the tour, see [`Edge::key`],
+    /// in the same order as [`Tour::edges`].
+    ///
+    /// The keys can be looked up in tabu lists or penalty maps without building an [`Edge`] or a
+    /// tuple for each lookup.
+    fn edge_set_hashes(&self) -> EdgeKeys<'_, Self> {
+        let key: fn((usize, usize, Scalar)) -> u64 = |(a, b, _)| Edge::new(a, b).key();
+        self.edges().map(key)
+    }
+
+    /// Removes the node at the given index from the tour by linking its predecessor directly with
+    /// its successor.
+    ///
+    /// Excluded nodes keep their index, but have neither predecessor nor successor. Excluding a
+    /// node that is not part of the tour has no effect. Returns an error if the index is out of
+    /// bounds or if the node is the last one remaining in the tour.
+    fn exclude(&mut self, index: usize) -> Result<(), UpdateTourError>;
+
+    /// Removes the node at the given index from the tour like [`Tour::exclude`], and returns the
+    /// decrease of the tour length, i.e. the cost of the two removed edges minus the cost of the
+    /// edge that reconnects the neighbours of the node.
+    ///
+    /// Together with [`Tour::insert_cheapest`], this allows to destroy and repair tours in large
+    /// neighbourhood searches while keeping track of their lengths. Returns an error if the index is
+    /// out of bounds, if the node is not part of the tour or if it is the last one remaining.
+    fn remove_with_gain(&mut self, index: usize) -> Result<Scalar, UpdateTourError> {
+        let node = self.get(index).ok_or(UpdateTourError::NodeNotFound)?;
+        if !self.is_included(index) {
+            return Err(UpdateTourError::InvalidTourOrder);
+        }
+
+        let gain = match (self.predecessor(&node), self.successor(&node)) {
+            (Some(pred), Some(succ)) => {
+                self.distance(&pred, &node) + self.distance(&node, &succ)
+                    - self.distance(&pred, &succ)
+            }
+            _ => return Err(UpdateTourError::BrokenTour),
+        };
+        self.exclude(index)?;
+
+        Ok(gain)
+    }
+
+    /// Inserts a previously excluded node back into the tour at the position that increases the
+    /// tour length the least.
+    ///
+    /// Including a node that is already part of the tour has no effect.
+    fn include(&mut self, index: usize) -> Result<(), UpdateTourError>;
+
+    /// Inserts a previously excluded node into the tour directly after the node at `after_index`.
+    ///
+    /// Returns an error if either index is out of bounds, if the node is already part of the tour or
+    /// if the node at `after_index` is not.
+    fn insert_after(&mut self, index: usize, after_index: usize) -> Result<(), UpdateTourError>;
+
+    /// Inserts a previously excluded node next to one of its candidates at the position that
+    /// increases the tour length the least, and returns the increase.
+    ///
+    /// Only the edges incident to the candidates of the node are examined, thus the candidates
+    /// must have been generated before, but the search does not depend on the number of nodes.
+    /// This suits online scenarios, in which the nodes of an instance are excluded initially and
+    /// inserted as they arrive. If none of the candidates is part of the tour, all edges are
+    /// examined as in [`Tour::cheapest_insertion`]. Returns an error if the index is out of bounds
+    /// or if the node is already part of the tour.
+    fn insert_cheapest(&mut self, index: usize) -> Result<Scalar, UpdateTourError> {
+        let node = self.get(index).ok_or(UpdateTourError::NodeNotFound)?;
+        if self.is_included(index) {
+            return Err(UpdateTourError::InvalidTourOrder);
+        }
+
+        let mut result: Option<(Scalar, TourNode)> = None;
+        for cand in node.candidates() {
+            if !self.is_included(self.index_of(cand)) {
+                continue;
+            }
+
+            let edges = [
+                (self.predecessor(cand), Some(*cand)),
+                (Some(*cand), self.successor(cand)),
+            ];
+            for (from, to) in edges.iter() {
+                if let (Some(from), Some(to)) = (from, to) {
+                    let cost = self.distance(from, &node) + self.distance(&node, to)
+                        - self.distance(from, to);
+                    match result {
+                        Some((best, _)) if best <= cost => {}
+                        _ => result = Some((cost, *from)),
+                    }
+                }
+            }
+        }
+
+        let (cost, after) = match result {
+            Some(x) => x,
+            None => self
+                .cheapest_insertion(index)
+                .ok_or(UpdateTourError::NodeNotFound)?,
+        };
+        self.insert_after(index, self.index_of(&after))?;
+
+        Ok(cost)
+    }
+
+    /// Returns `true` if the node at the given index is visited by the tour.
+    fn is_included(&self, index: usize) -> bool;
+
+    /// Returns the number of nodes visited by the tour.
+    fn n_included(&self) -> usize {
+        self.itr()
+            .filter(|node| self.is_included(self.index_of(node)))
+            .count()
+    }
+
+    /// Returns the prize collected by visiting the node at the given index.
+    ///
+    /// Skipping a node forfeits its prize. Defaults to zero for tours without prizes.
+    fn prize(&self, _index: usize) -> Scalar {
+        0.
+    }
+
+    /// Returns the group of the node at the given index.
+    ///
+    /// Defaults to `None` for tours without groups.
+    fn group(&self, _index: usize) -> Option<usize> {
+        None
+    }
+
+    /// Returns the sum of prizes of all nodes that are excluded from the tour.
+    fn penalty(&self) -> Scalar {
+        self.itr()
+            .map(|node| self.index_of(&node))
+            .filter(|index| !self.is_included(*index))
+            .map(|index| self.prize(index))
+            .sum()
+    }
+
+    /// Returns the cheapest increase of the tour length caused by inserting an excluded node,
+    /// together with the node after which it would be inserted.
+    ///
+    /// Returns `None` if the node is already part of the tour or the index is out of bounds.
+    fn cheapest_insertion(&self, index: usize) -> Option<(Scalar, TourNode)> {
+        let node = self.get(index)?;
+        if self.is_included(index) {
+            return None;
+        }
+
+        let mut result: Option<(Scalar, TourNode)> = None;
+        for from in self.itr() {
+            if !self.is_included(self.index_of(&from)) {
+                continue;
+            }
+
+            let to = self.successor(&from)?;
+            let cost =
+                self.distance(&from, &node) + self.distance(&node, &to) - self.distance(&from, &to);
+            match result {
+                Some((best, _)) if best <= cost => {}
+                _ => result = Some((cost, from)),
+            }
+        }
+
+        result
+    }
+
+    /// Returns a human-readable representation of the tour's internal structure for debugging.
+    ///
+    /// Lines are wrapped so that they do not exceed `width` characters, unless a single entry is
+    /// longer than that. The default implementation lists the node indices in the forward
+    /// traversal of the tour.
+    fn dump(&self, width: usize) -> String {
+        let tokens: Vec<String> = self
+            .tour_order()
+            .order()
+            .iter()
+            .map(|index| index.to_string())
+            .collect();
+        wrap_tokens("", &tokens, width)
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -151,6 +675,17 @@ pub enum NodeRel {
     None,
 }
 
+/// Direction in which [`Tour::extract_path`] walks along a tour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathDirection {
+    /// Follows the successors of the vertices.
+    Forward,
+    /// Follows the predecessors of the vertices.
+    Backward,
+    /// Follows the direction in which the path has fewer vertices.
+    Shorter,
+}
+
 pub struct TourIter<'s> {
     it: std::slice::Iter<'s, TourNode>,
 }
@@ -176,18 +711,141 @@ impl<'s> Iterator for TourIter<'s> {
     }
 }
 
+/// Returns `true` if two tours visit the same nodes in the same cycle, regardless of their
+/// starting node and direction.
+///
+/// Each edge of `a` is looked up among the neighbours of its endpoint in `b`, so the tours are not
+/// converted into orders unless they visit fewer than three nodes.
+pub fn same_cycle(a: &impl Tour, b: &impl Tour) -> bool {
+    if a.n_included() != b.n_included() {
+        return false;
+    }
+
+    if a.n_included() < 3 {
+        return a.tour_order() == b.tour_order();
+    }
+
+    a.edges().all(|(from, to, _)| {
+        from < b.len()
+            && b.is_included(from)
+            && [b.successor_at(from), b.predecessor_at(from)]
+                .iter()
+                .flatten()
+                .any(|node| b.index_of(node) == to)
+    })
+}
+
+/// Iterator over the keys of the edges of a tour, see [`Tour::edge_set_hashes`].
+pub type EdgeKeys<'s, T> = std::iter::Map<TourEdges<'s, T>, fn((usize, usize, Scalar)) -> u64>;
+
+/// Iterator over the edges of a tour, see [`Tour::edges`].
+pub struct TourEdges<'s, T>
+where
+    T: ?Sized,
+{
+    tour: &'s T,
+    current: Option<TourNode>,
+    remaining: usize,
+}
+
+impl<'s, T> TourEdges<'s, T>
+where
+    T: Tour + ?Sized,
+{
+    fn new(tour: &'s T) -> Self {
+        let remaining = match tour.n_included() {
+            n if n < 2 => 0,
+            n => n,
+        };
+
+        Self {
+            tour,
+            current: tour.at_position(0),
+            remaining,
+        }
+    }
+}
+
+impl<'s, T> Iterator for TourEdges<'s, T>
+where
+    T: Tour + ?Sized,
+{
+    type Item = (usize, usize, Scalar);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let from = self.current?;
+        let to = self.tour.successor(&from)?;
+        self.remaining -= 1;
+        self.current = Some(to);
+        Some((
+            self.tour.index_of(&from),
+            self.tour.index_of(&to),
+            self.tour.distance(&from, &to),
+        ))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
 pub trait STree {
     fn build_mst(&mut self);
 
     fn cost_m1t(&self) -> HeldKarpBound;
 }
 
+/// Flip that replaces the edges `(from_a, to_a)` and `(from_b, to_b)` by `(from_a, from_b)` and
+/// `(to_a, to_b)`, as in [`Tour::flip_at`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FlipSpec {
+    /// Index of the tail node of the first removed edge.
+    pub from_a: usize,
+    /// Index of the head node of the first removed edge, the successor of `from_a`.
+    pub to_a: usize,
+    /// Index of the tail node of the second removed edge.
+    pub from_b: usize,
+    /// Index of the head node of the second removed edge, the successor of `from_b`.
+    pub to_b: usize,
+}
+
+impl FlipSpec {
+    /// Creates the flip that removes the edges `(from_a, to_a)` and `(from_b, to_b)`, given by
+    /// the indices of their nodes in the container.
+    pub fn new(from_a: usize, to_a: usize, from_b: usize, to_b: usize) -> Self {
+        Self {
+            from_a,
+            to_a,
+            from_b,
+            to_b,
+        }
+    }
+}
+
+/// Restores the edges `(from_a, to_a)` and `(from_b, to_b)` removed by a flip.
+fn undo_flip<T>(tour: &mut T, spec: &FlipSpec)
+where
+    T: Tour + ?Sized,
+{
+    if tour.successor_at(spec.from_a) == tour.get(spec.from_b) {
+        tour.flip_at(spec.from_a, spec.from_b, spec.to_a, spec.to_b);
+    } else {
+        tour.flip_at(spec.from_b, spec.from_a, spec.to_b, spec.to_a);
+    }
+}
+
 pub enum HeldKarpBound {
     Value(Scalar),
     Optimal,
 }
 
-#[derive(Debug, CopyGetters, Getters)]
+#[derive(Clone, Debug, CopyGetters, Getters)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TourOrder {
     #[getset(get = "pub")]
     order: Vec<usize>,
@@ -245,6 +903,118 @@ impl TourOrder {
     pub fn set_cost(&mut self, cost: Scalar) {
         self.cost = cost;
     }
+
+    /// Returns the time needed to travel the cycle and serve each of its nodes, which is its
+    /// length plus the service times of its nodes.
+    pub fn duration<M>(&self, store: &DataStore<M>) -> Scalar {
+        crate::oracle::cycle_cost(store, &self.order)
+            + self
+                .order
+                .iter()
+                .map(|index| store.service_time(*index))
+                .sum::<Scalar>()
+    }
+
+    /// Returns the order in a canonical form, so that orders describing the same cycle are equal
+    /// regardless of their starting node and direction.
+    ///
+    /// The canonical order starts with the smallest index and continues towards the smaller of
+    /// its two neighbours.
+    pub fn canonical(&self) -> Vec<usize> {
+        self.canonical_iter().collect()
+    }
+
+    /// Iterates over the order in its canonical form, see [`TourOrder::canonical`], without
+    /// allocating it.
+    fn canonical_iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let len = self.order.len();
+        let start = self
+            .order
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, index)| **index)
+            .map_or(0, |(pos, _)| pos);
+
+        let forward =
+            len == 0 || self.order[(start + 1) % len] <= self.order[(start + len - 1) % len];
+        (0..len).map(move |ii| {
+            if forward {
+                self.order[(start + ii) % len]
+            } else {
+                self.order[(start + len - ii) % len]
+            }
+        })
+    }
+
+    /// Returns a copy of the order perturbed by `strength` random operations, each of which either
+    /// reverses a segment or swaps two nodes.
+    ///
+    /// Every operation replaces at most four edges of the tour, thus the strength bounds the
+    /// distance between the original and the perturbed tour. The cost of the returned order is
+    /// zero, since the cost of the perturbed tour is not known until it is measured, e.g. by
+    /// applying it to a tour.
+    pub fn perturbed<R>(&self, rng: &mut R, strength: usize) -> TourOrder
+    where
+        R: Rng + ?Sized,
+    {
+        let mut order = self.order.clone();
+        let len = order.len();
+
+        if len > 3 {
+            for _ in 0..strength {
+                let a = rng.gen_range(0..len);
+                let b = rng.gen_range(0..len);
+                if rng.gen::<bool>() {
+                    order[a.min(b)..=a.max(b)].reverse();
+                } else {
+                    order.swap(a, b);
+                }
+            }
+        }
+
+        TourOrder::with_ord(order)
+    }
+}
+
+/// Formats a tour order in cycle notation.
+///
+/// Runs of at least three consecutive indices, either ascending or descending, are compressed into
+/// `first-last`. For example, the order `[0, 1, 2, 3, 9, 8, 7, 4]` is written as `(0-3 9-7 4)`.
+impl Display for TourOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+
+        let mut ii = 0;
+        while ii < self.order.len() {
+            let first = self.order[ii];
+            let mut jj = ii;
+
+            if let Some(&next) = self.order.get(ii + 1) {
+                if next == first + 1 || next + 1 == first {
+                    let step = next as isize - first as isize;
+                    while jj + 1 < self.order.len()
+                        && self.order[jj + 1] as isize - self.order[jj] as isize == step
+                    {
+                        jj += 1;
+                    }
+                }
+            }
+
+            if ii > 0 {
+                write!(f, " ")?;
+            }
+
+            if jj - ii >= 2 {
+                write!(f, "{}-{}", first, self.order[jj])?;
+                ii = jj + 1;
+            } else {
+                write!(f, "{}", first)?;
+                ii += 1;
+            }
+        }
+
+        write!(f, ")")
+    }
 }
 
 impl Default for TourOrder {
@@ -256,6 +1026,26 @@ impl Default for TourOrder {
     }
 }
 
+/// Two orders are equal if they describe the same cycle, i.e. they only differ by rotation or
+/// reflection. Their costs are not compared.
+impl PartialEq for TourOrder {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.canonical_iter().eq(other.canonical_iter())
+    }
+}
+
+impl Eq for TourOrder {}
+
+/// Hashes the canonical order, consistent with [`PartialEq`].
+impl Hash for TourOrder {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for index in self.canonical_iter() {
+            index.hash(state);
+        }
+    }
+}
+
 impl<'s> IntoIterator for &'s TourOrder {
     type Item = &'s usize;
 
@@ -287,13 +1077,61 @@ macro_rules! tour_order {
     };
 }
 
-fn between<T>(from: T, mid: T, to: T) -> bool
-where
-    T: PartialEq + PartialOrd,
-{
-    if from <= to {
-        from <= mid && mid <= to
-    } else {
-        !(to < mid && mid < from)
+/// Joins tokens by spaces into lines of at most `width` characters, each starting with `prefix`.
+///
+/// Continuation lines are indented to align with the first token.
+pub(crate) fn wrap_tokens(prefix: &str, tokens: &[String], width: usize) -> String {
+    let indent = " ".repeat(prefix.len());
+    let mut result = String::from(prefix);
+    let mut line_len = prefix.len();
+
+    for (ii, token) in tokens.iter().enumerate() {
+        if ii > 0 {
+            if line_len + 1 + token.len() > width {
+                result.push('\n');
+                result.push_str(&indent);
+                line_len = indent.len();
+            } else {
+                result.push(' ');
+                line_len += 1;
+            }
+        }
+
+        result.push_str(token);
+        line_len += token.len();
     }
+
+    result
+}
+
+/// Checks whether a tour order is a permutation of all nodes in a tour of the given length.
+pub(crate) fn validate_order(order: &TourOrder, len: usize) -> Result<(), CyklError> {
+    if order.len() != len {
+        return Err(UpdateTourError::TourLenMismatched {
+            expected: len,
+            received: order.len(),
+        }
+        .into());
+    }
+
+    let mut seen = vec![false; len];
+    for &index in order {
+        match seen.get_mut(index) {
+            Some(true) => return Err(CyklError::DuplicateNode(index)),
+            Some(flag) => *flag = true,
+            None => return Err(CyklError::IndexOutOfBounds { index, len }),
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns an error for the first index in `indices` that is out of bounds.
+fn out_of_bounds(len: usize, indices: &[usize]) -> CyklError {
+    let index = indices
+        .iter()
+        .copied()
+        .find(|&index| index >= len)
+        .unwrap_or(len);
+    CyklError::IndexOutOfBounds { index, len }
 }
@@ -2,6 +2,12 @@ use std::{fmt::Display, ptr::NonNull};
 
 use crate::{data::NodeIndex, Scalar};
 
+/// Handle to a vertex of a tour.
+///
+/// Vertices are kept in an arena of the thread that created the tour. A handle is a plain pointer
+/// into the arena that is not checked on use: once its tour is dropped or repacked, the vertex may
+/// be reused by another tour or freed, thus using a handle that outlived its vertex is undefined
+/// behaviour.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub struct TourNode {
     pub(super) inner: Option<NonNull<InnerNode>>,
@@ -77,10 +83,8 @@ impl TourNode {
 
         match (self.inner, other.inner) {
             (Some(inner), Some(other_inner)) => unsafe {
-                match (*inner.as_ref()).best_neighbours[rank].as_ref() {
-                    Some(pair) => pair.0 == other_inner || pair.1 == other_inner,
-                    None => false,
-                }
+                let (a, b) = (*inner.as_ptr()).best_neighbours[rank];
+                follow(inner, a) == Some(other_inner) || follow(inner, b) == Some(other_inner)
             },
             _ => false,
         }
@@ -93,7 +97,8 @@ impl TourNode {
         {
             unsafe {
                 (*inner.as_ptr()).best_neighbours[1] = (*inner.as_ptr()).best_neighbours[0];
-                (*inner.as_ptr()).best_neighbours[0] = Some((inner1, inner2));
+                (*inner.as_ptr()).best_neighbours[0] =
+                    (link_to(inner, Some(inner1)), link_to(inner, Some(inner2)));
             }
         }
     }
@@ -117,51 +122,138 @@ pub(super) struct InnerNode {
     pub(super) segment: Option<NonNull<Segment>>,
     /// The rank of a node in its parent segment.
     pub(super) rank: i32,
-    /// The directly preceding neighbour of a node in a tour.
-    pub(super) predecessor: Option<NonNull<InnerNode>>,
-    /// The directly succeeding neighbour of a node in a tour.
-    pub(super) successor: Option<NonNull<InnerNode>>,
+    /// Accumulated cost of the path from the first node of the parent segment to a node.
+    pub(super) prefix_cost: Scalar,
+    /// Accumulated cost of the path from a node back to the first node of the parent segment,
+    /// which differs from `prefix_cost` for asymmetric costs.
+    pub(super) prefix_cost_rev: Scalar,
+    /// The directly preceding neighbour of a node in a tour, see [`pred`].
+    pub(super) predecessor: Link,
+    /// The directly succeeding neighbour of a node in a tour, see [`succ`].
+    pub(super) successor: Link,
     /// Number of edges that are incident to the node.
     pub(super) degree: i32,
     /// Penalty value of a node in the ascent scheme. Corresponds to pi in LKH report.
     pub(super) penalty_weight: Scalar,
     /// Edge with minimum distance that doesn't belong to the MST.
     // TODO: better name
-    pub(super) mst_final_edge: Option<NonNull<InnerNode>>,
+    pub(super) mst_final_edge: Link,
     /// The parent of a node in a minimum spanning tree.
-    pub(super) mst_parent: Option<NonNull<InnerNode>>,
+    pub(super) mst_parent: Link,
     /// Set of candidate nodes.
     pub(super) candidates: Vec<TourNode>,
 
-    pub(super) best_neighbours: Vec<Option<(NonNull<InnerNode>, NonNull<InnerNode>)>>,
+    pub(super) best_neighbours: [(Link, Link); 2],
     /// First original neighbour.
-    pub(super) orgn_nbr_1: Option<NonNull<InnerNode>>,
+    pub(super) orgn_nbr_1: Link,
     /// Flag to indicate whether an edge with the first original neighbour is excluded.
     pub(super) orgn_nbr_1_x: bool,
     /// Second original neighbour.
-    pub(super) orgn_nbr_2: Option<NonNull<InnerNode>>,
+    pub(super) orgn_nbr_2: Link,
     /// Flag to indicate whether an edge with the second original neighbour is excluded.
     pub(super) orgn_nbr_2_x: bool,
 }
 
+/// Distance between the linked vertices.
+#[cfg(feature = "u32-index")]
+type RawLink = i32;
+/// Distance between the linked vertices.
+#[cfg(not(feature = "u32-index"))]
+type RawLink = isize;
+
+/// Link from a vertex to another vertex of the same tour.
+///
+/// All vertices of a tour lie in one run of the arena, thus a link is stored as the distance from
+/// the linking to the linked vertex in the run. With the `u32-index` feature, links take four
+/// bytes like the node indices, which limits tours to 2^31 vertices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Link(RawLink);
+
+impl Link {
+    /// Link to no vertex.
+    pub(super) const NONE: Link = Link(RawLink::MIN);
+}
+
+/// Returns the vertex a link of `node` points to.
+///
+/// # Safety
+/// The link must be stored in `node`, and the linked vertex must lie in the same run.
+#[inline]
+// `RawLink` is already `isize` unless the `u32-index` feature narrows it.
+#[allow(clippy::unnecessary_cast)]
+pub(super) unsafe fn follow(node: NonNull<InnerNode>, link: Link) -> Option<NonNull<InnerNode>> {
+    if link == Link::NONE {
+        None
+    } else {
+        Some(NonNull::new_unchecked(
+            node.as_ptr().offset(link.0 as isize),
+        ))
+    }
+}
+
+/// Returns the link from `node` to `target`.
+///
+/// # Safety
+/// Both vertices must lie in the same run of the arena.
+#[inline]
+pub(super) unsafe fn link_to(node: NonNull<InnerNode>, target: Option<NonNull<InnerNode>>) -> Link {
+    match target {
+        Some(target) => Link(target.as_ptr().offset_from(node.as_ptr()) as RawLink),
+        None => Link::NONE,
+    }
+}
+
+/// Returns the directly preceding neighbour of a vertex, regardless of the orientation of its
+/// segment.
+#[inline]
+pub(super) unsafe fn pred(node: NonNull<InnerNode>) -> Option<NonNull<InnerNode>> {
+    follow(node, (*node.as_ptr()).predecessor)
+}
+
+/// Returns the directly succeeding neighbour of a vertex, regardless of the orientation of its
+/// segment.
+#[inline]
+pub(super) unsafe fn succ(node: NonNull<InnerNode>) -> Option<NonNull<InnerNode>> {
+    follow(node, (*node.as_ptr()).successor)
+}
+
+#[inline]
+pub(super) unsafe fn set_pred(node: NonNull<InnerNode>, target: Option<NonNull<InnerNode>>) {
+    (*node.as_ptr()).predecessor = link_to(node, target);
+}
+
+#[inline]
+pub(super) unsafe fn set_succ(node: NonNull<InnerNode>, target: Option<NonNull<InnerNode>>) {
+    (*node.as_ptr()).successor = link_to(node, target);
+}
+
+/// Swaps the predecessor and the successor of a vertex.
+#[inline]
+pub(super) unsafe fn swap_links(node: NonNull<InnerNode>) {
+    let inner = &mut *node.as_ptr();
+    std::mem::swap(&mut inner.predecessor, &mut inner.successor);
+}
+
 impl InnerNode {
     pub fn new(node: NodeIndex) -> Self {
         Self {
             index: node,
             rank: i32::MAX,
+            prefix_cost: 0.,
+            prefix_cost_rev: 0.,
             status: NodeStatus::Active,
             segment: None,
-            predecessor: None,
-            successor: None,
+            predecessor: Link::NONE,
+            successor: Link::NONE,
             degree: 0,
             penalty_weight: 0.,
-            mst_final_edge: None,
-            mst_parent: None,
+            mst_final_edge: Link::NONE,
+            mst_parent: Link::NONE,
             candidates: Vec::with_capacity(0),
-            best_neighbours: vec![None; 2],
-            orgn_nbr_1: None,
+            best_neighbours: [(Link::NONE, Link::NONE); 2],
+            orgn_nbr_1: Link::NONE,
             orgn_nbr_1_x: false,
-            orgn_nbr_2: None,
+            orgn_nbr_2: Link::NONE,
             orgn_nbr_2_x: false,
         }
     }
@@ -175,9 +267,9 @@ impl Display for InnerNode {
 
 macro_rules! exclude {
     ($inner:expr, $other:expr) => {
-        if (*$inner.as_ref()).orgn_nbr_1 == $other.inner {
+        if follow($inner, (*$inner.as_ptr()).orgn_nbr_1) == $other.inner {
             (*$inner.as_ptr()).orgn_nbr_1_x = true;
-        } else if (*$inner.as_ref()).orgn_nbr_2 == $other.inner {
+        } else if follow($inner, (*$inner.as_ptr()).orgn_nbr_2) == $other.inner {
             (*$inner.as_ptr()).orgn_nbr_2_x = true;
         }
     };
@@ -216,10 +308,10 @@ macro_rules! move_node {
                     while rank <= $el_cnt {
                         match opt {
                             Some(node) => {
-                                opt = (*node.as_ptr()).$kin2;
+                                opt = $kin2(node);
                                 (*node.as_ptr()).rank = target_rank $op rank;
                                 (*node.as_ptr()).segment = seg;
-                                std::mem::swap(&mut (*node.as_ptr()).successor, &mut (*node.as_ptr()).predecessor)
+                                swap_links(node)
                             }
                             None => panic!("Nullpointer"),
                         }
@@ -231,7 +323,7 @@ macro_rules! move_node {
                     while rank <= $el_cnt {
                         match opt {
                             Some(node) => {
-                                opt = (*node.as_ptr()).$kin1;
+                                opt = $kin1(node);
                                 (*node.as_ptr()).rank = target_rank $op rank;
                                 (*node.as_ptr()).segment = seg;
                             }
@@ -275,26 +367,25 @@ impl Segment {
         // TODO: better panic message.
         match (&self.first, &self.last) {
             (Some(first), Some(last)) => {
-                match (&(*first.as_ptr()).predecessor, &(*last.as_ptr()).successor) {
+                match (&pred(*first), &succ(*last)) {
                     (Some(p), Some(s)) => {
-                        if (*p.as_ptr()).predecessor == self.first {
-                            (*p.as_ptr()).predecessor = self.last;
+                        if pred(*p) == self.first {
+                            set_pred(*p, self.last);
                         } else {
-                            (*p.as_ptr()).successor = self.last;
+                            set_succ(*p, self.last);
                         }
 
-                        if (*s.as_ptr()).predecessor == self.last {
-                            (*s.as_ptr()).predecessor = self.first;
+                        if pred(*s) == self.last {
+                            set_pred(*s, self.first);
                         } else {
-                            (*s.as_ptr()).successor = self.first;
+                            set_succ(*s, self.first);
                         }
                     }
                     _ => panic!("Empty predecessor or successor in node."),
                 }
-                std::mem::swap(
-                    &mut (*first.as_ptr()).predecessor,
-                    &mut (*last.as_ptr()).successor,
-                );
+                let (pred_first, succ_last) = (pred(*first), succ(*last));
+                set_pred(*first, succ_last);
+                set_succ(*last, pred_first);
             }
             _ => panic!("Empty first or last pointers in segment."),
         }
@@ -311,7 +402,7 @@ impl Segment {
 
                 if d1 <= d2 {
                     if self.reverse {
-                        let tmp_ptr = (*node.as_ptr()).successor;
+                        let tmp_ptr = succ(*node);
                         match self.next {
                             Some(next) => {
                                 (*next.as_ptr()).move_front(
@@ -329,7 +420,7 @@ impl Segment {
                             Some(prev) => {
                                 (*prev.as_ptr()).move_back(
                                     self.first,
-                                    (*node.as_ptr()).predecessor,
+                                    pred(*node),
                                     d1,
                                     self.reverse,
                                 );
@@ -341,18 +432,13 @@ impl Segment {
                 } else if self.reverse {
                     match self.prev {
                         Some(prev) => {
-                            (*prev.as_ptr()).move_back(
-                                (*node.as_ptr()).successor,
-                                self.last,
-                                d2,
-                                self.reverse,
-                            );
+                            (*prev.as_ptr()).move_back(succ(*node), self.last, d2, self.reverse);
                         }
                         None => panic!("No prev"),
                     }
                     self.last = Some(*node);
                 } else {
-                    let tmp_ptr = (*node.as_ptr()).predecessor;
+                    let tmp_ptr = pred(*node);
                     match self.next {
                         Some(next) => {
                             (*next.as_ptr()).move_front(Some(*node), self.last, d2, self.reverse);
@@ -374,9 +460,9 @@ impl Segment {
         reverse: bool,
     ) {
         if self.reverse {
-            move_node!(self, first, predecessor, successor, !reverse, head, tail, el_cnt, -);
+            move_node!(self, first, pred, succ, !reverse, head, tail, el_cnt, -);
         } else {
-            move_node!(self, last, successor, predecessor, reverse, tail, head, el_cnt, +);
+            move_node!(self, last, succ, pred, reverse, tail, head, el_cnt, +);
         }
     }
 
@@ -388,9 +474,9 @@ impl Segment {
         reverse: bool,
     ) {
         if self.reverse {
-            move_node!(self, last, successor, predecessor, !reverse, tail, head, el_cnt, +);
+            move_node!(self, last, succ, pred, !reverse, tail, head, el_cnt, +);
         } else {
-            move_node!(self, first, predecessor, successor, reverse, head, tail, el_cnt, -);
+            move_node!(self, first, pred, succ, reverse, head, tail, el_cnt, -);
         }
     }
 }
@@ -401,14 +487,23 @@ pub fn to_nonnull<T>(x: T) -> Option<NonNull<T>> {
     Some(Box::leak(boxed).into())
 }
 
+/// Frees a value allocated by [`to_nonnull`].
+///
+/// # Safety
+/// The pointer must come from [`to_nonnull`], must not be freed before, and must not be used
+/// afterwards.
+pub(crate) unsafe fn free<T>(ptr: NonNull<T>) {
+    drop(Box::from_raw(ptr.as_ptr()));
+}
+
 macro_rules! change_kin {
     ($target:ident, $cond_kin:ident, $new_kin:ident) => {
         match $target {
             Some(node) => {
-                if (*node.as_ptr()).predecessor == Some(*$cond_kin) {
-                    (*node.as_ptr()).predecessor = Some(*$new_kin);
+                if pred(node) == Some(*$cond_kin) {
+                    set_pred(node, Some(*$new_kin));
                 } else {
-                    (*node.as_ptr()).successor = Some(*$new_kin);
+                    set_succ(node, Some(*$new_kin));
                 }
             }
             None => panic!("No predecessor when attempting to reverse segment."),
@@ -423,21 +518,18 @@ pub(super) unsafe fn reverse_int_seg(
     a: &NonNull<InnerNode>,
     b: &NonNull<InnerNode>,
 ) {
-    let a_pred = (*a.as_ptr()).predecessor;
-    let b_succ = (*b.as_ptr()).successor;
-    (*a.as_ptr()).predecessor = b_succ;
-    (*b.as_ptr()).successor = a_pred;
+    let a_pred = pred(*a);
+    let b_succ = succ(*b);
+    set_pred(*a, b_succ);
+    set_succ(*b, a_pred);
 
     let (rl, rr) = ((*a.as_ptr()).rank, (*b.as_ptr()).rank);
     let mut rank = rr;
     let mut node = *a;
 
     while rank >= rl {
-        let tmp = (*node.as_ptr()).successor;
-        std::mem::swap(
-            &mut (*node.as_ptr()).successor,
-            &mut (*node.as_ptr()).predecessor,
-        );
+        let tmp = succ(node);
+        swap_links(node);
         (*node.as_ptr()).rank = rank;
         rank -= 1;
 
@@ -464,18 +556,18 @@ pub(super) unsafe fn reverse_int_seg(
 }
 
 macro_rules! swap_sym {
-    ($head: ident, $tail:ident, $target:ident, $key:ident) => {
+    ($head: ident, $tail:ident, $target:ident, $key:ident, $set_key:ident) => {
         match ((*$head.as_ptr()).$target, (*$tail.as_ptr()).$target) {
             (Some(node_a), Some(node_b)) => {
-                let kin_from_a = (*node_a.as_ptr()).$key;
-                let kin_from_b = (*node_b.as_ptr()).$key;
+                let kin_from_a = $key(node_a);
+                let kin_from_b = $key(node_b);
 
                 match kin_from_a {
                     Some(kin) => {
-                        if (*kin.as_ptr()).predecessor == (*$head.as_ptr()).$target {
-                            (*kin.as_ptr()).predecessor = (*$tail.as_ptr()).$target;
+                        if pred(kin) == (*$head.as_ptr()).$target {
+                            set_pred(kin, (*$tail.as_ptr()).$target);
                         } else {
-                            (*kin.as_ptr()).successor = (*$tail.as_ptr()).$target;
+                            set_succ(kin, (*$tail.as_ptr()).$target);
                         }
                     }
                     None => panic!("Missing pointer"),
@@ -483,17 +575,17 @@ macro_rules! swap_sym {
 
                 match kin_from_b {
                     Some(kin) => {
-                        if (*kin.as_ptr()).predecessor == (*$tail.as_ptr()).$target {
-                            (*kin.as_ptr()).predecessor = (*$head.as_ptr()).$target;
+                        if pred(kin) == (*$tail.as_ptr()).$target {
+                            set_pred(kin, (*$head.as_ptr()).$target);
                         } else {
-                            (*kin.as_ptr()).successor = (*$head.as_ptr()).$target;
+                            set_succ(kin, (*$head.as_ptr()).$target);
                         }
                     }
                     None => panic!("Missing pointer"),
                 }
 
-                (*node_a.as_ptr()).$key = kin_from_b;
-                (*node_b.as_ptr()).$key = kin_from_a;
+                $set_key(node_a, kin_from_b);
+                $set_key(node_b, kin_from_a);
             }
             _ => panic!("Missing pointers"),
         }
@@ -504,33 +596,33 @@ macro_rules! swap_asym {
     ($head:ident, $tail:ident) => {
         match ((*$head.as_ptr()).first, (*$tail.as_ptr()).last) {
             (Some(node_a), Some(node_b)) => {
-                let a_pred = (*node_a.as_ptr()).predecessor;
-                let b_succ = (*node_b.as_ptr()).successor;
+                let a_pred = pred(node_a);
+                let b_succ = succ(node_b);
 
                 match a_pred {
-                    Some(pred) => {
-                        if (*pred.as_ptr()).predecessor == (*$head.as_ptr()).first {
-                            (*pred.as_ptr()).predecessor = (*$tail.as_ptr()).last;
+                    Some(kin) => {
+                        if pred(kin) == (*$head.as_ptr()).first {
+                            set_pred(kin, (*$tail.as_ptr()).last);
                         } else {
-                            (*pred.as_ptr()).successor = (*$tail.as_ptr()).last;
+                            set_succ(kin, (*$tail.as_ptr()).last);
                         }
                     }
                     None => panic!("Missing pointer"),
                 }
 
                 match b_succ {
-                    Some(succ) => {
-                        if (*succ.as_ptr()).predecessor == (*$tail.as_ptr()).last {
-                            (*succ.as_ptr()).predecessor = (*$head.as_ptr()).first;
+                    Some(kin) => {
+                        if pred(kin) == (*$tail.as_ptr()).last {
+                            set_pred(kin, (*$head.as_ptr()).first);
                         } else {
-                            (*succ.as_ptr()).successor = (*$head.as_ptr()).first;
+                            set_succ(kin, (*$head.as_ptr()).first);
                         }
                     }
                     None => panic!("Missing pointer"),
                 }
 
-                (*node_a.as_ptr()).predecessor = b_succ;
-                (*node_b.as_ptr()).successor = a_pred;
+                set_pred(node_a, b_succ);
+                set_succ(node_b, a_pred);
             }
             _ => panic!("Missing pointers"),
         }
@@ -548,10 +640,16 @@ pub unsafe fn reverse_segs(from: &NonNull<Segment>, to: &NonNull<Segment>) {
             break;
         }
 
+        if (*a.as_ptr()).next == Some(b) {
+            // The pointers swapped below would point into the other segment.
+            reverse_adjacent_segs(&a, &b);
+            break;
+        }
+
         match ((*a.as_ptr()).reverse, (*b.as_ptr()).reverse) {
             (true, true) | (false, false) => {
-                swap_sym!(a, b, first, predecessor);
-                swap_sym!(a, b, last, successor);
+                swap_sym!(a, b, first, pred, set_pred);
+                swap_sym!(a, b, last, succ, set_succ);
             }
             (true, false) | (false, true) => {
                 swap_asym!(a, b);
@@ -585,16 +683,8 @@ pub unsafe fn reverse_segs(from: &NonNull<Segment>, to: &NonNull<Segment>) {
 
         (*a.as_ptr()).next = (*b.as_ptr()).next;
         (*b.as_ptr()).prev = (*a.as_ptr()).prev;
-
-        if tmp_next == Some(b) {
-            // a is the neighbour directly in front of b.
-            (*b.as_ptr()).next = Some(a);
-            (*a.as_ptr()).prev = Some(b);
-            break;
-        } else {
-            (*b.as_ptr()).next = tmp_next;
-            (*a.as_ptr()).prev = tmp_prev;
-        }
+        (*b.as_ptr()).next = tmp_next;
+        (*a.as_ptr()).prev = tmp_prev;
 
         match tmp_next {
             Some(next) => a = next,
@@ -608,6 +698,73 @@ pub unsafe fn reverse_segs(from: &NonNull<Segment>, to: &NonNull<Segment>) {
     }
 }
 
+/// Reverses the path formed by two segments, where `a` directly precedes `b`.
+unsafe fn reverse_adjacent_segs(a: &NonNull<Segment>, b: &NonNull<Segment>) {
+    let (prev, next) = match ((*a.as_ptr()).prev, (*b.as_ptr()).next) {
+        (Some(prev), Some(next)) => (prev, next),
+        _ => panic!("Missing neighbouring segments."),
+    };
+
+    let (a_head, a_tail) = seg_ends(a);
+    let (b_head, b_tail) = seg_ends(b);
+
+    // Reversing the traversal direction of a segment turns the outer pointer of its head into the
+    // outer pointer of its tail and vice versa, so only the edges between the segments change.
+    (*a.as_ptr()).reverse ^= true;
+    (*b.as_ptr()).reverse ^= true;
+    std::mem::swap(&mut (*a.as_ptr()).rank, &mut (*b.as_ptr()).rank);
+
+    if next == *a {
+        // Both segments form the whole tour, which is now traversed in the opposite direction.
+        return;
+    }
+
+    let (_, prev_tail) = seg_ends(&prev);
+    let (next_head, _) = seg_ends(&next);
+
+    set_outer(&prev, &prev_tail, &b_tail, false);
+    set_outer(b, &b_tail, &prev_tail, true);
+    set_outer(b, &b_head, &a_tail, false);
+    set_outer(a, &a_tail, &b_head, true);
+    set_outer(a, &a_head, &next_head, false);
+    set_outer(&next, &next_head, &a_head, true);
+
+    (*prev.as_ptr()).next = Some(*b);
+    (*b.as_ptr()).prev = Some(prev);
+    (*b.as_ptr()).next = Some(*a);
+    (*a.as_ptr()).prev = Some(*b);
+    (*a.as_ptr()).next = Some(next);
+    (*next.as_ptr()).prev = Some(*a);
+}
+
+/// Returns the first and the last node of a segment in its traversal direction.
+unsafe fn seg_ends(seg: &NonNull<Segment>) -> (NonNull<InnerNode>, NonNull<InnerNode>) {
+    match ((*seg.as_ptr()).first, (*seg.as_ptr()).last) {
+        (Some(first), Some(last)) => {
+            if (*seg.as_ptr()).reverse {
+                (last, first)
+            } else {
+                (first, last)
+            }
+        }
+        _ => panic!("Empty first or last pointers in segment."),
+    }
+}
+
+/// Points the pointer of the head or tail of a segment that leaves the segment to `target`.
+unsafe fn set_outer(
+    seg: &NonNull<Segment>,
+    node: &NonNull<InnerNode>,
+    target: &NonNull<InnerNode>,
+    head: bool,
+) {
+    if head ^ (*seg.as_ptr()).reverse {
+        set_pred(*node, Some(*target));
+    } else {
+        set_succ(*node, Some(*target));
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum NodeStatus {
     Active,
@@ -0,0 +1,204 @@
+use std::cmp::Ordering;
+
+use crate::{data::DataStore, oracle::cycle_cost};
+
+use super::{Edge, TourOrder};
+
+/// Marks a missing neighbour in the adjacency of a node.
+const NONE: usize = usize::MAX;
+
+/// Set of edges that is grown into a tour, as in the greedy-edge construction or the merging of
+/// parent tours in crossover operators.
+///
+/// Every node has at most two neighbours, and the edges form node-disjoint paths, called
+/// fragments, until the last edge closes them into a single cycle. Edges that would violate either
+/// condition are rejected in constant time: degrees are tracked per node, the fragments of nodes
+/// in a union-find structure, and each endpoint of a fragment knows the opposite endpoint.
+#[derive(Clone, Debug)]
+pub struct PartialTour {
+    adj: Vec<[usize; 2]>,
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    other_end: Vec<usize>,
+    n_edges: usize,
+}
+
+impl PartialTour {
+    /// Creates a state of `len` nodes without edges, in which every node is a fragment of its own.
+    pub fn new(len: usize) -> Self {
+        Self {
+            adj: vec![[NONE; 2]; len],
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+            other_end: (0..len).collect(),
+            n_edges: 0,
+        }
+    }
+
+    /// Returns the number of nodes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.adj.len()
+    }
+
+    /// Returns `true` if there are no nodes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.adj.is_empty()
+    }
+
+    /// Returns the number of edges added so far.
+    #[inline]
+    pub fn n_edges(&self) -> usize {
+        self.n_edges
+    }
+
+    /// Returns the number of fragments, counting isolated nodes, or zero once the tour is closed.
+    pub fn n_fragments(&self) -> usize {
+        self.len() - self.n_edges
+    }
+
+    /// Returns the number of neighbours of a node.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn degree(&self, index: usize) -> usize {
+        self.adj[index].iter().filter(|&&x| x != NONE).count()
+    }
+
+    /// Returns the neighbours of a node.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.adj[index].iter().copied().filter(|&x| x != NONE)
+    }
+
+    /// Returns `true` if two nodes belong to the same fragment.
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` are out of bounds.
+    pub fn same_fragment(&self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Returns the opposite endpoint of the fragment that ends at a node, which is the node itself
+    /// if it is isolated, or `None` if the node is inside a fragment.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn fragment_end(&self, index: usize) -> Option<usize> {
+        if self.degree(index) < 2 {
+            Some(self.other_end[index])
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the edge between two nodes can be added, i.e. both nodes have fewer than
+    /// two neighbours and the edge either joins two fragments or closes the last fragment into a
+    /// tour through all nodes.
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` are out of bounds.
+    pub fn can_add(&self, a: usize, b: usize) -> bool {
+        if a == b || self.degree(a) > 1 || self.degree(b) > 1 {
+            return false;
+        }
+
+        // Both nodes are endpoints, thus they belong to the same fragment if and only if they are
+        // its two ends.
+        self.other_end[a] != b || (self.len() > 2 && self.n_edges + 1 == self.len())
+    }
+
+    /// Adds the edge between two nodes and returns `true`, or returns `false` and leaves the state
+    /// unchanged if the edge cannot be added, see [`PartialTour::can_add`].
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` are out of bounds.
+    pub fn add(&mut self, a: usize, b: usize) -> bool {
+        if !self.can_add(a, b) {
+            return false;
+        }
+
+        let (end_a, end_b) = (self.other_end[a], self.other_end[b]);
+        self.link(a, b);
+        self.link(b, a);
+        self.n_edges += 1;
+
+        if end_a != b {
+            self.other_end[end_a] = end_b;
+            self.other_end[end_b] = end_a;
+            self.union(a, b);
+        }
+
+        true
+    }
+
+    /// Adds an edge, see [`PartialTour::add`].
+    pub fn add_edge(&mut self, edge: Edge) -> bool {
+        self.add(edge.low(), edge.high())
+    }
+
+    /// Returns `true` if the edges form a single cycle through all nodes. States with fewer than
+    /// three nodes are complete once they form a single path.
+    pub fn is_complete(&self) -> bool {
+        match self.len() {
+            0 => true,
+            len if len < 3 => self.n_edges + 1 == len,
+            len => self.n_edges == len,
+        }
+    }
+
+    /// Converts a complete state into a tour order starting at node `0`, whose cost is its length
+    /// in a store, or returns `None` if the state is not complete.
+    pub fn into_order<M>(self, store: &DataStore<M>) -> Option<TourOrder> {
+        if !self.is_complete() {
+            return None;
+        }
+
+        let len = self.len();
+        let mut order = Vec::with_capacity(len);
+        let (mut prev, mut current) = (NONE, 0);
+        while order.len() < len {
+            order.push(current);
+            let next = self
+                .neighbours(current)
+                .find(|&x| x != prev)
+                .unwrap_or(NONE);
+            prev = current;
+            current = next;
+        }
+
+        let cost = cycle_cost(store, &order);
+        Some(TourOrder::with_cost(order, cost))
+    }
+
+    fn link(&mut self, a: usize, b: usize) {
+        let slot = if self.adj[a][0] == NONE { 0 } else { 1 };
+        self.adj[a][slot] = b;
+    }
+
+    fn find(&self, index: usize) -> usize {
+        let mut root = index;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        root
+    }
+
+    /// Merges the sets of two nodes by rank, which keeps the trees of the sets logarithmically
+    /// shallow without compressing paths in `find`.
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        match self.rank[a].cmp(&self.rank[b]) {
+            Ordering::Less => self.parent[a] = b,
+            Ordering::Greater => self.parent[b] = a,
+            Ordering::Equal => {
+                self.parent[a] = b;
+                self.rank[b] += 1;
+            }
+        }
+    }
+}
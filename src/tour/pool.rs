@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use super::TourOrder;
+
+/// Keeps the best distinct tours found during a run.
+///
+/// Tours are considered equal if they describe the same cycle, i.e. their orders only differ by
+/// rotation or reflection. The pool holds at most `capacity` tours, sorted by ascending cost.
+#[derive(Debug)]
+pub struct SolutionPool {
+    capacity: usize,
+    tours: Vec<TourOrder>,
+    keys: HashSet<Vec<usize>>,
+}
+
+impl SolutionPool {
+    /// Creates an empty pool that keeps at most `capacity` tours.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tours: Vec::with_capacity(capacity),
+            keys: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Offers a tour to the pool and returns `true` if it was stored.
+    ///
+    /// A tour is rejected if the pool already holds the same cycle, or if the pool is full and
+    /// the tour is not cheaper than the most expensive tour in the pool. Otherwise, the most
+    /// expensive tour is evicted if necessary.
+    pub fn insert(&mut self, order: TourOrder) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+
+        if self.tours.len() == self.capacity {
+            match self.tours.last() {
+                Some(worst) if worst.cost() <= order.cost() => return false,
+                _ => {}
+            }
+        }
+
+        let key = order.canonical();
+        if self.keys.contains(&key) {
+            return false;
+        }
+
+        let pos = self.tours.partition_point(|x| x.cost() <= order.cost());
+        self.tours.insert(pos, order);
+        self.keys.insert(key);
+
+        if self.tours.len() > self.capacity {
+            if let Some(evicted) = self.tours.pop() {
+                self.keys.remove(&evicted.canonical());
+            }
+        }
+
+        true
+    }
+
+    /// Returns `true` if the pool holds a tour that describes the same cycle as the given order.
+    pub fn contains(&self, order: &TourOrder) -> bool {
+        self.keys.contains(&order.canonical())
+    }
+
+    /// Returns the cheapest tour in the pool.
+    pub fn best(&self) -> Option<&TourOrder> {
+        self.tours.first()
+    }
+
+    /// Returns the tours in the pool, sorted by ascending cost.
+    pub fn tours(&self) -> &[TourOrder] {
+        &self.tours
+    }
+
+    /// Consumes the pool and returns its tours, sorted by ascending cost.
+    pub fn into_tours(self) -> Vec<TourOrder> {
+        self.tours
+    }
+
+    /// Returns the maximum number of tours in the pool.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of tours in the pool.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tours.len()
+    }
+
+    /// Returns `true` if the pool holds no tours.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tours.is_empty()
+    }
+}
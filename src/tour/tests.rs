@@ -1,5 +1,5 @@
 #![cfg(test)]
-use crate::tour::between;
+use crate::tour::cyclic::{between, cyclic_distance, wrapped_range};
 use crate::{
     data::NodeKind,
     data::{DataStore, Metric},
@@ -7,7 +7,7 @@ use crate::{
     Scalar,
 };
 
-use super::{Tour, TourOrder};
+use super::{Edge, PartialTour, SolutionPool, Tour, TourOrder};
 
 pub(crate) fn create_store(n_nodes: usize) -> DataStore<()> {
     let mut store = DataStore::<()>::with_capacity(Metric::Euc3d, n_nodes);
@@ -63,6 +63,204 @@ fn test_between() {
     assert!(!between(5, 3, 1)); // false
 }
 
+#[test]
+fn test_cyclic() {
+    assert_eq!(2, cyclic_distance(1, 3, 5));
+    assert_eq!(3, cyclic_distance(3, 1, 5));
+    assert_eq!(0, cyclic_distance(4, 4, 5));
+    assert_eq!(1, cyclic_distance(4, 5, 5));
+
+    assert_eq!(vec![1, 2, 3], wrapped_range(1, 3, 5).collect::<Vec<_>>());
+    assert_eq!(vec![3, 4, 0, 1], wrapped_range(3, 1, 5).collect::<Vec<_>>());
+    assert_eq!(vec![2], wrapped_range(2, 2, 5).collect::<Vec<_>>());
+    assert!(wrapped_range(3, 1, 5).all(|pos| between(3, pos, 1)));
+}
+
+#[test]
+fn test_display_order() {
+    assert_eq!("()", TourOrder::new().to_string());
+    assert_eq!("(3)", TourOrder::with_ord(vec![3]).to_string());
+    assert_eq!("(0 1)", TourOrder::with_ord(vec![0, 1]).to_string());
+    assert_eq!(
+        "(0-3 9-7 4 6 5 10-12)",
+        TourOrder::with_ord(vec![0, 1, 2, 3, 9, 8, 7, 4, 6, 5, 10, 11, 12]).to_string()
+    );
+}
+
+#[test]
+fn test_canonical_order() {
+    assert!(TourOrder::new().canonical().is_empty());
+    let expected = vec![0, 1, 2, 3, 4];
+    assert_eq!(
+        expected,
+        TourOrder::with_ord(vec![2, 3, 4, 0, 1]).canonical()
+    );
+    assert_eq!(
+        expected,
+        TourOrder::with_ord(vec![3, 2, 1, 0, 4]).canonical()
+    );
+    assert_eq!(
+        vec![0, 2, 4, 1, 3],
+        TourOrder::with_ord(vec![4, 2, 0, 3, 1]).canonical()
+    );
+}
+
+#[test]
+fn test_order_eq() {
+    use std::collections::HashSet;
+
+    let order = TourOrder::with_cost(vec![0, 1, 2, 3, 4], 10.);
+    assert_eq!(order, TourOrder::with_ord(vec![2, 3, 4, 0, 1]));
+    assert_eq!(order, TourOrder::with_ord(vec![3, 2, 1, 0, 4]));
+    assert_ne!(order, TourOrder::with_ord(vec![0, 2, 1, 3, 4]));
+    assert_ne!(order, TourOrder::with_ord(vec![0, 1, 2, 3]));
+    assert_eq!(TourOrder::new(), TourOrder::default());
+
+    let set: HashSet<_> = vec![
+        order.clone(),
+        TourOrder::with_ord(vec![4, 3, 2, 1, 0]),
+        TourOrder::with_ord(vec![1, 0, 4, 3, 2]),
+        TourOrder::with_ord(vec![1, 0, 2, 3, 4]),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(2, set.len());
+    assert!(set.contains(&order));
+}
+
+#[test]
+fn test_order_bytes() {
+    use crate::CyklError;
+
+    let order = TourOrder::with_cost(vec![0, 1, 2, 300, 299, 1_000_000, 5], 1234.5);
+    let bytes = order.to_bytes();
+    // Version, length, cost, and the differences 0, 1, 1, 298, -1, 999_701, -999_995.
+    assert_eq!(1 + 1 + 8 + 1 + 1 + 1 + 2 + 1 + 3 + 3, bytes.len());
+    let decoded = TourOrder::from_bytes(&bytes).unwrap();
+    assert_eq!(order.order(), decoded.order());
+    assert_eq!(order.cost(), decoded.cost());
+
+    let empty = TourOrder::new();
+    let decoded = TourOrder::from_bytes(&empty.to_bytes()).unwrap();
+    assert!(decoded.is_empty());
+    assert_eq!(Scalar::MAX, decoded.cost());
+
+    for bytes in [
+        &[][..],
+        &[2, 0, 0, 0, 0, 0, 0, 0, 0, 0][..],
+        &bytes[..bytes.len() - 1],
+        &[1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1][..],
+        // A length of 2^63 - 1, which must not be allocated.
+        &[
+            1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ][..],
+        // The index i64::MAX, followed by a step of 1 that overflows.
+        &[
+            1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0x01, 2,
+        ][..],
+    ] {
+        assert!(matches!(
+            TourOrder::from_bytes(bytes),
+            Err(CyklError::Parse(_))
+        ));
+    }
+
+    let mut trailing = order.to_bytes();
+    trailing.push(0);
+    assert!(TourOrder::from_bytes(&trailing).is_err());
+}
+
+#[test]
+fn test_solution_pool() {
+    let mut pool = SolutionPool::new(2);
+    assert!(pool.is_empty());
+    assert!(pool.best().is_none());
+
+    assert!(pool.insert(TourOrder::with_cost(vec![0, 1, 2, 3], 10.)));
+    // Rotations and reflections of stored tours are rejected.
+    assert!(!pool.insert(TourOrder::with_cost(vec![2, 1, 0, 3], 10.)));
+    assert!(pool.contains(&TourOrder::with_ord(vec![1, 2, 3, 0])));
+
+    assert!(pool.insert(TourOrder::with_cost(vec![0, 2, 1, 3], 12.)));
+    assert!(!pool.insert(TourOrder::with_cost(vec![0, 1, 3, 2], 12.)));
+    assert_eq!(2, pool.len());
+
+    // A cheaper tour evicts the most expensive one.
+    assert!(pool.insert(TourOrder::with_cost(vec![0, 1, 3, 2], 8.)));
+    assert!(!pool.contains(&TourOrder::with_ord(vec![0, 2, 1, 3])));
+    let costs: Vec<_> = pool.tours().iter().map(|x| x.cost()).collect();
+    assert_eq!(vec![8., 10.], costs);
+    assert_eq!(vec![0, 1, 3, 2], *pool.best().unwrap().order());
+
+    assert!(!SolutionPool::new(0).insert(TourOrder::with_nat_ord(3)));
+}
+
+#[test]
+fn test_wrap_tokens() {
+    let tokens: Vec<String> = (0..12).map(|ii| ii.to_string()).collect();
+    assert_eq!(
+        "ab 0 1 2 3\n   4 5 6 7\n   8 9 10\n   11",
+        super::wrap_tokens("ab ", &tokens, 10)
+    );
+}
+
+#[test]
+fn test_edge() {
+    let store = create_store(4);
+    let edge = Edge::new(3, 1);
+    assert_eq!(Edge::new(1, 3), edge);
+    assert_eq!(Edge::from((1, 3)), edge);
+    assert_eq!((1, 3), edge.nodes());
+    assert!(edge.contains(3) && !edge.contains(2));
+    assert_eq!(Some(1), edge.other(3));
+    assert_eq!(None, edge.other(0));
+    assert_eq!(store.cost(&1, &3), edge.length(&store));
+    assert_eq!("(1, 3)", edge.to_string());
+    assert_eq!((1 << 32) | 3, edge.key());
+    assert_eq!(edge, Edge::from_key(edge.key()));
+    assert_ne!(Edge::new(1, 4).key(), edge.key());
+}
+
+#[test]
+fn test_partial_tour() {
+    let store = create_store(5);
+    let mut partial = PartialTour::new(5);
+    assert_eq!(5, partial.n_fragments());
+    assert_eq!(Some(3), partial.fragment_end(3));
+
+    assert!(partial.add(0, 1));
+    assert!(partial.add_edge(Edge::new(3, 2)));
+    assert!(partial.add(1, 2));
+    assert_eq!(2, partial.n_fragments());
+    assert_eq!(2, partial.degree(1));
+    assert_eq!(None, partial.fragment_end(1));
+    assert_eq!(Some(3), partial.fragment_end(0));
+    assert!(partial.same_fragment(0, 3));
+    assert!(!partial.same_fragment(0, 4));
+
+    // Degrees are limited to two and fragments must not close early.
+    assert!(!partial.add(1, 4));
+    assert!(!partial.add(0, 3));
+    assert!(!partial.add(4, 4));
+    assert!(!partial.is_complete());
+
+    assert!(partial.add(3, 4));
+    assert!(partial.add(4, 0));
+    assert!(partial.is_complete());
+    assert_eq!(0, partial.n_fragments());
+
+    let order = partial.into_order(&store).unwrap();
+    assert_eq!(vec![0, 1, 2, 3, 4], order.canonical());
+    assert_eq!(8. * (3. as Scalar).sqrt(), order.cost());
+
+    let mut pair = PartialTour::new(2);
+    assert!(pair.add(0, 1));
+    assert!(!pair.add(1, 0));
+    assert_eq!(vec![0, 1], *pair.into_order(&store).unwrap().order());
+    assert!(PartialTour::new(3).into_order(&store).is_none());
+}
+
 #[allow(dead_code, unused_imports)]
 mod test_tll {
     use std::collections::HashMap;
@@ -71,9 +269,10 @@ mod test_tll {
 
     use crate::tour::{
         tests::{create_store, test_tour_order},
-        tll::TwoLevelList,
-        STree, Tour, TourIter, TourOrder,
+        tll::{ReversalPolicy, TwoLevelList},
+        STree, Tour, TourIter, TourNode, TourOrder,
     };
+    use crate::{CancellationToken, CyklError};
 
     #[test]
     fn test_apply() {
@@ -87,17 +286,778 @@ mod test_tll {
         test_suite::total_dist(&mut tour);
     }
 
+    #[test]
+    fn test_tour_order_into() {
+        let mut tour = TwoLevelList::new(&create_store(10), 4);
+        tour.apply(&TourOrder::with_ord(vec![3, 1, 4, 0, 5, 9, 2, 6, 8, 7]))
+            .unwrap();
+
+        // The buffer of the order is reused, and its previous content is replaced.
+        let mut order = TourOrder::with_nat_ord(12);
+        let buffer = order.order().as_ptr();
+        tour.tour_order_into(&mut order);
+        assert_eq!(buffer, order.order().as_ptr());
+        assert_eq!(tour.tour_order().order(), order.order());
+        assert_eq!(tour.tour_order().cost(), order.cost());
+    }
+
     #[test]
     fn test_between() {
         let mut tour = TwoLevelList::new(&create_store(10), 3);
         test_suite::between(&mut tour);
     }
 
+    #[test]
+    #[should_panic(expected = "Empty node")]
+    fn test_distance_empty_node() {
+        let tour = TwoLevelList::new(&create_store(10), 3);
+        tour.distance(&tour.get(0).unwrap(), &TourNode::default());
+    }
+
     #[test]
     fn test_flip_cases() {
         let mut tour = TwoLevelList::new(&create_store(100), 10);
         test_suite::flip(&mut tour);
     }
+
+    #[test]
+    fn test_flip_cases_eager() {
+        let mut tour = TwoLevelList::new(&create_store(100), 10)
+            .with_reversal_policy(ReversalPolicy::Eager { threshold: 100 });
+        test_suite::flip(&mut tour);
+        // Segments grow beyond the group size by splitting, but all of them are small enough.
+        assert!(!tour.dump(400).contains('<'));
+
+        tour.set_reversal_policy(ReversalPolicy::Eager { threshold: 3 });
+        test_suite::flip(&mut tour);
+        test_suite::at_position(&mut tour);
+        test_suite::path_length(&mut tour);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut tour = TwoLevelList::new(&create_store(10), 4);
+        tour.flip_at(3, 4, 7, 8);
+        tour.rev();
+        let expected = tour.tour_order();
+        assert!(tour.dump(80).contains('<'));
+
+        tour.normalize();
+        test_tour_order(&tour, &expected);
+        assert!(!tour.dump(80).contains('<'));
+        assert!(tour.dump(80).contains("reversed: false"));
+        assert_eq!(ReversalPolicy::Lazy, tour.reversal_policy());
+    }
+
+    #[test]
+    fn test_apply_with() {
+        // Costs are computed on the fly, which keeps a large store cheap.
+        let len = 200_000;
+        let mut store = DataStore::<()>::with_capacity(Metric::Euc2d, len);
+        for ii in 0..len {
+            store.add(NodeKind::Target, vec![ii as Scalar, 0.], ());
+        }
+        let mut tour = TwoLevelList::new(&store, 1000);
+        let token = CancellationToken::new();
+
+        let reversed = TourOrder::with_ord((0..len).rev().collect());
+        let mut percentages = Vec::new();
+        let result = tour.apply_with(&reversed, &token, |p| percentages.push(p));
+        assert!(result.is_ok());
+        test_tour_order(&tour, &reversed);
+        assert_eq!(4, percentages.len());
+        assert!(percentages.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(Some(&100.), percentages.last());
+
+        // Cancelling between chunks restores the previous order.
+        let natural = TourOrder::with_nat_ord(len);
+        let result = tour.apply_with(&natural, &token, |_| token.cancel());
+        assert!(matches!(result, Err(CyklError::Cancelled)));
+        test_tour_order(&tour, &reversed);
+        assert_eq!(2. * (len - 1) as Scalar, tour.total_distance());
+
+        let result = tour.apply_with(&natural, &token, |_| panic!("Tour was rebuilt"));
+        assert!(matches!(result, Err(CyklError::Cancelled)));
+        let result = tour.apply_with(&TourOrder::with_nat_ord(3), &token, |_| {});
+        assert!(matches!(result, Err(CyklError::Tour(_))));
+    }
+
+    #[test]
+    fn test_builder() {
+        use crate::{overlay::CostOverlay, tour::TourBuilder, CyklError};
+
+        let store = create_store(10);
+        let tour = TourBuilder::new().build(&store).unwrap();
+        test_tour_order(&tour, &TourOrder::with_nat_ord(10));
+        // Square root of 10, rounded up.
+        assert!(tour.dump(80).contains("3 segments"));
+
+        let order = TourOrder::with_ord(vec![0, 2, 4, 6, 8, 9, 7, 5, 3, 1]);
+        let mut overlay = CostOverlay::new();
+        overlay.set(0, 1, 100.);
+        let tour = TourBuilder::new()
+            .groupsize(4)
+            .reversal_policy(ReversalPolicy::Eager { threshold: 4 })
+            .order(order.clone())
+            .overlay(overlay)
+            .build(&store)
+            .unwrap();
+        test_tour_order(&tour, &order);
+        assert_eq!(
+            ReversalPolicy::Eager { threshold: 4 },
+            tour.reversal_policy()
+        );
+        assert!(tour.overlay().unwrap().contains(0, 1));
+        assert_eq!(100., tour.distance_at(&0, &1).unwrap());
+        // The length of the initial order is measured with the costs of the overlay.
+        let nodes = order.order();
+        let expected: Scalar = (0..nodes.len())
+            .map(|ii| {
+                let next = nodes[(ii + 1) % nodes.len()];
+                tour.distance_at(&nodes[ii], &next).unwrap()
+            })
+            .sum();
+        assert!((expected - tour.tour_order().cost()).abs() < 1e-9);
+
+        assert!(matches!(
+            TourBuilder::new()
+                .order(TourOrder::with_nat_ord(9))
+                .build(&store),
+            Err(CyklError::Tour(_))
+        ));
+    }
+
+    #[test]
+    fn test_repack() {
+        use crate::tour::{live_vertices, reserved_vertices};
+
+        let baseline = live_vertices();
+        let store = create_store(20);
+        let mut tour = TwoLevelList::new(&store, 4);
+        tour.flip_at(3, 4, 11, 12);
+        tour.flip_at(15, 16, 1, 2);
+        tour.exclude(7).unwrap();
+        let cands = vec![tour.get(5).unwrap(), tour.get(7).unwrap()];
+        tour.get(0).unwrap().set_candidates(cands);
+        let expected = tour.tour_order();
+
+        tour.repack();
+        assert_eq!(baseline + 20, live_vertices());
+        assert_eq!(expected, tour.tour_order());
+        assert!(!tour.is_included(7));
+        let cands: Vec<usize> = tour
+            .get(0)
+            .unwrap()
+            .candidates()
+            .iter()
+            .map(|x| tour.index_of(x))
+            .collect();
+        assert_eq!(vec![5, 7], cands);
+
+        tour.include(7).unwrap();
+        tour.flip_at(8, 9, 13, 14);
+        let expected = tour.tour_order();
+        tour.repack();
+        assert_eq!(baseline + 20, live_vertices());
+        test_tour_order(&tour, &expected);
+
+        let reserved = reserved_vertices();
+        for _ in 0..3 {
+            tour.repack();
+        }
+        assert_eq!(reserved, reserved_vertices());
+        test_tour_order(&tour, &expected);
+
+        drop(tour);
+        drop(store);
+        assert_eq!(baseline, live_vertices());
+
+        let mut tour = TwoLevelList::new(&create_store(100), 10);
+        tour.flip_at(30, 31, 60, 61);
+        tour.repack();
+        test_suite::flip(&mut tour);
+    }
+
+    #[test]
+    fn test_export() {
+        let mut tour = TwoLevelList::new(&create_store(8), 4);
+        assert_eq!(
+            concat!(
+                r#"{"nodes":8,"included":8,"reversed":false,"segments":["#,
+                r#"{"rank":0,"reverse":false,"next":1,"prev":1,"nodes":[0,1,2,3]},"#,
+                r#"{"rank":1,"reverse":false,"next":0,"prev":0,"nodes":[4,5,6,7]}]}"#
+            ),
+            tour.to_json()
+        );
+
+        tour.flip_at(3, 4, 7, 0);
+        assert!(tour
+            .to_json()
+            .contains(r#"{"rank":0,"reverse":true,"next":1,"prev":1,"nodes":[3,2,1,0]}"#));
+
+        let dot = tour.to_dot();
+        assert!(dot.starts_with("digraph tll {"));
+        assert!(dot.contains("s0 [label=\"#0 reversed\\n3 2 1 0\"];"));
+        assert!(dot.contains("s0 -> s1;"));
+        assert!(dot.contains("s1 -> s0;"));
+        assert!(dot.ends_with('}'));
+    }
+
+    #[test]
+    fn test_link_size() {
+        use crate::tour::node::Link;
+
+        // Links between vertices take as many bytes as raw node indices.
+        let expected = if cfg!(feature = "u32-index") {
+            4
+        } else {
+            std::mem::size_of::<usize>()
+        };
+        assert_eq!(expected, std::mem::size_of::<Link>());
+    }
+
+    #[test]
+    fn test_drop_releases_vertices() {
+        use crate::tour::{live_vertices, reserved_vertices};
+
+        let baseline = live_vertices();
+        let store = create_store(20);
+        let mut tour = TwoLevelList::new(&store, 4);
+        assert_eq!(baseline + 20, live_vertices());
+
+        tour.flip_at(3, 4, 11, 12);
+        tour.normalize();
+        tour.exclude(5).unwrap();
+        assert_eq!(baseline + 20, live_vertices());
+
+        // The tour keeps the nodes of the store alive.
+        drop(store);
+        assert_eq!(19, tour.n_included());
+
+        drop(tour);
+        assert_eq!(baseline, live_vertices());
+
+        let tour = TwoLevelList::new(&create_store(12), 4);
+        assert_eq!(baseline + 12, live_vertices());
+        drop(tour);
+        assert_eq!(baseline, live_vertices());
+    }
+
+    #[test]
+    fn test_drop_frees_blocks() {
+        use crate::tour::reserved_vertices;
+
+        let reserved = reserved_vertices();
+        let mut large = TwoLevelList::new(&create_store(1000), 10);
+        let grown = reserved_vertices();
+        assert!(grown >= reserved + 1000);
+
+        // Small tours share a block, which is only freed with the last of them.
+        let small = TwoLevelList::new(&create_store(20), 4);
+        let shared = reserved_vertices();
+        let other = TwoLevelList::new(&create_store(12), 4);
+        assert_eq!(shared, reserved_vertices());
+        drop(small);
+        assert_eq!(shared, reserved_vertices());
+        drop(other);
+        assert_eq!(grown, reserved_vertices());
+
+        // The old run of a repacked tour is freed, and so is the new one once the tour is dropped.
+        large.flip_at(300, 301, 600, 601);
+        large.repack();
+        assert_eq!(grown, reserved_vertices());
+        drop(large);
+        assert_eq!(reserved, reserved_vertices());
+    }
+
+    #[test]
+    fn test_flip_reversed_segment() {
+        let mut tour = TwoLevelList::new(&create_store(12), 4);
+
+        // Reverses the whole segment [4, 7].
+        tour.flip(
+            &tour.get(3).unwrap(),
+            &tour.get(4).unwrap(),
+            &tour.get(7).unwrap(),
+            &tour.get(8).unwrap(),
+        );
+        test_tour_order(
+            &tour,
+            &TourOrder::with_ord(vec![0, 1, 2, 3, 7, 6, 5, 4, 8, 9, 10, 11]),
+        );
+
+        // Reverses a path inside the reversed segment.
+        tour.flip(
+            &tour.get(7).unwrap(),
+            &tour.get(6).unwrap(),
+            &tour.get(5).unwrap(),
+            &tour.get(4).unwrap(),
+        );
+        test_tour_order(
+            &tour,
+            &TourOrder::with_ord(vec![0, 1, 2, 3, 7, 5, 6, 4, 8, 9, 10, 11]),
+        );
+    }
+
+    #[test]
+    fn test_flip_adjacent_segments() {
+        let mut tour = TwoLevelList::new(&create_store(16), 4);
+
+        // Splits the segments so that [5, 8] forms a reversed segment.
+        tour.flip(
+            &tour.get(4).unwrap(),
+            &tour.get(5).unwrap(),
+            &tour.get(8).unwrap(),
+            &tour.get(9).unwrap(),
+        );
+        test_tour_order(
+            &tour,
+            &TourOrder::with_ord(vec![0, 1, 2, 3, 4, 8, 7, 6, 5, 9, 10, 11, 12, 13, 14, 15]),
+        );
+
+        // Reverses a path that spans two neighbouring segments.
+        tour.flip(
+            &tour.get(4).unwrap(),
+            &tour.get(8).unwrap(),
+            &tour.get(10).unwrap(),
+            &tour.get(11).unwrap(),
+        );
+        test_tour_order(
+            &tour,
+            &TourOrder::with_ord(vec![0, 1, 2, 3, 4, 10, 9, 5, 6, 7, 8, 11, 12, 13, 14, 15]),
+        );
+
+        for node in tour.itr() {
+            let succ = tour.successor(&node).unwrap();
+            assert_eq!(Some(node), tour.predecessor(&succ));
+        }
+    }
+
+    #[test]
+    fn test_exhaustive() {
+        for groupsize in 1..=3 {
+            test_suite::exhaustive(|len| TwoLevelList::new(&create_store(len), groupsize), 8, 6);
+        }
+    }
+
+    #[test]
+    fn test_same_cycle() {
+        use crate::tour::same_cycle;
+
+        let store = create_store(10);
+        let a = TwoLevelList::new(&store, 3);
+        let mut b = TwoLevelList::new(&store, 4);
+        b.apply(&TourOrder::with_ord(vec![4, 3, 2, 1, 0, 9, 8, 7, 6, 5]))
+            .unwrap();
+        assert!(same_cycle(&a, &b));
+        b.rev();
+        assert!(same_cycle(&a, &b));
+
+        b.flip_at(2, 3, 6, 7);
+        assert!(!same_cycle(&a, &b));
+        b.flip_at(2, 6, 3, 7);
+        assert!(same_cycle(&b, &a));
+
+        b.exclude(5).unwrap();
+        assert!(!same_cycle(&a, &b));
+        let mut c = TwoLevelList::new(&store, 3);
+        c.exclude(5).unwrap();
+        assert!(same_cycle(&c, &b));
+    }
+
+    #[test]
+    fn test_between_at_scale() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let len = 1000;
+        let mut tour = TwoLevelList::new(&create_store(len), 10);
+        let mut rng = StdRng::seed_from_u64(5);
+
+        for step in 0..400 {
+            let (a, b) = (rng.gen_range(0..len), rng.gen_range(0..len));
+            if tour.is_included(a) && tour.is_included(b) {
+                let (from_a, from_b) = (tour.get(a).unwrap(), tour.get(b).unwrap());
+                let to_a = tour.successor(&from_a).unwrap();
+                let to_b = tour.successor(&from_b).unwrap();
+                if from_a != to_b && from_b != to_a && from_a != from_b {
+                    tour.flip(&from_a, &to_a, &from_b, &to_b);
+                }
+            }
+
+            match step % 50 {
+                0 => tour.rev(),
+                10 => assert!(tour.exclude(a).is_ok()),
+                30 if !tour.is_included(b) => assert!(tour.include(b).is_ok()),
+                _ => {}
+            }
+
+            if step % 20 == 19 {
+                let order = tour.tour_order();
+                let mut pos = vec![usize::MAX; len];
+                for (ii, &index) in order.order().iter().enumerate() {
+                    pos[index] = ii;
+                }
+
+                for _ in 0..50 {
+                    let [f, m, t] = [0; 3].map(|_| order.order()[rng.gen_range(0..order.len())]);
+                    assert_eq!(
+                        between(pos[f], pos[m], pos[t]),
+                        tour.between_at(f, m, t),
+                        "step {}: {} {} {}",
+                        step,
+                        f,
+                        m,
+                        t
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove_insert() {
+        use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+        use crate::{
+            alg::{cand_gen_nn, Objective},
+            data::GetIndex,
+        };
+
+        let len = 60;
+        let mut tour = TwoLevelList::new(&create_store(len), 6);
+        cand_gen_nn(&mut tour, 5, Objective::Minimize);
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut order: Vec<usize> = (0..len).collect();
+        order.shuffle(&mut rng);
+        assert!(tour.apply(&TourOrder::with_ord(order)).is_ok());
+
+        // Flips reverse segments, so that nodes are also removed from and inserted into reversed
+        // segments.
+        let flip = |tour: &mut TwoLevelList, a: usize, b: usize| {
+            let (from_a, from_b) = (tour.get(a).unwrap(), tour.get(b).unwrap());
+            let to_a = tour.successor(&from_a).unwrap();
+            let to_b = tour.successor(&from_b).unwrap();
+            if from_a != to_b && from_b != to_a && from_a != from_b {
+                tour.flip(&from_a, &to_a, &from_b, &to_b);
+            }
+        };
+
+        // Removals report the change of the tour length.
+        let mut removed: Vec<usize> = (0..len).step_by(3).collect();
+        removed.shuffle(&mut rng);
+        for (ii, index) in removed.iter().enumerate() {
+            flip(&mut tour, 2, [49, 23, 38, 59][ii % 4]);
+            let before = tour.tour_order().cost();
+            let gain = tour.remove_with_gain(*index).unwrap();
+            assert!(!tour.is_included(*index));
+            assert!(approx_eq!(
+                f64,
+                before - gain,
+                tour.tour_order().cost(),
+                epsilon = 1e-8
+            ));
+            assert!(tour.remove_with_gain(*index).is_err());
+
+            for node in tour
+                .itr()
+                .filter(|node| tour.is_included(node.index().get()))
+            {
+                let succ = tour.successor(&node).unwrap();
+                assert_eq!(Some(node), tour.predecessor(&succ));
+            }
+        }
+
+        for (ii, index) in removed.into_iter().enumerate() {
+            flip(&mut tour, 1, [50, 22, 37, 58][ii % 4]);
+            let before = tour.tour_order().cost();
+            let cost = tour.insert_cheapest(index).unwrap();
+            let order = tour.tour_order();
+            assert!(approx_eq!(f64, before + cost, order.cost(), epsilon = 1e-8));
+
+            for (pos, index) in order.order().iter().enumerate() {
+                let node = tour.get(*index).unwrap();
+                let succ = tour.successor(&node).unwrap();
+                assert_eq!(Some(node), tour.predecessor(&succ));
+                assert_eq!(Some(pos), tour.order_index(*index));
+            }
+
+            let v = order.order();
+            let n = v.len();
+            for ii in 0..n {
+                let (a, b, c) = (v[ii], v[(ii + 3) % n], v[(ii + n / 2) % n]);
+                assert!(tour.between_at(a, b, c));
+                assert!(!tour.between_at(c, b, a) || b == a || b == c);
+            }
+        }
+        assert_eq!(len, tour.n_included());
+    }
+
+    #[test]
+    fn test_distances() {
+        let tour = TwoLevelList::new(&create_store(5), 3);
+        let pairs = [(0, 1), (3, 3), (4, 0), (2, 4)];
+        let mut out = [Scalar::MAX; 4];
+
+        tour.distances(&pairs, &mut out);
+        for (d, (a, b)) in out.iter().zip(pairs.iter()) {
+            assert_eq!(tour.distance_at(a, b).unwrap(), *d);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Mismatched lengths")]
+    fn test_distances_mismatched() {
+        let tour = TwoLevelList::new(&create_store(5), 3);
+        tour.distances(&[(0, 1), (1, 2)], &mut [0.; 1]);
+    }
+
+    #[test]
+    fn test_fallible_ops() {
+        let mut tour = TwoLevelList::new(&create_store(10), 3);
+        test_suite::fallible_ops(&mut tour);
+    }
+
+    #[test]
+    fn test_dump() {
+        let mut tour = TwoLevelList::new(&create_store(10), 4);
+        tour.flip_at(3, 4, 7, 8);
+        let expected = "TwoLevelList: 10 nodes, 3 segments, reversed: false\n   \
+                        0 > | 0 1 2 3\n   \
+                        1 < | 7 6 5 4\n   \
+                        2 > | 8 9";
+        assert_eq!(expected, tour.dump(80));
+        assert!(tour.dump(14).contains("   1 < | 7 6 5\n         4"));
+    }
+
+    #[test]
+    fn test_at_position() {
+        let mut tour = TwoLevelList::new(&create_store(100), 10);
+        test_suite::at_position(&mut tour);
+    }
+
+    #[test]
+    fn test_path_length() {
+        let mut tour = TwoLevelList::new(&create_store(100), 10);
+        test_suite::path_length(&mut tour);
+        test_suite::extract_path(&mut tour);
+    }
+
+    #[test]
+    fn test_path_length_asymmetric() {
+        // The asymmetric part of the costs does not cancel out along cycles.
+        let n_nodes = 50;
+        let mut store = DataStore::<()>::with_capacity(Metric::Explicit, n_nodes);
+        for _ in 0..n_nodes {
+            store.add(NodeKind::Target, Vec::new(), ());
+        }
+        let costs = (0..n_nodes * n_nodes)
+            .map(|x| {
+                let (a, b) = (x / n_nodes, x % n_nodes);
+                (a.max(b) - a.min(b) + (a * a * b + b) % 10) as Scalar
+            })
+            .collect();
+        store.set_costs(costs).unwrap();
+        store.compute();
+
+        let walk = |tour: &TwoLevelList, from: usize, to: usize| {
+            let (mut node, target) = (tour.get(from).unwrap(), tour.get(to).unwrap());
+            let mut result = 0.;
+            loop {
+                let next = tour.successor(&node).unwrap();
+                result += tour.distance(&node, &next);
+                node = next;
+                if node == target {
+                    return result;
+                }
+            }
+        };
+
+        // Flips leave some of the segments reversed.
+        let mut tour = TwoLevelList::new(&store, 7);
+        assert!(tour.apply(&TourOrder::with_nat_ord(n_nodes)).is_ok());
+        for &(a, b) in &[(9, 30), (3, 5), (20, 42)] {
+            let (from_a, from_b) = (tour.get(a).unwrap(), tour.get(b).unwrap());
+            let to_a = tour.successor(&from_a).unwrap();
+            let to_b = tour.successor(&from_b).unwrap();
+            tour.flip(&from_a, &to_a, &from_b, &to_b);
+        }
+        assert!(tour.dump(400).contains('<'));
+
+        for &(from, to) in &[
+            (2, 5),
+            (5, 2),
+            (12, 25),
+            (25, 12),
+            (8, 40),
+            (40, 8),
+            (29, 11),
+        ] {
+            assert!(
+                approx_eq!(
+                    f64,
+                    walk(&tour, from, to),
+                    tour.path_length(from, to).unwrap()
+                ),
+                "Test path length from {} to {}",
+                from,
+                to
+            );
+        }
+
+        // The tour length follows the direction of the tour when nodes are removed or inserted.
+        for &(index, include) in &[(25, false), (38, false), (25, true), (38, true)] {
+            let (before, length) = (walk(&tour, 0, 0), tour.total_distance());
+            if include {
+                assert!(tour.include(index).is_ok());
+            } else {
+                assert!(tour.exclude(index).is_ok());
+            }
+            let delta = tour.total_distance() - length;
+            assert!(approx_eq!(
+                f64,
+                walk(&tour, 0, 0) - before,
+                delta,
+                epsilon = 1e-8
+            ));
+        }
+    }
+
+    #[test]
+    fn test_exclude_include() {
+        let mut tour = TwoLevelList::new(&create_store(10), 3);
+        test_suite::exclude_include(&mut tour);
+    }
+}
+
+// Tour that only implements the required operations, so the default implementations of the
+// remaining operations are exercised by the test suite.
+mod test_defaults {
+    use crate::{
+        data::GetIndex,
+        tour::{
+            tests::create_store, NodeRel, Tour, TourIter, TourNode, TourOrder, TwoLevelList,
+            UpdateTourError,
+        },
+        CyklError, Scalar,
+    };
+
+    use super::test_suite;
+
+    struct MinimalTour(TwoLevelList);
+
+    impl Tour for MinimalTour {
+        fn apply(&mut self, order: &TourOrder) -> Result<(), UpdateTourError> {
+            self.0.apply(order)
+        }
+
+        fn between(&self, from: &TourNode, mid: &TourNode, to: &TourNode) -> bool {
+            self.0.between(from, mid, to)
+        }
+
+        fn distance_at<I>(&self, a: &I, b: &I) -> Result<Scalar, CyklError>
+        where
+            I: GetIndex + PartialEq + Eq,
+        {
+            self.0.distance_at(a, b)
+        }
+
+        fn flip(&mut self, from_a: &TourNode, to_a: &TourNode, from_b: &TourNode, to_b: &TourNode) {
+            self.0.flip(from_a, to_a, from_b, to_b)
+        }
+
+        fn get(&self, index: usize) -> Option<TourNode> {
+            self.0.get(index)
+        }
+
+        fn successor(&self, kin: &TourNode) -> Option<TourNode> {
+            self.0.successor(kin)
+        }
+
+        fn predecessor(&self, kin: &TourNode) -> Option<TourNode> {
+            self.0.predecessor(kin)
+        }
+
+        fn rev(&mut self) {
+            self.0.rev()
+        }
+
+        fn tour_order(&self) -> TourOrder {
+            self.0.tour_order()
+        }
+
+        fn measure(&self, to: &TourOrder) -> Scalar {
+            self.0.measure(to)
+        }
+
+        fn reset(&mut self) {
+            self.0.reset()
+        }
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn total_distance(&self) -> Scalar {
+            self.0.total_distance()
+        }
+
+        fn itr(&self) -> TourIter<'_> {
+            self.0.itr()
+        }
+
+        fn exclude(&mut self, index: usize) -> Result<(), UpdateTourError> {
+            self.0.exclude(index)
+        }
+
+        fn include(&mut self, index: usize) -> Result<(), UpdateTourError> {
+            self.0.include(index)
+        }
+
+        fn insert_after(
+            &mut self,
+            index: usize,
+            after_index: usize,
+        ) -> Result<(), UpdateTourError> {
+            self.0.insert_after(index, after_index)
+        }
+
+        fn is_included(&self, index: usize) -> bool {
+            self.0.is_included(index)
+        }
+    }
+
+    #[test]
+    fn test_default_exhaustive() {
+        test_suite::exhaustive(
+            |len| MinimalTour(TwoLevelList::new(&create_store(len), 2)),
+            6,
+            6,
+        );
+    }
+
+    #[test]
+    fn test_default_ops() {
+        let mut tour = MinimalTour(TwoLevelList::new(&create_store(100), 10));
+        test_suite::flip(&mut tour);
+        test_suite::at_position(&mut tour);
+        test_suite::path_length(&mut tour);
+        test_suite::extract_path(&mut tour);
+
+        let mut tour = MinimalTour(TwoLevelList::new(&create_store(10), 3));
+        test_suite::between(&mut tour);
+        test_suite::fallible_ops(&mut tour);
+        test_suite::exclude_include(&mut tour);
+
+        let (a, b, c) = (
+            tour.get(0).unwrap(),
+            tour.get(1).unwrap(),
+            tour.get(5).unwrap(),
+        );
+        assert_eq!(tour.0.relation(&a, &b), tour.relation(&a, &b));
+        assert_eq!(tour.0.relation(&b, &a), tour.relation(&b, &a));
+        assert_eq!(NodeRel::None, tour.relation(&a, &c));
+    }
 }
 
 #[cfg(test)]
@@ -106,10 +1066,16 @@ use float_cmp::approx_eq;
 #[cfg(test)]
 #[allow(dead_code)]
 mod test_suite {
+    use float_cmp::approx_eq;
+
     use crate::{
         combine_range,
-        tour::{tests::test_tour_order, Tour, TourOrder},
-        tour_order, Scalar,
+        data::GetIndex,
+        tour::{
+            cyclic, tests::test_tour_order, Edge, FlipSpec, PathDirection, Tour, TourOrder,
+            UpdateTourError,
+        },
+        tour_order, CyklError, Scalar,
     };
 
     pub fn apply(tour: &mut impl Tour) {
@@ -168,6 +1134,452 @@ mod test_suite {
         assert!(tour.between_at(3, 8, 5)); // true
         assert!(tour.between_at(8, 5, 3)); // true
         assert!(!tour.between_at(8, 3, 5)); // false
+
+        // 0 -> 9 -> 8 -> 7 -> 6 -> 3 -> 4 -> 5 -> 2 -> 1
+        tour.rev();
+        assert!(!tour.between_at(0, 1, 2)); // false
+        assert!(tour.between_at(2, 1, 0)); // true
+        assert!(tour.between_at(8, 3, 5)); // true
+        assert!(!tour.between_at(3, 8, 5)); // false
+        tour.rev();
+    }
+
+    pub fn path_length(tour: &mut impl Tour) {
+        let n_nodes = 100;
+        assert_eq!(n_nodes, tour.len());
+        assert!(tour.apply(&TourOrder::with_nat_ord(n_nodes)).is_ok());
+
+        let unit = (3. as Scalar).sqrt();
+        assert!(approx_eq!(f64, 0., tour.path_length(7, 7).unwrap()));
+        assert!(approx_eq!(
+            f64,
+            5. * unit,
+            tour.path_length(2, 7).unwrap(),
+            epsilon = 1e-10
+        ));
+        assert!(approx_eq!(
+            f64,
+            30. * unit,
+            tour.path_length(5, 35).unwrap(),
+            epsilon = 1e-10
+        ));
+        assert!(approx_eq!(
+            f64,
+            101. * unit,
+            tour.path_length(98, 1).unwrap(),
+            epsilon = 1e-10
+        ));
+        assert_eq!(None, tour.path_length(7, n_nodes));
+        assert_eq!(None, tour.path_length(n_nodes + 3, 1));
+
+        // Mix flips of all cases and compare against a walk along the tour.
+        for &(a, b) in &[(2, 5), (9, 39), (35, 67), (79, 89), (44, 46)] {
+            let (from_a, from_b) = (tour.get(a).unwrap(), tour.get(b).unwrap());
+            let to_a = tour.successor(&from_a).unwrap();
+            let to_b = tour.successor(&from_b).unwrap();
+            tour.flip(&from_a, &to_a, &from_b, &to_b);
+            tour.rev();
+        }
+
+        for &(from, to) in &[
+            (0, 99),
+            (3, 5),
+            (5, 3),
+            (36, 68),
+            (68, 36),
+            (12, 12),
+            (95, 40),
+        ] {
+            let mut expected = 0.;
+            let mut node = tour.get(from).unwrap();
+            while node != tour.get(to).unwrap() {
+                let next = tour.successor(&node).unwrap();
+                expected += tour.distance(&node, &next);
+                node = next;
+            }
+
+            assert!(
+                approx_eq!(
+                    f64,
+                    expected,
+                    tour.path_length(from, to).unwrap(),
+                    epsilon = 1e-8
+                ),
+                "Test path length from {} to {}",
+                from,
+                to
+            );
+        }
+    }
+
+    pub fn extract_path(tour: &mut impl Tour) {
+        let n_nodes = tour.len();
+        assert!(tour.apply(&TourOrder::with_nat_ord(n_nodes)).is_ok());
+
+        assert_eq!(vec![7], tour.extract_path(7, 7, PathDirection::Shorter));
+        assert_eq!(
+            vec![2, 3, 4, 5],
+            tour.extract_path(2, 5, PathDirection::Forward)
+        );
+        assert_eq!(
+            vec![n_nodes - 2, n_nodes - 1, 0, 1],
+            tour.extract_path(n_nodes - 2, 1, PathDirection::Forward)
+        );
+        assert!(tour
+            .extract_path(0, n_nodes, PathDirection::Shorter)
+            .is_empty());
+        assert_eq!(
+            vec![5, 4, 3, 2],
+            tour.extract_path(5, 2, PathDirection::Backward)
+        );
+        assert_eq!(
+            vec![5, 4, 3, 2],
+            tour.extract_path(5, 2, PathDirection::Shorter)
+        );
+        assert_eq!(
+            vec![2, 3, 4, 5],
+            tour.extract_path(2, 5, PathDirection::Shorter)
+        );
+
+        let (from_a, from_b) = (tour.get(2).unwrap(), tour.get(9).unwrap());
+        let to_a = tour.successor(&from_a).unwrap();
+        let to_b = tour.successor(&from_b).unwrap();
+        tour.flip(&from_a, &to_a, &from_b, &to_b);
+
+        for &(from, to) in &[(0, 20), (20, 0), (5, 11), (9, 2)] {
+            let path = tour.extract_path(from, to, PathDirection::Forward);
+            let mut backward = tour.extract_path(to, from, PathDirection::Backward);
+            backward.reverse();
+            assert_eq!(path, backward);
+            let shorter = tour.extract_path(from, to, PathDirection::Shorter);
+            assert_eq!(path.len().min(tour.len() + 2 - path.len()), shorter.len());
+            assert_eq!(Some(&from), path.first());
+            assert_eq!(Some(&to), path.last());
+            for w in path.windows(2) {
+                assert_eq!(Some(w[1]), tour.successor_at(w[0]).map(|n| n.index().get()));
+            }
+            assert!(approx_eq!(
+                f64,
+                tour.path_length(from, to).unwrap(),
+                path.windows(2)
+                    .map(|w| tour.distance_at(&w[0], &w[1]).unwrap())
+                    .sum::<Scalar>(),
+                epsilon = 1e-8
+            ));
+        }
+    }
+
+    pub fn fallible_ops(tour: &mut impl Tour) {
+        assert_eq!(10, tour.len());
+        let mut order = (0..10).collect::<Vec<_>>();
+
+        order[3] = 7;
+        assert!(tour.apply(&TourOrder::with_ord(order.clone())).is_err());
+        assert!(matches!(
+            tour.try_apply(&TourOrder::with_ord(order.clone())),
+            Err(CyklError::DuplicateNode(7))
+        ));
+
+        order[3] = 10;
+        assert!(tour.apply(&TourOrder::with_ord(order.clone())).is_err());
+        assert!(matches!(
+            tour.try_apply(&TourOrder::with_ord(order)),
+            Err(CyklError::IndexOutOfBounds { index: 10, len: 10 })
+        ));
+
+        assert!(matches!(
+            tour.try_apply(&tour_order!(0..9)),
+            Err(CyklError::Tour(UpdateTourError::TourLenMismatched {
+                expected: 10,
+                received: 9
+            }))
+        ));
+
+        assert!(tour.try_apply(&TourOrder::with_nat_ord(10)).is_ok());
+        test_tour_order(tour, &TourOrder::with_nat_ord(10));
+
+        assert!(matches!(
+            tour.try_flip_at(2, 4, 5, 6),
+            Err(CyklError::NotSuccessor { from: 2, to: 4 })
+        ));
+        assert!(matches!(
+            tour.try_flip_at(2, 3, 5, 11),
+            Err(CyklError::IndexOutOfBounds { index: 11, len: 10 })
+        ));
+        test_tour_order(tour, &TourOrder::with_nat_ord(10));
+
+        assert!(tour.try_flip_at(2, 3, 5, 6).is_ok());
+        test_tour_order(tour, &tour_order!(0..3, (3..6).rev(), 6..10));
+
+        assert!(tour.try_between_at(0, 1, 2).unwrap());
+        assert!(tour.try_between_at(0, 1, 12).is_err());
+        assert!(tour.distance_at(&0, &1).is_ok());
+        assert!(matches!(
+            tour.distance_at(&0, &10),
+            Err(CyklError::IndexOutOfBounds { index: 10, len: 10 })
+        ));
+        assert_eq!(Some(3), tour.node(3).map(|node| node.get()));
+        assert!(tour.node(10).is_none());
+        assert_eq!(4, tour.index_of(&tour.get(4).unwrap()));
+
+        assert!(tour.apply(&TourOrder::with_nat_ord(10)).is_ok());
+        let moves = [FlipSpec::new(2, 3, 5, 6), FlipSpec::new(1, 2, 6, 7)];
+        assert!(tour.apply_moves(&moves).is_ok());
+        let expected = TourOrder::with_ord(vec![0, 1, 6, 3, 4, 5, 2, 7, 8, 9]);
+        test_tour_order(tour, &expected);
+
+        // The second flip is invalid after the first one, which is rolled back.
+        let moves = [FlipSpec::new(0, 1, 3, 4), FlipSpec::new(2, 3, 8, 9)];
+        assert!(matches!(
+            tour.apply_moves(&moves),
+            Err(CyklError::NotSuccessor { from: 2, to: 3 })
+        ));
+        test_tour_order(tour, &expected);
+        assert!(tour.apply_moves(&[]).is_ok());
+
+        let edges: Vec<_> = tour.edges().collect();
+        assert_eq!(10, edges.len());
+        assert!(tour
+            .edge_set_hashes()
+            .zip(&edges)
+            .all(|(key, &(a, b, _))| Edge::from_key(key) == Edge::new(a, b)));
+        assert_eq!(0, edges[0].0);
+        assert!(edges.windows(2).all(|pair| pair[0].1 == pair[1].0));
+        assert_eq!(
+            tour.tour_order().order()[1..],
+            edges[..9].iter().map(|edge| edge.1).collect::<Vec<_>>()[..]
+        );
+        assert!(approx_eq!(
+            Scalar,
+            tour.tour_order().cost(),
+            edges.iter().map(|edge| edge.2).sum()
+        ));
+    }
+
+    pub fn exclude_include(tour: &mut impl Tour) {
+        let len = tour.len();
+        assert!(tour.apply(&TourOrder::with_nat_ord(len)).is_ok());
+
+        assert!(tour.exclude(3).is_ok());
+        assert!(!tour.is_included(3));
+        assert_eq!(len - 1, tour.n_included());
+        assert_eq!(len, tour.len());
+        assert!(tour.successor_at(3).is_none());
+        assert_eq!(4, tour.successor_at(2).unwrap().index().get());
+        assert_eq!(2, tour.predecessor_at(4).unwrap().index().get());
+        assert_eq!(4, tour.at_position(3).unwrap().index().get());
+        assert_eq!(Some(3), tour.order_index(4));
+        assert_eq!(0., tour.penalty());
+        assert_eq!(None, tour.path_length(3, 5));
+        assert_eq!(None, tour.path_length(5, 3));
+        assert!(tour.path_length(2, 4).is_some());
+
+        // Excluding twice has no effect.
+        assert!(tour.exclude(3).is_ok());
+        assert_eq!(len - 1, tour.n_included());
+        assert!(matches!(
+            tour.exclude(len),
+            Err(UpdateTourError::NodeNotFound)
+        ));
+
+        // Flips only involve the nodes that are still part of the tour.
+        tour.flip_at(1, 2, 6, 7);
+        let mut expected = vec![0, 1, 6, 5, 4, 2, 7];
+        expected.extend(8..len);
+        assert_eq!(&expected, tour.tour_order().order());
+
+        assert!(tour.apply(&TourOrder::with_nat_ord(len)).is_ok());
+        assert!(tour.exclude(3).is_ok());
+        let (cost, after) = tour.cheapest_insertion(3).unwrap();
+        assert!(approx_eq!(f64, 0., cost, epsilon = 1e-10));
+        assert_eq!(2, after.index().get());
+        assert!(tour.cheapest_insertion(2).is_none());
+
+        assert!(tour.include(3).is_ok());
+        assert!(tour.is_included(3));
+        assert_eq!(&(0..len).collect::<Vec<_>>(), tour.tour_order().order());
+
+        for index in 0..(len - 1) {
+            assert!(tour.exclude(index).is_ok());
+        }
+        assert_eq!(1, tour.n_included());
+        assert!(tour.exclude(len - 1).is_err());
+        assert_eq!(len - 1, tour.successor_at(len - 1).unwrap().index().get());
+        assert_eq!(&vec![len - 1], tour.tour_order().order());
+
+        for index in 0..(len - 1) {
+            assert!(tour.include(index).is_ok());
+        }
+        assert_eq!(len, tour.n_included());
+        assert!(tour.apply(&TourOrder::with_nat_ord(len)).is_ok());
+
+        // Insertions into a reversed part of the tour.
+        tour.flip_at(1, 2, 6, 7);
+        assert!(tour.exclude(4).is_ok());
+        assert!(tour.exclude(8).is_ok());
+        assert!(tour.insert_after(4, 5).is_ok());
+        assert!(matches!(
+            tour.insert_after(4, 5),
+            Err(UpdateTourError::InvalidTourOrder)
+        ));
+        assert!(matches!(
+            tour.insert_after(3, 8),
+            Err(UpdateTourError::InvalidTourOrder)
+        ));
+
+        let c7 = tour.get(7).unwrap();
+        tour.get(8).unwrap().set_candidates(vec![c7]);
+        let cost = tour.insert_cheapest(8).unwrap();
+        assert!(approx_eq!(f64, 0., cost, epsilon = 1e-10));
+        let mut expected = vec![0, 1, 6, 5, 4, 3, 2, 7, 8];
+        expected.extend(9..len);
+        test_tour_order(tour, &TourOrder::with_ord(expected));
+        assert!(tour.insert_cheapest(8).is_err());
+
+        assert!(tour.apply(&TourOrder::with_nat_ord(len)).is_ok());
+    }
+
+    pub fn at_position(tour: &mut impl Tour) {
+        let n_nodes = 100;
+        assert_eq!(n_nodes, tour.len());
+        assert!(tour.apply(&TourOrder::with_nat_ord(n_nodes)).is_ok());
+
+        assert_eq!(Some(17), tour.at_position(17).map(|n| n.index().index()));
+        assert_eq!(Some(17), tour.order_index(17));
+        assert!(tour.at_position(n_nodes).is_none());
+        assert!(tour.order_index(n_nodes).is_none());
+
+        for &(a, b) in &[(2, 5), (9, 39), (35, 67), (79, 89), (44, 46), (0, 50)] {
+            let (from_a, from_b) = (tour.get(a).unwrap(), tour.get(b).unwrap());
+            let to_a = tour.successor(&from_a).unwrap();
+            let to_b = tour.successor(&from_b).unwrap();
+            tour.flip(&from_a, &to_a, &from_b, &to_b);
+            tour.rev();
+
+            let order = tour.tour_order();
+            for (pos, &index) in order.order().iter().enumerate() {
+                assert_eq!(
+                    Some(index),
+                    tour.at_position(pos).map(|n| n.index().index()),
+                    "Test node at position {}",
+                    pos
+                );
+                assert_eq!(
+                    Some(pos),
+                    tour.order_index(index),
+                    "Test position of {}",
+                    index
+                );
+            }
+        }
+    }
+
+    /// Compares `between_at` and `flip_at` with a brute-force oracle on plain orders for all
+    /// orders of up to `max_len` nodes that start at node `0`, all triples of nodes and all flips.
+    ///
+    /// The tour is checked once as applied, and for orders of up to `max_flipped_len` nodes again
+    /// after each flip and after reversing the flipped tour, when segments are reversed.
+    pub fn exhaustive<T, F>(make: F, max_len: usize, max_flipped_len: usize)
+    where
+        T: Tour,
+        F: Fn(usize) -> T,
+    {
+        for len in 3..=max_len {
+            let mut tour = make(len);
+            let mut rest: Vec<usize> = (1..len).collect();
+            permute(&mut rest, 0, &mut |rest| {
+                let order: Vec<usize> = std::iter::once(0).chain(rest.iter().copied()).collect();
+                assert!(tour.apply(&TourOrder::with_ord(order.clone())).is_ok());
+                check_between(&tour, &order);
+
+                for ii in 0..len {
+                    for jj in 0..len {
+                        let (from_a, from_b) = (order[ii], order[jj]);
+                        let (to_a, to_b) = (order[(ii + 1) % len], order[(jj + 1) % len]);
+                        if from_a == from_b || to_a == from_b || to_b == from_a {
+                            continue;
+                        }
+
+                        assert!(tour.apply(&TourOrder::with_ord(order.clone())).is_ok());
+                        tour.flip_at(from_a, to_a, from_b, to_b);
+                        let expected = oracle_flip(&order, ii, jj);
+                        assert_eq!(
+                            TourOrder::with_ord(expected.clone()).canonical(),
+                            tour.tour_order().canonical(),
+                            "flip ({}, {}) ({}, {}) of {:?}",
+                            from_a,
+                            to_a,
+                            from_b,
+                            to_b,
+                            order
+                        );
+
+                        if len <= max_flipped_len {
+                            let flipped = tour.tour_order().order().clone();
+                            check_between(&tour, &flipped);
+                            tour.rev();
+                            let reversed = tour.tour_order().order().clone();
+                            check_between(&tour, &reversed);
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    /// Calls `f` with every permutation of `items[start..]`.
+    fn permute<F>(items: &mut Vec<usize>, start: usize, f: &mut F)
+    where
+        F: FnMut(&[usize]),
+    {
+        if start == items.len() {
+            return f(items);
+        }
+
+        for ii in start..items.len() {
+            items.swap(start, ii);
+            permute(items, start + 1, f);
+            items.swap(start, ii);
+        }
+    }
+
+    /// Checks `between_at` for all triples of nodes against their positions in `order`, which is
+    /// the forward traversal of the tour.
+    fn check_between(tour: &impl Tour, order: &[usize]) {
+        let len = order.len();
+        let mut pos = vec![0; len];
+        for (ii, &index) in order.iter().enumerate() {
+            pos[index] = ii;
+        }
+
+        for from in 0..len {
+            for mid in 0..len {
+                for to in 0..len {
+                    assert_eq!(
+                        cyclic::between(pos[from], pos[mid], pos[to]),
+                        tour.between_at(from, mid, to),
+                        "between({}, {}, {}) in {:?}",
+                        from,
+                        mid,
+                        to,
+                        order
+                    );
+                }
+            }
+        }
+    }
+
+    /// Replaces the edges after positions `ii` and `jj` by reversing the path between them.
+    fn oracle_flip(order: &[usize], ii: usize, jj: usize) -> Vec<usize> {
+        let len = order.len();
+        let mut result = order.to_vec();
+        let (mut lo, mut hi) = ((ii + 1) % len, jj);
+        for _ in 0..(len + jj - ii) % len / 2 {
+            result.swap(lo, hi);
+            lo = (lo + 1) % len;
+            hi = (hi + len - 1) % len;
+        }
+        result
     }
 
     pub fn flip(tour: &mut impl Tour) {
@@ -1,20 +1,78 @@
 use std::ptr::NonNull;
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 use crate::{
-    data::{DataStore, GetIndex, NodeStore},
+    data::{DataStore, GetIndex, NodeIndex, NodeStore},
+    oracle::SharedOracle,
+    overlay::CostOverlay,
     tour::{
         node::{reverse_int_seg, reverse_segs},
         NodeStatus,
     },
-    Scalar,
+    CancellationToken, CyklError, Scalar,
 };
 
 use super::{
-    between,
-    node::{to_nonnull, Segment},
-    NodeRel, Tour, TourIter, TourNode, TourOrder, UpdateTourError,
+    arena::{self, Run},
+    cyclic::between,
+    free,
+    node::{
+        follow, link_to, pred, set_pred, set_succ, succ, swap_links, to_nonnull, InnerNode, Link,
+        Segment,
+    },
+    out_of_bounds, validate_order, NodeRel, Tour, TourIter, TourNode, TourOrder, UpdateTourError,
 };
 
+/// Number of vertices placed by [`TwoLevelList::apply_with`] between two checks of its token.
+const APPLY_CHUNK: usize = 1 << 16;
+
+/// Shares a list with the `rayon` workers that fill its segments during a rebuild.
+///
+/// The workers only read the shared parts of the list, and write to the vertices and segments
+/// that are distinct for each of them, see [`TwoLevelList::fill_segment`].
+#[cfg(feature = "parallel")]
+struct SharedList<'a>(&'a TwoLevelList);
+
+// SAFETY: The list is only shared by `fill_segments`, whose workers fill distinct segments.
+// `rebuild_with` has checked that the order is in bounds and free of duplicates, thus every
+// vertex and segment is written by one worker only. Besides, the workers read the store, the
+// oracle, the overlay and the indices of neighbouring vertices, none of which change during the
+// rebuild.
+#[cfg(feature = "parallel")]
+unsafe impl Sync for SharedList<'_> {}
+
+#[cfg(feature = "parallel")]
+impl SharedList<'_> {
+    unsafe fn fill_segment(&self, sidx: usize, order: &[usize]) -> Result<Scalar, UpdateTourError> {
+        self.0.fill_segment(sidx, order)
+    }
+}
+
+/// Decides how a [`TwoLevelList`] reverses whole segments while flipping.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReversalPolicy {
+    /// Reverses a segment by toggling its reverse flag, which takes constant time.
+    #[default]
+    Lazy,
+    /// Physically reverses segments of at most `threshold` vertices, so that they keep no reverse
+    /// flag, and toggles the flag of larger segments.
+    ///
+    /// Physical reversal takes time linear in the length of a segment, but keeps the vertices of
+    /// small segments in the order of their ranks.
+    Eager { threshold: usize },
+}
+
+/// State of a segment at one point in time, see [`TwoLevelList::to_json`].
+struct SegSnapshot {
+    rank: usize,
+    reverse: bool,
+    next: usize,
+    prev: usize,
+    nodes: Vec<usize>,
+}
+
 #[derive(Debug)]
 pub struct TwoLevelList {
     store: NodeStore,
@@ -22,10 +80,30 @@ pub struct TwoLevelList {
     nodes: Vec<TourNode>,
     total_dist: Scalar,
     rev: bool,
+    // Number of nodes visited by the tour.
+    n_included: usize,
+    // Number of segments that hold at least one node.
+    n_active_segs: usize,
+    overlay: Option<CostOverlay>,
+    // Source of the costs in place of the store.
+    oracle: Option<SharedOracle>,
+    policy: ReversalPolicy,
+    // Storage of all vertices, in the order of their indices until the tour is repacked.
+    vertices: Run,
+    // Segments reversed by the last flip, kept to reuse the allocation across flips.
+    flipped: Vec<NonNull<Segment>>,
 }
 
 impl TwoLevelList {
     pub fn new<M>(store: &DataStore<M>, groupsize: usize) -> Self {
+        let mut result = Self::unbuilt(store, groupsize);
+        result.apply(&TourOrder::with_nat_ord(store.len())).unwrap();
+        result
+    }
+
+    /// Allocates the segments and vertices of a tour over the nodes of a store, but leaves the
+    /// vertices unlinked until the tour is built by [`Tour::apply`].
+    pub(super) fn unbuilt<M>(store: &DataStore<M>, groupsize: usize) -> Self {
         let node_store = store.store();
         let n_nodes = store.len();
 
@@ -63,33 +141,857 @@ impl TwoLevelList {
             segments.push(s);
         }
 
-        let nodes = node_store
-            .into_iter()
-            .map(|node| TourNode::new(*node))
+        let vertices = arena::alloc(
+            node_store
+                .into_iter()
+                .map(|node| InnerNode::new(*node))
+                .collect(),
+        );
+        let nodes = (0..vertices.len)
+            .map(|offset| TourNode {
+                inner: Some(unsafe { vertices.at(offset) }),
+            })
             .collect();
 
-        let mut result = Self {
+        Self {
             store: node_store,
             nodes,
             segments,
             total_dist: 0.,
             rev: false,
+            n_included: n_nodes,
+            n_active_segs: n_segments,
+            overlay: None,
+            oracle: None,
+            policy: ReversalPolicy::default(),
+            vertices,
+            flipped: Vec::new(),
+        }
+    }
+
+    /// Sets the policy for reversing whole segments. Defaults to [`ReversalPolicy::Lazy`].
+    pub fn with_reversal_policy(mut self, policy: ReversalPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the policy for reversing whole segments, which applies to subsequent flips.
+    pub fn set_reversal_policy(&mut self, policy: ReversalPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns the policy for reversing whole segments.
+    pub fn reversal_policy(&self) -> ReversalPolicy {
+        self.policy
+    }
+
+    /// Clears the reverse flags of the tour and all segments by rebuilding the tour in its current
+    /// order, which takes O(N) time.
+    ///
+    /// Afterwards, the vertices of every segment are traversed in the order of their ranks, e.g.
+    /// before inspecting the segments with [`Tour::dump`]. The vertices are redistributed evenly
+    /// among the segments.
+    pub fn normalize(&mut self) {
+        let order = self.tour_order();
+        unsafe { self.rebuild(order.order()) }.expect("Order of the tour is valid");
+    }
+
+    /// Rearranges the tour's vertices like [`Tour::apply`], but rebuilds the tour in chunks of
+    /// whole segments, between which `token` is checked and `progress` is called with the
+    /// percentage of vertices placed so far.
+    ///
+    /// If the token is cancelled, the tour is restored to its previous order and
+    /// [`CyklError::Cancelled`] is returned. Restoring cannot be interrupted and takes as long as a
+    /// full rebuild.
+    pub fn apply_with<F>(
+        &mut self,
+        order: &TourOrder,
+        token: &CancellationToken,
+        mut progress: F,
+    ) -> Result<(), CyklError>
+    where
+        F: FnMut(f64),
+    {
+        let v_len = self.nodes.len();
+        validate_order(order, v_len)?;
+        if token.is_cancelled() {
+            return Err(CyklError::Cancelled);
+        }
+
+        let previous = self.tour_order();
+        let completed = unsafe {
+            self.rebuild_with(order.order(), |placed| {
+                progress(100. * placed as f64 / v_len as f64);
+                !token.is_cancelled()
+            })?
+        };
+
+        if completed {
+            progress(100.);
+            Ok(())
+        } else {
+            unsafe { self.rebuild(previous.order())? };
+            Err(CyklError::Cancelled)
+        }
+    }
+
+    /// Describes the segments in Graphviz DOT format, e.g. to inspect how the structure evolves
+    /// during long runs.
+    ///
+    /// Each segment becomes a box labelled with its rank, its reverse flag and its vertices in the
+    /// traversal of the tour, and the segments are linked in the same traversal.
+    pub fn to_dot(&self) -> String {
+        let segs = self.seg_snapshots();
+        let mut result = format!(
+            "digraph tll {{\n    label=\"{} nodes, {} segments, reversed: {}\";\n    node [shape=box];\n",
+            self.nodes.len(),
+            self.n_active_segs,
+            self.rev
+        );
+
+        for seg in &segs {
+            let nodes: Vec<String> = seg.nodes.iter().map(|x| x.to_string()).collect();
+            result.push_str(&format!(
+                "    s{} [label=\"#{}{}\\n{}\"];\n",
+                seg.rank,
+                seg.rank,
+                if seg.reverse { " reversed" } else { "" },
+                nodes.join(" ")
+            ));
+        }
+
+        for (ii, seg) in segs.iter().enumerate() {
+            let next = &segs[(ii + 1) % segs.len()];
+            result.push_str(&format!("    s{} -> s{};\n", seg.rank, next.rank));
+        }
+
+        result.push('}');
+        result
+    }
+
+    /// Describes the segments as a JSON object, e.g. to analyse how the structure evolves during
+    /// long runs with external tools.
+    ///
+    /// The segments are listed in the traversal of the tour. Each of them holds its rank, its
+    /// reverse flag, the ranks of the segments it is linked to, and its vertices in the traversal.
+    pub fn to_json(&self) -> String {
+        let segs: Vec<String> = self
+            .seg_snapshots()
+            .iter()
+            .map(|seg| {
+                let nodes: Vec<String> = seg.nodes.iter().map(|x| x.to_string()).collect();
+                format!(
+                    "{{\"rank\":{},\"reverse\":{},\"next\":{},\"prev\":{},\"nodes\":[{}]}}",
+                    seg.rank,
+                    seg.reverse,
+                    seg.next,
+                    seg.prev,
+                    nodes.join(",")
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"nodes\":{},\"included\":{},\"reversed\":{},\"segments\":[{}]}}",
+            self.nodes.len(),
+            self.n_included,
+            self.rev,
+            segs.join(",")
+        )
+    }
+
+    /// Attaches an overlay whose adjusted costs replace the costs of the store in all distances,
+    /// and returns the overlay that was attached before.
+    ///
+    /// The tour length is recomputed with the adjusted costs in O(N) time.
+    pub fn attach_overlay(&mut self, overlay: CostOverlay) -> Option<CostOverlay> {
+        let previous = self.overlay.replace(overlay);
+        self.refresh_costs();
+        previous
+    }
+
+    /// Detaches the overlay, after which distances are the costs of the store again, and returns
+    /// the overlay.
+    pub fn detach_overlay(&mut self) -> Option<CostOverlay> {
+        let previous = self.overlay.take();
+        if previous.is_some() {
+            self.refresh_costs();
+        }
+        previous
+    }
+
+    /// Returns the attached overlay.
+    pub fn overlay(&self) -> Option<&CostOverlay> {
+        self.overlay.as_ref()
+    }
+
+    /// Modifies the attached overlay with the given function and recomputes the tour length. Does
+    /// nothing if no overlay is attached.
+    pub fn update_overlay<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut CostOverlay),
+    {
+        if let Some(overlay) = &mut self.overlay {
+            f(overlay);
+            self.refresh_costs();
+        }
+    }
+
+    /// Attaches an oracle whose costs replace the costs of the store in all distances, and
+    /// returns the oracle that was attached before. An attached overlay adjusts the costs of the
+    /// oracle. The nodes of the tour remain those of the store it was created from, see the
+    /// [`oracle`](crate::oracle) module.
+    ///
+    /// Returns an error if the oracle does not cover the nodes of the tour, or if
+    /// [`DistanceOracle::prepare`](crate::oracle::DistanceOracle::prepare) fails, in which case
+    /// the tour keeps its costs. The tour length is recomputed with the costs of the oracle in
+    /// O(N) time.
+    pub fn attach_oracle(
+        &mut self,
+        oracle: SharedOracle,
+    ) -> Result<Option<SharedOracle>, CyklError> {
+        if oracle.len() != self.nodes.len() {
+            return Err(CyklError::LenMismatched {
+                expected: self.nodes.len(),
+                received: oracle.len(),
+            });
+        }
+        oracle.prepare()?;
+
+        let previous = self.oracle.replace(oracle);
+        self.refresh_costs();
+        Ok(previous)
+    }
+
+    /// Sets the overlay and the oracle of a new tour without recomputing the tour length, which
+    /// [`TourBuilder`](super::TourBuilder) does by building the tour once afterwards.
+    ///
+    /// Returns an error if the oracle does not cover the nodes of the tour or cannot be prepared.
+    pub(super) fn set_cost_sources(
+        &mut self,
+        overlay: Option<CostOverlay>,
+        oracle: Option<SharedOracle>,
+    ) -> Result<(), CyklError> {
+        if let Some(oracle) = &oracle {
+            if oracle.len() != self.nodes.len() {
+                return Err(CyklError::LenMismatched {
+                    expected: self.nodes.len(),
+                    received: oracle.len(),
+                });
+            }
+            oracle.prepare()?;
+        }
+
+        self.overlay = overlay;
+        self.oracle = oracle;
+        Ok(())
+    }
+
+    /// Detaches the oracle, after which distances are the costs of the store again, and returns
+    /// the oracle.
+    pub fn detach_oracle(&mut self) -> Option<SharedOracle> {
+        let previous = self.oracle.take();
+        if previous.is_some() {
+            self.refresh_costs();
+        }
+        previous
+    }
+
+    /// Returns the cost between two nodes from the oracle or the store, adjusted by the overlay if
+    /// one is attached.
+    #[inline]
+    fn cost<I>(&self, a: &I, b: &I) -> Scalar
+    where
+        I: GetIndex + PartialEq + Eq,
+    {
+        let base = match &self.oracle {
+            Some(oracle) if a != b => oracle.cost(a.get(), b.get()),
+            Some(_) => 0.,
+            None => self.store.cost(a, b),
+        };
+        match &self.overlay {
+            Some(overlay) => overlay.adjust(a.get(), b.get(), base).unwrap_or(base),
+            None => base,
+        }
+    }
+
+    /// Recomputes the tour length and the accumulated costs of all segments.
+    fn refresh_costs(&mut self) {
+        self.normalize();
+    }
+
+    /// Rebuilds the tour from a sequence of node indices.
+    ///
+    /// Nodes that do not appear in the sequence are excluded from the tour. Segments are filled in
+    /// the order of their ranks, unused segments are detached from the list of segments.
+    unsafe fn rebuild(&mut self, order: &[usize]) -> Result<(), UpdateTourError> {
+        self.rebuild_with(order, |_| true).map(|_| ())
+    }
+
+    /// Rebuilds the tour like [`TwoLevelList::rebuild`], and calls `on_chunk` with the number of
+    /// vertices placed so far after every chunk of segments that holds about [`APPLY_CHUNK`]
+    /// vertices.
+    ///
+    /// The segments of a chunk are filled independently of each other, with the `parallel`
+    /// feature on `rayon` workers. The rebuild stops as soon as `on_chunk` returns `false`, in
+    /// which case `false` is returned and the tour is left inconsistent until it is rebuilt again.
+    ///
+    /// Returns [`UpdateTourError::InvalidTourOrder`] without touching the tour if the sequence
+    /// holds an index out of bounds or an index twice.
+    unsafe fn rebuild_with<F>(
+        &mut self,
+        order: &[usize],
+        mut on_chunk: F,
+    ) -> Result<bool, UpdateTourError>
+    where
+        F: FnMut(usize) -> bool,
+    {
+        // Segments are filled concurrently, which requires that each vertex belongs to one segment.
+        let mut seen = vec![false; self.nodes.len()];
+        for &index in order {
+            match seen.get_mut(index) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(UpdateTourError::InvalidTourOrder),
+            }
+        }
+
+        self.rev = false;
+        self.total_dist = 0.;
+
+        for node in &self.nodes {
+            if let Some(inner) = node.inner {
+                (*inner.as_ptr()).segment = None;
+                set_pred(inner, None);
+                set_succ(inner, None);
+                (*inner.as_ptr()).rank = i32::MAX;
+            }
+        }
+
+        let v_len = order.len();
+        let max_len = match self.segments.first() {
+            Some(Some(seg)) => seg.as_ref().max_len,
+            _ => 0,
+        };
+        let s_len = if max_len == 0 {
+            0
+        } else {
+            v_len.div_ceil(max_len)
+        };
+        self.n_included = v_len;
+        self.n_active_segs = s_len;
+
+        for (sidx, els) in self.segments.iter().enumerate() {
+            match els {
+                Some(seg) => {
+                    (*seg.as_ptr()).reset();
+                    (*seg.as_ptr()).rank = sidx;
+
+                    if sidx < s_len {
+                        (*seg.as_ptr()).next = self.segments[(sidx + 1) % s_len];
+                        (*seg.as_ptr()).prev = self.segments[(s_len + sidx - 1) % s_len];
+                    }
+                }
+                None => return Err(UpdateTourError::BrokenTour),
+            }
+        }
+
+        let segs_per_chunk = APPLY_CHUNK.div_ceil(max_len.max(1));
+        let mut beg = 0;
+        while beg < s_len {
+            let end = (beg + segs_per_chunk).min(s_len);
+            self.total_dist += self.fill_segments(beg, end, order)?;
+            beg = end;
+
+            let placed = (end * max_len).min(v_len);
+            if placed < v_len && !on_chunk(placed) {
+                return Ok(false);
+            }
+        }
+
+        for seg in self.segments.iter().flatten() {
+            self.update_prefix_cost(seg);
+        }
+
+        Ok(true)
+    }
+
+    /// Links the vertices of the segments `beg..end` according to the order, and returns the
+    /// length of the edges leaving them.
+    ///
+    /// Every vertex sets its own links, and every segment its own ends, so that segments can be
+    /// filled concurrently. Lengths are summed in the order of the segments, which keeps the tour
+    /// length independent of the scheduling.
+    unsafe fn fill_segments(
+        &self,
+        beg: usize,
+        end: usize,
+        order: &[usize],
+    ) -> Result<Scalar, UpdateTourError> {
+        #[cfg(feature = "parallel")]
+        let lengths: Vec<Scalar> = {
+            let shared = SharedList(self);
+            (beg..end)
+                .into_par_iter()
+                .map(|sidx| shared.fill_segment(sidx, order))
+                .collect::<Result<_, _>>()?
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let lengths: Vec<Scalar> = (beg..end)
+            .map(|sidx| self.fill_segment(sidx, order))
+            .collect::<Result<_, _>>()?;
+
+        Ok(lengths.iter().sum())
+    }
+
+    /// Links the vertices of a segment according to the order, and returns the length of the
+    /// edges leaving them.
+    unsafe fn fill_segment(&self, sidx: usize, order: &[usize]) -> Result<Scalar, UpdateTourError> {
+        let els = self.segments[sidx];
+        let seg = els.ok_or(UpdateTourError::BrokenTour)?;
+
+        let v_len = order.len();
+        let max_len = seg.as_ref().max_len;
+        let beg_seg = sidx * max_len;
+        let end_seg = (beg_seg + max_len).min(v_len);
+        let mut length = 0.;
+
+        for iv in beg_seg..end_seg {
+            let node = |pos: usize| {
+                self.nodes
+                    .get(order[pos])
+                    .ok_or(UpdateTourError::NodeNotFound)
+            };
+            let el_v = node(iv)?;
+            let el_next = node((iv + 1) % v_len)?;
+            let el_prev = node((v_len + iv - 1) % v_len)?;
+
+            match (el_v.inner, el_next.inner, el_prev.inner) {
+                (Some(vtx), Some(vtx_nxt), Some(_)) => {
+                    set_pred(vtx, el_prev.inner);
+                    set_succ(vtx, el_next.inner);
+                    (*vtx.as_ptr()).rank = (iv - beg_seg) as i32;
+                    (*vtx.as_ptr()).segment = els;
+                    (*vtx.as_ptr()).status = NodeStatus::Active;
+
+                    length += self.cost(&(*vtx.as_ptr()).index, &(*vtx_nxt.as_ptr()).index);
+                }
+                _ => return Err(UpdateTourError::NodeNotFound),
+            }
+
+            if (*seg.as_ptr()).last.is_none() {
+                (*seg.as_ptr()).first = el_v.inner;
+            }
+            (*seg.as_ptr()).last = el_v.inner;
+        }
+
+        Ok(length)
+    }
+
+    /// Returns the first node in the list of nodes that is visited by the tour.
+    fn first_included(&self) -> Option<&TourNode> {
+        self.nodes.iter().find(|node| match node.inner {
+            Some(inner) => unsafe { (*inner.as_ptr()).segment.is_some() },
+            None => false,
+        })
+    }
+
+    /// Returns true if the vertices of a segment are traversed in the same order as their ranks.
+    #[inline]
+    unsafe fn is_forward(&self, seg: &NonNull<Segment>) -> bool {
+        !((*seg.as_ptr()).reverse ^ self.rev)
+    }
+
+    /// Returns the total cost of all edges lying inside a segment in the direction in which the
+    /// segment is traversed.
+    #[inline]
+    unsafe fn seg_cost(&self, seg: &NonNull<Segment>) -> Scalar {
+        match (*seg.as_ptr()).last {
+            Some(last) if self.is_forward(seg) => (*last.as_ptr()).prefix_cost,
+            Some(last) => (*last.as_ptr()).prefix_cost_rev,
+            None => 0.,
+        }
+    }
+
+    /// Orders two vertices of a segment, given in the order of their ranks, in the direction in
+    /// which the segment is traversed.
+    #[inline]
+    unsafe fn directed(
+        &self,
+        seg: &NonNull<Segment>,
+        low: NodeIndex,
+        high: NodeIndex,
+    ) -> (NodeIndex, NodeIndex) {
+        if self.is_forward(seg) {
+            (low, high)
+        } else {
+            (high, low)
+        }
+    }
+
+    /// Returns the first and the last vertices of a segment in the forward traversal of the tour.
+    #[inline]
+    unsafe fn seg_ends(&self, seg: &NonNull<Segment>) -> (NonNull<InnerNode>, NonNull<InnerNode>) {
+        match ((*seg.as_ptr()).first, (*seg.as_ptr()).last) {
+            (Some(first), Some(last)) => {
+                if self.is_forward(seg) {
+                    (first, last)
+                } else {
+                    (last, first)
+                }
+            }
+            _ => panic!("Empty first or last pointers in segment."),
+        }
+    }
+
+    /// Returns the number of vertices in a segment.
+    #[inline]
+    unsafe fn seg_len(&self, seg: &NonNull<Segment>) -> usize {
+        match ((*seg.as_ptr()).first, (*seg.as_ptr()).last) {
+            (Some(first), Some(last)) => {
+                ((*last.as_ptr()).rank - (*first.as_ptr()).rank + 1) as usize
+            }
+            _ => 0,
+        }
+    }
+
+    /// Reverses a segment physically if the reversal policy asks for it, so that its reverse flag
+    /// is cleared without changing the traversal of the tour.
+    ///
+    /// The links of all vertices are swapped and their ranks are mirrored, thus the former last
+    /// vertex becomes the first one.
+    unsafe fn settle_seg(&self, seg: &NonNull<Segment>) {
+        let threshold = match self.policy {
+            ReversalPolicy::Lazy => return,
+            ReversalPolicy::Eager { threshold } => threshold,
+        };
+
+        if !(*seg.as_ptr()).reverse || self.seg_len(seg) > threshold {
+            return;
+        }
+
+        if let (Some(first), Some(last)) = ((*seg.as_ptr()).first, (*seg.as_ptr()).last) {
+            let rank_sum = (*first.as_ptr()).rank + (*last.as_ptr()).rank;
+            let mut node = first;
+            loop {
+                let next = succ(node);
+                swap_links(node);
+                (*node.as_ptr()).rank = rank_sum - (*node.as_ptr()).rank;
+
+                match next {
+                    Some(next) if node != last => node = next,
+                    _ => break,
+                }
+            }
+
+            (*seg.as_ptr()).first = Some(last);
+            (*seg.as_ptr()).last = Some(first);
+            (*seg.as_ptr()).reverse = false;
+            self.update_prefix_cost(seg);
+        }
+    }
+
+    /// Returns the segment that directly follows a segment in the forward traversal of the tour.
+    #[inline]
+    unsafe fn next_seg(&self, seg: &NonNull<Segment>) -> NonNull<Segment> {
+        let next = if self.rev {
+            (*seg.as_ptr()).prev
+        } else {
+            (*seg.as_ptr()).next
         };
 
+        match next {
+            Some(next) => next,
+            None => panic!("Missing next segment"),
+        }
+    }
+
+    /// Returns the vertices in the order of their links, i.e. segment by segment along the links of
+    /// the segments and from the first to the last vertex inside each segment, followed by the
+    /// vertices that are not included in the tour.
+    unsafe fn physical_order(&self) -> Vec<NonNull<InnerNode>> {
+        let mut result = Vec::with_capacity(self.nodes.len());
+        if let Some(first) = self.first_seg() {
+            let mut seg = first;
+            loop {
+                if let (Some(head), Some(tail)) = ((*seg.as_ptr()).first, (*seg.as_ptr()).last) {
+                    let mut node = head;
+                    result.push(node);
+                    while node != tail {
+                        node = match succ(node) {
+                            Some(next) => next,
+                            None => break,
+                        };
+                        result.push(node);
+                    }
+                }
+
+                seg = match (*seg.as_ptr()).next {
+                    Some(next) if next != first => next,
+                    _ => break,
+                };
+            }
+        }
+
+        result.extend(
+            self.nodes
+                .iter()
+                .filter_map(|node| node.inner)
+                .filter(|inner| (*inner.as_ptr()).segment.is_none()),
+        );
         result
-            .apply(&TourOrder::with_ord((0..n_nodes).collect()))
-            .unwrap();
+    }
+
+    /// Returns the direct successor of a vertex in the forward traversal of the tour if `forward` is
+    /// set, and its direct predecessor otherwise.
+    ///
+    /// This is the hot path of all local searches, thus it follows the links of the vertex directly
+    /// and only reads the reverse flag of its segment.
+    #[inline]
+    unsafe fn neighbour(&self, node: NonNull<InnerNode>, forward: bool) -> Option<TourNode> {
+        let seg = (*node.as_ptr()).segment?;
+        let link = if forward ^ (*seg.as_ptr()).reverse ^ self.rev {
+            succ(node)
+        } else {
+            pred(node)
+        };
+
+        link.map(|_| TourNode { inner: link })
+    }
+
+    /// Returns the segments that hold vertices in the forward traversal of the tour, starting from
+    /// the segment with the lowest rank.
+    fn seg_snapshots(&self) -> Vec<SegSnapshot> {
+        let mut result = Vec::with_capacity(self.n_active_segs);
+        let first = match self.first_seg() {
+            Some(seg) => seg,
+            None => return result,
+        };
+
+        unsafe {
+            let rank = |seg: Option<NonNull<Segment>>| seg.map_or(0, |x| (*x.as_ptr()).rank);
+            let mut seg = first;
+            loop {
+                let mut nodes = Vec::with_capacity(self.seg_len(&seg));
+                let (head, tail) = self.seg_ends(&seg);
+                let mut node = TourNode { inner: Some(head) };
+                nodes.push(self.index_of(&node));
+
+                while node.inner != Some(tail) {
+                    node = match self.successor(&node) {
+                        Some(next) => next,
+                        None => break,
+                    };
+                    nodes.push(self.index_of(&node));
+                }
+
+                result.push(SegSnapshot {
+                    rank: (*seg.as_ptr()).rank,
+                    reverse: (*seg.as_ptr()).reverse,
+                    next: rank((*seg.as_ptr()).next),
+                    prev: rank((*seg.as_ptr()).prev),
+                    nodes,
+                });
+
+                seg = self.next_seg(&seg);
+                if seg == first {
+                    break;
+                }
+            }
+        }
 
         result
     }
+
+    /// Returns the segment with the lowest rank.
+    fn first_seg(&self) -> Option<NonNull<Segment>> {
+        self.segments
+            .iter()
+            .flatten()
+            .find(|seg| unsafe { (*seg.as_ptr()).rank == 0 })
+            .copied()
+    }
+
+    /// Returns the position of a vertex in the forward traversal of the tour, starting from the
+    /// head of the segment with the lowest rank.
+    unsafe fn raw_position(&self, node: &NonNull<InnerNode>) -> Option<usize> {
+        let target = (*node.as_ptr()).segment?;
+        let mut seg = self.first_seg()?;
+        let mut result = 0;
+
+        while seg != target {
+            result += self.seg_len(&seg);
+            seg = self.next_seg(&seg);
+        }
+
+        let (head, _) = self.seg_ends(&seg);
+        Some(result + ((*node.as_ptr()).rank - (*head.as_ptr()).rank).unsigned_abs() as usize)
+    }
+
+    /// Returns the vertex at a position in the forward traversal of the tour, starting from the
+    /// head of the segment with the lowest rank.
+    unsafe fn at_raw_position(&self, mut pos: usize) -> Option<TourNode> {
+        let mut seg = self.first_seg()?;
+
+        loop {
+            let len = self.seg_len(&seg);
+            if pos < len {
+                break;
+            }
+            pos -= len;
+            seg = self.next_seg(&seg);
+        }
+
+        // Walks from the nearer end of the segment.
+        let (head, tail) = self.seg_ends(&seg);
+        let len = self.seg_len(&seg);
+        let mut node = TourNode { inner: Some(head) };
+        if pos <= len / 2 {
+            for _ in 0..pos {
+                node = self.successor(&node)?;
+            }
+        } else {
+            node = TourNode { inner: Some(tail) };
+            for _ in 0..(len - pos - 1) {
+                node = self.predecessor(&node)?;
+            }
+        }
+
+        Some(node)
+    }
+
+    /// Adds `delta` to the ranks of a vertex and all vertices with higher ranks in its segment.
+    unsafe fn shift_ranks(&self, from: NonNull<InnerNode>, seg: &NonNull<Segment>, delta: i32) {
+        let mut node = from;
+        loop {
+            (*node.as_ptr()).rank += delta;
+            if Some(node) == (*seg.as_ptr()).last {
+                break;
+            }
+
+            match succ(node) {
+                Some(next) => node = next,
+                None => panic!("Nullpointer"),
+            }
+        }
+    }
+
+    /// Recomputes the accumulated costs of all vertices in a segment.
+    ///
+    /// The accumulated costs are stored in the order of ranks, thus they are only invalidated by
+    /// operations that reorder vertices inside a segment or move vertices between segments.
+    unsafe fn update_prefix_cost(&self, seg: &NonNull<Segment>) {
+        if let (Some(first), Some(last)) = ((*seg.as_ptr()).first, (*seg.as_ptr()).last) {
+            let mut node = first;
+            (*node.as_ptr()).prefix_cost = 0.;
+            (*node.as_ptr()).prefix_cost_rev = 0.;
+
+            while node != last {
+                match succ(node) {
+                    Some(next) => {
+                        let (a, b) = (&(*node.as_ptr()).index, &(*next.as_ptr()).index);
+                        (*next.as_ptr()).prefix_cost =
+                            (*node.as_ptr()).prefix_cost + self.cost(a, b);
+                        (*next.as_ptr()).prefix_cost_rev =
+                            (*node.as_ptr()).prefix_cost_rev + self.cost(b, a);
+                        node = next;
+                    }
+                    None => panic!("Nullpointer"),
+                }
+            }
+        }
+    }
+
+    /// Recomputes the accumulated costs of a segment and its two neighbouring segments.
+    unsafe fn update_prefix_cost_around(&self, seg: &NonNull<Segment>) {
+        self.update_prefix_cost(seg);
+
+        if let Some(prev) = (*seg.as_ptr()).prev {
+            self.update_prefix_cost(&prev);
+        }
+
+        if let Some(next) = (*seg.as_ptr()).next {
+            self.update_prefix_cost(&next);
+        }
+    }
+
+    unsafe fn path_len(
+        &self,
+        from: &NonNull<InnerNode>,
+        to: &NonNull<InnerNode>,
+    ) -> Option<Scalar> {
+        let (sf, st) = match ((*from.as_ptr()).segment, (*to.as_ptr()).segment) {
+            (Some(sf), Some(st)) => (sf, st),
+            _ => return None,
+        };
+
+        // Segments traversed against the order of ranks accumulate the costs of reversed edges.
+        let prefix = |node: &NonNull<InnerNode>, seg: &NonNull<Segment>| {
+            if self.is_forward(seg) {
+                (*node.as_ptr()).prefix_cost
+            } else {
+                (*node.as_ptr()).prefix_cost_rev
+            }
+        };
+
+        let (rf, rt) = ((*from.as_ptr()).rank, (*to.as_ptr()).rank);
+        let (cf, ct) = (prefix(from, &sf), prefix(to, &st));
+
+        // The path lies entirely inside a segment.
+        if sf == st && (rf == rt || self.is_forward(&sf) == (rf < rt)) {
+            return Some((ct - cf).abs());
+        }
+
+        let mut result = if self.is_forward(&sf) {
+            self.seg_cost(&sf) - cf
+        } else {
+            cf
+        };
+
+        let mut seg = sf;
+        loop {
+            let next = self.next_seg(&seg);
+            let (_, tail) = self.seg_ends(&seg);
+            let (head, _) = self.seg_ends(&next);
+            result += self.cost(&(*tail.as_ptr()).index, &(*head.as_ptr()).index);
+
+            if next == st {
+                break;
+            }
+
+            result += self.seg_cost(&next);
+            seg = next;
+        }
+
+        if self.is_forward(&st) {
+            Some(result + ct)
+        } else {
+            Some(result + self.seg_cost(&st) - ct)
+        }
+    }
+}
+
+/// Gives the vertices back to the arena of the thread and frees all segments.
+///
+/// Vertices returned by [`Tour::get`] point to released memory of the arena, which later tours may
+/// reuse or which may be freed, thus using them afterwards is undefined behaviour.
+impl Drop for TwoLevelList {
+    fn drop(&mut self) {
+        unsafe {
+            arena::release(std::mem::replace(&mut self.vertices, Run::empty()));
+            self.nodes.clear();
+
+            for seg in self.segments.drain(..).flatten() {
+                free(seg);
+            }
+        }
+    }
 }
 
 impl Tour for TwoLevelList {
     fn apply(&mut self, tour: &super::TourOrder) -> Result<(), UpdateTourError> {
-        self.rev = false;
         let order = tour.order();
         let v_len = self.nodes.len();
-        let s_len = self.segments.len();
 
         if order.len() != v_len {
             return Err(UpdateTourError::TourLenMismatched {
@@ -98,57 +1000,27 @@ impl Tour for TwoLevelList {
             });
         }
 
-        self.total_dist = 0.;
-        for (sidx, els) in self.segments.iter().enumerate() {
-            match els {
-                Some(seg) => unsafe {
-                    (*seg.as_ptr()).reset();
-                    (*seg.as_ptr()).rank = sidx;
-                    (*seg.as_ptr()).next = self.segments[(sidx + 1) % s_len];
-                    (*seg.as_ptr()).prev = self.segments[(s_len + sidx - 1) % s_len];
-
-                    let max_len = seg.as_ref().max_len;
-                    let beg_seg = sidx * max_len;
-                    let end_seg = (beg_seg + max_len).min(v_len);
-
-                    for iv in beg_seg..end_seg {
-                        let el_v = self.nodes.get(order[iv]).unwrap();
-                        let el_next = self.nodes.get(order[(iv + 1) % v_len]).unwrap();
-                        let el_prev = self.nodes.get(order[(v_len + iv - 1) % v_len]).unwrap();
-
-                        match (el_v.inner, el_next.inner, el_prev.inner) {
-                            (Some(vtx), Some(vtx_nxt), Some(vtx_prv)) => {
-                                (*vtx.as_ptr()).predecessor = el_prev.inner;
-                                (*vtx.as_ptr()).successor = el_next.inner;
-                                (*vtx.as_ptr()).rank = (iv - beg_seg) as i32;
-                                (*vtx.as_ptr()).segment = *els;
-                                (*vtx.as_ptr()).status = NodeStatus::Active;
-
-                                (*vtx_nxt.as_ptr()).predecessor = el_v.inner;
-                                (*vtx_prv.as_ptr()).successor = el_v.inner;
-
-                                self.total_dist += self
-                                    .store
-                                    .cost(&(*vtx.as_ptr()).index, &(*vtx_nxt.as_ptr()).index);
-                            }
-                            _ => panic!("Nodes not found"),
-                        }
-
-                        if (*seg.as_ptr()).last.is_none() {
-                            (*seg.as_ptr()).first = el_v.inner;
-                        }
-                        (*seg.as_ptr()).last = el_v.inner;
-                    }
-                },
-                None => panic!("Segment not found"),
-            }
+        if validate_order(tour, v_len).is_err() {
+            return Err(UpdateTourError::InvalidTourOrder);
         }
 
-        Ok(())
+        unsafe { self.rebuild(order) }
     }
 
+    /// Compares the ranks of the vertices inside a segment, or the ranks of their segments, which
+    /// are kept in cyclic order by all structural changes of the tour.
     #[inline]
     fn between(&self, from: &TourNode, mid: &TourNode, to: &TourNode) -> bool {
+        // Walking forward through the reversed tour means walking backward through its segments.
+        let (from, to) = if self.rev { (to, from) } else { (from, to) };
+        // A reversed segment inverts the rank comparisons below, which would exclude the ends of
+        // the path, thus coinciding vertices are settled first.
+        if mid == from || mid == to {
+            return true;
+        } else if from == to {
+            return false;
+        }
+
         match (from.inner, mid.inner, to.inner) {
             (Some(f), Some(m), Some(t)) => unsafe {
                 match (
@@ -193,46 +1065,60 @@ impl Tour for TwoLevelList {
         }
     }
 
-    #[inline]
-    fn between_at(&self, from_index: usize, mid_index: usize, to_index: usize) -> bool {
-        match (
-            self.get(from_index),
-            self.get(mid_index),
-            self.get(to_index),
-        ) {
-            (Some(from), Some(mid), Some(to)) => self.between(&from, &mid, &to),
-            _ => false,
-        }
-    }
-
     #[inline]
     fn distance(&self, a: &TourNode, b: &TourNode) -> Scalar {
         match (a.inner, b.inner) {
             (Some(ai), Some(bi)) => unsafe {
-                self.store
-                    .cost(&(*ai.as_ptr()).index, &(*bi.as_ptr()).index)
+                self.cost(&(*ai.as_ptr()).index, &(*bi.as_ptr()).index)
             },
-            _ => 0.,
+            _ => panic!("Empty node"),
         }
     }
 
     #[inline]
-    fn distance_at<I>(&self, a: &I, b: &I) -> crate::Scalar
+    fn distance_at<I>(&self, a: &I, b: &I) -> Result<Scalar, CyklError>
     where
         I: GetIndex + PartialEq + Eq,
     {
-        self.store.cost(a, b)
+        let len = self.nodes.len();
+        if a.get() < len && b.get() < len {
+            Ok(self.cost(a, b))
+        } else {
+            Err(out_of_bounds(len, &[a.get(), b.get()]))
+        }
     }
 
     #[inline]
-    fn flip_at(&mut self, from_a: usize, to_a: usize, from_b: usize, to_b: usize) {
-        if let (Some(fa), Some(ta), Some(fb), Some(tb)) = (
-            self.get(from_a),
-            self.get(to_a),
-            self.get(from_b),
-            self.get(to_b),
-        ) {
-            self.flip(&fa, &ta, &fb, &tb);
+    fn distances(&self, pairs: &[(usize, usize)], out: &mut [Scalar]) {
+        assert_eq!(
+            pairs.len(),
+            out.len(),
+            "Mismatched lengths of pairs and output"
+        );
+        match &self.oracle {
+            Some(oracle) => {
+                for (x, &(a, b)) in out.iter_mut().zip(pairs) {
+                    *x = if a == b { 0. } else { oracle.cost(a, b) };
+                }
+            }
+            None => self.store.costs(pairs, out),
+        }
+        if let Some(overlay) = &self.overlay {
+            for (x, &(a, b)) in out.iter_mut().zip(pairs) {
+                if let Some(cost) = overlay.adjust(a, b, *x) {
+                    *x = cost;
+                }
+            }
+        }
+    }
+
+    fn path_length(&self, from_index: usize, to_index: usize) -> Option<Scalar> {
+        match (self.nodes.get(from_index), self.nodes.get(to_index)) {
+            (Some(from), Some(to)) => match (from.inner, to.inner) {
+                (Some(f), Some(t)) => unsafe { self.path_len(&f, &t) },
+                _ => None,
+            },
+            _ => None,
         }
     }
 
@@ -255,26 +1141,32 @@ impl Tour for TwoLevelList {
                         // resides in the same segment. In this case, we will flip either the
                         // local path or the entire segment if both nodes are the end nodes
                         // of that segment.
-                        if sfa == stb && (*tbn.as_ptr()).rank <= (*fan.as_ptr()).rank {
-                            if ((*sfa.as_ptr()).first == from_a.inner
+                        if sfa == stb && within_seg(&sfa, &tbn, &fan) {
+                            if ((*sfa.as_ptr()).first == Some(fan)
                                 && (*sfa.as_ptr()).reverse
-                                && (*sfa.as_ptr()).last == to_b.inner)
-                                || ((*sfa.as_ptr()).first == to_b.inner
-                                    && (*sfa.as_ptr()).last == from_a.inner)
+                                && (*sfa.as_ptr()).last == Some(tbn))
+                                || ((*sfa.as_ptr()).first == Some(tbn)
+                                    && !(*sfa.as_ptr()).reverse
+                                    && (*sfa.as_ptr()).last == Some(fan))
                             {
-                                return (*sfa.as_ptr()).reverse();
+                                (*sfa.as_ptr()).reverse();
+                                return self.settle_seg(&sfa);
                             }
-                            return reverse_int_seg(&sfa, &tbn, &fan);
-                        } else if sfb == sta && (*tan.as_ptr()).rank <= (*fbn.as_ptr()).rank {
-                            if ((*sfb.as_ptr()).first == from_b.inner
+                            reverse_int_seg_ranked(&sfa, &tbn, &fan);
+                            return self.update_prefix_cost(&sfa);
+                        } else if sfb == sta && within_seg(&sfb, &tan, &fbn) {
+                            if ((*sfb.as_ptr()).first == Some(fbn)
                                 && (*sfb.as_ptr()).reverse
-                                && (*sfb.as_ptr()).last == to_a.inner)
-                                || ((*sfb.as_ptr()).first == to_a.inner
-                                    && (*sfb.as_ptr()).last == from_b.inner)
+                                && (*sfb.as_ptr()).last == Some(tan))
+                                || ((*sfb.as_ptr()).first == Some(tan)
+                                    && !(*sfb.as_ptr()).reverse
+                                    && (*sfb.as_ptr()).last == Some(fbn))
                             {
-                                return (*sfb.as_ptr()).reverse();
+                                (*sfb.as_ptr()).reverse();
+                                return self.settle_seg(&sfb);
                             }
-                            return reverse_int_seg(&sfb, &tan, &fbn);
+                            reverse_int_seg_ranked(&sfb, &tan, &fbn);
+                            return self.update_prefix_cost(&sfb);
                         }
 
                         // Case 2: Both paths (to_b, from_a) AND (to_a, from_b) consist of a
@@ -295,12 +1187,14 @@ impl Tour for TwoLevelList {
                             // split a
                             split = true;
                             (*sfa.as_ptr()).split(&tan);
+                            self.update_prefix_cost_around(&sfa);
                         }
 
                         if sfb == stb {
                             // split b
                             split = true;
                             (*sfb.as_ptr()).split(&tbn);
+                            self.update_prefix_cost_around(&sfb);
                         }
 
                         if split {
@@ -318,22 +1212,42 @@ impl Tour for TwoLevelList {
                         let diff1 = if sta_r <= sfb_r {
                             sfb_r - sta_r
                         } else {
-                            self.segments.len() - sta_r + sfb_r
+                            self.n_active_segs - sta_r + sfb_r
                         };
 
                         let diff2 = if stb_r <= sfa_r {
                             sfa_r - stb_r
                         } else {
-                            self.segments.len() - stb_r + sfa_r
+                            self.n_active_segs - stb_r + sfa_r
                         };
 
-                        if diff1 <= diff2 {
+                        let (from, to) = if diff1 <= diff2 {
                             // Reverses the path (to_a, from_b).
-                            reverse_segs(&sta, &sfb);
+                            (sta, sfb)
                         } else {
                             // Reverses the path (to_b, from_a).
-                            reverse_segs(&stb, &sfa);
+                            (stb, sfa)
                         };
+
+                        let mut reversed = std::mem::take(&mut self.flipped);
+                        reversed.clear();
+                        if self.policy != ReversalPolicy::Lazy {
+                            let mut seg = from;
+                            reversed.push(seg);
+                            while seg != to {
+                                match (*seg.as_ptr()).next {
+                                    Some(next) => seg = next,
+                                    None => panic!("Missing next segment"),
+                                }
+                                reversed.push(seg);
+                            }
+                        }
+
+                        reverse_segs(&from, &to);
+                        for seg in &reversed {
+                            self.settle_seg(seg);
+                        }
+                        self.flipped = reversed;
                     }
                     _ => panic!("DataNode without segment while flipping."),
                 }
@@ -347,6 +1261,33 @@ impl Tour for TwoLevelList {
         self.nodes.get(index).copied()
     }
 
+    fn at_position(&self, pos: usize) -> Option<TourNode> {
+        let len = self.n_included;
+        if pos >= len {
+            return None;
+        }
+
+        match self.first_included() {
+            Some(TourNode { inner: Some(first) }) => unsafe {
+                let offset = self.raw_position(first)?;
+                self.at_raw_position((offset + pos) % len)
+            },
+            _ => None,
+        }
+    }
+
+    fn order_index(&self, index: usize) -> Option<usize> {
+        let len = self.n_included;
+        match (self.first_included(), self.nodes.get(index)) {
+            (Some(TourNode { inner: Some(first) }), Some(TourNode { inner: Some(node) })) => unsafe {
+                let offset = self.raw_position(first)?;
+                let pos = self.raw_position(node)?;
+                Some((len + pos - offset) % len)
+            },
+            _ => None,
+        }
+    }
+
     #[inline]
     fn relation(&self, base: &TourNode, targ: &TourNode) -> NodeRel {
         match base.inner {
@@ -354,8 +1295,8 @@ impl Tour for TwoLevelList {
                 match (*inner.as_ptr()).segment {
                     Some(seg) => {
                         match (
-                            (*inner.as_ptr()).predecessor == targ.inner,
-                            (*inner.as_ptr()).successor == targ.inner,
+                            pred(inner) == targ.inner,
+                            succ(inner) == targ.inner,
                             (*seg.as_ptr()).reverse ^ self.rev,
                         ) {
                             (true, false, true) | (false, true, false) => NodeRel::Predecessor,
@@ -372,63 +1313,29 @@ impl Tour for TwoLevelList {
 
     #[inline]
     fn successor(&self, node: &TourNode) -> Option<TourNode> {
-        match node.inner {
-            Some(inner) => unsafe {
-                match (*inner.as_ptr()).segment {
-                    Some(seg) => {
-                        if (*seg.as_ptr()).reverse ^ self.rev {
-                            (*inner.as_ptr()).predecessor.map(|_| TourNode {
-                                inner: (*inner.as_ptr()).predecessor,
-                            })
-                        } else {
-                            (*inner.as_ptr()).successor.map(|_| TourNode {
-                                inner: (*inner.as_ptr()).successor,
-                            })
-                        }
-                    }
-                    None => None,
-                }
-            },
-            None => None,
-        }
+        node.inner
+            .and_then(|inner| unsafe { self.neighbour(inner, true) })
     }
 
     #[inline]
     fn successor_at(&self, kin_index: usize) -> Option<TourNode> {
         match self.nodes.get(kin_index) {
-            Some(kin) => self.successor(kin),
-            None => None,
+            Some(TourNode { inner: Some(inner) }) => unsafe { self.neighbour(*inner, true) },
+            _ => None,
         }
     }
 
     #[inline]
     fn predecessor(&self, node: &TourNode) -> Option<TourNode> {
-        match node.inner {
-            Some(inner) => unsafe {
-                match (*inner.as_ptr()).segment {
-                    Some(seg) => {
-                        if (*seg.as_ptr()).reverse ^ self.rev {
-                            (*inner.as_ptr()).successor.map(|_| TourNode {
-                                inner: (*inner.as_ptr()).successor,
-                            })
-                        } else {
-                            (*inner.as_ptr()).predecessor.map(|_| TourNode {
-                                inner: (*inner.as_ptr()).predecessor,
-                            })
-                        }
-                    }
-                    None => None,
-                }
-            },
-            None => None,
-        }
+        node.inner
+            .and_then(|inner| unsafe { self.neighbour(inner, false) })
     }
 
     #[inline]
     fn predecessor_at(&self, kin_index: usize) -> Option<TourNode> {
         match self.nodes.get(kin_index) {
-            Some(kin) => self.predecessor(kin),
-            None => None,
+            Some(TourNode { inner: Some(inner) }) => unsafe { self.neighbour(*inner, false) },
+            _ => None,
         }
     }
 
@@ -438,41 +1345,43 @@ impl Tour for TwoLevelList {
     }
 
     fn tour_order(&self) -> TourOrder {
-        let mut result = Vec::with_capacity(self.nodes.len());
+        let mut order = TourOrder::with_capacity(self.n_included);
+        self.tour_order_into(&mut order);
+        order
+    }
+
+    fn tour_order_into(&self, order: &mut TourOrder) {
+        let result = &mut order.order;
+        result.clear();
         let mut d = 0.;
 
-        match self.nodes.first() {
-            Some(first) => {
-                result.push(first.index().get());
-                let mut nopt = self.successor(first);
-
-                loop {
-                    match nopt {
-                        Some(node) => {
-                            d += self.distance(&self.predecessor(&node).unwrap(), &node);
-                            if node.inner == first.inner {
-                                break;
-                            }
+        if let Some(first) = self.first_included() {
+            result.push(first.index().get());
+            let mut nopt = self.successor(first);
 
-                            result.push(node.index().get());
-                            nopt = self.successor(&node);
-                        }
-                        None => return TourOrder::default(),
-                    }
+            while let Some(node) = nopt {
+                d += self.distance(&self.predecessor(&node).unwrap(), &node);
+                if node.inner == first.inner {
+                    order.cost = d;
+                    return;
                 }
 
-                TourOrder::with_cost(result, d)
+                result.push(node.index().get());
+                nopt = self.successor(&node);
             }
-            None => TourOrder::default(),
         }
+
+        // Like the default order, which is empty and of maximal cost.
+        result.clear();
+        order.cost = Scalar::MAX;
     }
 
     fn measure(&self, to: &TourOrder) -> Scalar {
-        if self.len() == to.len() {
+        if !to.is_empty() && to.len() <= self.len() {
             let v = to.order();
-            let mut cost = self.distance_at(v.first().unwrap(), v.last().unwrap());
+            let mut cost = self.cost(v.last().unwrap(), v.first().unwrap());
             for pair in v.windows(2) {
-                cost += self.distance_at(&pair[0], &pair[1]);
+                cost += self.cost(&pair[0], &pair[1]);
             }
             cost
         } else {
@@ -491,21 +1400,323 @@ impl Tour for TwoLevelList {
         self.nodes.len()
     }
 
-    #[inline]
-    fn is_empty(&self) -> bool {
-        self.nodes.is_empty()
-    }
-
     #[inline]
     fn total_distance(&self) -> crate::Scalar {
         self.total_dist
     }
 
+    /// Lists the segments in the forward traversal of the tour, one segment per line.
+    ///
+    /// Each line starts with the rank of the segment, followed by its orientation (`>` if
+    /// forward, `<` if reversed) and the node indices in the forward traversal of the tour.
+    fn dump(&self, width: usize) -> String {
+        let mut result = format!(
+            "TwoLevelList: {} nodes, {} segments, reversed: {}",
+            self.nodes.len(),
+            self.n_active_segs,
+            self.rev
+        );
+
+        for seg in self.seg_snapshots() {
+            let tokens: Vec<String> = seg.nodes.iter().map(|x| x.to_string()).collect();
+            let prefix = format!("{:>4} {} | ", seg.rank, if seg.reverse { '<' } else { '>' });
+            result.push('\n');
+            result.push_str(&super::wrap_tokens(&prefix, &tokens, width));
+        }
+
+        result
+    }
+
+    /// Moves all vertices into a new run of the arena in the order of their links, so that walking
+    /// through the tour touches adjacent memory. Takes O(N) time.
+    ///
+    /// The candidates and all other links between vertices are preserved. The old run is given
+    /// back to the arena, thus using vertices returned by [`Tour::get`] before is undefined
+    /// behaviour.
+    fn repack(&mut self) {
+        unsafe {
+            let olds = self.physical_order();
+            let mut vertices = Vec::with_capacity(olds.len());
+            for old in &olds {
+                let placeholder = InnerNode::new((*old.as_ptr()).index);
+                vertices.push(std::mem::replace(&mut *old.as_ptr(), placeholder));
+            }
+
+            let run = arena::alloc(vertices);
+            let mut news = vec![None; self.nodes.len()];
+            for (pos, old) in olds.iter().enumerate() {
+                news[(*old.as_ptr()).index.index()] = Some(run.at(pos));
+            }
+
+            // The placeholders keep the indices of the old vertices until they are released.
+            let remap = |link: Option<NonNull<InnerNode>>| match link {
+                Some(old) => news[(*old.as_ptr()).index.index()],
+                None => None,
+            };
+
+            // Links are distances in the run, thus they are followed from the old vertex and
+            // measured again from the new one.
+            for (pos, old) in olds.iter().enumerate() {
+                let new = run.at(pos);
+                let relink = |link: Link| link_to(new, remap(follow(*old, link)));
+                let node = &mut *new.as_ptr();
+                node.predecessor = relink(node.predecessor);
+                node.successor = relink(node.successor);
+                node.mst_final_edge = relink(node.mst_final_edge);
+                node.mst_parent = relink(node.mst_parent);
+                node.orgn_nbr_1 = relink(node.orgn_nbr_1);
+                node.orgn_nbr_2 = relink(node.orgn_nbr_2);
+                for cand in node.candidates.iter_mut() {
+                    cand.inner = remap(cand.inner);
+                }
+                for pair in node.best_neighbours.iter_mut() {
+                    *pair = (relink(pair.0), relink(pair.1));
+                }
+            }
+
+            for seg in self.segments.iter().flatten() {
+                (*seg.as_ptr()).first = remap((*seg.as_ptr()).first);
+                (*seg.as_ptr()).last = remap((*seg.as_ptr()).last);
+            }
+
+            arena::release(std::mem::replace(&mut self.vertices, run));
+
+            for (node, new) in self.nodes.iter_mut().zip(news) {
+                node.inner = new;
+            }
+        }
+    }
+
     fn itr(&self) -> TourIter {
         TourIter {
             it: self.nodes.iter(),
         }
     }
+
+    /// Removes a node from the tour.
+    ///
+    /// Unlinks the node from its segment, which takes O(groupsize) time. The tour is only rebuilt
+    /// if the segment would become empty.
+    fn exclude(&mut self, index: usize) -> Result<(), UpdateTourError> {
+        let node = match self.nodes.get(index) {
+            Some(TourNode { inner: Some(node) }) => *node,
+            _ => return Err(UpdateTourError::NodeNotFound),
+        };
+
+        if !self.is_included(index) {
+            return Ok(());
+        }
+
+        if self.n_included == 1 {
+            return Err(UpdateTourError::InvalidTourOrder);
+        }
+
+        unsafe {
+            let seg = (*node.as_ptr())
+                .segment
+                .ok_or(UpdateTourError::BrokenTour)?;
+
+            // With two nodes left, both neighbours of the remaining node coincide.
+            if self.n_included == 2 || self.seg_len(&seg) == 1 {
+                let order: Vec<usize> = self
+                    .tour_order()
+                    .order()
+                    .iter()
+                    .copied()
+                    .filter(|x| *x != index)
+                    .collect();
+                return self.rebuild(&order);
+            }
+
+            let (low, high) = match (pred(node), succ(node)) {
+                (Some(low), Some(high)) => (low, high),
+                _ => return Err(UpdateTourError::BrokenTour),
+            };
+
+            if succ(low) == Some(node) {
+                set_succ(low, Some(high));
+            } else {
+                set_pred(low, Some(high));
+            }
+
+            if pred(high) == Some(node) {
+                set_pred(high, Some(low));
+            } else {
+                set_succ(high, Some(low));
+            }
+
+            if (*seg.as_ptr()).first == Some(node) {
+                (*seg.as_ptr()).first = Some(high);
+            }
+
+            if (*seg.as_ptr()).last == Some(node) {
+                (*seg.as_ptr()).last = Some(low);
+            } else {
+                self.shift_ranks(high, &seg, -1);
+            }
+
+            let (ia, ib) = self.directed(&seg, (*low.as_ptr()).index, (*high.as_ptr()).index);
+            let ix = (*node.as_ptr()).index;
+            self.total_dist -= self.cost(&ia, &ix) + self.cost(&ix, &ib) - self.cost(&ia, &ib);
+
+            (*node.as_ptr()).segment = None;
+            set_pred(node, None);
+            set_succ(node, None);
+            (*node.as_ptr()).rank = i32::MAX;
+            self.n_included -= 1;
+            self.update_prefix_cost(&seg);
+        }
+
+        Ok(())
+    }
+
+    /// Inserts a node into the tour at its cheapest position.
+    ///
+    /// Finding the position takes O(N) time, while the insertion itself takes O(groupsize) time.
+    fn include(&mut self, index: usize) -> Result<(), UpdateTourError> {
+        if index >= self.nodes.len() {
+            return Err(UpdateTourError::NodeNotFound);
+        }
+
+        if self.is_included(index) {
+            return Ok(());
+        }
+
+        match self.cheapest_insertion(index) {
+            Some((_, after)) => self.insert_after(index, after.index().get()),
+            None => Err(UpdateTourError::BrokenTour),
+        }
+    }
+
+    /// Splices a node into the segment of the node after which it is inserted, which takes
+    /// O(groupsize) time. Segments may thus grow beyond the group size until the tour is rebuilt.
+    fn insert_after(&mut self, index: usize, after_index: usize) -> Result<(), UpdateTourError> {
+        let (node, after) = match (self.nodes.get(index), self.nodes.get(after_index)) {
+            (Some(TourNode { inner: Some(node) }), Some(TourNode { inner: Some(after) })) => {
+                (*node, *after)
+            }
+            _ => return Err(UpdateTourError::NodeNotFound),
+        };
+
+        if self.is_included(index) || !self.is_included(after_index) {
+            return Err(UpdateTourError::InvalidTourOrder);
+        }
+
+        // With two nodes, both neighbours of a node coincide, which leaves the orientation of the
+        // links ambiguous.
+        if self.n_included == 2 {
+            let mut order = self.tour_order().order().clone();
+            let pos = order
+                .iter()
+                .position(|x| *x == after_index)
+                .ok_or(UpdateTourError::BrokenTour)?;
+            order.insert(pos + 1, index);
+            return unsafe { self.rebuild(&order) };
+        }
+
+        unsafe {
+            let seg = (*after.as_ptr())
+                .segment
+                .ok_or(UpdateTourError::BrokenTour)?;
+            let forward = self.is_forward(&seg);
+
+            // The node is linked between `after` and its successor in the traversal, which is
+            // the neighbour with the higher rank if the segment is traversed forwards.
+            let next = if forward { succ(after) } else { pred(after) }
+                .ok_or(UpdateTourError::BrokenTour)?;
+
+            if pred(next) == Some(after) {
+                set_pred(next, Some(node));
+            } else {
+                set_succ(next, Some(node));
+            }
+
+            // Shifts the ranks of all nodes behind the inserted node in its segment.
+            let (low, high) = if forward {
+                set_succ(after, Some(node));
+                (after, next)
+            } else {
+                set_pred(after, Some(node));
+                (next, after)
+            };
+            set_pred(node, Some(low));
+            set_succ(node, Some(high));
+            (*node.as_ptr()).segment = Some(seg);
+            (*node.as_ptr()).status = NodeStatus::Active;
+
+            if forward {
+                (*node.as_ptr()).rank = (*after.as_ptr()).rank + 1;
+                if (*seg.as_ptr()).last == Some(after) {
+                    (*seg.as_ptr()).last = Some(node);
+                } else {
+                    self.shift_ranks(high, &seg, 1);
+                }
+            } else {
+                (*node.as_ptr()).rank = (*after.as_ptr()).rank;
+                if (*seg.as_ptr()).first == Some(after) {
+                    (*seg.as_ptr()).first = Some(node);
+                }
+                self.shift_ranks(after, &seg, 1);
+            }
+
+            let (ia, ib) = self.directed(&seg, (*low.as_ptr()).index, (*high.as_ptr()).index);
+            let ix = (*node.as_ptr()).index;
+            self.total_dist += self.cost(&ia, &ix) + self.cost(&ix, &ib) - self.cost(&ia, &ib);
+            self.n_included += 1;
+            self.update_prefix_cost(&seg);
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn is_included(&self, index: usize) -> bool {
+        match self.nodes.get(index) {
+            Some(TourNode { inner: Some(inner) }) => unsafe { (*inner.as_ptr()).segment.is_some() },
+            _ => false,
+        }
+    }
+
+    #[inline]
+    fn n_included(&self) -> usize {
+        self.n_included
+    }
+
+    #[inline]
+    fn prize(&self, index: usize) -> Scalar {
+        self.store.prize(index)
+    }
+
+    #[inline]
+    fn group(&self, index: usize) -> Option<usize> {
+        self.store.group(index)
+    }
+}
+
+/// Returns `true` if the path from `head` to `tail`, following the orientation of their common
+/// segment, does not leave the segment.
+#[inline]
+unsafe fn within_seg(
+    seg: &NonNull<Segment>,
+    head: &NonNull<InnerNode>,
+    tail: &NonNull<InnerNode>,
+) -> bool {
+    ((*head.as_ptr()).rank <= (*tail.as_ptr()).rank) ^ (*seg.as_ptr()).reverse
+}
+
+/// Reverses the path between `head` and `tail` inside their segment, regardless of the
+/// orientation of the segment.
+#[inline]
+unsafe fn reverse_int_seg_ranked(
+    seg: &NonNull<Segment>,
+    head: &NonNull<InnerNode>,
+    tail: &NonNull<InnerNode>,
+) {
+    if (*head.as_ptr()).rank <= (*tail.as_ptr()).rank {
+        reverse_int_seg(seg, head, tail);
+    } else {
+        reverse_int_seg(seg, tail, head);
+    }
 }
 
 // impl STree for TwoLevelList {
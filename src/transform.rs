@@ -0,0 +1,150 @@
+//! Transformations between variants of the travelling salesman problem.
+use crate::{
+    data::{DataStore, Metric},
+    tour::TourOrder,
+    CyklError, Scalar,
+};
+
+/// Asymmetric instance produced by [`noon_bean`], together with the information needed to map
+/// its tours back to the original instance.
+#[derive(Debug)]
+pub struct NoonBean {
+    store: DataStore<()>,
+    // Cluster of each node.
+    clusters: Vec<usize>,
+    n_clusters: usize,
+    penalty: Scalar,
+}
+
+impl NoonBean {
+    /// Returns the transformed instance with explicit, asymmetric costs.
+    pub fn store(&self) -> &DataStore<()> {
+        &self.store
+    }
+
+    /// Consumes the transformation and returns the transformed instance.
+    pub fn into_store(self) -> DataStore<()> {
+        self.store
+    }
+
+    /// Returns the number of clusters, including the singleton clusters of ungrouped nodes.
+    pub fn n_clusters(&self) -> usize {
+        self.n_clusters
+    }
+
+    /// Returns the constant added to the cost of every arc between two clusters.
+    ///
+    /// The cost of a tour that visits each cluster contiguously exceeds the cost of the
+    /// corresponding tour in the original instance by exactly `n_clusters() * penalty()`.
+    pub fn penalty(&self) -> Scalar {
+        self.penalty
+    }
+
+    /// Maps a tour of the transformed instance back to a tour of the original instance that
+    /// visits one node per cluster.
+    ///
+    /// The representative of a cluster is the node at which the tour enters the cluster.
+    pub fn map_back(&self, order: &TourOrder) -> TourOrder {
+        let v = order.order();
+        let len = v.len();
+        let cluster = |pos: usize| self.clusters.get(v[pos % len]).copied();
+
+        let start = (0..len)
+            .find(|pos| cluster(*pos) != cluster(*pos + len - 1))
+            .unwrap_or(0);
+
+        let mut result = Vec::with_capacity(self.n_clusters);
+        for pos in start..(start + len) {
+            if result.is_empty() || cluster(pos) != cluster(pos + len - 1) {
+                result.push(v[pos % len]);
+            }
+        }
+
+        TourOrder::with_ord(result)
+    }
+}
+
+/// Transforms a generalised instance, in which exactly one node of each group must be visited,
+/// into an asymmetric instance with the Noon–Bean transformation.
+///
+/// Nodes of each group are linked to a zero-cost cycle in the order they are listed. An arc that
+/// leaves a group from a node is charged the cost of leaving from the node's successor in that
+/// cycle, plus a large penalty that forces optimal tours to visit each group contiguously. Nodes
+/// that do not belong to any group form groups of their own. The costs of the input store must
+/// already be computed.
+///
+/// See C. Noon and J. Bean (1993), "An efficient transformation of the generalized traveling
+/// salesman problem".
+pub fn noon_bean<M>(store: &DataStore<M>, groups: &[Vec<usize>]) -> Result<NoonBean, CyklError> {
+    let len = store.len();
+
+    let mut members: Vec<Vec<usize>> = Vec::with_capacity(groups.len());
+    let mut clusters = vec![usize::MAX; len];
+    for group in groups.iter().filter(|group| !group.is_empty()) {
+        for index in group {
+            match clusters.get(*index) {
+                Some(&usize::MAX) => clusters[*index] = members.len(),
+                Some(_) => return Err(CyklError::DuplicateNode(*index)),
+                None => return Err(CyklError::IndexOutOfBounds { index: *index, len }),
+            }
+        }
+        members.push(group.clone());
+    }
+
+    for (index, cluster) in clusters.iter_mut().enumerate() {
+        if *cluster == usize::MAX {
+            *cluster = members.len();
+            members.push(vec![index]);
+        }
+    }
+
+    // Successor of each node in the cycle of its cluster.
+    let mut next = vec![0; len];
+    for group in &members {
+        for (pos, index) in group.iter().enumerate() {
+            next[*index] = group[(pos + 1) % group.len()];
+        }
+    }
+
+    let mut max_cost: Scalar = 0.;
+    for a in 0..len {
+        for b in 0..len {
+            max_cost = max_cost.max(store.cost(&a, &b));
+        }
+    }
+
+    // Any tour of the original instance costs less than the penalty, and any tour that uses a
+    // forbidden arc costs more than all tours that do not.
+    let n = len as Scalar;
+    let penalty = n * max_cost + 1.;
+    let forbidden = n * (penalty + max_cost) + 1.;
+
+    let mut costs = vec![0.; len * len];
+    for a in 0..len {
+        for b in 0..len {
+            costs[a * len + b] = if a == b {
+                0.
+            } else if clusters[a] != clusters[b] {
+                store.cost(&next[a], &b) + penalty
+            } else if next[a] == b {
+                0.
+            } else {
+                forbidden
+            };
+        }
+    }
+
+    let mut result = DataStore::with_capacity(Metric::Explicit, len);
+    for node in &store.store() {
+        result.add(node.kind(), Vec::with_capacity(0), ());
+    }
+    result.set_costs(costs)?;
+    result.compute();
+
+    Ok(NoonBean {
+        store: result,
+        clusters,
+        n_clusters: members.len(),
+        penalty,
+    })
+}
@@ -0,0 +1,297 @@
+//! Rendering of tours into image files.
+use std::{collections::HashSet, fmt::Write, path::Path};
+
+use crate::{
+    data::{DataStore, Projection},
+    tour::{Edge, TourOrder},
+    CyklError, Scalar,
+};
+
+/// Size of the longer side of a rendered image in pixels.
+const CANVAS_SIZE: Scalar = 1000.;
+/// Space between the bounding box of all nodes and the border of an image in pixels.
+const MARGIN: Scalar = 20.;
+
+const COLOR_NODE: &str = "#333333";
+const COLOR_EDGE: &str = "#9e9e9e";
+const COLOR_REMOVED: &str = "#d32f2f";
+const COLOR_ADDED: &str = "#388e3c";
+
+/// Writes a tour as an SVG image to the given path.
+///
+/// Only the first two coordinates of each node are used for drawing.
+pub fn tour_svg<M, P>(store: &DataStore<M>, order: &TourOrder, path: P) -> Result<(), CyklError>
+where
+    P: AsRef<Path>,
+{
+    let canvas = Canvas::new(store)?;
+    let mut svg = canvas.header();
+
+    for (a, b) in edges(order).iter().map(Edge::nodes) {
+        canvas.line(&mut svg, store, a, b, COLOR_EDGE);
+    }
+
+    canvas.nodes(&mut svg, store);
+    svg.push_str("</svg>\n");
+
+    std::fs::write(path, svg)?;
+    Ok(())
+}
+
+/// Writes the difference between two tours as an SVG image to the given path.
+///
+/// Edges that are contained in `old` but not in `new` are drawn in red, edges that are only
+/// contained in `new` in green. Edges shared by both tours are drawn in grey. Only the first two
+/// coordinates of each node are used for drawing.
+pub fn diff_svg<M, P>(
+    store: &DataStore<M>,
+    old: &TourOrder,
+    new: &TourOrder,
+    path: P,
+) -> Result<(), CyklError>
+where
+    P: AsRef<Path>,
+{
+    let canvas = Canvas::new(store)?;
+    let mut svg = canvas.header();
+
+    let old_edges = edges(old);
+    let new_edges = edges(new);
+
+    for (a, b) in old_edges.intersection(&new_edges).map(Edge::nodes) {
+        canvas.line(&mut svg, store, a, b, COLOR_EDGE);
+    }
+
+    for (a, b) in old_edges.difference(&new_edges).map(Edge::nodes) {
+        canvas.line(&mut svg, store, a, b, COLOR_REMOVED);
+    }
+
+    for (a, b) in new_edges.difference(&old_edges).map(Edge::nodes) {
+        canvas.line(&mut svg, store, a, b, COLOR_ADDED);
+    }
+
+    canvas.nodes(&mut svg, store);
+    svg.push_str("</svg>\n");
+
+    std::fs::write(path, svg)?;
+    Ok(())
+}
+
+/// Writes a tour as a PNG image to the given path.
+///
+/// Raster images keep a constant file size regardless of the number of nodes, which makes them
+/// preferable to SVG for huge instances. Only the first two coordinates of each node are used for
+/// drawing.
+#[cfg(feature = "png")]
+pub fn tour_png<M, P>(store: &DataStore<M>, order: &TourOrder, path: P) -> Result<(), CyklError>
+where
+    P: AsRef<Path>,
+{
+    use plotters::prelude::*;
+
+    let canvas = Canvas::new(store)?;
+    let (width, height) = canvas.size();
+    let to_px = |x: &[Scalar]| {
+        let p = canvas.project(x);
+        (p.0.round() as i32, p.1.round() as i32)
+    };
+    let render_err =
+        |err: DrawingAreaErrorKind<_>| CyklError::Io(std::io::Error::other(err.to_string()));
+
+    let root = BitMapBackend::new(path.as_ref(), (width.ceil() as u32, height.ceil() as u32))
+        .into_drawing_area();
+    root.fill(&WHITE).map_err(render_err)?;
+
+    let edge_color = RGBColor(0x9e, 0x9e, 0x9e);
+    for (a, b) in edges(order).iter().map(Edge::nodes) {
+        if let (Some(xa), Some(xb)) = (store.coord(a), store.coord(b)) {
+            root.draw(&PathElement::new(vec![to_px(xa), to_px(xb)], edge_color))
+                .map_err(render_err)?;
+        }
+    }
+
+    let node_color = RGBColor(0x33, 0x33, 0x33);
+    for idx in 0..store.len() {
+        if let Some(x) = store.coord(idx) {
+            root.draw(&Circle::new(to_px(x), 1, node_color.filled()))
+                .map_err(render_err)?;
+        }
+    }
+
+    root.present().map_err(render_err)?;
+    Ok(())
+}
+
+/// Writes a tour as a closed GPX track to the given path.
+///
+/// The store must have been built from latitudes and longitudes, either with a geodesic metric or
+/// with a projection, otherwise [`CyklError::MissingCoordinates`] is returned.
+pub fn tour_gpx<M, P>(store: &DataStore<M>, order: &TourOrder, path: P) -> Result<(), CyklError>
+where
+    P: AsRef<Path>,
+{
+    let points = route(store, order)?;
+    let mut gpx = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <gpx version=\"1.1\" creator=\"cykl\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n\
+         <trk><name>tour</name><trkseg>\n",
+    );
+    for (lat, lon) in points {
+        let _ = writeln!(gpx, "<trkpt lat=\"{}\" lon=\"{}\"></trkpt>", lat, lon);
+    }
+    gpx.push_str("</trkseg></trk>\n</gpx>\n");
+
+    std::fs::write(path, gpx)?;
+    Ok(())
+}
+
+/// Writes a tour as a closed KML path to the given path.
+///
+/// The store must have been built from latitudes and longitudes, either with a geodesic metric or
+/// with a projection, otherwise [`CyklError::MissingCoordinates`] is returned.
+pub fn tour_kml<M, P>(store: &DataStore<M>, order: &TourOrder, path: P) -> Result<(), CyklError>
+where
+    P: AsRef<Path>,
+{
+    let points = route(store, order)?;
+    let mut kml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <kml xmlns=\"http://www.opengis.net/kml/2.2\">\n\
+         <Placemark><name>tour</name><LineString><coordinates>\n",
+    );
+    // KML lists longitude before latitude.
+    for (lat, lon) in points {
+        let _ = writeln!(kml, "{},{}", lon, lat);
+    }
+    kml.push_str("</coordinates></LineString></Placemark>\n</kml>\n");
+
+    std::fs::write(path, kml)?;
+    Ok(())
+}
+
+/// Returns the latitudes and longitudes along a tour, ending at its first node.
+fn route<M>(store: &DataStore<M>, order: &TourOrder) -> Result<Vec<(Scalar, Scalar)>, CyklError> {
+    let latlon =
+        store.metric().is_geodesic() || !matches!(store.projection(), Projection::Identity);
+    if !latlon {
+        return Err(CyklError::MissingCoordinates);
+    }
+
+    let v = order.order();
+    v.iter()
+        .chain(v.first())
+        .map(|idx| match store.origin(*idx) {
+            Some(x) if x.len() >= 2 => Ok((x[0], x[1])),
+            _ => Err(CyklError::MissingCoordinates),
+        })
+        .collect()
+}
+
+/// Returns the set of undirected edges in a tour order.
+pub(crate) fn edges(order: &TourOrder) -> HashSet<Edge> {
+    let v = order.order();
+    let mut result = HashSet::with_capacity(v.len());
+
+    if v.len() < 2 {
+        return result;
+    }
+
+    for ii in 0..v.len() {
+        result.insert(Edge::new(v[ii], v[(ii + 1) % v.len()]));
+    }
+
+    result
+}
+
+/// Maps node coordinates onto the pixel space of an image.
+pub(crate) struct Canvas {
+    min: (Scalar, Scalar),
+    scale: Scalar,
+    width: Scalar,
+    height: Scalar,
+}
+
+impl Canvas {
+    pub(crate) fn new<M>(store: &DataStore<M>) -> Result<Self, CyklError> {
+        if store.dim() < 2 {
+            return Err(CyklError::MissingCoordinates);
+        }
+
+        let (mut min, mut max) = ((Scalar::MAX, Scalar::MAX), (Scalar::MIN, Scalar::MIN));
+        for idx in 0..store.len() {
+            if let Some(x) = store.coord(idx) {
+                min = (min.0.min(x[0]), min.1.min(x[1]));
+                max = (max.0.max(x[0]), max.1.max(x[1]));
+            }
+        }
+
+        if store.is_empty() {
+            min = (0., 0.);
+            max = (0., 0.);
+        }
+
+        let extent = (max.0 - min.0).max(max.1 - min.1);
+        let scale = if extent > 0. {
+            (CANVAS_SIZE - 2. * MARGIN) / extent
+        } else {
+            1.
+        };
+
+        Ok(Self {
+            min,
+            scale,
+            width: (max.0 - min.0) * scale + 2. * MARGIN,
+            height: (max.1 - min.1) * scale + 2. * MARGIN,
+        })
+    }
+
+    /// Returns the width and height of the image in pixels.
+    #[cfg(feature = "png")]
+    pub(crate) fn size(&self) -> (Scalar, Scalar) {
+        (self.width, self.height)
+    }
+
+    /// Converts coordinates into pixel positions. The y-axis points upwards as in the input data.
+    pub(crate) fn project(&self, x: &[Scalar]) -> (Scalar, Scalar) {
+        (
+            (x[0] - self.min.0) * self.scale + MARGIN,
+            self.height - ((x[1] - self.min.1) * self.scale + MARGIN),
+        )
+    }
+
+    fn header(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" \
+             viewBox=\"0 0 {w:.2} {h:.2}\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+            w = self.width,
+            h = self.height
+        )
+    }
+
+    fn line<M>(&self, svg: &mut String, store: &DataStore<M>, a: usize, b: usize, color: &str) {
+        if let (Some(xa), Some(xb)) = (store.coord(a), store.coord(b)) {
+            let (pa, pb) = (self.project(xa), self.project(xb));
+            writeln!(
+                svg,
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"1.5\"/>",
+                pa.0, pa.1, pb.0, pb.1, color
+            )
+            .unwrap();
+        }
+    }
+
+    fn nodes<M>(&self, svg: &mut String, store: &DataStore<M>) {
+        for idx in 0..store.len() {
+            if let Some(x) = store.coord(idx) {
+                let p = self.project(x);
+                writeln!(
+                    svg,
+                    "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"2\" fill=\"{}\"/>",
+                    p.0, p.1, COLOR_NODE
+                )
+                .unwrap();
+            }
+        }
+    }
+}
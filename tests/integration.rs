@@ -1,9 +1,131 @@
 #![cfg(test)]
-use cykl::load_tsp;
+use cykl::{
+    alg::{
+        cand_gen_nn,
+        pipeline::{DoubleBridgeKick, GreedyConstruction, OrOptDescent, Pipeline, TwoOptDescent},
+        Objective, Scan, Termination,
+    },
+    data::{DataStore, Metric, NodeKind},
+    load_tsp,
+    tour::TwoLevelList,
+};
 
 #[test]
 fn test_a280() {
-    let model = load_tsp("./tests/data/a280.tsp", 20);
+    let model = load_tsp("./tests/data/a280.tsp", 20).unwrap();
     assert_eq!(0, model.n_depots());
     assert_eq!(280, model.n_nodes());
 }
+
+#[test]
+fn test_load_missing_file() {
+    assert!(load_tsp("./tests/data/missing.tsp", 20).is_err());
+}
+
+/// Largest relative gap to the best-known length that the solver may leave on the corpus.
+const CORPUS_TOLERANCE: f64 = 0.03;
+
+/// Number of kicks of the iterated local search that solves the corpus.
+const CORPUS_ITERATIONS: usize = 50;
+
+/// Generator of pseudo-random numbers whose sequence depends only on the seed, so that generated
+/// instances do not change with the version of any dependency.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Generates the nodes of a corpus instance with integral coordinates in `[0, 1000]`.
+///
+/// `uniform` instances spread the nodes uniformly, `clustered` instances place them around one
+/// centre per 20 nodes.
+fn generate_instance(kind: &str, n_nodes: usize, seed: u64) -> Vec<[f64; 2]> {
+    let mut rng = SplitMix64(seed);
+    let point = |rng: &mut SplitMix64| [rng.next_f64() * 1000., rng.next_f64() * 1000.];
+
+    let nodes: Vec<[f64; 2]> = match kind {
+        "uniform" => (0..n_nodes).map(|_| point(&mut rng)).collect(),
+        "clustered" => {
+            let centres: Vec<_> = (0..(n_nodes / 20).max(1))
+                .map(|_| point(&mut rng))
+                .collect();
+            (0..n_nodes)
+                .map(|ii| {
+                    let centre = centres[ii % centres.len()];
+                    // The sum of three uniform numbers approximates a normal distribution.
+                    let mut offset = || (0..3).map(|_| rng.next_f64() - 0.5).sum::<f64>() * 60.;
+                    [centre[0] + offset(), centre[1] + offset()]
+                })
+                .collect()
+        }
+        _ => panic!("Unknown instance kind {}", kind),
+    };
+
+    nodes
+        .into_iter()
+        .map(|[x, y]| [x.round().clamp(0., 1000.), y.round().clamp(0., 1000.)])
+        .collect()
+}
+
+/// Solves each instance of the corpus in `tests/data/corpus.csv` and compares the tour length with
+/// the best-known length stored there, so that changes to the moves cannot silently degrade the
+/// quality of the solvers.
+///
+/// The instances are solved by an iterated local search with 2-opt and Or-opt descents and
+/// double-bridge kicks, which stops after a fixed number of iterations, so that the result does not
+/// depend on the speed of the machine. The best-known lengths are the shortest tours found by the
+/// same search with 5000 iterations, run in release mode with the seeds 1 to 5.
+#[test]
+fn test_corpus() {
+    let corpus = std::fs::read_to_string("./tests/data/corpus.csv").unwrap();
+    for line in corpus
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+    {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let (name, kind) = (fields[0], fields[1]);
+        let n_nodes: usize = fields[2].parse().unwrap();
+        let seed: u64 = fields[3].parse().unwrap();
+        let best_known: f64 = fields[4].parse().unwrap();
+
+        let mut store = DataStore::with_capacity(Metric::Euc2d, n_nodes);
+        for pos in generate_instance(kind, n_nodes, seed) {
+            store.add(NodeKind::Target, pos.to_vec(), ());
+        }
+        store.compute();
+
+        let mut tour = TwoLevelList::new(&store, 16);
+        cand_gen_nn(&mut tour, 8, Objective::Minimize);
+        let order = Pipeline::new(Termination::Iterations(CORPUS_ITERATIONS))
+            .construct(GreedyConstruction(vec![0]))
+            .improve(TwoOptDescent(Scan::FirstImprovement))
+            .improve(OrOptDescent(Scan::FirstImprovement))
+            .perturb(DoubleBridgeKick::new(1))
+            .run(&mut tour)
+            .unwrap();
+
+        assert_eq!(n_nodes, order.len(), "{}", name);
+        let gap = order.cost() / best_known - 1.;
+        assert!(
+            gap <= CORPUS_TOLERANCE,
+            "{}: length {:.2} is {:.2}% above the best-known length {:.2}",
+            name,
+            order.cost(),
+            100. * gap,
+            best_known
+        );
+    }
+}